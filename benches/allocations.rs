@@ -0,0 +1,99 @@
+//! Counts the allocations of parsing and analyzing a seeded synthetic conversation, and the most memory allocated at
+//! once during each, through a counting global allocator, for comparing how much a change allocates rather than how
+//! fast it runs. The `large` fixture by default, `BENCH_FIXTURE=small` or `medium` for a quicker run.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use igdm_sentiment_analysis::{
+    parser::ConversationDirectory,
+    synthetic::{self, BenchFixture},
+};
+
+/// How many allocations (and reallocations) were made.
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// How many bytes are allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+/// The most bytes allocated at once.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+impl Counting {
+    fn grow(size: usize) {
+        let allocated = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(allocated, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            Self::grow(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let reallocated = System.realloc(pointer, layout, new_size);
+        if !reallocated.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+            Self::grow(new_size);
+        }
+        reallocated
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Runs `f`, printing how many allocations it made and the most memory it had allocated at once (beyond what was
+/// already allocated before it).
+fn measure<T>(name: &str, f: impl FnOnce() -> T) -> T {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let value = f();
+    println!(
+        "{name:>8}: {:>10} allocations, peak {:>7.1} MB, {:.1?}",
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        (PEAK.load(Ordering::Relaxed) - before) as f64 / 1_000_000.0,
+        start.elapsed(),
+    );
+    value
+}
+
+fn main() {
+    let fixture = match std::env::var("BENCH_FIXTURE").as_deref() {
+        Ok("small") => BenchFixture::Small,
+        Ok("medium") => BenchFixture::Medium,
+        _ => BenchFixture::Large,
+    };
+    let config = fixture.config();
+    let dir = std::env::temp_dir().join(format!("igdm-allocations-{}", std::process::id()));
+    synthetic::write_export(&config, &dir).unwrap();
+
+    println!("{} messages", config.messages);
+    let conversation = measure("parse", || {
+        ConversationDirectory::try_from(PathBuf::from(&dir))
+            .unwrap()
+            .parse()
+            .unwrap()
+    });
+    measure("analyze", || conversation.analyze());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,114 @@
+//! Benchmarks for each stage of the pipeline, driven by seeded synthetic conversations
+//! so that results are comparable across machines.
+
+use std::path::PathBuf;
+
+use chrono::TimeDelta;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use igdm_sentiment_analysis::{
+    analyzer::AnalyzeOptions,
+    parser::{decode_escapes, decode_escapes_in_place, ConversationDirectory},
+    plotter::{extract_data, PlotType},
+    stats::{smoothen_wrt_time, AggregateStat},
+    synthetic::{self, BenchFixture, SyntheticConfig},
+};
+
+/// A config for a conversation of (exactly) `messages` messages.
+fn config(messages: usize) -> SyntheticConfig {
+    SyntheticConfig {
+        messages,
+        ..BenchFixture::Small.config()
+    }
+}
+
+fn escape_decoding(c: &mut Criterion) {
+    let conversation = synthetic::generate_conversation(&config(10_000));
+    let encoded = synthetic::encode_export(&conversation, &conversation.messages).unwrap();
+
+    let mut group = c.benchmark_group("escape decoding");
+    group.throughput(Throughput::Bytes(encoded.len() as u64));
+    group.bench_function("decode_escapes", |b| {
+        b.iter(|| decode_escapes(encoded.as_bytes()))
+    });
+    group.bench_function("decode_escapes_in_place", |b| {
+        b.iter_batched(
+            || encoded.clone().into_bytes(),
+            |mut buffer| decode_escapes_in_place(&mut buffer),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn full_file_parse(c: &mut Criterion) {
+    let fixture = BenchFixture::Small.config();
+    let dir = std::env::temp_dir().join(format!("igdm-bench-{}", std::process::id()));
+    synthetic::write_export(&fixture, &dir).unwrap();
+
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(fixture.messages as u64));
+    group.bench_function("ConversationDirectory::parse", |b| {
+        b.iter(|| {
+            ConversationDirectory::try_from(PathBuf::from(&dir))
+                .unwrap()
+                .parse()
+                .unwrap()
+        })
+    });
+    group.finish();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn analyze(c: &mut Criterion) {
+    let conversation = synthetic::generate_conversation(&config(10_000));
+
+    let mut group = c.benchmark_group("analyze");
+    group.throughput(Throughput::Elements(10_000));
+    group.bench_function("analyze 10k messages", |b| {
+        b.iter(|| conversation.analyze())
+    });
+    let serial = AnalyzeOptions {
+        threads: Some(1),
+        ..AnalyzeOptions::default()
+    };
+    group.bench_function("analyze 10k messages, 1 thread", |b| {
+        b.iter(|| conversation.analyze_with(&serial))
+    });
+    let group_chat = synthetic::generate_conversation(&SyntheticConfig {
+        participants: 15,
+        ..config(10_000)
+    });
+    group.bench_function("analyze 10k messages, 15 participants", |b| {
+        b.iter(|| group_chat.analyze())
+    });
+    group.finish();
+}
+
+fn plot_data(c: &mut Criterion) {
+    let analysis = synthetic::generate_conversation(&config(10_000)).analyze();
+    let data = extract_data(&analysis, PlotType::Compound);
+    let scores = data.get(&analysis.names.id("Alice").unwrap()).unwrap();
+
+    let mut group = c.benchmark_group("plot data");
+    group.bench_function("extract_data", |b| {
+        b.iter(|| extract_data(&analysis, PlotType::Compound))
+    });
+    group.bench_function("smoothen_wrt_time", |b| {
+        b.iter_batched(
+            || scores.clone(),
+            |scores| smoothen_wrt_time(&scores, TimeDelta::days(3), AggregateStat::Mean),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    escape_decoding,
+    full_file_parse,
+    analyze,
+    plot_data
+);
+criterion_main!(benches);
@@ -0,0 +1,18 @@
+//! Feeds arbitrary bytes through the escape decoders and the json parser of a single message file,
+//! neither of which should ever panic: bad input must come back as an error.
+//! The in place escape decoder must also decode everything the same way as the allocating one.
+
+#![no_main]
+
+use igdm_sentiment_analysis::parser::{
+    decode_escapes, decode_escapes_in_place, ParsedConversation,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let decoded = decode_escapes(data);
+    let mut in_place = data.to_vec();
+    decode_escapes_in_place(&mut in_place);
+    assert_eq!(in_place, decoded);
+    let _ = ParsedConversation::from_bytes(data);
+});
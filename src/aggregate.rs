@@ -0,0 +1,67 @@
+//! Module containing the shared way of bucketing time series by calendar period,
+//! so every daily or weekly aggregate agrees on where a day or week starts.
+//!
+//! Periods are in UTC, days start at midnight, weeks on Monday, and months on their first day.
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+
+pub const DAY_MS: usize = 24 * 60 * 60 * 1000;
+pub const WEEK_MS: usize = 7 * DAY_MS;
+/// The epoch fell on a Thursday, this many days after the Monday starting its week.
+const EPOCH_WEEKDAY: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    /// The index of the period the timestamp (in milliseconds since the epoch) falls in,
+    /// consecutive periods have consecutive indices.
+    pub fn bucket(self, timestamp_ms: usize) -> usize {
+        match self {
+            Self::Day => timestamp_ms / DAY_MS,
+            Self::Week => (timestamp_ms + EPOCH_WEEKDAY * DAY_MS) / WEEK_MS,
+            Self::Month => chrono::DateTime::from_timestamp_millis(timestamp_ms as i64)
+                .map(|date| (date.year() - 1970) as usize * 12 + date.month0() as usize)
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The timestamp at the start of the period with the given index,
+    /// clamped to the epoch for the week containing it (which started a few days before).
+    pub fn start_ms(self, bucket: usize) -> usize {
+        match self {
+            Self::Day => bucket * DAY_MS,
+            Self::Week => (bucket * WEEK_MS).saturating_sub(EPOCH_WEEKDAY * DAY_MS),
+            Self::Month => i32::try_from(bucket / 12)
+                .ok()
+                .and_then(|years| NaiveDate::from_ymd_opt(1970 + years, bucket as u32 % 12 + 1, 1))
+                .map(|date| {
+                    date.and_time(chrono::NaiveTime::MIN)
+                        .and_utc()
+                        .timestamp_millis() as usize
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Groups timestamped values by the period they fall in, keyed by period index.
+pub fn bucket<T>(
+    values: impl IntoIterator<Item = (usize, T)>,
+    period: Period,
+) -> BTreeMap<usize, Vec<T>> {
+    let mut buckets: BTreeMap<usize, Vec<T>> = BTreeMap::new();
+    for (timestamp_ms, value) in values {
+        buckets
+            .entry(period.bucket(timestamp_ms))
+            .or_default()
+            .push(value);
+    }
+    buckets
+}
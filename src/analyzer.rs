@@ -1,14 +1,95 @@
-use std::collections::HashMap;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+};
 
+use anyhow::{bail, Context, Result};
+use chrono::TimeDelta;
+use rayon::prelude::*;
+use unicase::UniCase;
 use vader_sentiment::SentimentIntensityAnalyzer;
+use zeroize::Zeroize;
 
-use crate::parser::{Message, ParsedConversation, Participant};
+use crate::{
+    diagnostics::{Diagnostics, MalformedScore, PlotIssue},
+    drift::Fingerprint,
+    emoji,
+    events::EventMarker,
+    language::{Language, LanguageDetector},
+    lexicon::Lexicon,
+    openers::{OpenerBuilder, Openers},
+    palette::Palette,
+    parser::{ExportBoundaries, Message, ParsedConversation, ParticipantId, ParticipantNames},
+    progress::{Phase, Progress},
+    quotes::{QuoteDetector, QuotePolicy},
+    register::{RegisterBuilder, RegisterSeries},
+    stats::AggregateStat,
+    summary::{Bootstrap, ParticipantSummary},
+    text,
+    timezone::Tz,
+    tokenizer::{self, Tokenizer},
+    transform::RepeatCount,
+};
 
 pub struct AnalyzedConversation {
-    pub analysis: HashMap<Participant, Vec<(Message, Score)>>,
+    pub analysis: HashMap<ParticipantId, Vec<(Message, Score)>>,
+    /// the names of the participants, for presenting the analysis
+    pub names: ParticipantNames,
+    pub diagnostics: Diagnostics,
+    /// whether message content was dropped after scoring (for privacy, or to save memory), in which case every
+    /// message's `content` is `None` and features that need the text are unavailable
+    pub content_dropped: bool,
+    /// identifies the analyzer the messages were scored with, see `drift`
+    pub fingerprint: Fingerprint,
+    /// the thresholds consumers should use to classify scores with `Score::classify`
+    pub thresholds: Thresholds,
+    /// the statistic compound scores are aggregated with, carried over from `AnalyzeOptions::aggregate`
+    pub aggregate: AggregateStat,
+    /// how much messages flagged as quoted count in aggregates, carried over from `AnalyzeOptions::quotes`
+    pub quotes: QuotePolicy,
+    /// how messages standing for collapsed repeats count in message volumes, carried over from `AnalyzeOptions::repeats`
+    pub repeats: RepeatCount,
+    /// the longest silence that doesn't end a session, see `AnalyzeOptions::session_gap`
+    pub session_gap: TimeDelta,
+    /// how each participant writes, month by month, computed from the text before it could be dropped
+    pub register: RegisterSeries,
+    /// how each participant opened sessions, computed from the text before it could be dropped
+    pub openers: Openers,
+    /// days to mark on the plots, expanded over the conversation's time range by `events::EventConfig::expand`
+    pub events: Vec<EventMarker>,
+    /// how the series of the plots are told apart
+    pub palette: Palette,
+    /// the time zone the times of the plots are shown in, see `timezone`
+    pub time_zone: Tz,
+    /// the messages of the participants with an attachment and no text, unscored, ordered by time, carried over from
+    /// `ParsedConversation::attachment_only` so features counting messages can count media (see `Message::attachments`) too,
+    /// along with those with nothing but links and mentions, see `AnalyzeOptions::strip_urls_and_mentions`
+    pub attachment_only: Vec<Message>,
+    /// the messages of the participants with fewer tokens than `AnalyzeOptions::min_tokens`, unscored, ordered by time,
+    /// still counted in message volumes, see `AnalyzedConversation::counted_messages`
+    pub too_short: Vec<Message>,
+    /// the messages of the participants in another language than english, unscored, ordered by time, still counted in
+    /// message volumes, see `AnalyzeOptions::english_only`
+    pub other_language: Vec<Message>,
+    /// the records of the participants' voice and video calls, unscored, ordered by time,
+    /// carried over from `ParsedConversation::calls`
+    pub calls: Vec<Message>,
+    /// carried over from `ParsedConversation::title`, the plots are captioned with it
+    pub title: Option<String>,
+    /// carried over from `ParsedConversation::export_boundaries`
+    pub export_boundaries: ExportBoundaries,
+    /// carried over from `ParsedConversation::me`, asymmetric metrics are presented from their side
+    pub me: Option<ParticipantId>,
+    /// how to compute the confidence intervals of the summaries, see `AnalyzedConversation::set_bootstrap`
+    pub(crate) bootstrap: Option<Bootstrap>,
+    /// computed on first use, bootstrapping them can take a while
+    pub(crate) summaries: OnceLock<Vec<ParticipantSummary>>,
+    /// what the plots drawn so far left out, until taken by `AnalyzedConversation::take_plot_issues`
+    pub(crate) plot_issues: Mutex<Vec<PlotIssue>>,
 }
 
-#[derive(Clone, Debug, Copy, PartialEq)]
+#[derive(Clone, Debug, Copy, PartialEq, serde::Serialize)]
 pub struct Score {
     pub pos: f64,
     pub neu: f64,
@@ -16,37 +97,1234 @@ pub struct Score {
     pub compound: f64,
 }
 
+/// Which way a message leans, see `Score::classify`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+/// The compound scores separating positive, neutral, and negative messages, see `Thresholds::new`.
+///
+/// A compound score exactly at a threshold counts as neutral.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// compound scores above this are positive
+    pub positive: f64,
+    /// compound scores below this are negative
+    pub negative: f64,
+}
+
+impl Default for Thresholds {
+    /// The thresholds recommended by the authors of vader.
+    fn default() -> Self {
+        Self {
+            positive: 0.05,
+            negative: -0.05,
+        }
+    }
+}
+
+impl Thresholds {
+    /// Thresholds within the range of compound scores, [-1, 1], failing if either isn't or the negative one is above the
+    /// positive one. They can be equal, leaving only that one score neutral.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::analyzer::{Sentiment, Thresholds};
+    ///
+    /// let thresholds = Thresholds::new(0.0, 0.0)?;
+    /// assert_eq!(thresholds.classify(0.0), Sentiment::Neutral);
+    /// assert_eq!(thresholds.classify(0.001), Sentiment::Positive);
+    ///
+    /// assert!(Thresholds::new(-0.1, 0.1).is_err());
+    /// assert!(Thresholds::new(1.5, -0.05).is_err());
+    /// assert!(Thresholds::new(f64::NAN, -0.05).is_err());
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn new(positive: f64, negative: f64) -> Result<Self> {
+        for (name, threshold) in [("positive", positive), ("negative", negative)] {
+            if !(-1.0..=1.0).contains(&threshold) {
+                bail!("the {name} threshold should be a number from -1 to 1, got {threshold}");
+            }
+        }
+        if negative > positive {
+            bail!("the negative threshold ({negative}) should be at most the positive threshold ({positive})");
+        }
+        Ok(Self { positive, negative })
+    }
+
+    /// Classifies a compound score, or an aggregate of compound scores like a mean.
+    pub fn classify(&self, compound: f64) -> Sentiment {
+        if compound > self.positive {
+            Sentiment::Positive
+        } else if compound < self.negative {
+            Sentiment::Negative
+        } else {
+            Sentiment::Neutral
+        }
+    }
+}
+
+impl Score {
+    /// The score of a message with no sentiment whatsoever, also substituted for scores the analyzer failed to produce.
+    pub const NEUTRAL: Self = Self {
+        pos: 0.0,
+        neu: 1.0,
+        neg: 0.0,
+        compound: 0.0,
+    };
+
+    /// How far the positive, neutral, and negative proportions may sum from 1 (vader rounds them) and still be sensible.
+    const SUM_TOLERANCE: f64 = 0.01;
+
+    /// Converts the map returned by vader's `polarity_scores` into a score,
+    /// failing if any of the keys are missing or the values don't make sense together.
+    pub fn from_polarity_scores(scores: &HashMap<&str, f64>) -> Result<Self> {
+        let get = |key| {
+            scores
+                .get(key)
+                .copied()
+                .with_context(|| format!("analyzer returned no \"{key}\" score"))
+        };
+        let score = Self {
+            pos: get("pos")?,
+            neu: get("neu")?,
+            neg: get("neg")?,
+            compound: get("compound")?,
+        };
+
+        if ![score.pos, score.neu, score.neg]
+            .iter()
+            .all(|proportion| (0.0..=1.0).contains(proportion))
+        {
+            bail!("analyzer returned proportions outside of [0, 1]: {score:?}");
+        }
+        if !(-1.0..=1.0).contains(&score.compound) {
+            bail!("analyzer returned a compound score outside of [-1, 1]: {score:?}");
+        }
+        // vader returns all zeros for text with nothing to score
+        let sum = score.pos + score.neu + score.neg;
+        if sum != 0.0 && (sum - 1.0).abs() > Self::SUM_TOLERANCE {
+            bail!("analyzer returned proportions that sum to {sum}: {score:?}");
+        }
+
+        Ok(score)
+    }
+
+    /// Classifies the score by its compound value,
+    /// every feature that buckets messages by sentiment should go through this so they all agree.
+    pub fn classify(&self, thresholds: &Thresholds) -> Sentiment {
+        thresholds.classify(self.compound)
+    }
+
+    /// How far from the exact values of a perfectly neutral score a score may be and still count as one.
+    const NEUTRAL_EPSILON: f64 = 1e-9;
+
+    /// Whether this is (within floating point error) the score of a message with no sentiment whatsoever,
+    /// i.e. one where vader found nothing in its lexicon.
+    pub fn is_neutral(&self) -> bool {
+        self.neu >= 1.0 - Self::NEUTRAL_EPSILON
+            && self.pos.abs() < Self::NEUTRAL_EPSILON
+            && self.neg.abs() < Self::NEUTRAL_EPSILON
+            && self.compound.abs() < Self::NEUTRAL_EPSILON
+    }
+}
+
+/// Options controlling how a conversation is analyzed.
+///
+/// With `strip_urls_and_mentions`, a message is scored without its links and mentions, and one with nothing else in it
+/// isn't scored at all:
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     analyzer::{AnalyzeOptions, Analyzer},
+///     parser::ParsedConversation,
+/// };
+///
+/// let stripping = AnalyzeOptions {
+///     strip_urls_and_mentions: true,
+///     ..AnalyzeOptions::default()
+/// };
+/// let text = "@sam this is great https://www.instagram.com/reel/C1x2y3zAbCd/ https://www.instagram.com/p/Qw3rTy/";
+/// let plain = Analyzer::new(&AnalyzeOptions::default()).score_text(text)?;
+/// let stripped = Analyzer::new(&stripping).score_text(text)?;
+/// assert!(stripped.score.pos > plain.score.pos);
+/// assert!(stripped.score.neu < plain.score.neu);
+/// assert_eq!(stripped.tokens, 3);
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "@ana https://www.instagram.com/reel/C1x2y3zAbCd/"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "this is great @ben"}
+///   ]
+/// }"#;
+/// let analysis = ParsedConversation::from_bytes(export)?.analyze_with(&stripping);
+/// let ben = analysis.names.id("Ben").unwrap();
+/// assert!(analysis.analysis[&ben].is_empty());
+/// // it's kept as it was, like a message with only an attachment
+/// assert_eq!(analysis.attachment_only[0].text(), "@ana https://www.instagram.com/reel/C1x2y3zAbCd/");
+/// let ana = analysis.names.id("Ana").unwrap();
+/// assert_eq!(analysis.analysis[&ana][0].0.text(), "this is great @ben");
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// The messages are scored in parallel, on rayon's global thread pool unless `threads` is set, and scored the same on
+/// any number of threads:
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     analyzer::AnalyzeOptions,
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// let config = SyntheticConfig {
+///     participants: 4,
+///     messages: 3000,
+///     ..SyntheticConfig::default()
+/// };
+/// let conversation = synthetic::generate_conversation(&config);
+/// let on = |threads| conversation.analyze_with(&AnalyzeOptions { threads: Some(threads), ..AnalyzeOptions::default() });
+/// let (serial, parallel) = (on(1), on(4));
+/// for (participant, series) in &serial.analysis {
+///     let scores = |series: &[(_, _)]| series.iter().map(|(_, score)| *score).collect::<Vec<_>>();
+///     assert_eq!(scores(series), scores(&parallel.analysis[participant]));
+/// }
+/// ```
+///
+/// Messages in another language than english are detected (among `languages` and english), and with `english_only`
+/// left unscored, though still counted:
+///
+/// ```
+/// use igdm_sentiment_analysis::{analyzer::AnalyzeOptions, language::Language, parser::ParsedConversation};
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000180000, "content": "no puedo esperar a verte este fin de semana"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "te quiero mucho, eres la mejor amiga"},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "I can't wait to see you this weekend, it's going to be great"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "lol ok"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// let options = AnalyzeOptions {
+///     languages: vec!["spa".parse()?],
+///     english_only: true,
+///     ..AnalyzeOptions::default()
+/// };
+/// let analysis = conversation.analyze_with(&options);
+/// let (ana, ben) = (analysis.names.id("Ana").unwrap(), analysis.names.id("Ben").unwrap());
+/// assert_eq!(analysis.analysis[&ana].len(), 1);
+/// assert_eq!(analysis.analysis[&ben].len(), 1);
+/// assert_eq!(analysis.analysis[&ben][0].0.language, Some(Language::ENGLISH));
+/// assert_eq!(analysis.other_language.len(), 2);
+/// assert!(analysis.other_language.iter().all(|message| message.language.is_some_and(|l| l.code() == "spa")));
+/// assert_eq!(analysis.counted_messages(ana).count(), 2);
+/// assert_eq!(analysis.diagnostics.other_language["Ben"], 1);
+///
+/// // scored otherwise, with their languages
+/// let analysis = conversation.analyze_with(&AnalyzeOptions { english_only: false, ..options });
+/// assert_eq!(analysis.analysis[&ben].len(), 2);
+/// assert!(analysis.other_language.is_empty());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnalyzeOptions {
+    /// drop the content of each message as soon as it's been scored (overwriting it in memory first), and that of the
+    /// unscored ones once the analysis is done, along with the text they reply to and the captions and links they share,
+    /// keeping only their metadata and scores, see `AnalyzedConversation::content_dropped`
+    pub drop_content: bool,
+    /// how to classify scores as positive/neutral/negative, carried over to `AnalyzedConversation::thresholds`
+    pub thresholds: Thresholds,
+    /// a gap between consecutive messages at least this long starts a new session,
+    /// messages in different sessions are never treated as replies to each other
+    pub session_gap: TimeDelta,
+    /// messages with fewer tokens (words, emoji, URLs, and mentions, see `tokenizer`) than this are left unscored, out of the
+    /// participants' series, since vader's scores of one or two word messages (like "lol") are mostly noise, but they're
+    /// still counted in message volumes (see `AnalyzedConversation::too_short`), 0 scores every message with text, and 1
+    /// (the default) every message with at least a token, leaving out those with only punctuation or emoticons
+    pub min_tokens: usize,
+    /// how compound scores are aggregated (over days, smoothing windows, and in the summaries)
+    pub aggregate: AggregateStat,
+    /// how much messages flagged as likely quoted (see `quotes`) count in the summaries and daily aggregates
+    pub quotes: QuotePolicy,
+    /// how messages standing for a run of repeats collapsed into them (see `transform::CollapseRepeats`) count in message volumes
+    pub repeats: RepeatCount,
+    /// score messages without their URLs and @mentions (see `tokenizer::strip_urls_and_mentions`), which vader takes for
+    /// neutral words, leaving messages with nothing else unscored like those with only an attachment
+    pub strip_urls_and_mentions: bool,
+    /// how many threads to score the messages on, rather than rayon's global thread pool (`None`), which doesn't change
+    /// any score
+    pub threads: Option<usize>,
+    /// words added to vader's lexicon, see `lexicon`
+    pub lexicon: Lexicon,
+    /// the languages the conversation is in besides english, which the languages of the messages are detected among,
+    /// every language there is if empty, see `language`
+    pub languages: Vec<Language>,
+    /// leave the messages detected to be in another language than english unscored, out of the participants' series,
+    /// since vader only knows english, they're still counted in message volumes (see `AnalyzedConversation::other_language`)
+    pub english_only: bool,
+}
+
+impl Default for AnalyzeOptions {
+    fn default() -> Self {
+        Self {
+            drop_content: false,
+            thresholds: Thresholds::default(),
+            session_gap: TimeDelta::hours(6),
+            min_tokens: 1,
+            aggregate: AggregateStat::default(),
+            quotes: QuotePolicy::default(),
+            repeats: RepeatCount::default(),
+            strip_urls_and_mentions: false,
+            threads: None,
+            lexicon: Lexicon::default(),
+            languages: Vec::new(),
+            english_only: false,
+        }
+    }
+}
+
+/// What scores the text of the messages, vader by default (see `Vader`).
+///
+/// A backend scoring every text the same, for looking at the rest of an analysis without vader's scores:
+///
+/// ```
+/// use anyhow::Result;
+/// use igdm_sentiment_analysis::{
+///     analyzer::{AnalyzeOptions, Score, SentimentBackend},
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// struct Constant(Score);
+///
+/// impl SentimentBackend for Constant {
+///     fn name(&self) -> &str {
+///         "constant"
+///     }
+///
+///     fn score(&self, _: &str) -> Result<Score> {
+///         Ok(self.0)
+///     }
+/// }
+///
+/// let pleased = Score { pos: 1.0, neu: 0.0, neg: 0.0, compound: 0.5 };
+/// let conversation = synthetic::generate_conversation(&SyntheticConfig { messages: 200, ..SyntheticConfig::default() });
+/// // scoring every message, even those without a token
+/// let options = AnalyzeOptions { min_tokens: 0, ..AnalyzeOptions::default() };
+/// let analysis = conversation.analyze_with_backend(&options, &Constant(pleased));
+/// let scores = analysis.analysis.values().flatten().map(|(_, score)| *score).collect::<Vec<_>>();
+/// assert_eq!(scores.len(), 200);
+/// assert!(scores.iter().all(|score| *score == pleased));
+/// // analyses scored by different backends aren't comparable
+/// assert_ne!(analysis.fingerprint.settings, conversation.analyze_with(&options).fingerprint.settings);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub trait SentimentBackend: Send + Sync {
+    /// a name for the backend, part of the fingerprint of the analyses it scored (see `drift::Fingerprint`)
+    fn name(&self) -> &str;
+
+    /// Scores the text, failing if it can't, which the analysis reports and substitutes a neutral score for.
+    fn score(&self, text: &str) -> Result<Score>;
+
+    /// Scores a handful of sentences with known sentiment, to detect a backend that doesn't behave as expected before
+    /// a long run rather than midway through it. `Pipeline::run` calls it on the backend it scores with, a backend
+    /// that can't tell those sentences apart (e.g. one scoring everything the same for tests) can override it.
+    fn self_test(&self) -> Result<()> {
+        let cases = [
+            ("I love this, it is great!", Sentiment::Positive),
+            ("I hate this, it is terrible.", Sentiment::Negative),
+            ("The table is in the kitchen.", Sentiment::Neutral),
+        ];
+        for (text, expected) in cases {
+            let score = self
+                .score(text)
+                .with_context(|| format!("{} self-test failed on \"{text}\"", self.name()))?;
+            let sentiment = score.classify(&Thresholds::default());
+            if sentiment != expected {
+                bail!(
+                    "{} self-test failed: \"{text}\" should be {expected:?}, but was scored {sentiment:?} ({score:?})",
+                    self.name()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: SentimentBackend + ?Sized> SentimentBackend for &T {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn score(&self, text: &str) -> Result<Score> {
+        (**self).score(text)
+    }
+
+    fn self_test(&self) -> Result<()> {
+        (**self).self_test()
+    }
+}
+
+/// The vader sentiment analyzer, the default backend, scoring emoji (see `emoji`) and the words of a `Lexicon` too.
+pub struct Vader {
+    /// vader's lexicon with the added words
+    lexicon: HashMap<UniCase<&'static str>, f64>,
+}
+
+impl Vader {
+    /// The name of vader, see `SentimentBackend::name`.
+    pub const NAME: &'static str = "vader";
+
+    /// Vader, with the words of the lexicon added to its own.
+    pub fn with_lexicon(lexicon: &Lexicon) -> Self {
+        Self {
+            lexicon: lexicon.merged(),
+        }
+    }
+}
+
+impl Default for Vader {
+    fn default() -> Self {
+        Self::with_lexicon(&Lexicon::default())
+    }
+}
+
+impl SentimentBackend for Vader {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn score(&self, text: &str) -> Result<Score> {
+        let analyzer = SentimentIntensityAnalyzer::from_lexicon(&self.lexicon);
+        Score::from_polarity_scores(&analyzer.polarity_scores(&emoji::replace(text)))
+    }
+}
+
+/// The scoring half of a configured analysis, for scoring text the same way a conversation's messages are scored.
+pub struct Analyzer<'a> {
+    backend: Box<dyn SentimentBackend + 'a>,
+    thresholds: Thresholds,
+    min_tokens: usize,
+    strip_urls_and_mentions: bool,
+    detector: LanguageDetector,
+    english_only: bool,
+    raw_text: bool,
+}
+
+/// Ad hoc text scored by `Analyzer::score_text`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct ScoredText {
+    pub score: Score,
+    pub sentiment: Sentiment,
+    /// how many tokens (see `tokenizer`) the text has
+    pub tokens: usize,
+    /// the language the text was detected to be in (see `language`), `None` if it couldn't be told confidently
+    pub language: Option<Language>,
+    /// how confident the detection of the language is, from `language::MIN_CONFIDENCE` to 1
+    pub language_confidence: Option<f64>,
+    /// false if the text had fewer than `AnalyzeOptions::min_tokens` tokens, or was in another language than english
+    /// with `AnalyzeOptions::english_only`, and was given a neutral score instead
+    pub scored: bool,
+}
+
+impl Analyzer<'static> {
+    /// An analyzer scoring text with vader, with the words of `AnalyzeOptions::lexicon` added to its lexicon.
+    pub fn new(options: &AnalyzeOptions) -> Self {
+        Self::with_backend(options, Vader::with_lexicon(&options.lexicon))
+    }
+}
+
+impl<'a> Analyzer<'a> {
+    /// An analyzer scoring text with `backend` rather than vader.
+    pub fn with_backend(options: &AnalyzeOptions, backend: impl SentimentBackend + 'a) -> Self {
+        Self {
+            backend: Box::new(backend),
+            thresholds: options.thresholds,
+            min_tokens: options.min_tokens,
+            strip_urls_and_mentions: options.strip_urls_and_mentions,
+            detector: LanguageDetector::new(&options.languages),
+            english_only: options.english_only,
+            raw_text: false,
+        }
+    }
+
+    /// Scores ad hoc text as exported (see `Analyzer::score_text`), like messages parsed without `transform::CleanText`.
+    #[must_use]
+    pub fn raw_text(mut self, raw_text: bool) -> Self {
+        self.raw_text = raw_text;
+        self
+    }
+
+    /// The part of the text that's scored, see `AnalyzeOptions::strip_urls_and_mentions`.
+    pub(crate) fn scored_text<'t>(&self, text: &'t str) -> Cow<'t, str> {
+        if self.strip_urls_and_mentions {
+            Cow::Owned(tokenizer::strip_urls_and_mentions(text))
+        } else {
+            Cow::Borrowed(text)
+        }
+    }
+
+    /// Whether the text has too few tokens to be scored, see `AnalyzeOptions::min_tokens`.
+    pub(crate) fn too_short(&self, text: &str) -> bool {
+        self.min_tokens > 0
+            && Tokenizer::default()
+                .tokens(text)
+                .nth(self.min_tokens - 1)
+                .is_none()
+    }
+
+    /// Scores the text exactly as a message with that text would be scored in an analysis with the same options,
+    /// failing where the analysis would substitute a neutral score (and report it in its diagnostics), and neutral if
+    /// it's too short to be scored, where the analysis leaves it unscored.
+    pub fn score(&self, text: &str) -> Result<Score> {
+        self.score_scored_text(&self.scored_text(text))
+    }
+
+    /// Scores the part of a text that's scored, see `Analyzer::scored_text`.
+    fn score_scored_text(&self, text: &str) -> Result<Score> {
+        if self.too_short(text) {
+            return Ok(Score::NEUTRAL);
+        }
+        self.backend.score(text)
+    }
+
+    /// The language the text is detected to be in, and whether that leaves it unscored, see
+    /// `AnalyzeOptions::english_only`.
+    pub(crate) fn detect(&self, text: &str) -> (Option<(Language, f64)>, bool) {
+        let detected = self.detector.detect_with_confidence(text);
+        let other = detected.is_some_and(|(language, _)| language != Language::ENGLISH);
+        (detected, self.english_only && other)
+    }
+
+    /// Scores and classifies ad hoc text exactly as the analysis would a message with that text: cleaned as it's parsed
+    /// (see `text::clean`, unless `Analyzer::raw_text`), then left unscored (neutral) if it's too short or in another
+    /// language with `AnalyzeOptions::english_only`, counting the tokens of the part of it that's scored.
+    pub fn score_text(&self, text: &str) -> Result<ScoredText> {
+        let text = if self.raw_text {
+            Cow::Borrowed(text)
+        } else {
+            Cow::Owned(text::clean(text))
+        };
+        let text = self.scored_text(&text);
+        let (detected, other_language) = self.detect(&text);
+        let scored = !other_language && !self.too_short(&text);
+        let score = if scored {
+            self.backend.score(&text)?
+        } else {
+            Score::NEUTRAL
+        };
+        Ok(ScoredText {
+            score,
+            sentiment: score.classify(&self.thresholds),
+            tokens: Tokenizer::default().tokens(&text).count(),
+            language: detected.map(|(language, _)| language),
+            language_confidence: detected.map(|(_, confidence)| confidence),
+            scored,
+        })
+    }
+}
+
+/// A message, and the reply from another participant that came right after it in the same session,
+/// see `AnalyzedConversation::reply_pairs`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplyPair {
+    pub stimulus_sender: ParticipantId,
+    pub stimulus_timestamp_ms: usize,
+    pub stimulus_compound: f64,
+    pub responder: ParticipantId,
+    pub response_timestamp_ms: usize,
+    pub response_compound: f64,
+}
+
+/// Drops the content of the message (and of the message it replies to, and the caption and link of what it shares),
+/// overwriting it in memory first, see `AnalyzeOptions::drop_content`.
+fn drop_content(message: &mut Message) {
+    let reply = message
+        .reply_to
+        .as_mut()
+        .and_then(|reply| reply.text.take());
+    let (caption, link) = match &mut message.share {
+        Some(share) => (share.text.take(), share.link.take()),
+        None => (None, None),
+    };
+    for mut content in message
+        .content
+        .take()
+        .into_iter()
+        .chain(reply)
+        .chain(caption)
+        .chain(link)
+    {
+        content.zeroize();
+    }
+}
+
+/// How many messages are scored in parallel at a time, see `AnalyzedConversation::from_conversation`.
+const SCORED_CHUNK: usize = 4096;
+
 impl ParsedConversation {
+    /// Analyzes the conversation with the default options. Each message is scored once, in a single pass over the
+    /// messages, into its sender's series, which keeps the order of the conversation.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{
+    ///     analyzer::{AnalyzeOptions, Analyzer},
+    ///     synthetic::{self, SyntheticConfig},
+    /// };
+    ///
+    /// let config = SyntheticConfig {
+    ///     participants: 6,
+    ///     messages: 600,
+    ///     ..SyntheticConfig::default()
+    /// };
+    /// let conversation = synthetic::generate_conversation(&config);
+    /// let analysis = conversation.analyze();
+    /// let analyzer = Analyzer::new(&AnalyzeOptions::default());
+    ///
+    /// assert_eq!(analysis.analysis.len(), 6);
+    /// for participant in &conversation.participants {
+    ///     // the same as going over the messages once for each participant, but for those without a token
+    ///     let sent = conversation.messages.iter().filter(|message| {
+    ///         message.sender == *participant && !analysis.too_short.contains(message)
+    ///     });
+    ///     let series = &analysis.analysis[participant];
+    ///     assert_eq!(series.len(), sent.clone().count());
+    ///     for ((message, score), sent) in series.iter().zip(sent) {
+    ///         assert_eq!((message.timestamp, message.text()), (sent.timestamp, sent.text()));
+    ///         assert_eq!(score.compound, analyzer.score(sent.text())?.compound);
+    ///     }
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
     pub fn analyze(&self) -> AnalyzedConversation {
-        let analyzer = SentimentIntensityAnalyzer::new();
+        self.analyze_with(&AnalyzeOptions::default())
+    }
 
-        let analysis = self
-            .participants
+    /// Analyzes the conversation without consuming it, so it can be analyzed again with different options.
+    pub fn analyze_with(&self, options: &AnalyzeOptions) -> AnalyzedConversation {
+        self.clone().into_analyzed(options)
+    }
+
+    /// `ParsedConversation::analyze_with`, scoring the messages with `backend` rather than vader, see `SentimentBackend`.
+    pub fn analyze_with_backend(
+        &self,
+        options: &AnalyzeOptions,
+        backend: &impl SentimentBackend,
+    ) -> AnalyzedConversation {
+        let analyzer = Analyzer::with_backend(options, backend);
+        AnalyzedConversation::from_conversation(self.clone(), options, analyzer, &mut |_| {})
+    }
+
+    /// Analyzes the conversation, consuming it so messages are moved into the analysis rather than copied.
+    pub fn into_analyzed(self, options: &AnalyzeOptions) -> AnalyzedConversation {
+        self.into_analyzed_with_progress(options, |_| {})
+    }
+
+    /// `ParsedConversation::into_analyzed`, reporting to `progress` as each message is scored, see `progress`.
+    pub fn into_analyzed_with_progress(
+        self,
+        options: &AnalyzeOptions,
+        mut progress: impl FnMut(Progress),
+    ) -> AnalyzedConversation {
+        AnalyzedConversation::from_conversation(
+            self,
+            options,
+            Analyzer::new(options),
+            &mut progress,
+        )
+    }
+
+    /// `ParsedConversation::into_analyzed_with_progress`, scoring the messages with `backend` rather than vader, see
+    /// `SentimentBackend`.
+    pub fn into_analyzed_with_backend(
+        self,
+        options: &AnalyzeOptions,
+        backend: impl SentimentBackend,
+        mut progress: impl FnMut(Progress),
+    ) -> AnalyzedConversation {
+        let analyzer = Analyzer::with_backend(options, backend);
+        AnalyzedConversation::from_conversation(self, options, analyzer, &mut progress)
+    }
+}
+
+impl AnalyzedConversation {
+    fn from_conversation(
+        conversation: ParsedConversation,
+        options: &AnalyzeOptions,
+        analyzer: Analyzer,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Self {
+        let ParsedConversation {
+            participants,
+            messages,
+            names,
+            mut diagnostics,
+            export_boundaries,
+            me,
+            attachment_only,
+            calls,
+            system: _,
+            title,
+            thread_path: _,
+        } = conversation;
+
+        // every participant gets a series, even if they never sent anything
+        let mut analysis: HashMap<ParticipantId, Vec<(Message, Score)>> = participants
             .iter()
-            .map(|participant| {
-                (
-                    participant.clone(),
-                    self.messages
-                        .iter()
-                        .filter(|message| message.sender_name == participant.name)
-                        .map(|message| {
-                            let scores = analyzer.polarity_scores(&message.content);
-
-                            (
-                                message.clone(),
-                                Score {
-                                    pos: *scores.get("pos").unwrap(),
-                                    neu: *scores.get("neu").unwrap(),
-                                    neg: *scores.get("neg").unwrap(),
-                                    compound: *scores.get("compound").unwrap(),
-                                },
-                            )
+            .map(|participant| (*participant, Vec::new()))
+            .collect();
+
+        let mut register = RegisterBuilder::default();
+        let mut quotes = QuoteDetector::default();
+        let mut openers = OpenerBuilder::new(options.session_gap);
+        // messages with nothing but links and mentions have nothing to score once they're stripped, like those with
+        // only an attachment
+        let (messages, attachment_only) = if options.strip_urls_and_mentions {
+            let (link_only, messages) = messages.into_iter().partition::<Vec<_>, _>(|message| {
+                tokenizer::strip_urls_and_mentions(message.text()).is_empty()
+            });
+            let mut attachment_only = attachment_only;
+            attachment_only.extend(link_only);
+            attachment_only.sort_by_key(|message| message.timestamp);
+            (messages, attachment_only)
+        } else {
+            (messages, attachment_only)
+        };
+        // messages too short to be scored are left out of the series, but not out of the message volumes
+        let (too_short, messages) = if options.min_tokens > 0 {
+            messages.into_iter().partition::<Vec<_>, _>(|message| {
+                analyzer.too_short(&analyzer.scored_text(message.text()))
+            })
+        } else {
+            (Vec::new(), messages)
+        };
+        // unscored, but they still open sessions, so they're fed to the openers in order with the scored messages
+        let listed = |messages: Vec<Message>| {
+            messages
+                .into_iter()
+                .filter(|message| participants.contains(&message.sender))
+                .collect::<Vec<_>>()
+        };
+        let (mut attachment_only, mut too_short) = (listed(attachment_only), listed(too_short));
+        let mut unscored = attachment_only.iter().chain(&too_short).collect::<Vec<_>>();
+        unscored.sort_by_key(|message| message.timestamp);
+        let mut unscored = unscored.into_iter().peekable();
+
+        // score each message once, and put it in its sender's series, the messages are scored in parallel a chunk at a
+        // time (so progress is still reported as they're scored), then taken in order
+        let pool = options.threads.and_then(|threads| {
+            // on rayon's global thread pool if one can't be started
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .ok()
+        });
+        let mut other_language = Vec::new();
+        let total = messages.len();
+        let mut messages = messages.into_iter().peekable();
+        let mut done = 0;
+        while messages.peek().is_some() {
+            let chunk = messages.by_ref().take(SCORED_CHUNK).collect::<Vec<_>>();
+            let score_chunk = || {
+                chunk
+                    .par_iter()
+                    .map(|message| {
+                        analysis.contains_key(&message.sender).then(|| {
+                            let (detected, other_language) =
+                                analyzer.detect(&analyzer.scored_text(message.text()));
+                            let score = (!other_language).then(|| analyzer.score(message.text()));
+                            (detected.map(|(language, _)| language), score)
                         })
-                        .collect::<Vec<_>>(),
-                )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let scores = match &pool {
+                Some(pool) => pool.install(score_chunk),
+                None => score_chunk(),
+            };
+            for (mut message, score) in chunk.into_iter().zip(scores) {
+                progress(Progress {
+                    phase: Phase::Analyze,
+                    done,
+                    total,
+                });
+                done += 1;
+                let (Some(series), Some((language, score))) =
+                    (analysis.get_mut(&message.sender), score)
+                else {
+                    *diagnostics
+                        .unmatched_senders
+                        .entry(names.name(message.sender).to_string())
+                        .or_default() += 1;
+                    continue;
+                };
+                message.language = language;
+                while let Some(unscored) =
+                    unscored.next_if(|unscored| unscored.timestamp <= message.timestamp)
+                {
+                    openers.add(unscored, None);
+                }
+                let Some(score) = score else {
+                    // in another language, unscored like a message too short to be scored
+                    *diagnostics
+                        .other_language
+                        .entry(names.name(message.sender).to_string())
+                        .or_default() += 1;
+                    openers.add(&message, None);
+                    if options.drop_content {
+                        drop_content(&mut message);
+                    }
+                    other_language.push(message);
+                    continue;
+                };
+
+                let score = score.unwrap_or_else(|error| {
+                    // don't abort the whole run over one message, substitute a neutral score and report it
+                    diagnostics.malformed_scores.push(MalformedScore {
+                        sender: names.name(message.sender).to_string(),
+                        timestamp_ms: message.timestamp_ms(),
+                        reason: error.to_string(),
+                    });
+                    Score::NEUTRAL
+                });
+                openers.add(&message, Some(score.compound));
+                register.add(&message);
+                message.quote = quotes.check(&message);
+                if options.drop_content {
+                    drop_content(&mut message);
+                }
+                series.push((message, score));
+            }
+        }
+        for unscored in unscored {
+            openers.add(unscored, None);
+        }
+        let mut calls = calls
+            .into_iter()
+            .filter(|call| participants.contains(&call.sender))
+            .collect::<Vec<_>>();
+        if options.drop_content {
+            // the unscored messages are kept too, so their text has to go as well
+            for message in attachment_only
+                .iter_mut()
+                .chain(&mut too_short)
+                .chain(&mut calls)
+            {
+                drop_content(message);
+            }
+        }
+        progress(Progress {
+            phase: Phase::Analyze,
+            done: total,
+            total,
+        });
+
+        Self {
+            analysis,
+            names,
+            diagnostics,
+            content_dropped: options.drop_content,
+            fingerprint: Fingerprint::scored_by(options, analyzer.backend.name()),
+            thresholds: options.thresholds,
+            aggregate: options.aggregate,
+            quotes: options.quotes,
+            repeats: options.repeats,
+            session_gap: options.session_gap,
+            register: register.build(),
+            openers: openers.build(),
+            events: Vec::new(),
+            palette: Palette::default(),
+            time_zone: Tz::UTC,
+            bootstrap: None,
+            summaries: OnceLock::new(),
+            plot_issues: Mutex::default(),
+            attachment_only,
+            too_short,
+            other_language,
+            calls,
+            title,
+            export_boundaries,
+            me,
+        }
+    }
+
+    /// Takes what the plots drawn since the last call left out (see `Diagnostics::plot_issues`), plots are drawn through `&self`
+    /// so they can't add it to `diagnostics` themselves.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::synthetic::{self, SyntheticConfig};
+    ///
+    /// let config = SyntheticConfig {
+    ///     messages: 200,
+    ///     ..SyntheticConfig::default()
+    /// };
+    /// let mut analysis = synthetic::generate_conversation(&config).analyze();
+    /// // a score plotters can't draw
+    /// let series = analysis.analysis.values_mut().next().unwrap();
+    /// series[0].1.compound = f64::NAN;
+    ///
+    /// #[cfg(feature = "plot")]
+    /// {
+    ///     use igdm_sentiment_analysis::plotter::PlotType;
+    ///
+    ///     // the plot is still drawn, with everything but that point
+    ///     analysis.plot_png(PlotType::Compound)?;
+    ///     let issues = analysis.take_plot_issues();
+    ///     assert_eq!(issues.len(), 1);
+    ///     assert_eq!(issues[0].index, Some(0));
+    ///     assert!(analysis.take_plot_issues().is_empty());
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn take_plot_issues(&self) -> Vec<PlotIssue> {
+        std::mem::take(
+            &mut self
+                .plot_issues
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        )
+    }
+
+    /// The timestamps of the first and last scored messages, `None` if there are none.
+    pub fn time_range(&self) -> Option<(usize, usize)> {
+        let timestamps = || {
+            self.analysis
+                .values()
+                .flat_map(|series| series.iter().map(|(message, _)| message.timestamp_ms()))
+        };
+        Some((timestamps().min()?, timestamps().max()?))
+    }
+
+    /// The messages of the participant counted in message volumes (the message counts of the summaries, the volume plot,
+    /// and the bursts), those that were scored and those too short to be (see `AnalyzeOptions::min_tokens`) or in another
+    /// language (see `AnalyzeOptions::english_only`), unordered.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{analyzer::AnalyzeOptions, parser::ParsedConversation};
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000180000, "content": "that sounds wonderful, glad you enjoyed it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "lol"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "ok"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "I had such a lovely day at the beach"}
+    ///   ]
+    /// }"#;
+    /// let conversation = ParsedConversation::from_bytes(export)?;
+    /// let (ana, ben) = (conversation.names.id("Ana").unwrap(), conversation.names.id("Ben").unwrap());
+    ///
+    /// let every = conversation.analyze();
+    /// assert_eq!((every.analysis[&ana].len(), every.analysis[&ben].len()), (3, 1));
+    ///
+    /// let filtered = conversation.analyze_with(&AnalyzeOptions {
+    ///     min_tokens: 3,
+    ///     ..AnalyzeOptions::default()
+    /// });
+    /// assert_eq!((filtered.analysis[&ana].len(), filtered.analysis[&ben].len()), (1, 1));
+    /// assert_eq!(filtered.too_short.iter().map(|message| message.text()).collect::<Vec<_>>(), ["ok", "lol"]);
+    /// // the short messages aren't scored, but they're still messages
+    /// assert_eq!(filtered.counted_messages(ana).count(), 3);
+    /// assert_eq!(filtered.summaries()[0].messages, every.summaries()[0].messages);
+    /// assert!(filtered.summaries()[0].compound.unwrap().value > every.summaries()[0].compound.unwrap().value);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn counted_messages(&self, participant: ParticipantId) -> impl Iterator<Item = &Message> {
+        let scored = self.analysis.get(&participant).into_iter().flatten();
+        scored.map(|(message, _)| message).chain(
+            self.too_short
+                .iter()
+                .chain(&self.other_language)
+                .filter(move |message| message.sender == participant),
+        )
+    }
+
+    /// Every scored message, from every participant, in the order they were sent
+    /// (messages sent at the same millisecond are ordered by sender, so the order is deterministic).
+    pub fn timeline(&self) -> Vec<(&Message, &Score)> {
+        let mut timeline = self
+            .analysis
+            .values()
+            .flat_map(|series| series.iter().map(|(message, score)| (message, score)))
+            .collect::<Vec<_>>();
+        timeline.sort_by_key(|(message, _)| (message.timestamp, message.sender));
+        timeline
+    }
+
+    /// Pairs every message that got a reply with that reply.
+    ///
+    /// A reply is the next message in the conversation if it's from someone else and was sent within
+    /// `session_gap` of the message, so pairs never span a session boundary. When someone double texts,
+    /// only their last message before the reply is paired with it, and messages after the first one of
+    /// a reply are not replies themselves.
+    pub fn reply_pairs(&self) -> Vec<ReplyPair> {
+        self.timeline()
+            .windows(2)
+            .filter_map(|window| {
+                let [(stimulus, stimulus_score), (response, response_score)] = window else {
+                    return None;
+                };
+                if stimulus.sender == response.sender
+                    || response.timestamp - stimulus.timestamp >= self.session_gap
+                {
+                    return None;
+                }
+                Some(ReplyPair {
+                    stimulus_sender: stimulus.sender,
+                    stimulus_timestamp_ms: stimulus.timestamp_ms(),
+                    stimulus_compound: stimulus_score.compound,
+                    responder: response.sender,
+                    response_timestamp_ms: response.timestamp_ms(),
+                    response_compound: response_score.compound,
+                })
             })
-            .collect();
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A backend returning the score maps a misbehaving analyzer might, picked by the text.
+    struct Malformed;
+
+    impl SentimentBackend for Malformed {
+        fn name(&self) -> &str {
+            "malformed"
+        }
+
+        fn score(&self, text: &str) -> Result<Score> {
+            let mut scores =
+                HashMap::from([("pos", 0.5), ("neu", 0.5), ("neg", 0.0), ("compound", 0.6)]);
+            match text {
+                "missing key" => {
+                    scores.remove("compound");
+                }
+                "not a number" => {
+                    scores.insert("compound", f64::NAN);
+                }
+                "not a proportion" => {
+                    scores.insert("neg", f64::NAN);
+                }
+                _ => {}
+            }
+            Score::from_polarity_scores(&scores)
+        }
+    }
+
+    #[test]
+    fn malformed_scores_are_neutral_and_reported() {
+        let export = br#"{
+          "participants": [{"name": "Ana"}],
+          "messages": [
+            {"sender_name": "Ana", "timestamp_ms": 1700000300000, "content": "not a proportion"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000200000, "content": "not a number"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000100000, "content": "missing key"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "well formed"}
+          ]
+        }"#;
+        let conversation = ParsedConversation::from_bytes(export).unwrap();
+        let analysis = conversation.analyze_with_backend(&AnalyzeOptions::default(), &Malformed);
+        let ana = analysis.names.id("Ana").unwrap();
+
+        let scores = analysis.analysis[&ana]
+            .iter()
+            .map(|(message, score)| (message.text(), *score))
+            .collect::<Vec<_>>();
+        assert_eq!(scores.len(), 4);
+        assert_eq!(scores[0].0, "well formed");
+        assert_eq!(scores[0].1.compound, 0.6);
+        assert!(scores[1..]
+            .iter()
+            .all(|(_, score)| *score == Score::NEUTRAL));
+
+        let malformed = &analysis.diagnostics.malformed_scores;
+        let reported = malformed
+            .iter()
+            .map(|malformed| (malformed.timestamp_ms, malformed.reason.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(reported.len(), 3);
+        assert!(malformed.iter().all(|malformed| malformed.sender == "Ana"));
+        assert_eq!(reported[0].0, 1700000100000);
+        assert!(reported[0].1.contains(r#"no "compound" score"#));
+        assert_eq!(reported[1].0, 1700000200000);
+        assert!(reported[1].1.contains("compound score outside of [-1, 1]"));
+        assert_eq!(reported[2].0, 1700000300000);
+        assert!(reported[2].1.contains("proportions outside of [0, 1]"));
+        assert!(!analysis.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn score_text_agrees_with_the_analysis() {
+        let texts = [
+            "this is gr\u{200B}eat",
+            "te quiero mucho, eres la mejor amiga",
+            "I can't wait to see you this weekend, it's going to be great",
+        ];
+        let messages = texts
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, text)| {
+                let sender = ["Ana", "Ben"][i % 2];
+                serde_json::json!({"sender_name": sender, "timestamp_ms": 1700000000000u64 + i as u64 * 60000, "content": text})
+            })
+            .collect::<Vec<_>>();
+        let export = serde_json::json!({
+            "participants": [{"name": "Ana"}, {"name": "Ben"}],
+            "messages": messages,
+        });
+        let conversation = ParsedConversation::from_bytes(export.to_string().as_bytes()).unwrap();
+        let options = AnalyzeOptions {
+            languages: vec!["spa".parse().unwrap()],
+            english_only: true,
+            ..AnalyzeOptions::default()
+        };
+        let analysis = conversation.analyze_with(&options);
+        let analyzer = Analyzer::new(&options);
+
+        let scored = |timestamp_ms: usize| {
+            analysis
+                .analysis
+                .values()
+                .flatten()
+                .find(|(message, _)| message.timestamp_ms() == timestamp_ms)
+                .map(|(_, score)| *score)
+        };
+        // the zero-width space is removed before scoring, as it is when the message is parsed
+        let great = analyzer.score_text(texts[0]).unwrap();
+        assert!(great.scored);
+        assert_eq!(Some(great.score), scored(1700000000000));
+        assert!(great.score.compound > 0.5);
+        let raw = Analyzer::new(&options)
+            .raw_text(true)
+            .score_text(texts[0])
+            .unwrap();
+        assert_ne!(raw.score, great.score);
+
+        // in spanish, so left unscored like the message
+        let spanish = analyzer.score_text(texts[1]).unwrap();
+        assert_eq!(scored(1700000060000), None);
+        assert_eq!(analysis.other_language.len(), 1);
+        assert!(!spanish.scored);
+        assert_eq!(spanish.score, Score::NEUTRAL);
+        assert_eq!(spanish.language, analysis.other_language[0].language);
+        assert!(spanish.language_confidence.unwrap() >= crate::language::MIN_CONFIDENCE);
+
+        let english = analyzer.score_text(texts[2]).unwrap();
+        assert_eq!(english.language, Some(Language::ENGLISH));
+        assert_eq!(Some(english.score), scored(1700000120000));
+    }
+
+    #[test]
+    fn min_tokens_counts_tokens_from_one_and_is_off_at_zero() {
+        let export = br#"{
+          "participants": [{"name": "Ana"}],
+          "messages": [
+            {"sender_name": "Ana", "timestamp_ms": 1700000180000, "content": "so good"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "?!"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": ":)"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "lol"}
+          ]
+        }"#;
+        let conversation = ParsedConversation::from_bytes(export).unwrap();
+        let with = |min_tokens| {
+            conversation.analyze_with(&AnalyzeOptions {
+                min_tokens,
+                ..AnalyzeOptions::default()
+            })
+        };
+        let texts = |messages: &[Message]| {
+            messages
+                .iter()
+                .map(|message| message.text().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(AnalyzeOptions::default().min_tokens, 1);
+
+        // with it off, even the emoticon, which has no tokens, is scored
+        let off = with(0);
+        let ana = off.names.id("Ana").unwrap();
+        assert_eq!(off.analysis[&ana].len(), 4);
+        assert!(off.too_short.is_empty());
+        assert!(off.analysis[&ana][1].1.compound > 0.0);
+
+        // one token is one token: the messages with none are left unscored
+        let one = with(1);
+        assert_eq!(texts(&one.too_short), [":)", "?!"]);
+        assert_eq!(one.analysis[&ana].len(), 2);
+        assert_ne!(one.fingerprint, off.fingerprint);
+
+        let two = with(2);
+        assert_eq!(texts(&two.too_short), ["lol", ":)", "?!"]);
+        assert_ne!(two.fingerprint, one.fingerprint);
+    }
+
+    #[test]
+    fn neutral_scores_are_told_within_floating_point_error() {
+        let score = |pos, neu, neg, compound| Score {
+            pos,
+            neu,
+            neg,
+            compound,
+        };
+        assert!(Score::NEUTRAL.is_neutral());
+        // just inside the tolerance
+        assert!(score(0.0, 1.0, 0.0, 1e-17).is_neutral());
+        assert!(score(0.0, 1.0, 0.0, -1e-10).is_neutral());
+        assert!(score(0.0, 1.0 - 1e-10, 0.0, 0.0).is_neutral());
+        assert!(score(1e-10, 1.0 - 1e-10, 0.0, 0.0).is_neutral());
+        // just outside it
+        assert!(!score(0.0, 1.0, 0.0, 1e-6).is_neutral());
+        assert!(!score(0.0, 1.0, 0.0, -1e-8).is_neutral());
+        assert!(!score(0.0, 1.0 - 1e-8, 0.0, 0.0).is_neutral());
+        assert!(!score(0.0, 1.0 - 1e-8, 1e-8, 0.0).is_neutral());
+    }
+
+    #[test]
+    fn scores_at_a_threshold_are_neutral() {
+        let thresholds = Thresholds::default();
+        assert_eq!(thresholds.classify(0.05), Sentiment::Neutral);
+        assert_eq!(thresholds.classify(-0.05), Sentiment::Neutral);
+        assert_eq!(thresholds.classify(0.0500001), Sentiment::Positive);
+        assert_eq!(thresholds.classify(-0.0500001), Sentiment::Negative);
+
+        // at the ends of the range, nothing is beyond them
+        let thresholds = Thresholds::new(1.0, -1.0).unwrap();
+        assert_eq!(thresholds.classify(1.0), Sentiment::Neutral);
+        assert_eq!(thresholds.classify(-1.0), Sentiment::Neutral);
+
+        // the same applies to a message's score
+        let score = |compound| Score {
+            compound,
+            ..Score::NEUTRAL
+        };
+        let thresholds = Thresholds::new(0.3, -0.2).unwrap();
+        assert_eq!(score(0.3).classify(&thresholds), Sentiment::Neutral);
+        assert_eq!(score(-0.2).classify(&thresholds), Sentiment::Neutral);
+    }
 
-        AnalyzedConversation { analysis }
+    #[test]
+    fn invalid_thresholds_are_rejected() {
+        for (positive, negative) in [
+            (-0.05, 0.05),
+            (0.05, f64::NAN),
+            (f64::NAN, -0.05),
+            (1.01, -0.05),
+            (0.05, -1.01),
+            (f64::INFINITY, -0.05),
+        ] {
+            assert!(
+                Thresholds::new(positive, negative).is_err(),
+                "{positive}, {negative}"
+            );
+        }
+        assert!(Thresholds::new(0.0, 0.0).is_ok());
+        assert!(Thresholds::new(1.0, -1.0).is_ok());
     }
 }
@@ -0,0 +1,203 @@
+//! Module containing the analysis of how quickly the conversation cools off after a burst of messages:
+//! an exponential decay fitted to each participant's daily message counts after every burst, summarized as a half-life.
+//!
+//! Days are UTC days. A burst is a run of consecutive days with at least `BURST_MULTIPLE` times the conversation's
+//! overall messages per active day (and at least `MIN_BURST_MESSAGES` messages), its peak is its busiest day.
+//! The decay is fitted over the `DECAY_DAYS` days starting with the peak, as a line through `ln(1 + messages)`
+//! (so days without messages still count), and only if all of those days come before the next burst and the end of the data.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    aggregate::{Period, DAY_MS},
+    analyzer::AnalyzedConversation,
+    parser::ParticipantId,
+    stats::{self, fit_line},
+};
+
+/// A day with at least this many times the conversation's overall messages per active day is part of a burst.
+pub const BURST_MULTIPLE: f64 = 3.0;
+/// A day with fewer messages than this is never part of a burst, however quiet the conversation is otherwise.
+pub const MIN_BURST_MESSAGES: usize = 20;
+/// The decay after a burst is fitted over this many days, starting with its peak.
+pub const DECAY_DAYS: usize = 21;
+const YEAR_MS: f64 = 365.25 * DAY_MS as f64;
+
+/// A burst of messages, and how each participant cooled off after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Burst {
+    /// midnight (UTC) at the start of the burst's first day, in milliseconds since the epoch
+    pub start_ms: usize,
+    /// midnight at the start of the burst's busiest day (the first of them, on ties), where the decay is fitted from
+    pub peak_ms: usize,
+    /// midnight at the end of the burst's last day (exclusive)
+    pub end_ms: usize,
+    /// how many messages everyone sent on the peak day
+    pub peak_messages: usize,
+    /// a fit for every participant who sent a message on the peak day, ordered like `participants_oriented`,
+    /// empty if the burst was excluded
+    pub fits: Vec<DecayFit>,
+    /// why the burst wasn't fitted, if it wasn't
+    pub excluded: Option<String>,
+}
+
+/// An exponential decay fitted to one participant's daily messages after a burst.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct DecayFit {
+    #[serde(skip)]
+    pub participant: ParticipantId,
+    /// how many messages they sent on the peak day
+    pub peak_messages: usize,
+    /// the fitted rate `1 + messages` decays at, per day, negative if they picked up instead
+    pub rate: f64,
+    /// the fitted `1 + messages` on the peak day
+    pub initial: f64,
+    /// how many days it takes the fitted `1 + messages` to halve, `None` unless the rate is positive
+    pub half_life_days: Option<f64>,
+}
+
+impl DecayFit {
+    /// The fitted messages per day, `days` after the peak.
+    pub fn eval(&self, days: f64) -> f64 {
+        (self.initial * (-self.rate * days).exp() - 1.0).max(0.0)
+    }
+}
+
+/// How quickly a participant cools off after bursts, over every burst they have a half-life in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecaySummary {
+    pub participant: ParticipantId,
+    /// how many bursts they have a half-life in
+    pub bursts: usize,
+    pub median_half_life_days: Option<f64>,
+    /// how much their half-life changes per year, according to a line fitted to their half-lives over time,
+    /// `None` if there aren't at least 2 bursts to fit it to
+    pub change_per_year: Option<f64>,
+}
+
+impl AnalyzedConversation {
+    /// Every burst of messages, in order, with the decay after it fitted unless it was excluded (see the module documentation).
+    pub fn bursts(&self) -> Vec<Burst> {
+        let participants = self.participants_oriented();
+        let mut days: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, participant) in participants.iter().enumerate() {
+            for message in self.counted_messages(*participant) {
+                days.entry(Period::Day.bucket(message.timestamp_ms()))
+                    .or_insert_with(|| vec![0; participants.len()])[i] +=
+                    self.repeats.volume(message);
+            }
+        }
+        let Some(&last) = days.keys().next_back() else {
+            return Vec::new();
+        };
+        let total = |day: usize| {
+            days.get(&day)
+                .map_or(0, |counts| counts.iter().sum::<usize>())
+        };
+        let overall_per_day =
+            days.keys().map(|day| total(*day)).sum::<usize>() as f64 / days.len() as f64;
+        let threshold = (overall_per_day * BURST_MULTIPLE).max(MIN_BURST_MESSAGES as f64);
+
+        // runs of consecutive burst days, as (first day, last day)
+        let mut runs: Vec<(usize, usize)> = Vec::new();
+        for day in days.keys().copied() {
+            if (total(day) as f64) < threshold {
+                continue;
+            }
+            match runs.last_mut() {
+                Some((_, end)) if *end + 1 == day => *end = day,
+                _ => runs.push((day, day)),
+            }
+        }
+
+        runs.iter()
+            .enumerate()
+            .map(|(i, &(start, end))| {
+                // max_by_key keeps the last of the maxima, so go in reverse to get the first
+                let peak = (start..=end)
+                    .rev()
+                    .max_by_key(|day| total(*day))
+                    .unwrap_or(start);
+                let excluded = match runs.get(i + 1) {
+                    Some((next, _)) if *next < peak + DECAY_DAYS => Some(format!(
+                        "the next burst starts {} days after the peak, {DECAY_DAYS} are needed",
+                        next - peak
+                    )),
+                    _ if last + 1 < peak + DECAY_DAYS => Some(format!(
+                        "the data ends {} days after the peak, {DECAY_DAYS} are needed",
+                        last + 1 - peak
+                    )),
+                    _ => None,
+                };
+                let fits = match excluded {
+                    Some(_) => Vec::new(),
+                    None => participants
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, participant)| {
+                            let messages =
+                                |day: usize| days.get(&day).map_or(0, |counts| counts[index]);
+                            let peak_messages = messages(peak);
+                            if peak_messages == 0 {
+                                return None;
+                            }
+                            let (slope, intercept) = fit_line(
+                                (0..DECAY_DAYS)
+                                    .map(|t| (t as f64, (messages(peak + t) as f64).ln_1p())),
+                            )?;
+                            let rate = -slope;
+                            Some(DecayFit {
+                                participant: *participant,
+                                peak_messages,
+                                rate,
+                                initial: intercept.exp(),
+                                half_life_days: (rate > 0.0).then(|| std::f64::consts::LN_2 / rate),
+                            })
+                        })
+                        .collect(),
+                };
+                Burst {
+                    start_ms: Period::Day.start_ms(start),
+                    peak_ms: Period::Day.start_ms(peak),
+                    end_ms: Period::Day.start_ms(end + 1),
+                    peak_messages: total(peak),
+                    fits,
+                    excluded,
+                }
+            })
+            .collect()
+    }
+
+    /// The median half-life of each participant over the bursts, and how it's changing over the years,
+    /// ordered like `participants_oriented`.
+    pub fn decay_summaries(&self, bursts: &[Burst]) -> Vec<DecaySummary> {
+        self.participants_oriented()
+            .into_iter()
+            .map(|participant| {
+                let half_lives = bursts
+                    .iter()
+                    .filter_map(|burst| {
+                        let fit = burst
+                            .fits
+                            .iter()
+                            .find(|fit| fit.participant == participant)?;
+                        Some((burst.peak_ms, fit.half_life_days?))
+                    })
+                    .collect::<Vec<_>>();
+                DecaySummary {
+                    participant,
+                    bursts: half_lives.len(),
+                    median_half_life_days: stats::median(
+                        &mut half_lives.iter().map(|(_, days)| *days).collect::<Vec<_>>(),
+                    ),
+                    change_per_year: fit_line(
+                        half_lives
+                            .iter()
+                            .map(|(peak_ms, days)| (*peak_ms as f64 / YEAR_MS, *days)),
+                    )
+                    .map(|(slope, _)| slope),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,276 @@
+//! Module responsible for exporting the conversation's activity as an iCalendar (RFC 5545) file,
+//! with an all-day event per day with messages, so the conversation can be overlaid on a calendar app.
+//!
+//! Days run from local midnight to local midnight, in the configured time zone (like the events, see `events`).
+
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{
+    analyzer::{AnalyzedConversation, Sentiment},
+    drift,
+    timezone::Tz,
+};
+
+/// Content lines longer than this many octets are folded onto continuation lines.
+const MAX_LINE_OCTETS: usize = 75;
+
+/// Options for `AnalyzedConversation::to_ics`.
+#[derive(Debug, Clone, Copy)]
+pub struct CalendarConfig {
+    /// the time zone days start and end in, see `timezone`
+    pub time_zone: Tz,
+    /// days with fewer messages than this get no event
+    pub min_messages: usize,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            time_zone: Tz::UTC,
+            min_messages: 1,
+        }
+    }
+}
+
+/// Escapes text for a TEXT property value: backslashes, semicolons, and commas are escaped with a backslash,
+/// and line breaks become `\n`.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Appends a content line, folded so no line is longer than `MAX_LINE_OCTETS` octets
+/// (continuation lines start with a space, and characters are never split), and terminated with CRLF.
+fn push_line(ics: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            ics.push_str("\r\n ");
+            octets = 1;
+        }
+        ics.push(c);
+        octets += c.len_utf8();
+    }
+    ics.push_str("\r\n");
+}
+
+impl AnalyzedConversation {
+    /// Renders an iCalendar file with an all-day event for every local day with at least `min_messages` messages,
+    /// titled with the day's message count and compound score (aggregated with `AnalyzedConversation::aggregate`,
+    /// and marked 🙂/😐/🙁 by its classification), and describing each participant's part of the day.
+    pub fn to_ics(&self, config: &CalendarConfig) -> String {
+        // each participant's (compound, weight) pairs, see `QuotePolicy::weight`
+        let mut days: BTreeMap<NaiveDate, Vec<_>> = BTreeMap::new();
+        for (message, score) in self.timeline() {
+            let Some(time) = chrono::DateTime::from_timestamp_millis(message.timestamp_ms() as i64)
+            else {
+                continue;
+            };
+            days.entry(time.with_timezone(&config.time_zone).date_naive())
+                .or_default()
+                .push((
+                    message.sender,
+                    (score.compound, self.quotes.weight(message)),
+                ));
+        }
+
+        // every event is stamped with the time of the last message, so the file only changes when the conversation does
+        let stamp = self
+            .time_range()
+            .and_then(|(_, last)| chrono::DateTime::from_timestamp_millis(last as i64))
+            .unwrap_or_default()
+            .format("%Y%m%dT%H%M%SZ");
+        let participants = self.participants_oriented();
+        // a hash of the names rather than the names, which may have nothing a UID can hold (e.g. only emoji), in order
+        // so the same conversation gets the same UIDs whoever is `me`
+        let mut names = participants
+            .iter()
+            .map(|participant| self.names.name(*participant))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+        let uid_suffix = format!("{:016x}", drift::fnv1a(names.join("\0").bytes()));
+        let stat = self.aggregate.describe();
+
+        let mut ics = String::new();
+        push_line(&mut ics, "BEGIN:VCALENDAR");
+        push_line(&mut ics, "VERSION:2.0");
+        push_line(&mut ics, "PRODID:-//igdm-sentiment-analysis//EN");
+        push_line(&mut ics, "CALSCALE:GREGORIAN");
+        for (day, messages) in days {
+            if messages.len() < config.min_messages {
+                continue;
+            }
+            let mut compounds = messages
+                .iter()
+                .map(|(_, weighted)| *weighted)
+                .collect::<Vec<_>>();
+            let Some(compound) = self.aggregate.apply_weighted(&mut compounds) else {
+                continue;
+            };
+            let face = match self.thresholds.classify(compound) {
+                Sentiment::Positive => '🙂',
+                Sentiment::Neutral => '😐',
+                Sentiment::Negative => '🙁',
+            };
+            let description = participants
+                .iter()
+                .filter_map(|participant| {
+                    let mut compounds = messages
+                        .iter()
+                        .filter(|(sender, _)| sender == participant)
+                        .map(|(_, weighted)| *weighted)
+                        .collect::<Vec<_>>();
+                    let count = compounds.len();
+                    let compound = self.aggregate.apply_weighted(&mut compounds)?;
+                    Some(format!(
+                        "{}: {count} messages, {stat} compound {compound:+.2}",
+                        self.display_name(*participant)
+                    ))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            push_line(&mut ics, "BEGIN:VEVENT");
+            push_line(
+                &mut ics,
+                &format!(
+                    "UID:{}-{uid_suffix}@igdm-sentiment-analysis",
+                    day.format("%Y%m%d")
+                ),
+            );
+            push_line(&mut ics, &format!("DTSTAMP:{stamp}"));
+            push_line(
+                &mut ics,
+                &format!("DTSTART;VALUE=DATE:{}", day.format("%Y%m%d")),
+            );
+            push_line(
+                &mut ics,
+                &format!(
+                    "DTEND;VALUE=DATE:{}",
+                    (day + TimeDelta::days(1)).format("%Y%m%d")
+                ),
+            );
+            push_line(
+                &mut ics,
+                &format!(
+                    "SUMMARY:{}",
+                    escape_text(&format!(
+                        "{face} {} messages, compound {compound:+.2}",
+                        messages.len()
+                    ))
+                ),
+            );
+            push_line(
+                &mut ics,
+                &format!("DESCRIPTION:{}", escape_text(&description)),
+            );
+            // an all-day event that doesn't make anyone look busy
+            push_line(&mut ics, "TRANSP:TRANSPARENT");
+            push_line(&mut ics, "END:VEVENT");
+        }
+        push_line(&mut ics, "END:VCALENDAR");
+        ics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ParsedConversation;
+
+    /// The lines of the file, with their CRLF terminators.
+    fn physical_lines(ics: &str) -> Vec<&str> {
+        assert!(ics.ends_with("\r\n"));
+        ics.split_terminator("\r\n").collect()
+    }
+
+    #[test]
+    fn folding_never_splits_a_character_across_lines() {
+        // the emoji would take octets 74 to 77 of the first line
+        let line = format!("DESCRIPTION:{}😀 and then some", "a".repeat(61));
+        let mut ics = String::new();
+        push_line(&mut ics, &line);
+
+        let lines = physical_lines(&ics);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 73);
+        assert_eq!(lines[1], " 😀 and then some");
+        // unfolding gives the line back
+        assert_eq!(ics.replace("\r\n ", ""), format!("{line}\r\n"));
+
+        let mut ics = String::new();
+        push_line(&mut ics, &format!("SUMMARY:{}", "😀🙂x".repeat(40)));
+        for line in physical_lines(&ics) {
+            assert!(line.len() <= MAX_LINE_OCTETS, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn text_escapes_its_separators_and_line_breaks() {
+        assert_eq!(escape_text("a;b,c"), r"a\;b\,c");
+        assert_eq!(escape_text(r"back\slash"), r"back\\slash");
+        assert_eq!(escape_text("one\ntwo\r\nthree"), r"one\ntwo\nthree");
+        assert_eq!(escape_text("😀 plain"), "😀 plain");
+    }
+
+    /// The calendar of a day of messages between `names`.
+    fn calendar(names: [&str; 2]) -> String {
+        let export = format!(
+            r#"{{
+              "participants": [{{"name": "{0}"}}, {{"name": "{1}"}}],
+              "messages": [
+                {{"sender_name": "{1}", "timestamp_ms": 1700000100000, "content": "great, see you then"}},
+                {{"sender_name": "{0}", "timestamp_ms": 1700000000000, "content": "dinner at 8; bring wine, please"}}
+              ]
+            }}"#,
+            names[0], names[1]
+        );
+        ParsedConversation::from_bytes(export.as_bytes())
+            .unwrap()
+            .analyze()
+            .to_ics(&CalendarConfig::default())
+    }
+
+    #[test]
+    fn descriptions_keep_a_line_per_participant_escaped() {
+        let ics = calendar(["Ana; the first", "Ben, Jr."]);
+        for line in physical_lines(&ics) {
+            assert!(line.len() <= MAX_LINE_OCTETS, "{line:?}");
+        }
+        let unfolded = ics.replace("\r\n ", "");
+        let description = unfolded
+            .lines()
+            .find_map(|line| line.strip_prefix("DESCRIPTION:"))
+            .unwrap();
+        let parts = description.split(r"\n").collect::<Vec<_>>();
+        assert_eq!(parts.len(), 2, "{description}");
+        assert!(parts[0].starts_with(r"Ana\; the first: 1 messages\, "));
+        assert!(parts[1].starts_with(r"Ben\, Jr.: 1 messages\, "));
+    }
+
+    #[test]
+    fn uids_tell_apart_conversations_of_names_without_letters() {
+        let uid = |ics: &str| {
+            ics.lines()
+                .find(|line| line.starts_with("UID:"))
+                .unwrap()
+                .to_string()
+        };
+        let (hearts, stars) = (calendar(["💖", "💕"]), calendar(["⭐", "🌟"]));
+        assert_ne!(uid(&hearts), uid(&stars));
+        assert_eq!(uid(&hearts), uid(&calendar(["💕", "💖"])));
+    }
+}
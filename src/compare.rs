@@ -0,0 +1,291 @@
+//! Module responsible for comparing two sets of analysis options over the same conversation,
+//! to see what tuning an option actually changes.
+//!
+//! The conversation is parsed once, and analyzed with each profile in parallel.
+
+use std::fmt::Write;
+
+use crate::{
+    analyzer::{AnalyzedConversation, Score, Sentiment, SentimentBackend},
+    format::{format_count, format_date_time},
+    parser::ParsedConversation,
+    settings::Profile,
+};
+
+/// How many of the largest score changes the table lists.
+const LARGEST_CHANGES: usize = 10;
+
+/// One analysis of the conversation.
+pub struct Run {
+    pub profile: Profile,
+    pub analysis: AnalyzedConversation,
+}
+
+pub struct Comparison {
+    pub baseline: Run,
+    pub variant: Run,
+}
+
+/// A message, and its score in each of the runs.
+#[derive(Debug, Clone)]
+pub struct ScoreChange<'a> {
+    pub sender: &'a str,
+    pub timestamp_ms: usize,
+    /// `None` if the content was dropped
+    pub content: Option<&'a str>,
+    pub baseline: Score,
+    pub variant: Score,
+}
+
+impl Comparison {
+    /// Analyzes the conversation with both profiles, in parallel.
+    pub fn run(conversation: &ParsedConversation, baseline: Profile, variant: Profile) -> Self {
+        let (baseline_analysis, variant_analysis) = std::thread::scope(|scope| {
+            let variant = scope.spawn(|| conversation.analyze_with(&variant.options));
+            let baseline = conversation.analyze_with(&baseline.options);
+            let variant = variant
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            (baseline, variant)
+        });
+        Self::from_analyses(baseline, baseline_analysis, variant, variant_analysis)
+    }
+
+    /// `Comparison::run`, scoring the messages with `backend` rather than vader, see `SentimentBackend`.
+    pub fn run_with_backend<B: SentimentBackend + ?Sized>(
+        conversation: &ParsedConversation,
+        baseline: Profile,
+        variant: Profile,
+        backend: &B,
+    ) -> Self {
+        let (baseline_analysis, variant_analysis) = std::thread::scope(|scope| {
+            let variant =
+                scope.spawn(|| conversation.analyze_with_backend(&variant.options, &backend));
+            let baseline = conversation.analyze_with_backend(&baseline.options, &backend);
+            let variant = variant
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            (baseline, variant)
+        });
+        Self::from_analyses(baseline, baseline_analysis, variant, variant_analysis)
+    }
+
+    fn from_analyses(
+        baseline: Profile,
+        baseline_analysis: AnalyzedConversation,
+        variant: Profile,
+        variant_analysis: AnalyzedConversation,
+    ) -> Self {
+        Self {
+            baseline: Run {
+                profile: baseline,
+                analysis: baseline_analysis,
+            },
+            variant: Run {
+                profile: variant,
+                analysis: variant_analysis,
+            },
+        }
+    }
+
+    /// Every message, with its score in both runs.
+    ///
+    /// Both runs analyze the same parsed messages in the same order, so each participant's series line up message for message.
+    fn paired_scores(&self) -> impl Iterator<Item = ScoreChange<'_>> {
+        let baseline = &self.baseline.analysis;
+        let variant = &self.variant.analysis;
+        baseline
+            .participants_by_name()
+            .into_iter()
+            .flat_map(move |participant| {
+                let sender = baseline.names.name(participant);
+                baseline.analysis[&participant]
+                    .iter()
+                    .zip(variant.analysis.get(&participant).into_iter().flatten())
+                    .map(move |((message, baseline), (_, variant))| ScoreChange {
+                        sender,
+                        timestamp_ms: message.timestamp_ms(),
+                        content: message.content.as_deref(),
+                        baseline: *baseline,
+                        variant: *variant,
+                    })
+            })
+    }
+
+    /// How many messages are classified differently by the two runs, each using its own thresholds.
+    pub fn flipped(&self) -> usize {
+        let (baseline, variant) = (
+            &self.baseline.analysis.thresholds,
+            &self.variant.analysis.thresholds,
+        );
+        self.paired_scores()
+            .filter(|pair| pair.baseline.classify(baseline) != pair.variant.classify(variant))
+            .count()
+    }
+
+    /// The messages whose compound score changed the most, largest change first.
+    pub fn largest_changes(&self, count: usize) -> Vec<ScoreChange<'_>> {
+        let mut changes = self
+            .paired_scores()
+            .filter(|pair| pair.baseline.compound != pair.variant.compound)
+            .collect::<Vec<_>>();
+        changes.sort_by(|a, b| {
+            let change =
+                |pair: &ScoreChange| (pair.variant.compound - pair.baseline.compound).abs();
+            change(b).total_cmp(&change(a))
+        });
+        changes.truncate(count);
+        changes
+    }
+
+    /// Renders the comparison as a plain text table: summary metrics side by side,
+    /// how many classifications flipped, and the largest individual score changes.
+    pub fn table(&self) -> String {
+        let runs = [&self.baseline, &self.variant].map(|run| (run, summary(&run.analysis)));
+        let mut rows = vec![(
+            String::new(),
+            runs.iter()
+                .map(|(run, _)| run.profile.name.clone())
+                .collect::<Vec<_>>(),
+        )];
+        let mut row = |label: &str, metric: &dyn Fn(&AnalyzedConversation, &Summary) -> String| {
+            rows.push((
+                label.to_string(),
+                runs.iter()
+                    .map(|(run, summary)| metric(&run.analysis, summary))
+                    .collect(),
+            ));
+        };
+
+        row("messages", &|_, summary| format_count(summary.messages));
+        row("mean compound", &|_, summary| {
+            format_mean(summary.mean_compound)
+        });
+        for (label, sentiment) in [
+            ("positive", Sentiment::Positive),
+            ("neutral", Sentiment::Neutral),
+            ("negative", Sentiment::Negative),
+        ] {
+            row(label, &|_, summary| {
+                let count = summary.classified(sentiment);
+                format!(
+                    "{} ({:.1}%)",
+                    format_count(count),
+                    100.0 * count as f64 / summary.messages.max(1) as f64
+                )
+            });
+        }
+        for participant in self.baseline.analysis.participants_by_name() {
+            let name = self.baseline.analysis.names.name(participant);
+            row(&format!("{name} mean compound"), &|analysis, _| {
+                let series = analysis.analysis.get(&participant);
+                let scores = series
+                    .into_iter()
+                    .flatten()
+                    .map(|(_, score)| score.compound);
+                format_mean(mean(scores))
+            });
+        }
+        let messages = runs[0].1.messages;
+
+        let widths = (0..3)
+            .map(|column| {
+                rows.iter()
+                    .map(|(label, values)| match column {
+                        0 => label.chars().count(),
+                        i => values[i - 1].chars().count(),
+                    })
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+
+        let mut table = String::new();
+        // writing to a string can't fail
+        for (label, values) in &rows {
+            let _ = writeln!(
+                table,
+                "{label:<w0$}  {:>w1$}  {:>w2$}",
+                values[0],
+                values[1],
+                w0 = widths[0],
+                w1 = widths[1],
+                w2 = widths[2],
+            );
+        }
+        let _ = writeln!(
+            table,
+            "\nclassification flipped: {} of {} messages",
+            format_count(self.flipped()),
+            format_count(messages)
+        );
+
+        let changes = self.largest_changes(LARGEST_CHANGES);
+        if !changes.is_empty() {
+            let _ = writeln!(table, "largest compound score changes:");
+        }
+        for change in changes {
+            let _ = write!(
+                table,
+                "  {} {}: {:+.2} -> {:+.2}",
+                format_date_time(change.timestamp_ms),
+                change.sender,
+                change.baseline.compound,
+                change.variant.compound
+            );
+            if let Some(text) = change.content {
+                let mut content = text.chars().take(60).collect::<String>();
+                if content.len() < text.len() {
+                    content.push('…');
+                }
+                let _ = write!(table, "  {content:?}");
+            }
+            let _ = writeln!(table);
+        }
+        table
+    }
+}
+
+/// The headline numbers of one run.
+struct Summary {
+    messages: usize,
+    mean_compound: Option<f64>,
+    /// how many messages were classified as positive, neutral, and negative
+    classified: [usize; 3],
+}
+
+impl Summary {
+    fn classified(&self, sentiment: Sentiment) -> usize {
+        match sentiment {
+            Sentiment::Positive => self.classified[0],
+            Sentiment::Neutral => self.classified[1],
+            Sentiment::Negative => self.classified[2],
+        }
+    }
+}
+
+fn summary(analysis: &AnalyzedConversation) -> Summary {
+    let scores = || analysis.analysis.values().flatten().map(|(_, score)| score);
+    let mut classified = [0; 3];
+    for score in scores() {
+        classified[match score.classify(&analysis.thresholds) {
+            Sentiment::Positive => 0,
+            Sentiment::Neutral => 1,
+            Sentiment::Negative => 2,
+        }] += 1;
+    }
+    Summary {
+        messages: scores().count(),
+        mean_compound: mean(scores().map(|score| score.compound)),
+        classified,
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (count, sum) = values.fold((0, 0.0), |(count, sum), value| (count + 1, sum + value));
+    (count > 0).then(|| sum / count as f64)
+}
+
+fn format_mean(mean: Option<f64>) -> String {
+    mean.map_or_else(|| "n/a".to_string(), |mean| format!("{mean:+.3}"))
+}
@@ -0,0 +1,293 @@
+//! Module for collecting things worth telling the user about that aren't errors,
+//! e.g. data that was left out of the analysis and why.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::format::{format_count, format_date_time};
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct Diagnostics {
+    /// senders that aren't listed as participants of the conversation,
+    /// and how many of their messages were left out of the analysis because of it
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub unmatched_senders: BTreeMap<String, usize>,
+    /// senders that weren't listed as participants of the conversation (e.g. since they left the group),
+    /// who were made participants, in the order they first sent something
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unlisted_senders: Vec<String>,
+    /// how many messages each message transform that dropped any dropped, in the order they were applied
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dropped_by_transform: Vec<(String, usize)>,
+    /// participants, and how many of their messages were left unscored for being in another language than english, see
+    /// `AnalyzeOptions::english_only`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub other_language: BTreeMap<String, usize>,
+    /// messages the analyzer couldn't score properly, which were given a neutral score instead
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub malformed_scores: Vec<MalformedScore>,
+    /// messages whose timestamp was outside the plausible window, see `parser::TimestampValidation`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub implausible_timestamps: Vec<ImplausibleTimestamp>,
+    /// messages whose timestamp was in seconds in a message file whose other timestamps are in milliseconds,
+    /// see `parser::TimestampUnit::Auto`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub mixed_unit_timestamps: Vec<MixedUnitTimestamp>,
+    /// the largest runs of repeats collapsed into one message (see `transform::CollapseRepeats`), largest first,
+    /// at most `transform::LISTED_RUNS` of them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub collapsed_repeats: Vec<CollapsedRun>,
+    /// points and series the plots left out because they couldn't be drawn, in the order the plots were drawn
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub plot_issues: Vec<PlotIssue>,
+    /// the entries of the conversation's folder in the export's zip archive that were left out, since their names
+    /// aren't utf-8 (decoded lossily), see `parser::ConversationDirectory`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_archive_entries: Vec<String>,
+    /// how many messages were left out for being in more than one message file of the export
+    #[serde(skip_serializing_if = "is_zero")]
+    pub duplicate_messages: usize,
+    /// how many messages were left unscored for being notices instagram wrote itself, see `parser::SystemMessages`
+    #[serde(skip_serializing_if = "is_zero")]
+    pub system_messages: usize,
+    /// the numbers of the message files missing from the conversation's folder (2, of `message_1.json`, `message_3.json`
+    /// and `message_4.json`), which likely leave a gap in it, see `parser::ConversationDirectory::strict_numbering`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_message_files: Vec<u32>,
+    /// the message files numbered the same as another one (like `message_1.json` and `message_01.json`), all but the
+    /// first of them by name, which are read anyway
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_message_files: Vec<PathBuf>,
+    /// the message files that were skipped because they couldn't be read or parsed,
+    /// see `parser::ConversationDirectory::lenient`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unreadable_files: Vec<UnreadableFile>,
+}
+
+fn is_zero(count: &usize) -> bool {
+    *count == 0
+}
+
+/// A message file that couldn't be read or parsed, and why.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct UnreadableFile {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Identifies a message that couldn't be scored, and why.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct MalformedScore {
+    pub sender: String,
+    pub timestamp_ms: usize,
+    pub reason: String,
+}
+
+/// Identifies a message with an implausible timestamp, and where it was moved to (if it wasn't left out).
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct ImplausibleTimestamp {
+    pub sender: String,
+    pub timestamp_ms: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clamped_to: Option<usize>,
+}
+
+/// Identifies a message whose timestamp was in seconds, unlike the rest of its message file.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct MixedUnitTimestamp {
+    pub sender: String,
+    /// the timestamp as the file has it
+    pub timestamp_secs: usize,
+}
+
+/// Identifies a run of repeats that was collapsed into its first message, without the text of the message.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct CollapsedRun {
+    pub sender: String,
+    pub first_ms: usize,
+    pub last_ms: usize,
+    /// how many messages the run had
+    pub messages: usize,
+}
+
+/// A point or a whole series of a participant that a plot left out, and why.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct PlotIssue {
+    pub plot: String,
+    pub participant: String,
+    /// the index of the point in the participant's series, `None` if the whole series was left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    pub problem: String,
+}
+
+impl PlotIssue {
+    /// A human readable description of the issue.
+    pub fn describe(&self) -> String {
+        match self.index {
+            Some(index) => format!(
+                "the {} plot left out point {index} of \"{}\"'s series, {}",
+                self.plot, self.participant, self.problem
+            ),
+            None => format!(
+                "the {} plot left out \"{}\"'s series, {}",
+                self.plot, self.participant, self.problem
+            ),
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.unmatched_senders.is_empty()
+            && self.unlisted_senders.is_empty()
+            && self.dropped_by_transform.is_empty()
+            && self.other_language.is_empty()
+            && self.malformed_scores.is_empty()
+            && self.implausible_timestamps.is_empty()
+            && self.mixed_unit_timestamps.is_empty()
+            && self.collapsed_repeats.is_empty()
+            && self.plot_issues.is_empty()
+            && self.skipped_archive_entries.is_empty()
+            && self.duplicate_messages == 0
+            && self.system_messages == 0
+            && self.missing_message_files.is_empty()
+            && self.duplicate_message_files.is_empty()
+            && self.unreadable_files.is_empty()
+    }
+
+    /// Human readable descriptions of everything noteworthy, one per line.
+    pub fn warnings(&self) -> Vec<String> {
+        self.unmatched_senders
+            .iter()
+            .map(|(sender, count)| {
+                format!(
+                    "{} messages from \"{sender}\" were skipped, they aren't a listed participant",
+                    format_count(*count)
+                )
+            })
+            .chain(self.unlisted_senders.iter().map(|sender| {
+                format!("\"{sender}\" isn't a listed participant (they may have left the group), their messages are analyzed anyway")
+            }))
+            .chain(self.other_language.iter().map(|(sender, count)| {
+                format!(
+                    "{} messages from \"{sender}\" were in another language than english, and weren't scored",
+                    format_count(*count)
+                )
+            }))
+            .chain(self.malformed_scores.iter().map(|malformed| {
+                format!(
+                    "the message from \"{}\" at {} was given a neutral score, {}",
+                    malformed.sender,
+                    format_date_time(malformed.timestamp_ms),
+                    malformed.reason
+                )
+            }))
+            .chain(self.implausible_timestamps.iter().map(|implausible| {
+                let action = match implausible.clamped_to {
+                    Some(timestamp_ms) => format!("was moved to {}", format_date_time(timestamp_ms)),
+                    None => "was left out".to_string(),
+                };
+                format!(
+                    "the message from \"{}\" at {} has an implausible timestamp, and {action}",
+                    implausible.sender,
+                    format_date_time(implausible.timestamp_ms)
+                )
+            }))
+            .chain(self.mixed_unit_timestamps.iter().map(|mixed| {
+                format!(
+                    "the message from \"{}\" at {} has its timestamp in seconds, unlike the rest of its message file, and was read as {}",
+                    mixed.sender,
+                    mixed.timestamp_secs,
+                    format_date_time(mixed.timestamp_secs.saturating_mul(1000))
+                )
+            }))
+            .chain(self.collapsed_repeats.iter().map(|run| {
+                format!(
+                    "{} consecutive copies of a message from \"{}\" between {} and {} were collapsed into one",
+                    format_count(run.messages),
+                    run.sender,
+                    format_date_time(run.first_ms),
+                    format_date_time(run.last_ms)
+                )
+            }))
+            .chain((self.duplicate_messages > 0).then(|| {
+                format!(
+                    "{} messages were in more than one message file, and were only counted once",
+                    format_count(self.duplicate_messages)
+                )
+            }))
+            .chain((self.system_messages > 0).then(|| {
+                format!(
+                    "{} messages were notices instagram wrote itself (like \"Liked a message\"), and weren't scored",
+                    format_count(self.system_messages)
+                )
+            }))
+            .chain((!self.missing_message_files.is_empty()).then(|| {
+                let numbers = self
+                    .missing_message_files
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>();
+                format!(
+                    "the message files numbered {} are missing, the conversation likely has gaps where they were",
+                    numbers.join(", ")
+                )
+            }))
+            .chain(self.duplicate_message_files.iter().map(|path| {
+                format!(
+                    "{} is numbered the same as another message file, both were read",
+                    path.display()
+                )
+            }))
+            .chain(self.unreadable_files.iter().map(|unreadable| {
+                format!(
+                    "{} was skipped, it couldn't be parsed: {}",
+                    unreadable.path.display(),
+                    unreadable.error
+                )
+            }))
+            .chain(self.plot_issues.iter().map(PlotIssue::describe))
+            .chain(self.skipped_archive_entries.iter().map(|name| {
+                format!("the archive entry \"{name}\" was skipped, its name isn't utf-8")
+            }))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warnings_show_dates_rather_than_timestamps() {
+        let diagnostics = Diagnostics {
+            malformed_scores: vec![MalformedScore {
+                sender: "Ana".to_string(),
+                timestamp_ms: 1_700_000_000_000,
+                reason: "analyzer returned no \"compound\" score".to_string(),
+            }],
+            implausible_timestamps: vec![
+                ImplausibleTimestamp {
+                    sender: "Ben".to_string(),
+                    timestamp_ms: 4_102_444_800_000,
+                    clamped_to: Some(1_700_000_060_000),
+                },
+                ImplausibleTimestamp {
+                    sender: "Ben".to_string(),
+                    timestamp_ms: 86_400_000,
+                    clamped_to: None,
+                },
+            ],
+            ..Diagnostics::default()
+        };
+        assert_eq!(
+            diagnostics.warnings(),
+            [
+                "the message from \"Ana\" at 2023-11-14 22:13 UTC was given a neutral score, analyzer returned no \"compound\" score",
+                "the message from \"Ben\" at 2100-01-01 00:00 UTC has an implausible timestamp, and was moved to 2023-11-14 22:14 UTC",
+                "the message from \"Ben\" at 1970-01-02 00:00 UTC has an implausible timestamp, and was left out",
+            ]
+        );
+    }
+}
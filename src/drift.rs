@@ -0,0 +1,410 @@
+//! Module responsible for telling whether scores from different runs are comparable.
+//!
+//! Every analysis records the `Fingerprint` of the analyzer that scored it (in `analysis.json` and the manifest).
+//! An earlier `analysis.json` can be checked against the current analyzer (see `EarlierAnalysis::check`):
+//! if the fingerprints differ, a random sample of its messages is rescored to measure how far the scores drifted.
+//!
+//! The lexicon hash covers vader's word and emoji lexicons, not its code, so a vader update that only changes
+//! how the lexicon is applied shows up as a new tool version (the dependency is pinned by the lockfile) rather than a new lexicon.
+
+use std::{fmt::Write, path::Path};
+
+use anyhow::{bail, Context, Result};
+use rand::seq::SliceRandom;
+
+use crate::{
+    analyzer::{AnalyzeOptions, Analyzer, Vader},
+    emoji,
+    format::{format_count, format_date_time, format_delta},
+    rng::Seed,
+};
+
+/// How many of the largest score changes of a rescored sample are listed.
+pub const LARGEST_CHANGES: usize = 5;
+/// Smaller changes aren't listed among the largest, they're rounding (vader's sums aren't in a fixed order).
+pub const LISTED_MIN_DELTA: f64 = 0.0005;
+
+/// Identifies the analyzer an analysis was scored with: scores from analyses with the same fingerprint are comparable.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Fingerprint {
+    /// the version of this tool
+    pub tool_version: String,
+    /// a hash of vader's word and emoji lexicons, with the words added to them (see `emoji` and `AnalyzeOptions::lexicon`),
+    /// in hex
+    pub lexicon: String,
+    /// a hash of the analysis options that change the numbers (see `Fingerprint::new`), in hex
+    pub settings: String,
+}
+
+/// FNV-1a, so the hashes don't depend on the standard library's hasher (which may change between releases).
+pub(crate) fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes
+        .into_iter()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl Fingerprint {
+    /// The fingerprint of an analysis with the options: every option except `drop_content` and `threads` (which don't change any
+    /// score) is hashed.
+    pub fn new(options: &AnalyzeOptions) -> Self {
+        Self::scored_by(options, Vader::NAME)
+    }
+
+    /// The fingerprint of an analysis with the options, scored by the backend named `backend` (see
+    /// `SentimentBackend::name`), the lexicon is vader's whichever backend it is.
+    pub fn scored_by(options: &AnalyzeOptions, backend: &str) -> Self {
+        let mut words = vader_sentiment::LEXICON
+            .iter()
+            .map(|(word, valence)| (**word, *valence))
+            .collect::<Vec<_>>();
+        words.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let mut emoji = vader_sentiment::EMOJI_LEXICON
+            .iter()
+            .map(|(emoji, description)| (*emoji, *description))
+            .collect::<Vec<_>>();
+        emoji.sort_unstable();
+        // the words added to vader's, after those the emoji are scored as
+        let mut added = options.lexicon.applied().collect::<Vec<_>>();
+        added.sort_unstable_by(|a, b| a.0.cmp(b.0));
+        let lexicon = words
+            .iter()
+            .flat_map(|(word, valence)| {
+                word.bytes()
+                    .chain([0])
+                    .chain(valence.to_bits().to_le_bytes())
+            })
+            .chain(emoji.iter().flat_map(|(emoji, description)| {
+                emoji
+                    .bytes()
+                    .chain([0])
+                    .chain(description.bytes())
+                    .chain([0])
+            }))
+            .chain(emoji::words().chain(added).flat_map(|(word, valence)| {
+                word.bytes()
+                    .chain([0])
+                    .chain(valence.to_bits().to_le_bytes())
+            }));
+
+        let mut settings = format!(
+            "positive={};negative={};session_gap_ms={};min_tokens={};aggregate={};quoted={};repeats={:?}",
+            options.thresholds.positive,
+            options.thresholds.negative,
+            options.session_gap.num_milliseconds(),
+            options.min_tokens,
+            options.aggregate,
+            options.quotes,
+            options.repeats,
+        );
+        // only when it's on, so analyses from before the option existed keep their fingerprint
+        if options.strip_urls_and_mentions {
+            settings.push_str(";strip_urls_and_mentions");
+        }
+        // likewise, the languages only change scores when messages in other languages are left unscored
+        if options.english_only {
+            let mut languages = options
+                .languages
+                .iter()
+                .map(|language| language.code())
+                .collect::<Vec<_>>();
+            languages.sort_unstable();
+            languages.dedup();
+            settings.push_str(&format!(";english_only;languages={}", languages.join(",")));
+        }
+        // likewise only for other backends than vader
+        if backend != Vader::NAME {
+            settings.push_str(&format!(";backend={backend}"));
+        }
+
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            lexicon: format!("{:016x}", fnv1a(lexicon)),
+            settings: format!("{:016x}", fnv1a(settings.bytes())),
+        }
+    }
+
+    /// How the earlier fingerprint differs from this one, one description per part, empty if they're the same.
+    pub fn differences(&self, earlier: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.tool_version != earlier.tool_version {
+            differences.push(format!(
+                "it was written by version {}, this is version {}",
+                earlier.tool_version, self.tool_version
+            ));
+        }
+        if self.lexicon != earlier.lexicon {
+            differences.push("the lexicon changed".to_string());
+        }
+        if self.settings != earlier.settings {
+            differences.push(
+                "the analysis settings differ (thresholds, session gap, min tokens, aggregate statistic, quoted or repeat counting)"
+                    .to_string(),
+            );
+        }
+        differences
+    }
+}
+
+/// What to do when an earlier analysis was scored by a different analyzer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MismatchPolicy {
+    /// list the differences, and carry on
+    Warn,
+    /// fail, listing the differences
+    Refuse,
+    /// rescore a random sample of its messages to measure the drift
+    #[default]
+    Rescore,
+}
+
+/// The parts of an `analysis.json` needed to check it, see `EarlierAnalysis::load`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct EarlierAnalysis {
+    /// `None` if it was written before fingerprints were recorded
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+    participants: Vec<EarlierParticipant>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct EarlierParticipant {
+    name: String,
+    messages: Vec<EarlierMessage>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct EarlierMessage {
+    timestamp_ms: usize,
+    #[serde(default)]
+    content: Option<String>,
+    score: EarlierScore,
+}
+
+#[derive(Debug, Clone, Copy, serde::Deserialize)]
+struct EarlierScore {
+    compound: f64,
+}
+
+/// A message of the sample whose score changed, without its text.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Rescored {
+    pub participant: String,
+    pub timestamp_ms: usize,
+    pub earlier: f64,
+    pub current: f64,
+}
+
+impl Rescored {
+    pub fn delta(&self) -> f64 {
+        self.current - self.earlier
+    }
+}
+
+/// How much a random sample of an earlier analysis' messages changed when rescored.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RescoreSample {
+    /// how many of its messages have text to rescore
+    pub population: usize,
+    pub sampled: usize,
+    /// the mean absolute change in compound score
+    pub mean_abs_delta: f64,
+    /// the mean change in compound score, to tell a shift from noise
+    pub mean_delta: f64,
+    pub max_abs_delta: f64,
+    /// how many of the sampled messages are classified differently (with the current thresholds)
+    pub reclassified: usize,
+    /// the largest changes, largest first, at most `LARGEST_CHANGES`
+    pub largest: Vec<Rescored>,
+}
+
+/// The outcome of checking an earlier analysis against the current analyzer.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct DriftReport {
+    pub current: Fingerprint,
+    pub earlier: Option<Fingerprint>,
+    /// how the fingerprints differ, see `Fingerprint::differences`
+    pub differences: Vec<String>,
+    /// `None` unless the fingerprints differ and the policy was to rescore
+    pub sample: Option<RescoreSample>,
+}
+
+impl EarlierAnalysis {
+    /// Loads the `analysis.json` of an earlier run (see `AnalyzedConversation::to_json`).
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read(path)
+            .with_context(|| format!("failed to read the earlier analysis {}", path.display()))?;
+        serde_json::from_slice(&contents)
+            .with_context(|| format!("{} isn't an analysis.json", path.display()))
+    }
+
+    /// Checks whether the analysis is comparable to one with the options, following the policy if it isn't.
+    ///
+    /// Rescoring samples `sample_size` of its messages with text, seeded by `seed`, and fails if it has none
+    /// (e.g. it was written with the text dropped, see `AnalyzeOptions::drop_content`).
+    pub fn check(
+        &self,
+        options: &AnalyzeOptions,
+        policy: MismatchPolicy,
+        sample_size: usize,
+        seed: Seed,
+    ) -> Result<DriftReport> {
+        let current = Fingerprint::new(options);
+        let differences = match &self.fingerprint {
+            Some(earlier) => current.differences(earlier),
+            None => vec![
+                "it has no fingerprint, it was written by a version from before they were recorded"
+                    .to_string(),
+            ],
+        };
+        let mut report = DriftReport {
+            current,
+            earlier: self.fingerprint.clone(),
+            differences,
+            sample: None,
+        };
+        if report.differences.is_empty() {
+            return Ok(report);
+        }
+        match policy {
+            MismatchPolicy::Warn => {}
+            MismatchPolicy::Refuse => bail!(
+                "the earlier analysis isn't comparable: {}",
+                report.differences.join(", ")
+            ),
+            MismatchPolicy::Rescore => {
+                report.sample = Some(self.rescore(options, sample_size, seed)?)
+            }
+        }
+        Ok(report)
+    }
+
+    /// Rescores a random sample of the messages with text, see `check`.
+    fn rescore(
+        &self,
+        options: &AnalyzeOptions,
+        sample_size: usize,
+        seed: Seed,
+    ) -> Result<RescoreSample> {
+        let population = self
+            .participants
+            .iter()
+            .flat_map(|participant| {
+                participant.messages.iter().filter_map(|message| {
+                    let text = message.content.as_deref().filter(|text| !text.is_empty())?;
+                    Some((participant.name.as_str(), message, text))
+                })
+            })
+            .collect::<Vec<_>>();
+        if population.is_empty() {
+            bail!("the earlier analysis has no message text to rescore, it was written with the text dropped");
+        }
+
+        let analyzer = Analyzer::new(options);
+        let mut rescored = population
+            .choose_multiple(&mut seed.rng("drift"), sample_size.max(1))
+            .map(|(participant, message, text)| {
+                Ok(Rescored {
+                    participant: participant.to_string(),
+                    timestamp_ms: message.timestamp_ms,
+                    earlier: message.score.compound,
+                    current: analyzer
+                        .score(text)
+                        .with_context(|| {
+                            format!(
+                                "failed to rescore the message from \"{participant}\" at {}",
+                                message.timestamp_ms
+                            )
+                        })?
+                        .compound,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let sampled = rescored.len();
+        let mean = |values: &mut dyn Iterator<Item = f64>| values.sum::<f64>() / sampled as f64;
+        let thresholds = &options.thresholds;
+        let reclassified = rescored
+            .iter()
+            .filter(|message| {
+                thresholds.classify(message.earlier) != thresholds.classify(message.current)
+            })
+            .count();
+        rescored.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+        let sample = RescoreSample {
+            population: population.len(),
+            sampled,
+            mean_abs_delta: mean(&mut rescored.iter().map(|message| message.delta().abs())),
+            mean_delta: mean(&mut rescored.iter().map(Rescored::delta)),
+            max_abs_delta: rescored
+                .first()
+                .map_or(0.0, |message| message.delta().abs()),
+            reclassified,
+            largest: rescored
+                .into_iter()
+                .filter(|message| message.delta().abs() >= LISTED_MIN_DELTA)
+                .take(LARGEST_CHANGES)
+                .collect(),
+        };
+        Ok(sample)
+    }
+}
+
+impl DriftReport {
+    /// Renders the report as plain text.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        // writing to a string can't fail
+        if self.differences.is_empty() {
+            let _ = writeln!(
+                text,
+                "The earlier analysis was scored by the same analyzer, its scores are comparable."
+            );
+            return text;
+        }
+        let _ = writeln!(
+            text,
+            "The earlier analysis was scored by a different analyzer:"
+        );
+        for difference in &self.differences {
+            let _ = writeln!(text, "  - {difference}");
+        }
+        let Some(sample) = &self.sample else {
+            return text;
+        };
+        let _ = writeln!(
+            text,
+            "Rescored {} of its {} messages with text:",
+            format_count(sample.sampled),
+            format_count(sample.population)
+        );
+        let _ = writeln!(
+            text,
+            "  mean absolute compound change {:.4} (mean change {}, largest {:.4})",
+            sample.mean_abs_delta,
+            format_delta(sample.mean_delta, 4),
+            sample.max_abs_delta
+        );
+        let _ = writeln!(
+            text,
+            "  {} ({:.1}%) classified differently",
+            format_count(sample.reclassified),
+            sample.reclassified as f64 / sample.sampled as f64 * 100.0
+        );
+        if !sample.largest.is_empty() {
+            let _ = writeln!(text, "Largest changes:");
+            for message in &sample.largest {
+                let _ = writeln!(
+                    text,
+                    "  {} at {}: {:+.3} -> {:+.3}",
+                    message.participant,
+                    format_date_time(message.timestamp_ms),
+                    message.earlier,
+                    message.current
+                );
+            }
+        }
+        text
+    }
+}
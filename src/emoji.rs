@@ -0,0 +1,182 @@
+//! Module containing the sentiment of common emoji, which vader mostly misses: its `polarity_scores` replaces each emoji
+//! it knows with its description in place (like "red heart" or "thumbs up"), which is scored like the rest of the text
+//! but often has no sentiment in its lexicon, and it doesn't know newer emoji at all.
+//!
+//! Each emoji of `EMOJI_VALENCES` is replaced by a word added to vader's lexicon with the emoji's valence before vader
+//! sees the text, so it's scored once, by its valence, rather than also by its description. Messages of only emoji
+//! are scored, and messages mixing text and emoji combine both. The valences roughly follow the Emoji Sentiment Ranking
+//! (Kralj Novak et al., 2015), scaled to vader's -4 to 4, with a few emoji it predates. Variation selectors and skin
+//! tones don't change an emoji. Other emoji are left to vader, and scored by the words of their description.
+//!
+//! ```
+//! use igdm_sentiment_analysis::analyzer::{AnalyzeOptions, Analyzer};
+//!
+//! let analyzer = Analyzer::new(&AnalyzeOptions::default());
+//! let compound = |text: &str| analyzer.score(text).map(|score| score.compound);
+//!
+//! // only emoji
+//! assert!(compound("😂😂")? > 0.0);
+//! assert!(compound("❤️")? > 0.0);
+//! assert!(compound("👍🏽")? > 0.0);
+//! assert!(compound("😭")? < 0.0);
+//! assert_eq!(compound("🪑")?, 0.0);
+//!
+//! // only text, scored as vader scores it
+//! let vader = vader_sentiment::SentimentIntensityAnalyzer::new();
+//! assert_eq!(compound("see you at the station")?, 0.0);
+//! assert_eq!(compound("that was a great game")?, vader.polarity_scores("that was a great game")["compound"]);
+//!
+//! // both
+//! assert!(compound("that was a great game 🔥")? > compound("that was a great game")?);
+//! assert!(compound("that was a great game 😡")? < compound("that was a great game")?);
+//! assert!(compound("missed the bus 😭")? < 0.0);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Emoji, the words they're scored as (see the module docs), and their valences, from -4 to 4 like vader's lexicon.
+pub const EMOJI_VALENCES: &[(&str, &str, f64)] = &[
+    ("😂", "emoji_joy", 1.5),
+    ("🤣", "emoji_rofl", 1.5),
+    ("😀", "emoji_grinning", 2.0),
+    ("😁", "emoji_beaming", 1.8),
+    ("😄", "emoji_smile", 2.2),
+    ("😊", "emoji_blush", 2.5),
+    ("🙂", "emoji_slight_smile", 1.2),
+    ("😅", "emoji_sweat_smile", 0.8),
+    ("😉", "emoji_wink", 1.6),
+    ("😎", "emoji_sunglasses", 1.7),
+    ("😍", "emoji_heart_eyes", 2.7),
+    ("🥰", "emoji_smiling_hearts", 2.8),
+    ("😘", "emoji_kiss", 2.8),
+    ("🥳", "emoji_partying", 2.6),
+    ("🥺", "emoji_pleading", 0.5),
+    ("❤", "emoji_red_heart", 3.0),
+    ("💕", "emoji_two_hearts", 2.7),
+    ("💖", "emoji_sparkling_heart", 2.7),
+    ("💗", "emoji_growing_heart", 2.6),
+    ("🫶", "emoji_heart_hands", 2.6),
+    ("🎉", "emoji_party_popper", 2.4),
+    ("✨", "emoji_sparkles", 1.5),
+    ("🔥", "emoji_fire", 1.4),
+    ("👍", "emoji_thumbs_up", 1.8),
+    ("👏", "emoji_clap", 1.8),
+    ("🙏", "emoji_folded_hands", 1.6),
+    ("💯", "emoji_hundred", 1.8),
+    ("😬", "emoji_grimacing", -0.8),
+    ("😭", "emoji_sob", -0.8),
+    ("😢", "emoji_cry", -2.0),
+    ("😞", "emoji_disappointed", -2.2),
+    ("😔", "emoji_pensive", -1.6),
+    ("😩", "emoji_weary", -1.6),
+    ("😫", "emoji_tired", -1.6),
+    ("😒", "emoji_unamused", -1.8),
+    ("🙄", "emoji_eye_roll", -1.4),
+    ("😤", "emoji_huffing", -1.2),
+    ("😠", "emoji_angry", -2.8),
+    ("😡", "emoji_pouting", -3.0),
+    ("🤬", "emoji_cursing", -3.2),
+    ("🤮", "emoji_vomiting", -2.6),
+    ("💔", "emoji_broken_heart", -2.4),
+    ("👎", "emoji_thumbs_down", -1.8),
+];
+
+/// Whether the character only changes how the emoji before it looks: a variation selector or a skin tone.
+fn is_modifier(c: char) -> bool {
+    matches!(c, '\u{fe0e}' | '\u{fe0f}' | '\u{1f3fb}'..='\u{1f3ff}')
+}
+
+/// The word the grapheme is scored as, if it's one of `EMOJI_VALENCES`.
+fn word(grapheme: &str) -> Option<&'static str> {
+    // every emoji of the table starts with a character that's neither ascii nor alphanumeric
+    if grapheme
+        .chars()
+        .next()
+        .is_none_or(|c| c.is_ascii() || c.is_alphanumeric())
+    {
+        return None;
+    }
+    let emoji = grapheme
+        .chars()
+        .filter(|c| !is_modifier(*c))
+        .collect::<String>();
+    EMOJI_VALENCES
+        .iter()
+        .find(|(listed, ..)| *listed == emoji)
+        .map(|(_, word, _)| *word)
+}
+
+/// The text with the emoji of `EMOJI_VALENCES` replaced by the words they're scored as, each on its own, so there's
+/// nothing left of them for vader to describe.
+pub(crate) fn replace(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut replaced = String::with_capacity(text.len());
+    let mut any = false;
+    for grapheme in text.graphemes(true) {
+        match word(grapheme) {
+            Some(word) => {
+                any = true;
+                replaced.push(' ');
+                replaced.push_str(word);
+                replaced.push(' ');
+            }
+            None => replaced.push_str(grapheme),
+        }
+    }
+    if any {
+        Cow::Owned(replaced)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// The words the emoji are scored as, and their valences, added to vader's lexicon.
+pub(crate) fn words() -> impl Iterator<Item = (&'static str, f64)> {
+    EMOJI_VALENCES
+        .iter()
+        .map(|(_, word, valence)| (*word, *valence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{SentimentBackend, Vader};
+
+    #[test]
+    fn replaced_emoji_are_not_also_described() {
+        let vader = Vader::default();
+        for (emoji, word, _) in EMOJI_VALENCES {
+            assert_eq!(
+                vader.score(emoji).unwrap(),
+                vader.score(word).unwrap(),
+                "{emoji}"
+            );
+        }
+        // vader would read 😂 as "face with tears of joy", which is positive on its own
+        assert!(vader.score("face with tears of joy").unwrap().compound > 0.0);
+        // the modifiers go with the emoji
+        assert_eq!(
+            vader.score("that was great 👍🏽").unwrap(),
+            vader.score("that was great emoji_thumbs_up").unwrap()
+        );
+    }
+
+    #[test]
+    fn other_emoji_are_scored_by_their_description() {
+        let vader = Vader::default();
+        assert_eq!(word("😱"), None);
+        let screaming = vader.score("the exam is tomorrow 😱").unwrap();
+        assert_eq!(
+            screaming,
+            vader
+                .score("the exam is tomorrow face screaming in fear")
+                .unwrap()
+        );
+        assert!(screaming.compound < 0.0);
+    }
+}
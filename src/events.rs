@@ -0,0 +1,362 @@
+//! Module containing event markers: labeled days (holidays, birthdays) that are drawn onto the compound plot,
+//! and whose messages are compared against the rest of the conversation.
+//!
+//! Events are configured as yearly recurrences, and expanded into one marker per occurrence over the years the conversation spans.
+//! A day runs from local midnight to local midnight, in the configured time zone (see `timezone`).
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Weekday};
+
+use crate::{
+    analyzer::AnalyzedConversation,
+    timezone::{self, Tz},
+};
+
+/// A single occurrence of an event, covering one local day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventMarker {
+    /// what kind of event this is, e.g. "US holidays", events are drawn and summarized per category
+    pub category: String,
+    pub label: String,
+    /// local midnight at the start of the day, in milliseconds since the epoch
+    pub start_ms: usize,
+    /// local midnight at the end of the day (exclusive)
+    pub end_ms: usize,
+}
+
+/// How an event recurs from year to year.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    /// the same date every year, see `MonthDay::date_in`
+    Fixed { month: u32, day: u32 },
+    /// the `n`th given weekday of the month, counting from the end of the month if `n` is negative
+    NthWeekday { month: u32, weekday: Weekday, n: i8 },
+    /// a number of days before or after (western) Easter Sunday
+    Easter { offset_days: i64 },
+}
+
+impl Recurrence {
+    /// The date of the event in `year`, `None` if there isn't one.
+    pub fn date_in(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            Self::Fixed { month, day } => MonthDay { month, day }.date_in(year),
+            Self::NthWeekday { month, weekday, n } if n >= 0 => {
+                NaiveDate::from_weekday_of_month_opt(year, month, weekday, u8::try_from(n).ok()?)
+            }
+            Self::NthWeekday { month, weekday, n } => {
+                let first_of_next = if month == 12 {
+                    NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+                } else {
+                    NaiveDate::from_ymd_opt(year, month + 1, 1)?
+                };
+                let last = first_of_next.pred_opt()?;
+                let back = (last.weekday().num_days_from_monday() + 7
+                    - weekday.num_days_from_monday())
+                    % 7;
+                let date = last - TimeDelta::days(i64::from(back) + 7 * (i64::from(-n) - 1));
+                (date.month() == month).then_some(date)
+            }
+            Self::Easter { offset_days } => {
+                Some(easter_sunday(year)? + TimeDelta::days(offset_days))
+            }
+        }
+    }
+}
+
+/// The date of (western) Easter Sunday, by the anonymous Gregorian algorithm.
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
+
+/// The built-in sets of holidays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum HolidaySet {
+    /// major US federal and cultural holidays
+    Us,
+    /// holidays observed across most of Europe
+    Eu,
+}
+
+impl HolidaySet {
+    pub fn category(self) -> &'static str {
+        match self {
+            Self::Us => "US holidays",
+            Self::Eu => "EU holidays",
+        }
+    }
+
+    pub fn holidays(self) -> &'static [(&'static str, Recurrence)] {
+        use Recurrence::*;
+        match self {
+            Self::Us => &[
+                ("New Year's Day", Fixed { month: 1, day: 1 }),
+                (
+                    "Martin Luther King Jr. Day",
+                    NthWeekday {
+                        month: 1,
+                        weekday: Weekday::Mon,
+                        n: 3,
+                    },
+                ),
+                ("Valentine's Day", Fixed { month: 2, day: 14 }),
+                (
+                    "Presidents' Day",
+                    NthWeekday {
+                        month: 2,
+                        weekday: Weekday::Mon,
+                        n: 3,
+                    },
+                ),
+                (
+                    "Memorial Day",
+                    NthWeekday {
+                        month: 5,
+                        weekday: Weekday::Mon,
+                        n: -1,
+                    },
+                ),
+                ("Juneteenth", Fixed { month: 6, day: 19 }),
+                ("Independence Day", Fixed { month: 7, day: 4 }),
+                (
+                    "Labor Day",
+                    NthWeekday {
+                        month: 9,
+                        weekday: Weekday::Mon,
+                        n: 1,
+                    },
+                ),
+                ("Halloween", Fixed { month: 10, day: 31 }),
+                ("Veterans Day", Fixed { month: 11, day: 11 }),
+                (
+                    "Thanksgiving",
+                    NthWeekday {
+                        month: 11,
+                        weekday: Weekday::Thu,
+                        n: 4,
+                    },
+                ),
+                ("Christmas Day", Fixed { month: 12, day: 25 }),
+                ("New Year's Eve", Fixed { month: 12, day: 31 }),
+            ],
+            Self::Eu => &[
+                ("New Year's Day", Fixed { month: 1, day: 1 }),
+                ("Valentine's Day", Fixed { month: 2, day: 14 }),
+                ("Good Friday", Easter { offset_days: -2 }),
+                ("Easter Sunday", Easter { offset_days: 0 }),
+                ("Easter Monday", Easter { offset_days: 1 }),
+                ("Labour Day", Fixed { month: 5, day: 1 }),
+                ("Europe Day", Fixed { month: 5, day: 9 }),
+                ("All Saints' Day", Fixed { month: 11, day: 1 }),
+                ("Christmas Eve", Fixed { month: 12, day: 24 }),
+                ("Christmas Day", Fixed { month: 12, day: 25 }),
+                ("St. Stephen's Day", Fixed { month: 12, day: 26 }),
+                ("New Year's Eve", Fixed { month: 12, day: 31 }),
+            ],
+        }
+    }
+}
+
+/// A day of the year, like a birthday, written as `MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthDay {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl MonthDay {
+    /// The date in `year`, February 29th falls on the 28th in years that aren't leap years.
+    pub fn date_in(self, year: i32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, self.month, self.day).or_else(|| {
+            (self.month == 2 && self.day == 29)
+                .then(|| NaiveDate::from_ymd_opt(year, 2, 28))
+                .flatten()
+        })
+    }
+}
+
+impl FromStr for MonthDay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (month, day) = s
+            .split_once('-')
+            .with_context(|| format!("expected a date like 03-14, got \"{s}\""))?;
+        let month_day = Self {
+            month: month.parse().context("invalid month")?,
+            day: day.parse().context("invalid day")?,
+        };
+        // 2000 is a leap year, so every valid month-day exists in it
+        if NaiveDate::from_ymd_opt(2000, month_day.month, month_day.day).is_none() {
+            bail!("{s} isn't a day of the year");
+        }
+        Ok(month_day)
+    }
+}
+
+impl Display for MonthDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:02}-{:02}", self.month, self.day)
+    }
+}
+
+/// Which events to mark.
+#[derive(Debug, Clone)]
+pub struct EventConfig {
+    pub holidays: Vec<HolidaySet>,
+    pub birthdays: Vec<MonthDay>,
+    /// the time zone the events happen in, which decides where each day starts and ends
+    pub time_zone: Tz,
+}
+
+impl Default for EventConfig {
+    fn default() -> Self {
+        Self {
+            holidays: Vec::new(),
+            birthdays: Vec::new(),
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+impl EventConfig {
+    /// Expands the configured events into markers for every occurrence that overlaps the time range
+    /// (inclusive, in milliseconds since the epoch), ordered by time.
+    pub fn expand(&self, first_ms: usize, last_ms: usize) -> Vec<EventMarker> {
+        let year_of = |timestamp_ms: usize| {
+            Some(
+                DateTime::from_timestamp_millis(i64::try_from(timestamp_ms).ok()?)?
+                    .with_timezone(&self.time_zone)
+                    .year(),
+            )
+        };
+        let (Some(first_year), Some(last_year)) = (year_of(first_ms), year_of(last_ms)) else {
+            return Vec::new();
+        };
+
+        let mut recurrences = self
+            .holidays
+            .iter()
+            .flat_map(|set| {
+                set.holidays()
+                    .iter()
+                    .map(|(label, recurrence)| (set.category(), label.to_string(), *recurrence))
+            })
+            .collect::<Vec<_>>();
+        for birthday in &self.birthdays {
+            recurrences.push((
+                "birthdays",
+                format!("birthday ({birthday})"),
+                Recurrence::Fixed {
+                    month: birthday.month,
+                    day: birthday.day,
+                },
+            ));
+        }
+
+        let mut markers = Vec::new();
+        for year in first_year..=last_year {
+            for (category, label, recurrence) in &recurrences {
+                let Some((start_ms, end_ms)) = recurrence
+                    .date_in(year)
+                    .and_then(|date| self.day_bounds(date))
+                else {
+                    continue;
+                };
+                if end_ms > first_ms && start_ms <= last_ms {
+                    markers.push(EventMarker {
+                        category: category.to_string(),
+                        label: label.clone(),
+                        start_ms,
+                        end_ms,
+                    });
+                }
+            }
+        }
+        markers.sort_by_key(|marker| marker.start_ms);
+        markers
+    }
+
+    /// Local midnight at the start and end of the day, in milliseconds since the epoch.
+    fn day_bounds(&self, date: NaiveDate) -> Option<(usize, usize)> {
+        let midnight = |date: NaiveDate| {
+            let local = date.and_hms_opt(0, 0, 0)?;
+            let timestamp = timezone::from_local(self.time_zone, local)?;
+            usize::try_from(timestamp.timestamp_millis()).ok()
+        };
+        Some((midnight(date)?, midnight(date.succ_opt()?)?))
+    }
+}
+
+/// The mean compound score of the messages sent on the days of a category of events, next to that of every other message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventComparison {
+    pub category: String,
+    /// how many days of this category the conversation spans
+    pub days: usize,
+    /// how many messages were sent on those days
+    pub messages: usize,
+    /// `None` if no messages were sent on those days
+    pub mean_compound: Option<f64>,
+    /// the mean compound score of every message not sent on those days, `None` if there are none
+    pub baseline: Option<f64>,
+}
+
+impl AnalyzedConversation {
+    /// Compares the messages sent on each category of event days against the rest, ordered by category.
+    pub fn event_comparisons(&self) -> Vec<EventComparison> {
+        let mut categories: BTreeMap<&str, Vec<(usize, usize)>> = BTreeMap::new();
+        for marker in &self.events {
+            categories
+                .entry(&marker.category)
+                .or_default()
+                .push((marker.start_ms, marker.end_ms));
+        }
+
+        let timeline = self.timeline();
+        categories
+            .into_iter()
+            .map(|(category, mut days)| {
+                // two events of the same category on the same day (e.g. two birthdays) only count once
+                days.sort_unstable();
+                days.dedup();
+
+                let (mut on, mut off) = ((0, 0.0), (0, 0.0));
+                for (message, score) in &timeline {
+                    // the last day starting at or before the message
+                    let i = days.partition_point(|(start, _)| *start <= message.timestamp_ms());
+                    let bucket = match i.checked_sub(1).map(|i| days[i]) {
+                        Some((_, end)) if message.timestamp_ms() < end => &mut on,
+                        _ => &mut off,
+                    };
+                    bucket.0 += 1;
+                    bucket.1 += score.compound;
+                }
+                let mean = |(count, sum): (usize, f64)| (count > 0).then(|| sum / count as f64);
+
+                EventComparison {
+                    category: category.to_string(),
+                    days: days.len(),
+                    messages: on.0,
+                    mean_compound: mean(on),
+                    baseline: mean(off),
+                }
+            })
+            .collect()
+    }
+}
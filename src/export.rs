@@ -0,0 +1,232 @@
+//! Module responsible for exporting the analysis as json, for use in other tools.
+//!
+//! Participants are referred to by name throughout, since ids are only meaningful within a single run.
+
+use anyhow::Result;
+
+use crate::{
+    analyzer::{AnalyzedConversation, Score},
+    bursts::DecayFit,
+    drift::Fingerprint,
+    gaps::{Gap, WeeklyGaps},
+    language::Language,
+    openers::OpenerSummary,
+    quotes::QuoteReason,
+    register::MonthlyRegister,
+    relationship::RelationshipMetrics,
+    summary::ParticipantSummary,
+};
+
+/// Written as `analysis.json`.
+#[derive(serde::Serialize)]
+struct AnalysisExport<'a> {
+    /// identifies the analyzer the scores came from, see `drift`
+    fingerprint: &'a Fingerprint,
+    /// the participant who made the export, if known
+    me: Option<&'a str>,
+    /// the statistic the summaries' compound scores are aggregated with, see `AggregateStat`
+    aggregate_stat: String,
+    /// how much messages flagged as quoted count in the summaries, see `QuotePolicy`
+    quotes: String,
+    /// ordered by name, with `me` first
+    participants: Vec<ParticipantExport<'a>>,
+    reply_pairs: Vec<ReplyPairExport<'a>>,
+    relationship: RelationshipExport<'a>,
+    gaps: GapsExport,
+    bursts: BurstsExport<'a>,
+}
+
+#[derive(serde::Serialize)]
+struct GapsExport {
+    weekly: Vec<WeeklyGaps>,
+    longest_silence: Option<Gap>,
+}
+
+#[derive(serde::Serialize)]
+struct BurstsExport<'a> {
+    /// in order, with the reason a burst wasn't fitted if it wasn't
+    bursts: Vec<BurstExport<'a>>,
+    /// ordered by name, with `me` first
+    half_lives: Vec<DecaySummaryExport<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct BurstExport<'a> {
+    start_ms: usize,
+    peak_ms: usize,
+    end_ms: usize,
+    peak_messages: usize,
+    fits: Vec<DecayFitExport<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    excluded: Option<&'a str>,
+}
+
+#[derive(serde::Serialize)]
+struct DecayFitExport<'a> {
+    participant: &'a str,
+    #[serde(flatten)]
+    fit: &'a DecayFit,
+}
+
+#[derive(serde::Serialize)]
+struct DecaySummaryExport<'a> {
+    participant: &'a str,
+    bursts: usize,
+    median_half_life_days: Option<f64>,
+    change_per_year: Option<f64>,
+}
+
+#[derive(serde::Serialize)]
+struct ParticipantExport<'a> {
+    name: &'a str,
+    summary: &'a ParticipantSummary,
+    messages: Vec<MessageExport<'a>>,
+    /// ordered by month
+    register: &'a [MonthlyRegister],
+    /// how they opened sessions, omitted if they never sent a message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    openers: Option<&'a OpenerSummary>,
+}
+
+#[derive(serde::Serialize)]
+struct MessageExport<'a> {
+    timestamp_ms: usize,
+    /// omitted if the content was dropped after scoring
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<&'a str>,
+    score: Score,
+    /// why the message is likely quoted, omitted if it isn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quote: Option<QuoteReason>,
+    /// the language it's written in, omitted if it wasn't detected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<Language>,
+    /// how many messages it stands for if a run of repeats was collapsed into it, omitted if it wasn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repeats: Option<usize>,
+}
+
+#[derive(serde::Serialize)]
+struct ReplyPairExport<'a> {
+    stimulus_sender: &'a str,
+    stimulus_timestamp_ms: usize,
+    stimulus_compound: f64,
+    responder: &'a str,
+    response_timestamp_ms: usize,
+    response_compound: f64,
+}
+
+#[derive(serde::Serialize)]
+struct RelationshipExport<'a> {
+    responsiveness: Vec<ResponsivenessExport<'a>>,
+}
+
+#[derive(serde::Serialize)]
+struct ResponsivenessExport<'a> {
+    stimulus_sender: &'a str,
+    responder: &'a str,
+    pairs: usize,
+    slope: Option<f64>,
+}
+
+impl AnalyzedConversation {
+    /// Serializes each participant's summary, every scored message, the register metrics, how they opened sessions, the reply pairs, the relationship metrics,
+    /// the weekly gaps between messages, and the decay after each burst as pretty-printed json.
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        // the summaries are ordered by name, with `me` first
+        let participants = self
+            .summaries()
+            .iter()
+            .map(|summary| {
+                let participant = summary.participant;
+                ParticipantExport {
+                    name: self.names.name(participant),
+                    summary,
+                    messages: self.analysis[&participant]
+                        .iter()
+                        .map(|(message, score)| MessageExport {
+                            timestamp_ms: message.timestamp_ms(),
+                            content: message.content.as_deref(),
+                            score: *score,
+                            quote: message.quote,
+                            language: message.language,
+                            repeats: (message.repeats > 1).then_some(message.repeats),
+                        })
+                        .collect(),
+                    register: self.register.get(participant),
+                    openers: self.openers.get(participant),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let pairs = self.reply_pairs();
+        let metrics = RelationshipMetrics::from_pairs(&pairs);
+        let bursts = self.bursts();
+        let export = AnalysisExport {
+            fingerprint: &self.fingerprint,
+            me: self.me.map(|me| self.names.name(me)),
+            aggregate_stat: self.aggregate.to_string(),
+            quotes: self.quotes.to_string(),
+            participants,
+            reply_pairs: pairs
+                .iter()
+                .map(|pair| ReplyPairExport {
+                    stimulus_sender: self.names.name(pair.stimulus_sender),
+                    stimulus_timestamp_ms: pair.stimulus_timestamp_ms,
+                    stimulus_compound: pair.stimulus_compound,
+                    responder: self.names.name(pair.responder),
+                    response_timestamp_ms: pair.response_timestamp_ms,
+                    response_compound: pair.response_compound,
+                })
+                .collect(),
+            relationship: RelationshipExport {
+                responsiveness: metrics
+                    .responsiveness
+                    .iter()
+                    .map(|responsiveness| ResponsivenessExport {
+                        stimulus_sender: self.names.name(responsiveness.stimulus_sender),
+                        responder: self.names.name(responsiveness.responder),
+                        pairs: responsiveness.pairs,
+                        slope: responsiveness.slope,
+                    })
+                    .collect(),
+            },
+            gaps: GapsExport {
+                weekly: self.weekly_gaps(),
+                longest_silence: self.longest_silence(),
+            },
+            bursts: BurstsExport {
+                bursts: bursts
+                    .iter()
+                    .map(|burst| BurstExport {
+                        start_ms: burst.start_ms,
+                        peak_ms: burst.peak_ms,
+                        end_ms: burst.end_ms,
+                        peak_messages: burst.peak_messages,
+                        fits: burst
+                            .fits
+                            .iter()
+                            .map(|fit| DecayFitExport {
+                                participant: self.names.name(fit.participant),
+                                fit,
+                            })
+                            .collect(),
+                        excluded: burst.excluded.as_deref(),
+                    })
+                    .collect(),
+                half_lives: self
+                    .decay_summaries(&bursts)
+                    .into_iter()
+                    .map(|summary| DecaySummaryExport {
+                        participant: self.names.name(summary.participant),
+                        bursts: summary.bursts,
+                        median_half_life_days: summary.median_half_life_days,
+                        change_per_year: summary.change_per_year,
+                    })
+                    .collect(),
+            },
+        };
+
+        Ok(serde_json::to_vec_pretty(&export)?)
+    }
+}
@@ -0,0 +1,184 @@
+//! Module responsible for the receipts behind the trends: each participant's most positive and most negative messages.
+//!
+//! Only scored messages are candidates, so calls, system messages, and messages too short (or otherwise left unscored)
+//! never appear, and neither do those vader found no sentiment in, which the plots leave out as outliers. Messages
+//! excluded by the quote policy (see `AnalyzedConversation::quotes`) are left out too.
+
+use std::fmt::Write;
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    analyzer::{AnalyzedConversation, Score},
+    format::{format_count, format_date_time},
+    parser::{Message, ParticipantId},
+};
+
+/// One of the most positive or most negative messages.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Extreme {
+    pub timestamp: DateTime<Utc>,
+    pub compound: f64,
+    /// the text of the message, `None` if it was dropped (see `AnalyzeOptions::drop_content`)
+    pub text: Option<String>,
+}
+
+/// A participant's most positive and most negative messages, see `AnalyzedConversation::extremes`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParticipantExtremes {
+    #[serde(skip)]
+    pub participant: ParticipantId,
+    /// their positive messages with the highest compound scores, highest first
+    pub most_positive: Vec<Extreme>,
+    /// their negative messages with the lowest compound scores, lowest first
+    pub most_negative: Vec<Extreme>,
+}
+
+impl AnalyzedConversation {
+    /// Each participant's `n` most positive and `n` most negative messages (fewer if they sent fewer positive or
+    /// negative ones), ordered by name with the exporter first (see `participants_oriented`). Messages with the same
+    /// score are ordered by when they were sent, earliest first. See the module docs for which messages are candidates.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{extremes::Extreme, parser::ParsedConversation};
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000400000, "content": "I love it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000300000, "content": "see you at the station"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000200000, "content": "I hate this,\nit's awful\nand terrible"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000100000, "content": "I love it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "what a wonderful, amazing day"},
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000050000, "content": "Ben started a video chat"}
+    ///   ]
+    /// }"#;
+    /// let analysis = ParsedConversation::from_bytes(export)?.analyze();
+    /// let extremes = analysis.extremes(2);
+    /// let (ana, ben) = (&extremes[0], &extremes[1]);
+    ///
+    /// let texts = |extremes: &[Extreme]| {
+    ///     let text = |extreme: &Extreme| (extreme.timestamp.timestamp_millis(), extreme.text.clone().unwrap());
+    ///     extremes.iter().map(text).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(
+    ///     texts(&ana.most_positive),
+    ///     [(1700000000000, "what a wonderful, amazing day".to_string()), (1700000100000, "I love it".to_string())]
+    /// );
+    /// // only one of them is negative, and the neutral one is never listed
+    /// assert_eq!(texts(&ana.most_negative), [(1700000200000, "I hate this,\nit's awful\nand terrible".to_string())]);
+    /// // the call isn't a message
+    /// assert!(ben.most_positive.is_empty() && ben.most_negative.is_empty());
+    ///
+    /// let table = analysis.extremes_table(2, 20);
+    /// assert!(table.contains("-0."));
+    /// assert!(table.lines().any(|line| line.ends_with("I hate this, it's a…")));
+    /// assert!(table.lines().any(|line| line.ends_with("Ben: no positive or negative messages")));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn extremes(&self, n: usize) -> Vec<ParticipantExtremes> {
+        self.participants_oriented()
+            .into_iter()
+            .map(|participant| {
+                let mut candidates = self
+                    .analysis
+                    .get(&participant)
+                    .into_iter()
+                    .flatten()
+                    .filter(|(message, score)| {
+                        !score.is_neutral() && self.quotes.weight(message) > 0.0
+                    })
+                    .collect::<Vec<_>>();
+                // earliest first among equal scores, the sorts below are stable
+                candidates.sort_by_key(|(message, _)| message.timestamp);
+                let extremes = |candidates: Vec<&(Message, Score)>| {
+                    candidates
+                        .into_iter()
+                        .take(n)
+                        .map(|(message, score)| Extreme {
+                            timestamp: message.timestamp,
+                            compound: score.compound,
+                            text: message.content.clone(),
+                        })
+                        .collect()
+                };
+
+                let mut positive = candidates
+                    .iter()
+                    .copied()
+                    .filter(|(_, score)| score.compound > 0.0)
+                    .collect::<Vec<_>>();
+                positive.sort_by(|(_, a), (_, b)| b.compound.total_cmp(&a.compound));
+                let mut negative = candidates
+                    .iter()
+                    .copied()
+                    .filter(|(_, score)| score.compound < 0.0)
+                    .collect::<Vec<_>>();
+                negative.sort_by(|(_, a), (_, b)| a.compound.total_cmp(&b.compound));
+
+                ParticipantExtremes {
+                    participant,
+                    most_positive: extremes(positive),
+                    most_negative: extremes(negative),
+                }
+            })
+            .collect()
+    }
+
+    /// The `n` most positive and most negative messages of each participant (see `AnalyzedConversation::extremes`) as
+    /// text, a line per message with its date and compound score, its text on one line (line breaks and runs of
+    /// whitespace become single spaces) and cut to `width` characters.
+    pub fn extremes_table(&self, n: usize, width: usize) -> String {
+        let mut table = String::new();
+        // writing to a string can't fail
+        let _ = writeln!(
+            table,
+            "Most positive and negative messages (up to {} of each)",
+            format_count(n)
+        );
+        for extremes in self.extremes(n) {
+            let name = self.display_name(extremes.participant);
+            if extremes.most_positive.is_empty() && extremes.most_negative.is_empty() {
+                let _ = writeln!(table, "  {name}: no positive or negative messages");
+                continue;
+            }
+            for (label, listed) in [
+                ("most positive", &extremes.most_positive),
+                ("most negative", &extremes.most_negative),
+            ] {
+                if listed.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(table, "  {name}, {label}:");
+                for extreme in listed {
+                    let text = match &extreme.text {
+                        Some(text) => one_line(text, width),
+                        None => "(text dropped)".to_string(),
+                    };
+                    let _ = writeln!(
+                        table,
+                        "    {}  {:+.3}  {text}",
+                        format_date_time(extreme.timestamp.timestamp_millis().max(0) as usize),
+                        extreme.compound,
+                    );
+                }
+            }
+        }
+        table
+    }
+}
+
+/// The text on one line, its whitespace (line breaks included) collapsed into single spaces, cut to `width` characters
+/// with an ellipsis if it's longer.
+fn one_line(text: &str, width: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= width {
+        return text;
+    }
+    let mut cut = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    cut.push('…');
+    cut
+}
@@ -0,0 +1,158 @@
+//! Module containing the formatting of the numbers shown to people: durations, counts, and changes, so the report,
+//! the plots, and the warnings all show them the same way (a week is "7d" everywhere).
+//!
+//! The exports keep raw numbers, these are only for reading. There's no localization, counts are grouped with commas.
+
+/// A second, in milliseconds.
+pub const SECOND_MS: f64 = 1000.0;
+/// A minute, in milliseconds.
+pub const MINUTE_MS: f64 = 60.0 * SECOND_MS;
+/// An hour, in milliseconds.
+pub const HOUR_MS: f64 = 60.0 * MINUTE_MS;
+/// A day, in milliseconds.
+pub const DAY_MS: f64 = 24.0 * HOUR_MS;
+/// A year as shown in durations, 365 days.
+pub const YEAR_MS: f64 = 365.0 * DAY_MS;
+
+/// The units of `format_duration`, from the smallest up, with their length in milliseconds.
+const UNITS: [(&str, f64); 5] = [
+    ("s", SECOND_MS),
+    ("m", MINUTE_MS),
+    ("h", HOUR_MS),
+    ("d", DAY_MS),
+    ("y", YEAR_MS),
+];
+
+/// A duration of `ms` milliseconds, in the largest unit it's at least one of, and the next smaller unit if it isn't
+/// a whole number of them, rounded to that smaller unit. Seconds are whole, under a second it's whole milliseconds.
+///
+/// ```
+/// use igdm_sentiment_analysis::format::{format_duration, DAY_MS, HOUR_MS, MINUTE_MS, YEAR_MS};
+///
+/// assert_eq!(format_duration(0.0), "0ms");
+/// assert_eq!(format_duration(850.4), "850ms");
+/// assert_eq!(format_duration(999.7), "1s");
+/// assert_eq!(format_duration(42_000.0), "42s");
+/// assert_eq!(format_duration(59_600.0), "1m");
+/// assert_eq!(format_duration(90_000.0), "1m 30s");
+/// assert_eq!(format_duration(2.0 * HOUR_MS + 14.0 * MINUTE_MS), "2h 14m");
+/// assert_eq!(format_duration(24.0 * HOUR_MS - 10_000.0), "1d");
+/// assert_eq!(format_duration(3.0 * DAY_MS + 6.0 * HOUR_MS), "3d 6h");
+/// assert_eq!(format_duration(7.0 * DAY_MS), "7d");
+/// assert_eq!(format_duration(YEAR_MS + 20.0 * DAY_MS), "1y 20d");
+/// assert_eq!(format_duration(12.0 * YEAR_MS), "12y");
+/// assert_eq!(format_duration(-90.0 * MINUTE_MS), "-1h 30m");
+/// assert_eq!(format_duration(f64::NAN), "n/a");
+/// ```
+pub fn format_duration(ms: f64) -> String {
+    if !ms.is_finite() {
+        return "n/a".to_string();
+    }
+    if ms < 0.0 {
+        return format!("-{}", format_duration(-ms));
+    }
+    let ms = ms.round();
+    if ms < SECOND_MS {
+        return format!("{ms:.0}ms");
+    }
+    // rounding can carry into the next unit (59.6s is 1m), so pick the unit after rounding to its smaller unit
+    for (i, (unit, size)) in UNITS.iter().enumerate() {
+        let (smaller, resolution) = match i {
+            0 => ("", SECOND_MS),
+            _ => UNITS[i - 1],
+        };
+        let rounded = (ms / resolution).round() * resolution;
+        if UNITS.get(i + 1).is_some_and(|(_, next)| rounded >= *next) {
+            continue;
+        }
+        let whole = (rounded / size).floor();
+        let rest = ((rounded - whole * size) / resolution).round();
+        return if rest == 0.0 {
+            format!("{whole:.0}{unit}")
+        } else {
+            format!("{whole:.0}{unit} {rest:.0}{smaller}")
+        };
+    }
+    unreachable!("the largest unit always matches")
+}
+
+/// A count, with its thousands separated by commas.
+///
+/// ```
+/// use igdm_sentiment_analysis::format::format_count;
+///
+/// assert_eq!(format_count(0), "0");
+/// assert_eq!(format_count(999), "999");
+/// assert_eq!(format_count(1_000), "1,000");
+/// assert_eq!(format_count(12_345), "12,345");
+/// assert_eq!(format_count(1_234_567_890), "1,234,567,890");
+/// ```
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+/// A change, signed and with `precision` decimals, after an arrow pointing the way it went,
+/// or "=" if it rounds to nothing.
+///
+/// ```
+/// use igdm_sentiment_analysis::format::format_delta;
+///
+/// assert_eq!(format_delta(0.1234, 3), "▲ +0.123");
+/// assert_eq!(format_delta(-0.05, 3), "▼ -0.050");
+/// assert_eq!(format_delta(0.0004, 3), "= 0.000");
+/// assert_eq!(format_delta(-0.0004, 3), "= 0.000");
+/// assert_eq!(format_delta(2.26, 1), "▲ +2.3");
+/// assert_eq!(format_delta(f64::NAN, 2), "n/a");
+/// ```
+pub fn format_delta(delta: f64, precision: usize) -> String {
+    if !delta.is_finite() {
+        return "n/a".to_string();
+    }
+    match format!("{:.precision$}", delta.abs()) {
+        magnitude if magnitude.bytes().all(|byte| matches!(byte, b'0' | b'.')) => {
+            format!("= {magnitude}")
+        }
+        magnitude if delta > 0.0 => format!("▲ +{magnitude}"),
+        magnitude => format!("▼ -{magnitude}"),
+    }
+}
+
+/// A change in a duration of `ms` milliseconds, as `format_duration` after the arrow of `format_delta`.
+///
+/// ```
+/// use igdm_sentiment_analysis::format::{format_duration_delta, DAY_MS, HOUR_MS};
+///
+/// assert_eq!(format_duration_delta(2.0 * DAY_MS + 3.0 * HOUR_MS), "▲ 2d 3h");
+/// assert_eq!(format_duration_delta(-5.0 * HOUR_MS), "▼ 5h");
+/// assert_eq!(format_duration_delta(0.2), "= 0ms");
+/// ```
+pub fn format_duration_delta(ms: f64) -> String {
+    let duration = format_duration(ms.abs());
+    match ms {
+        ms if !ms.is_finite() => duration,
+        ms if ms.abs() < 0.5 => format!("= {duration}"),
+        ms if ms > 0.0 => format!("▲ {duration}"),
+        _ => format!("▼ {duration}"),
+    }
+}
+
+/// The (UTC) date and time of the timestamp, or the timestamp itself if it's out of range.
+///
+/// ```
+/// use igdm_sentiment_analysis::format::format_date_time;
+///
+/// assert_eq!(format_date_time(1_700_000_000_000), "2023-11-14 22:13 UTC");
+/// ```
+pub fn format_date_time(timestamp_ms: usize) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp_ms as i64)
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp_ms.to_string())
+}
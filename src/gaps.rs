@@ -0,0 +1,83 @@
+//! Module containing the analysis of the silences between messages: the time between consecutive messages,
+//! regardless of who sent them.
+//!
+//! Only the time between two messages is a gap, so the time before the first message never counts,
+//! and neither does the time across a boundary between message files (see `parser::ExportBoundaries`).
+
+use crate::{
+    aggregate::{self, Period},
+    analyzer::AnalyzedConversation,
+    format::HOUR_MS,
+    stats::quantile,
+};
+
+/// A gap between two consecutive messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct Gap {
+    pub from_ms: usize,
+    pub to_ms: usize,
+}
+
+impl Gap {
+    /// How long the gap is, in milliseconds.
+    pub fn duration_ms(&self) -> f64 {
+        (self.to_ms - self.from_ms) as f64
+    }
+
+    pub fn hours(&self) -> f64 {
+        self.duration_ms() / HOUR_MS
+    }
+}
+
+/// The distribution of the gaps starting in a week.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct WeeklyGaps {
+    /// midnight (UTC) on the Monday starting the week, in milliseconds since the epoch
+    pub week_start_ms: usize,
+    pub gaps: usize,
+    pub median_hours: f64,
+    pub p90_hours: f64,
+}
+
+impl AnalyzedConversation {
+    /// The gaps between every pair of consecutive messages, in order.
+    pub fn gaps(&self) -> Vec<Gap> {
+        self.timeline()
+            .windows(2)
+            .map(|window| Gap {
+                from_ms: window[0].0.timestamp_ms(),
+                to_ms: window[1].0.timestamp_ms(),
+            })
+            .filter(|gap| !self.export_boundaries.spans(gap.from_ms, gap.to_ms))
+            .collect()
+    }
+
+    /// The median and 90th percentile of the gaps starting in each week that has any, in order.
+    pub fn weekly_gaps(&self) -> Vec<WeeklyGaps> {
+        aggregate::bucket(
+            self.gaps()
+                .into_iter()
+                .map(|gap| (gap.from_ms, gap.hours())),
+            Period::Week,
+        )
+        .into_iter()
+        .filter_map(|(week, mut hours)| {
+            hours.sort_by(f64::total_cmp);
+            Some(WeeklyGaps {
+                week_start_ms: Period::Week.start_ms(week),
+                gaps: hours.len(),
+                median_hours: quantile(&hours, 0.5)?,
+                p90_hours: quantile(&hours, 0.9)?,
+            })
+        })
+        .collect()
+    }
+
+    /// The longest gap in the conversation (the earliest, if there's a tie).
+    pub fn longest_silence(&self) -> Option<Gap> {
+        self.gaps()
+            .into_iter()
+            .rev()
+            .max_by_key(|gap| gap.to_ms - gap.from_ms)
+    }
+}
@@ -0,0 +1,139 @@
+//! Module detecting the language messages are written in, since vader only knows english: it reads the words of other
+//! languages as neutral (or worse, as english words that happen to be spelled the same), so their scores are noise.
+//!
+//! Detection is conservative, a language is only given to a message with at least `MIN_WORDS` words, detected with a
+//! confidence of at least `MIN_CONFIDENCE`, so short english messages ("lol that was so funny") aren't taken for another
+//! language. Chat messages are short, and detection is much more accurate among the few languages a conversation is
+//! in (english is always one of them) than among every language there is. Languages are named by their ISO 639-3 codes.
+//!
+//! ```
+//! use igdm_sentiment_analysis::language::{Language, LanguageDetector};
+//!
+//! let spanish: Language = "spa".parse()?;
+//! let detector = LanguageDetector::new(&[spanish]);
+//! let detect = |text| detector.detect(text).map(Language::code);
+//! assert_eq!(detect("I can't wait to see you this weekend, it's going to be great"), Some("eng"));
+//! assert_eq!(detect("no puedo esperar a verte este fin de semana, va a ser genial"), Some("spa"));
+//! assert_eq!(detect("te quiero mucho amor"), Some("spa"));
+//! // too short, or too unsure, to tell
+//! assert_eq!(detect("lol that was so funny"), None);
+//! assert_eq!(detect("i love you so much"), None);
+//! assert_eq!(detect("jajaja sí"), None);
+//!
+//! assert_eq!(spanish.name(), "Spanish");
+//! assert!("xyz".parse::<Language>().unwrap_err().to_string().contains("spa"));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{anyhow, Error};
+use whatlang::{Detector, Lang};
+
+/// How many words (runs of characters with a letter among them) a message needs for its language to be detected.
+pub const MIN_WORDS: usize = 3;
+
+/// How confident the detection has to be (from 0 to 1) for a message to be given a language.
+pub const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Codes of languages, given as examples when a code isn't one.
+const EXAMPLES: [&str; 4] = ["eng", "spa", "por", "fra"];
+
+/// A language, serialized as its ISO 639-3 code (e.g. `eng`, `spa`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Language(Lang);
+
+impl Language {
+    /// The only language vader knows.
+    pub const ENGLISH: Self = Self(Lang::Eng);
+
+    /// The ISO 639-3 code of the language.
+    pub fn code(self) -> &'static str {
+        self.0.code()
+    }
+
+    /// The english name of the language.
+    pub fn name(self) -> &'static str {
+        self.0.eng_name()
+    }
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    /// Reads the ISO 639-3 code of a language, e.g. `spa`.
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        Lang::from_code(code.to_ascii_lowercase())
+            .map(Self)
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown language \"{code}\", expected an ISO 639-3 code like {}",
+                    EXAMPLES.join(", ")
+                )
+            })
+    }
+}
+
+impl TryFrom<String> for Language {
+    type Error = Error;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        code.parse()
+    }
+}
+
+impl From<Language> for String {
+    fn from(language: Language) -> Self {
+        language.code().to_string()
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Detects the languages of messages among a few languages, or every language it knows (by default).
+#[derive(Debug, Clone, Default)]
+pub struct LanguageDetector(Detector);
+
+impl LanguageDetector {
+    /// Detects among the languages and english, or among every language it knows if none are given.
+    pub fn new(languages: &[Language]) -> Self {
+        if languages.is_empty() {
+            return Self::default();
+        }
+        let mut allowed = languages
+            .iter()
+            .map(|language| language.0)
+            .collect::<Vec<_>>();
+        if !allowed.contains(&Lang::Eng) {
+            allowed.push(Lang::Eng);
+        }
+        Self(Detector::with_allowlist(allowed))
+    }
+
+    /// The language the text is written in, `None` unless it's detected confidently, see the module docs.
+    pub fn detect(&self, text: &str) -> Option<Language> {
+        self.detect_with_confidence(text)
+            .map(|(language, _)| language)
+    }
+
+    /// The language the text is written in and how confident the detection is (from `MIN_CONFIDENCE` to 1), `None`
+    /// unless it's detected confidently.
+    pub fn detect_with_confidence(&self, text: &str) -> Option<(Language, f64)> {
+        let words = text
+            .split_whitespace()
+            .filter(|word| word.chars().any(char::is_alphabetic))
+            .count();
+        if words < MIN_WORDS {
+            return None;
+        }
+        self.0
+            .detect(text)
+            .filter(|info| info.confidence() >= MIN_CONFIDENCE)
+            .map(|info| (Language(info.lang()), info.confidence()))
+    }
+}
@@ -0,0 +1,214 @@
+//! Module containing words added to vader's lexicon (see `analyzer::Vader`), for the slang and inside jokes of a
+//! conversation that vader takes for neutral words.
+//!
+//! A lexicon is read from a tab separated file of words and their valences, from -4 (most negative) to 4 (most
+//! positive) like vader's, one per line (blank lines and lines starting with `#` are skipped), or from a json object of
+//! words and valences. Words vader already knows keep vader's valence, unless the lexicon overrides them.
+//!
+//! ```
+//! use igdm_sentiment_analysis::{
+//!     analyzer::{AnalyzeOptions, Analyzer},
+//!     lexicon::Lexicon,
+//! };
+//!
+//! let lexicon = Lexicon::from_tsv("# slang\nbased\t2.5\nmid\t-1.5\nvibes\nlol\t9\ngood\t-3\n");
+//! assert_eq!(
+//!     lexicon.warnings(),
+//!     [
+//!         "line 4: expected a word and its valence separated by a tab",
+//!         "line 5: the valence of \"lol\" is 9, outside of -4 to 4",
+//!         "\"good\" is already in vader's lexicon, ignored unless the lexicon overrides it",
+//!     ]
+//! );
+//!
+//! let score = |lexicon: &Lexicon, text: &str| {
+//!     let options = AnalyzeOptions { lexicon: lexicon.clone(), ..AnalyzeOptions::default() };
+//!     let score = Analyzer::new(&options).score(text)?;
+//!     Ok::<_, anyhow::Error>(score.compound)
+//! };
+//! assert_eq!(score(&Lexicon::default(), "that movie was mid")?, 0.0);
+//! assert!(score(&lexicon, "that movie was mid")? < 0.0);
+//! assert!(score(&lexicon, "she's so based")? > 0.0);
+//!
+//! assert!(score(&lexicon, "good")? > 0.0);
+//! let overriding = lexicon.overriding(true);
+//! assert!(overriding.warnings().len() == 2);
+//! assert!(score(&overriding, "good")? < 0.0);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Context, Result};
+use unicase::UniCase;
+
+use crate::emoji;
+
+/// How far from neutral a valence can be, either way, in vader's lexicon.
+pub const MAX_VALENCE: f64 = 4.0;
+
+/// Words and their valences, added to vader's lexicon.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lexicon {
+    /// the words (lowercase, vader's lexicon ignores case) and their valences, in the order they were read, the words are
+    /// leaked as they're read since vader's analyzer borrows its lexicon (a lexicon is only read once for a run)
+    entries: Vec<(&'static str, f64)>,
+    /// whether the words vader already knows take the valences of this lexicon
+    overriding: bool,
+    /// what was wrong with the lines that were skipped
+    skipped: Vec<String>,
+}
+
+impl Lexicon {
+    /// A lexicon of the words and valences, a word listed again takes the later valence.
+    pub fn new(entries: impl IntoIterator<Item = (String, f64)>) -> Self {
+        let mut lexicon = Self::default();
+        for (word, valence) in entries {
+            lexicon.insert(word, valence);
+        }
+        lexicon
+    }
+
+    /// Reads the lexicon from a json file (by its extension) or a tab separated one.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read the lexicon from {}", path.display()))?;
+        if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        {
+            Self::from_json(&contents)
+                .with_context(|| format!("failed to parse the lexicon from {}", path.display()))
+        } else {
+            Ok(Self::from_tsv(&contents))
+        }
+    }
+
+    /// Reads a tab separated lexicon, skipping the lines that aren't a word and its valence (see `Lexicon::warnings`).
+    pub fn from_tsv(contents: &str) -> Self {
+        let mut lexicon = Self::default();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let entry = line
+                .split_once('\t')
+                .ok_or_else(|| "expected a word and its valence separated by a tab".to_string())
+                .and_then(|(word, valence)| {
+                    let (word, valence) = (word.trim(), valence.trim());
+                    let valence = valence.parse().map_err(|_| {
+                        format!("the valence of \"{word}\" isn't a number: \"{valence}\"")
+                    })?;
+                    check(word, valence).map(|()| (word, valence))
+                });
+            match entry {
+                Ok((word, valence)) => lexicon.insert(word.to_string(), valence),
+                Err(problem) => lexicon
+                    .skipped
+                    .push(format!("line {}: {problem}", number + 1)),
+            }
+        }
+        lexicon
+    }
+
+    /// Reads a json lexicon, an object of words and their valences, skipping the words whose valences aren't sensible
+    /// (see `Lexicon::warnings`), failing if it isn't such an object.
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let entries: serde_json::Map<String, serde_json::Value> = serde_json::from_str(contents)?;
+        let mut lexicon = Self::default();
+        for (word, valence) in entries {
+            let valence = valence
+                .as_f64()
+                .ok_or_else(|| format!("the valence of \"{word}\" isn't a number: {valence}"))
+                .and_then(|valence| check(&word, valence).map(|()| valence));
+            match valence {
+                Ok(valence) => lexicon.insert(word, valence),
+                Err(problem) => lexicon.skipped.push(problem),
+            }
+        }
+        Ok(lexicon)
+    }
+
+    fn insert(&mut self, word: String, valence: f64) {
+        let word = word.to_lowercase();
+        match self.entries.iter_mut().find(|(listed, _)| *listed == word) {
+            Some(entry) => entry.1 = valence,
+            None => self
+                .entries
+                .push((Box::leak(word.into_boxed_str()), valence)),
+        }
+    }
+
+    /// Whether the words vader already knows take the valences of this lexicon rather than keeping vader's.
+    #[must_use]
+    pub fn overriding(mut self, overriding: bool) -> Self {
+        self.overriding = overriding;
+        self
+    }
+
+    /// The words and valences of the lexicon, lowercase, in the order they were read.
+    pub fn entries(&self) -> &[(&'static str, f64)] {
+        &self.entries
+    }
+
+    /// The words and valences added to vader's lexicon: all of them when overriding, else those vader doesn't know.
+    pub fn applied(&self) -> impl Iterator<Item = (&'static str, f64)> + '_ {
+        self.entries
+            .iter()
+            .copied()
+            .filter(|(word, _)| self.overriding || !in_vader(word))
+    }
+
+    /// What's wrong with the lines that were skipped, by line number in a tab separated lexicon, and the words vader
+    /// already knows when the lexicon doesn't override them.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.skipped.clone();
+        if !self.overriding {
+            warnings.extend(
+                self.entries
+                    .iter()
+                    .filter(|(word, _)| in_vader(word))
+                    .map(|(word, _)| {
+                        format!("\"{word}\" is already in vader's lexicon, ignored unless the lexicon overrides it")
+                    }),
+            );
+        }
+        warnings
+    }
+
+    /// Whether no words are added to vader's lexicon.
+    pub fn is_empty(&self) -> bool {
+        self.applied().next().is_none()
+    }
+
+    /// Vader's lexicon with the words the emoji are scored as (see `emoji`), and the words of this one.
+    pub(crate) fn merged(&self) -> HashMap<UniCase<&'static str>, f64> {
+        vader_sentiment::LEXICON
+            .iter()
+            .map(|(word, valence)| (**word, *valence))
+            .chain(emoji::words())
+            .chain(self.applied())
+            .map(|(word, valence)| (UniCase::new(word), valence))
+            .collect()
+    }
+}
+
+/// What's wrong with the entry, if it can't be added.
+fn check(word: &str, valence: f64) -> Result<(), String> {
+    if word.is_empty() || word.contains(char::is_whitespace) {
+        // vader looks words up one token at a time
+        Err(format!("\"{word}\" isn't a single word"))
+    } else if !(-MAX_VALENCE..=MAX_VALENCE).contains(&valence) {
+        Err(format!(
+            "the valence of \"{word}\" is {valence}, outside of -{MAX_VALENCE} to {MAX_VALENCE}"
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Whether the word is in vader's lexicon.
+fn in_vader(word: &str) -> bool {
+    vader_sentiment::LEXICON.contains_key(&UniCase::new(word))
+}
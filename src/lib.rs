@@ -0,0 +1,85 @@
+//! Runs sentiment analysis on instagram DM's and plots them over time.
+//!
+//! The binary in `main.rs` is a thin wrapper around `pipeline::Pipeline`, the individual stages are
+//! exposed as well so that benchmarks (and other tools) can drive them directly.
+//!
+//! The heavier dependencies are behind cargo features, all on by default:
+//! - `plot` - the plots (`plotter`), with `plotters`, and `image` to encode them as png
+//! - `zip` - reading an export's zip archive (`parser::ConversationDirectory`), and writing the outputs into one
+//!   (`sink::ZipSink`)
+//! - `html` - reading the message files of an export requested in html (`parser::html`), with `scraper`
+//! - `gzip` - reading gzip-compressed message files (`message_1.json.gz`), with `flate2`
+//!
+//! Parsing, analysis, the report, and the exports work without any features.
+//!
+//! ```
+//! #[cfg(feature = "plot")]
+//! use igdm_sentiment_analysis::plotter::PlotType;
+//! use igdm_sentiment_analysis::{
+//!     pipeline::Pipeline,
+//!     sink::DirectorySink,
+//!     synthetic::{self, BenchFixture},
+//! };
+//!
+//! // generate a small conversation to analyze
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-doc");
+//! synthetic::write_export(&BenchFixture::Small.config(), &dir)?;
+//!
+//! let mut output = Pipeline::new(&dir).run()?;
+//! assert_eq!(output.analysis.analysis.len(), 2);
+//!
+//! let mut sink = DirectorySink::new(dir.join("output"));
+//! output.write_report(&mut sink)?;
+//! output.write_json_export(&mut sink)?;
+//! #[cfg(feature = "plot")]
+//! output.write_plots(&mut sink, &[PlotType::Compound])?;
+//! assert!(output.manifest.failed_artifacts().next().is_none());
+//! output.write_manifest(&mut sink)?;
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub mod aggregate;
+pub mod analyzer;
+pub mod bursts;
+pub mod calendar;
+pub mod compare;
+pub mod diagnostics;
+pub mod drift;
+pub mod emoji;
+pub mod events;
+pub mod export;
+pub mod extremes;
+pub mod format;
+pub mod gaps;
+pub mod language;
+pub mod lexicon;
+pub mod manifest;
+pub mod openers;
+pub mod palette;
+pub mod parser;
+pub mod phases;
+pub mod pipeline;
+#[cfg(feature = "plot")]
+pub mod plotter;
+pub mod progress;
+pub mod quiz;
+pub mod quotes;
+pub mod register;
+pub mod relationship;
+pub mod report;
+pub mod review;
+pub mod rhythm;
+pub mod rng;
+pub mod settings;
+pub mod sink;
+pub mod stats;
+pub mod summary;
+pub mod synthetic;
+pub mod text;
+pub mod threads;
+pub mod timeseries;
+pub mod timezone;
+pub mod tokenizer;
+pub mod transform;
+pub mod weekly;
@@ -1,46 +1,910 @@
-mod analyzer;
-mod parser;
-mod plotter;
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
 
-use std::path::PathBuf;
+use anyhow::{bail, Context, Result};
+use chrono::{FixedOffset, NaiveDate, TimeDelta};
+use clap::{Parser, Subcommand};
+#[cfg(feature = "plot")]
+use igdm_sentiment_analysis::plotter::PlotType;
+#[cfg(feature = "zip")]
+use igdm_sentiment_analysis::sink::ZipSink;
+use igdm_sentiment_analysis::{
+    aggregate::Period,
+    analyzer::{AnalyzeOptions, Analyzer, Thresholds},
+    calendar::CalendarConfig,
+    drift::{EarlierAnalysis, MismatchPolicy},
+    events::{EventConfig, HolidaySet, MonthDay},
+    format::format_count,
+    language::Language,
+    lexicon::Lexicon,
+    manifest::Manifest,
+    palette::Palette,
+    parser::{
+        html::{HtmlOptions, DEFAULT_DATE_FORMAT},
+        whatsapp::{DateOrder, WhatsAppOptions},
+        Aliases, InboxDirectory, InputFormat, SharedCaptions, SystemMessages, TimestampPolicy,
+        TimestampUnit, TimestampValidation,
+    },
+    pipeline::Pipeline,
+    progress::{Phase, Progress},
+    quiz::QuizConfig,
+    quotes::QuotePolicy,
+    rng::Seed,
+    settings::Profile,
+    sink::{DirectorySink, OutputSink},
+    stats::AggregateStat,
+    synthetic::{self, BenchFixture, SyntheticConfig},
+    threads::{ThreadComparison, ThreadProfile},
+    timezone::{self, Tz},
+    transform::{
+        CleanText, CollapseRepeats, DropUnsent, MessageTransform, RepeatCount, TransformPipeline,
+    },
+};
+use indicatif::{ProgressBar, ProgressFinish, ProgressStyle};
 
-use anyhow::Result;
-use clap::Parser;
-use parser::ConversationDirectory;
+/// How many characters of the thread titles the table of the threads of an inbox shows.
+const THREAD_TITLE_CHARS: usize = 32;
 
 #[derive(Parser, Debug)]
-#[command(version, author, about, long_about = None)]
+#[command(version, author, about, long_about = None, subcommand_negates_reqs = true)]
 struct Args {
-    #[arg(short, long, help = "input directory containing message json files")]
-    path: PathBuf,
+    #[arg(
+        short,
+        long,
+        required = true,
+        help = "input directory containing message json files (or a folder above them, if theirs is the only conversation below it), a single message file, or the zip archive of an export (optionally followed by the folder of a conversation in it)"
+    )]
+    path: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value = "./output",
+        help = "directory to write the plots and other outputs to"
+    )]
+    output_dir: PathBuf,
+    #[arg(
+        long,
+        conflicts_with = "output_dir",
+        help = "write every output into this zip archive instead of a directory"
+    )]
+    output_zip: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with = "output_zip",
+        help = "treat the input directory as the inbox of an export (messages/inbox), or its zip archive, analyzing every conversation in it into a folder of the output directory named after it"
+    )]
+    inbox: bool,
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        conflicts_with = "inbox",
+        help = "read the files of the input directory named like this pattern (* standing for any text, e.g. \"chat-*.json\") as its message files, instead of those named message_<number>.json"
+    )]
+    glob: Option<String>,
+    #[arg(long, help = "record how long each phase took in the manifest")]
+    timings: bool,
+    #[arg(
+        long,
+        help = "parse the conversation every time, instead of loading it from the cache in the output directory's .cache folder when its message files haven't changed"
+    )]
+    no_cache: bool,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = Seed::default(),
+        help = "seed for everything randomized, a number or `random`"
+    )]
+    seed: Seed,
+    #[arg(
+        long,
+        help = "how many threads to parse message files, score messages, and resample on [default: one per cpu]"
+    )]
+    threads: Option<usize>,
+    #[arg(
+        long,
+        help = "use less memory on very large conversations, by dropping message text once it's been scored"
+    )]
+    low_memory: bool,
+    #[arg(
+        long,
+        help = "wipe message text from memory as soon as it's been scored, so no output can contain it (features that need the text are unavailable)"
+    )]
+    drop_content_after_analysis: bool,
+    #[arg(
+        long,
+        help = "exit successfully even if some of the outputs couldn't be written"
+    )]
+    best_effort: bool,
+    #[arg(
+        long,
+        help = "skip message files that can't be read or parsed, listing them at the end, instead of failing"
+    )]
+    lenient: bool,
+    #[arg(
+        long,
+        help = "fail if a message file is missing from the numbering of the others (like message_2.json, beside message_1.json and message_3.json), instead of warning about the gap"
+    )]
+    strict_numbering: bool,
+    #[arg(
+        long,
+        help = "fail on message files with fields this version doesn't know of (at their top level or in their messages), naming them, to notice when exports start having something new"
+    )]
+    strict_fields: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = InputFormat::Auto,
+        alias = "format",
+        help = "what the message files are, `auto` reads .txt files as whatsapp chats and .html files as html exports, and tells telegram chats by their contents, discord exports are never guessed"
+    )]
+    input_format: InputFormat,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DateOrder::Auto,
+        help = "whether the dates of a whatsapp chat are day or month first, `auto` tells from the dates that only make sense one way"
+    )]
+    date_order: DateOrder,
+    #[arg(
+        long,
+        default_value = DEFAULT_DATE_FORMAT,
+        help = "the format of the timestamps of an html export (in chrono's strftime syntax), which are written in the language it was requested in"
+    )]
+    html_date_format: String,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = Thresholds::default().positive,
+        allow_negative_numbers = true,
+        help = "compound scores above this count as positive"
+    )]
+    positive_threshold: f64,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = Thresholds::default().negative,
+        allow_negative_numbers = true,
+        help = "compound scores below this count as negative"
+    )]
+    negative_threshold: f64,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TimestampPolicy::default(),
+        help = "what to do with messages whose timestamp is outside --plausible-since..--plausible-until, they're always counted in the diagnostics"
+    )]
+    implausible_timestamps: TimestampPolicy,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TimestampUnit::Milliseconds,
+        help = "the unit of the timestamps of the message files, `auto` reads those too small to be milliseconds as seconds"
+    )]
+    timestamp_unit: TimestampUnit,
+    #[arg(
+        long,
+        value_name = "DATE",
+        default_value = "2004-01-01",
+        help = "messages from before this date (YYYY-MM-DD, utc) have implausible timestamps"
+    )]
+    plausible_since: NaiveDate,
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "messages from after this date (YYYY-MM-DD, utc) have implausible timestamps [default: tomorrow]"
+    )]
+    plausible_until: Option<NaiveDate>,
+    #[arg(
+        long,
+        default_value_t = 360,
+        help = "minutes of silence after which the conversation is considered a new session, replies never span sessions"
+    )]
+    session_gap: u32,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 1,
+        help = "leave messages with fewer tokens (words, emoji, urls, mentions) than this unscored, still counting them in message volumes (the default leaves out messages of only punctuation or emoticons, 0 scores them too)"
+    )]
+    min_tokens: usize,
+    #[arg(
+        long,
+        global = true,
+        help = "score messages without their urls and @mentions, leaving messages with nothing else unscored like those with only an attachment"
+    )]
+    strip_urls_and_mentions: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "add the words of this lexicon to vader's, for slang it takes for neutral words: a tab separated file of words and valences from -4 to 4 (one per line), or a json object of them"
+    )]
+    lexicon: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        requires = "lexicon",
+        help = "let the words of --lexicon that vader already knows take the lexicon's valences, instead of ignoring them"
+    )]
+    lexicon_overrides: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "CODES",
+        value_delimiter = ',',
+        help = "the languages the conversation is in besides english, as ISO 639-3 codes (e.g. spa,por), to detect the languages of messages among [default: every language]"
+    )]
+    languages: Vec<Language>,
+    #[arg(
+        long,
+        global = true,
+        help = "leave the messages detected to be in another language than english unscored (vader only knows english), they still count in message volumes"
+    )]
+    english_only: bool,
+    #[arg(
+        long,
+        default_value_t = AggregateStat::default(),
+        help = "how to aggregate compound scores over days, smoothing windows, and the summary: mean, median, trimmed:PERCENT, or winsorized:PERCENT"
+    )]
+    aggregate_stat: AggregateStat,
+    #[arg(
+        long,
+        default_value_t = QuotePolicy::default(),
+        help = "how much messages that look quoted (pasted, in quotation marks, or impersonal) count in the summary and daily aggregates: keep, exclude, or down-weight:WEIGHT"
+    )]
+    quoted: QuotePolicy,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SharedCaptions::default(),
+        help = "whether the captions of shared posts and reels count toward sentiment, as part of the message sharing them"
+    )]
+    shared_captions: SharedCaptions,
+    #[arg(
+        long,
+        help = "collapse runs of 3 or more consecutive repeats of a message from the same sender (chain messages, copypasta) into one, the largest runs are listed in the warnings"
+    )]
+    collapse_repeats: bool,
+    #[arg(
+        long,
+        help = "keep the messages their sender unsent, which are left out by default"
+    )]
+    keep_unsent: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "keep the text of messages as exported, instead of composing its accents (NFC) and removing invisible characters like zero-width spaces"
+    )]
+    raw_text: bool,
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "also keep messages whose whole text matches this pattern (* standing for any text, e.g. \"* a aimé un message\") apart as notices instagram wrote itself, rather than scoring them, can be repeated"
+    )]
+    system_message: Vec<String>,
+    #[arg(
+        long,
+        conflicts_with = "system_message",
+        help = "score the notices instagram writes itself (like \"Liked a message\"), which are kept apart by default"
+    )]
+    score_system_messages: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = RepeatCount::default(),
+        help = "how a message standing for collapsed repeats counts in message volumes (message counts, daily volumes), its sentiment always counts once"
+    )]
+    count_repeats: RepeatCount,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_name = "BASELINE,VARIANT",
+        help = "instead of the usual outputs, compare the analysis under two settings files (toml), e.g. baseline.toml,variant.toml"
+    )]
+    compare_settings: Option<Vec<PathBuf>>,
+    #[arg(
+        long,
+        help = "also write the scores, reply pairs, and relationship metrics to analysis.json"
+    )]
+    export_json: bool,
+    #[arg(
+        long,
+        help = "also plot how each participant's replies track the tone of the messages they reply to"
+    )]
+    plot_responsiveness: bool,
+    #[arg(
+        long,
+        help = "also draw a printable sheet of small monthly plots of the daily mean compound, a row per year"
+    )]
+    plot_sparklines: bool,
+    #[arg(
+        long,
+        help = "also plot how many messages each participant sent every day"
+    )]
+    plot_volume: bool,
+    #[arg(
+        long,
+        requires = "plot_volume",
+        help = "overlay the decay fitted after each burst of messages on the volume plot"
+    )]
+    overlay_decay: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Palette::default(),
+        help = "how the participants are told apart in the plots, mono uses marker shapes and dash patterns instead of colors, for printing in grayscale"
+    )]
+    palette: Palette,
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = "UTC",
+        value_parser = timezone::parse,
+        help = "the IANA time zone the times of the plots are shown in, days start and end in (for the holidays and birthdays, --export-ics, and --export-rhythm), and the timestamps of whatsapp chats and html exports are in, e.g. Europe/London"
+    )]
+    timezone: Tz,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "also write every score and daily means as influxdb line protocol (ms precision) to this file, relative to the output"
+    )]
+    export_lineprotocol: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "also write daily mean compound scores, one column per participant, as csv to this file, relative to the output"
+    )]
+    export_csv_timeseries: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Period::Day,
+        requires = "export_csv_timeseries",
+        help = "the calendar periods (utc days from midnight, weeks from monday, or months from the 1st) --export-csv-timeseries has a row for"
+    )]
+    timeseries_period: Period,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "also write a digest of each participant's weeks (message count, scores, reply time, top emoji) as csv to this file, relative to the output"
+    )]
+    export_weekly: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "also write each participant's message count and mean compound score by hour of the day and day of the week (in --timezone) as csv to this file, relative to the output"
+    )]
+    export_rhythm: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "also write an icalendar file with an all-day event per day with messages (in --timezone), to this file relative to the output"
+    )]
+    export_ics: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["low_memory", "drop_content_after_analysis"],
+        help = "also write the parsed conversation (every message file merged, decoded, cleaned and ordered by time) as json to this file, relative to the output"
+    )]
+    export_conversation: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = CalendarConfig::default().min_messages,
+        help = "leave days with fewer messages than this out of --export-ics"
+    )]
+    ics_min_messages: usize,
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        help = "built-in sets of holidays to mark on the compound plot and compare in the report"
+    )]
+    holidays: Vec<HolidaySet>,
+    #[arg(
+        long,
+        help = "a yearly date (MM-DD) to mark on the compound plot and compare in the report, can be repeated"
+    )]
+    birthday: Vec<MonthDay>,
+    #[arg(
+        long,
+        allow_hyphen_values = true,
+        conflicts_with = "timezone",
+        help = "deprecated, pass --timezone instead: a utc offset of whole hours, read as the Etc/GMT time zone of that offset"
+    )]
+    utc_offset: Option<FixedOffset>,
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "your name as it appears in the conversation, otherwise it's detected from the export when the conversation is inside a full export"
+    )]
+    me: Option<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "a toml (or .json) file of names to read as other names (`\"old name\" = \"new name\"`), so someone who changed their display name is one participant"
+    )]
+    aliases: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "RESAMPLES",
+        help = "give the summary numbers 95% confidence intervals, from this many bootstrap resamples (e.g. 1000)"
+    )]
+    bootstrap: Option<usize>,
+    #[arg(
+        long,
+        value_name = "QUESTIONS",
+        conflicts_with_all = ["low_memory", "drop_content_after_analysis"],
+        help = "also write a who-said-it quiz of this many strongly scored messages, with a separate answer key"
+    )]
+    quiz: Option<usize>,
+    #[arg(
+        long,
+        default_value_t = QuizConfig::default().min_magnitude,
+        help = "only quiz on messages with a compound score at least this far from 0"
+    )]
+    quiz_min_magnitude: f64,
+    #[arg(
+        long,
+        default_value_t = QuizConfig::default().max_chars,
+        help = "only quiz on messages at most this many characters long"
+    )]
+    quiz_max_chars: usize,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "also print each participant's N most positive and N most negative messages after the report"
+    )]
+    extremes: Option<usize>,
+    #[arg(
+        long,
+        value_name = "CHARS",
+        default_value_t = 60,
+        requires = "extremes",
+        help = "cut the messages --extremes prints to this many characters, each on one line"
+    )]
+    extremes_width: usize,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Generate a synthetic conversation export
+    Generate {
+        #[arg(short, long, help = "directory to write the message json files to")]
+        output: PathBuf,
+        #[arg(long, default_value_t = 2, help = "number of participants")]
+        participants: usize,
+        #[arg(long, default_value_t = 10_000, help = "number of messages")]
+        messages: usize,
+        #[arg(
+            long,
+            value_enum,
+            help = "generate one of the standard benchmark datasets, overrides the other options"
+        )]
+        bench_fixture: Option<BenchFixture>,
+    },
+    /// Score the text read from stdin as if it were a message, and print the result as json
+    Score,
+    /// Check whether the scores of an earlier analysis.json are comparable to this version's, measuring the drift if they aren't
+    Drift {
+        #[arg(help = "the analysis.json of the earlier run (written with --export-json)")]
+        earlier: PathBuf,
+        #[arg(
+            long,
+            value_enum,
+            default_value_t = MismatchPolicy::default(),
+            help = "what to do if it was scored by a different analyzer (version, lexicon, or settings)"
+        )]
+        on_mismatch: MismatchPolicy,
+        #[arg(
+            long,
+            default_value_t = 500,
+            help = "how many of its messages to rescore, picked at random with --seed"
+        )]
+        sample: usize,
+    },
+    /// Write a shareable card and markdown section of one calendar year of the conversation (given with -p)
+    YearReview {
+        #[arg(help = "the calendar year to review, e.g. 2023 (utc)")]
+        year: i32,
+    },
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    // options of features this build was made without are still listed in the help, so they fail clearly instead
+    for (used, option) in [
+        (args.plot_responsiveness, "--plot-responsiveness"),
+        (args.plot_sparklines, "--plot-sparklines"),
+        (args.plot_volume, "--plot-volume"),
+        (args.overlay_decay, "--overlay-decay"),
+        (args.palette != Palette::default(), "--palette"),
+    ] {
+        if used && !cfg!(feature = "plot") {
+            return Err(missing_feature(option, "plot"));
+        }
+    }
+    if args.output_zip.is_some() && !cfg!(feature = "zip") {
+        return Err(missing_feature("--output-zip", "zip"));
+    }
+    if let Some(offset) = args.utc_offset {
+        eprintln!(
+            "warning: --utc-offset is deprecated, pass the time zone with --timezone instead"
+        );
+        args.timezone = timezone::from_offset(offset)?;
+    }
 
-    let analysis = ConversationDirectory::try_from(args.path)
-        .unwrap()
-        .parse()?
-        .analyze();
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("failed to start the thread pool")?;
+    }
 
-    // if it doesn't already exist, create the output directory
-    let output_dir = "./output";
-    if !PathBuf::from(output_dir).exists() {
-        std::fs::create_dir(output_dir)?;
+    let lexicon = match &args.lexicon {
+        Some(path) => Lexicon::load(path)?.overriding(args.lexicon_overrides),
+        None => Lexicon::default(),
+    };
+    for warning in lexicon.warnings() {
+        eprintln!("warning: skipped in the lexicon: {warning}");
     }
+    let options = AnalyzeOptions {
+        drop_content: args.low_memory || args.drop_content_after_analysis,
+        thresholds: Thresholds::new(args.positive_threshold, args.negative_threshold)?,
+        session_gap: TimeDelta::minutes(args.session_gap.into()),
+        min_tokens: args.min_tokens,
+        aggregate: args.aggregate_stat,
+        quotes: args.quoted,
+        repeats: args.count_repeats,
+        strip_urls_and_mentions: args.strip_urls_and_mentions,
+        // on the global thread pool, sized by --threads
+        threads: None,
+        lexicon,
+        languages: args.languages.clone(),
+        english_only: args.english_only,
+    };
 
-    // generate every plot
-    for plot_type in [
-        plotter::PlotType::Positive,
-        plotter::PlotType::Negative,
-        plotter::PlotType::Neutral,
-        plotter::PlotType::Compound,
-    ] {
-        analysis.plot(
-            plot_type,
-            &PathBuf::from(format!("{output_dir}/{plot_type}.png")),
+    let review_year = match args.command {
+        Some(Command::Generate {
+            output,
+            participants,
+            messages,
+            bench_fixture,
+        }) => {
+            let config = match bench_fixture {
+                Some(fixture) => fixture.config(),
+                None => SyntheticConfig {
+                    participants,
+                    messages,
+                    seed: args.seed,
+                    ..SyntheticConfig::default()
+                },
+            };
+            let files = synthetic::write_export(&config, &output)?;
+            println!(
+                "wrote {} files to {}",
+                format_count(files.len()),
+                output.display()
+            );
+            return Ok(());
+        }
+        Some(Command::Score) => {
+            let mut text = String::new();
+            std::io::stdin()
+                .read_to_string(&mut text)
+                .context("failed to read the text to score from stdin")?;
+            // a trailing newline (e.g. from `echo`) isn't part of the text
+            let scored = Analyzer::new(&options)
+                .raw_text(args.raw_text)
+                .score_text(text.trim_end_matches(['\n', '\r']))?;
+            println!("{}", serde_json::to_string_pretty(&scored)?);
+            return Ok(());
+        }
+        Some(Command::Drift {
+            earlier,
+            on_mismatch,
+            sample,
+        }) => {
+            let report =
+                EarlierAnalysis::load(&earlier)?.check(&options, on_mismatch, sample, args.seed)?;
+            print!("{}", report.to_text());
+            return Ok(());
+        }
+        Some(Command::YearReview { year }) => Some(year),
+        None => None,
+    };
+
+    let path = args.path.clone().context("an input path is required")?;
+    if !args.inbox {
+        let sink: Box<dyn OutputSink> = match &args.output_zip {
+            #[cfg(feature = "zip")]
+            Some(archive) => Box::new(ZipSink::create(archive)?),
+            #[cfg(not(feature = "zip"))]
+            Some(_) => unreachable!("--output-zip is rejected without the zip feature"),
+            None => Box::new(DirectorySink::new(&args.output_dir)),
+        };
+        return run(&args, &options, path, sink, review_year).map(|_| ());
+    }
+
+    // every conversation gets its own folder of the output directory, one failing doesn't stop the others
+    let inbox = InboxDirectory::try_from(path.clone())
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    for (name, reason) in &inbox.skipped {
+        eprintln!("warning: skipped {name}: {reason}");
+    }
+    let mut failed = 0;
+    let mut profiles = Vec::new();
+    for (name, conversation) in &inbox.conversations {
+        println!("== {name} ==");
+        let sink = Box::new(DirectorySink::new(args.output_dir.join(name)));
+        let path = conversation.path().to_path_buf();
+        match run(&args, &options, path, sink, review_year) {
+            Ok(profile) => profiles.extend(profile),
+            Err(error) => {
+                eprintln!("error: {name}: {error:#}");
+                failed += 1;
+            }
+        }
+    }
+    // the threads side by side, next to their folders
+    if !profiles.is_empty() {
+        let mut comparison = ThreadComparison::new(profiles);
+        comparison.palette = args.palette;
+        let table = comparison.table(THREAD_TITLE_CHARS);
+        println!("== threads ==");
+        print!("{table}");
+        let mut sink = DirectorySink::new(&args.output_dir);
+        sink.write(Path::new("threads.txt"), table.as_bytes())?;
+        #[cfg(feature = "plot")]
+        sink.write(Path::new("threads.png"), &comparison.plot_png()?)?;
+    }
+    if failed > 0 && !args.best_effort {
+        bail!(
+            "{} of the {} conversations failed",
+            format_count(failed),
+            format_count(inbox.conversations.len())
+        );
+    }
+    Ok(())
+}
+
+/// Analyzes the conversation at `path` into `sink`, writing what the arguments ask for
+/// (or the year review of `review_year`, if given), and returns its headline numbers as a thread of an inbox, unless
+/// it compared settings or reviewed a year.
+fn run(
+    args: &Args,
+    options: &AnalyzeOptions,
+    path: PathBuf,
+    mut sink: Box<dyn OutputSink>,
+    review_year: Option<i32>,
+) -> Result<Option<ThreadProfile>> {
+    let folder = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut pipeline = Pipeline::new(path)
+        .timestamps(
+            TimestampValidation::new(
+                args.plausible_since,
+                args.plausible_until,
+                args.implausible_timestamps,
+            )
+            .unit(args.timestamp_unit),
+        )
+        .shared_captions(args.shared_captions)
+        .options(options.clone())
+        .events(EventConfig {
+            holidays: args.holidays.clone(),
+            birthdays: args.birthday.clone(),
+            time_zone: args.timezone,
+        })
+        .palette(args.palette)
+        .time_zone(args.timezone)
+        .seed(args.seed)
+        .lenient(args.lenient)
+        .strict_numbering(args.strict_numbering)
+        .strict_fields(args.strict_fields)
+        .format(args.input_format)
+        .whatsapp(WhatsAppOptions {
+            date_order: args.date_order,
+            time_zone: args.timezone,
+        })
+        .html(HtmlOptions {
+            date_format: args.html_date_format.clone(),
+            time_zone: args.timezone,
+        })
+        .timings(args.timings)
+        .export_conversation(args.export_conversation.is_some());
+    let mut transforms = TransformPipeline::standard();
+    if args.keep_unsent {
+        transforms = transforms.without(DropUnsent.name());
+    }
+    if args.raw_text {
+        transforms = transforms.without(CleanText.name());
+    }
+    if args.collapse_repeats {
+        transforms = transforms.with(CollapseRepeats::default());
+    }
+    pipeline = pipeline.transforms(transforms);
+    if let Some(pattern) = &args.glob {
+        pipeline = pipeline.glob(pattern);
+    }
+    if args.score_system_messages {
+        pipeline = pipeline.system_messages(SystemMessages::none());
+    } else if !args.system_message.is_empty() {
+        let mut system_messages = SystemMessages::standard();
+        for pattern in &args.system_message {
+            system_messages = system_messages.with(pattern);
+        }
+        pipeline = pipeline.system_messages(system_messages);
+    }
+    if let Some(aliases) = &args.aliases {
+        pipeline = pipeline.aliases(Aliases::load(aliases)?);
+    }
+    if let Some(me) = &args.me {
+        pipeline = pipeline.me(me);
+    }
+    // the cache keeps the text of every message, which is what dropping it promises not to leave behind
+    if !args.no_cache && !args.drop_content_after_analysis {
+        pipeline = pipeline.cache(args.output_dir.join(".cache"));
+    }
+    if let Some(resamples) = args.bootstrap {
+        pipeline = pipeline.bootstrap(resamples);
+    }
+    let bar = progress_bar();
+    pipeline = pipeline.progress({
+        let bar = bar.clone();
+        move |progress: Progress| {
+            bar.set_length(progress.total as u64);
+            bar.set_position(progress.done as u64);
+            bar.set_message(match progress.phase {
+                Phase::Parse => "parsing",
+                Phase::Analyze => "scoring",
+            });
+        }
+    });
+
+    if let Some(profiles) = &args.compare_settings {
+        let [baseline, variant] = profiles.as_slice() else {
+            bail!("--compare-settings takes exactly two settings files");
+        };
+        let mut output = pipeline.compare(
+            Profile::load(baseline, options)?,
+            Profile::load(variant, options)?,
+        )?;
+        bar.finish_and_clear();
+        for warning in output.manifest.diagnostics.warnings() {
+            eprintln!("warning: {warning}");
+        }
+        print!("{}", output.comparison.table());
+        output.write_artifacts(sink.as_mut())?;
+        output.write_manifest(sink.as_mut())?;
+        sink.finish()?;
+        report_failures(&output.manifest, args.best_effort)?;
+        return Ok(None);
+    }
+
+    let mut output = pipeline.run()?;
+    bar.finish_and_clear();
+    for warning in output.analysis.diagnostics.warnings() {
+        eprintln!("warning: {warning}");
+    }
+
+    if let Some(year) = review_year {
+        print!("{}", output.write_year_review(sink.as_mut(), year)?);
+        output.write_manifest(sink.as_mut())?;
+        sink.finish()?;
+        report_failures(&output.manifest, args.best_effort)?;
+        return Ok(None);
+    }
+
+    print!("{}", output.analysis.report());
+    println!();
+    print!("{}", output.analysis.stats().table());
+    if let Some(n) = args.extremes {
+        println!();
+        print!("{}", output.analysis.extremes_table(n, args.extremes_width));
+    }
+    output.write_report(sink.as_mut())?;
+    #[cfg(feature = "plot")]
+    {
+        output.write_plots(sink.as_mut(), &PlotType::ALL)?;
+        output.write_gaps_plot(sink.as_mut())?;
+        if args.plot_responsiveness {
+            output.write_responsiveness_plot(sink.as_mut())?;
+        }
+        if args.plot_sparklines {
+            output.write_sparklines_plot(sink.as_mut())?;
+        }
+        if args.plot_volume {
+            output.write_volume_plot(sink.as_mut(), args.overlay_decay)?;
+        }
+        for issue in &output.manifest.diagnostics.plot_issues {
+            eprintln!("warning: {}", issue.describe());
+        }
+    }
+    if args.export_json {
+        output.write_json_export(sink.as_mut())?;
+    }
+    if let Some(path) = &args.export_lineprotocol {
+        output.write_line_protocol(sink.as_mut(), path)?;
+    }
+    if let Some(path) = &args.export_csv_timeseries {
+        output.write_csv_timeseries(sink.as_mut(), path, args.timeseries_period)?;
+    }
+    if let Some(path) = &args.export_weekly {
+        output.write_weekly_csv(sink.as_mut(), path)?;
+    }
+    if let Some(path) = &args.export_rhythm {
+        output.write_rhythm_csv(sink.as_mut(), path)?;
+    }
+    if let Some(path) = &args.export_conversation {
+        output.write_conversation(sink.as_mut(), path)?;
+    }
+    if let Some(path) = &args.export_ics {
+        output.write_ics(
+            sink.as_mut(),
+            path,
+            &CalendarConfig {
+                time_zone: args.timezone,
+                min_messages: args.ics_min_messages,
+            },
         )?;
     }
+    if let Some(questions) = args.quiz {
+        let config = QuizConfig {
+            questions,
+            min_magnitude: args.quiz_min_magnitude,
+            max_chars: args.quiz_max_chars,
+        };
+        output.write_quiz(sink.as_mut(), &config)?;
+    }
+    output.write_manifest(sink.as_mut())?;
+    sink.finish()?;
+
+    report_failures(&output.manifest, args.best_effort)?;
+    Ok(Some(output.analysis.thread_profile(&folder)))
+}
+
+/// A bar on stderr following the parsing and the scoring, hidden when stderr isn't a terminal, and cleared once they're
+/// done (or failed) so nothing is left of it before the warnings and the report.
+fn progress_bar() -> ProgressBar {
+    let style = ProgressStyle::with_template("{msg:>7} [{bar:40}] {pos}/{len}")
+        .expect("the template is valid")
+        .progress_chars("=> ");
+    ProgressBar::new(0)
+        .with_style(style)
+        .with_finish(ProgressFinish::AndClear)
+}
+
+/// The error for a command line option that needs a cargo feature this build was made without.
+fn missing_feature(option: &str, feature: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{option} needs the `{feature}` feature, which this build was made without (rebuild with `--features {feature}`)"
+    )
+}
+
+/// Reports every output that couldn't be written at the end, rather than stopping at the first failure,
+/// failing unless `best_effort` is set.
+fn report_failures(manifest: &Manifest, best_effort: bool) -> Result<()> {
+    let failures = manifest.failed_artifacts().collect::<Vec<_>>();
+    for failure in &failures {
+        eprintln!(
+            "error: failed to write {}: {}",
+            failure.path.display(),
+            failure.error.as_deref().unwrap_or_default()
+        );
+    }
+    if !failures.is_empty() && !best_effort {
+        bail!(
+            "{} of the outputs couldn't be written",
+            format_count(failures.len())
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,106 @@
+//! Module responsible for describing a run: what was read, what was written, and (optionally) how long it took.
+
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::Result;
+
+use crate::{diagnostics::Diagnostics, drift::Fingerprint, rng::Seed, sink::OutputSink};
+
+/// Written as `manifest.json` next to the other artifacts of a run.
+#[derive(serde::Serialize, Debug, Default)]
+pub struct Manifest {
+    pub tool_version: String,
+    pub input: Option<PathBuf>,
+    /// the seed every randomized component of the run was derived from
+    pub seed: Seed,
+    /// identifies the analyzer the run's scores came from, see `drift`, `None` for runs that don't score a conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<Fingerprint>,
+    pub artifacts: Vec<Artifact>,
+    #[serde(skip_serializing_if = "Diagnostics::is_empty")]
+    pub diagnostics: Diagnostics,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timings>,
+}
+
+/// A file the run tried to write, and whether it succeeded.
+#[derive(serde::Serialize, Debug)]
+pub struct Artifact {
+    pub path: PathBuf,
+    /// why the artifact couldn't be written, if it couldn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Wall-clock durations of the phases of a run, in the order they ran.
+#[derive(serde::Serialize, Debug, Default)]
+pub struct Timings {
+    pub phases: Vec<PhaseTiming>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub millis: f64,
+}
+
+impl Manifest {
+    pub fn new(input: Option<PathBuf>, seed: Seed, record_timings: bool) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            input,
+            seed,
+            fingerprint: None,
+            artifacts: Vec::new(),
+            diagnostics: Diagnostics::default(),
+            timings: record_timings.then(Timings::default),
+        }
+    }
+
+    /// Runs `f`, recording how long it took under `phase` if timings are enabled.
+    pub fn time<T>(&mut self, phase: &str, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        if let Some(timings) = &mut self.timings {
+            timings.phases.push(PhaseTiming {
+                phase: phase.to_string(),
+                millis: start.elapsed().as_secs_f64() * 1000.0,
+            });
+        }
+        result
+    }
+
+    /// Records the outcome of an attempt to write the artifact at `path`.
+    pub fn record_artifact(&mut self, path: PathBuf, result: &Result<()>) {
+        self.artifacts.push(Artifact {
+            path,
+            error: result.as_ref().err().map(|error| format!("{error:#}")),
+        });
+    }
+
+    /// Renders an artifact and writes it into the sink at `path`, timing it under `phase` and recording the outcome.
+    pub fn write_artifact(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        phase: &str,
+        path: &Path,
+        render: impl FnOnce() -> Result<Vec<u8>>,
+    ) {
+        let result = self.time(phase, || sink.write(path, &render()?));
+        self.record_artifact(sink.location(path), &result);
+    }
+
+    /// The artifacts that couldn't be written.
+    pub fn failed_artifacts(&self) -> impl Iterator<Item = &Artifact> {
+        self.artifacts
+            .iter()
+            .filter(|artifact| artifact.error.is_some())
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
@@ -0,0 +1,215 @@
+//! Module containing the conversation starter analysis: what the first message of each session
+//! (see `AnalyzeOptions::session_gap`) looks like, per participant who sent it.
+//!
+//! These need the text of the messages, so they're accumulated while the conversation is analyzed
+//! (before the text can be dropped, see `AnalyzeOptions::drop_content`). Messages with an attachment and no text
+//! (see `ParsedConversation::attachment_only`) aren't scored, but they're fed in too, since they open sessions like any other message.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::TimeDelta;
+
+use crate::{
+    parser::{Attachment, Message, ParticipantId},
+    text::normalize,
+    tokenizer::{self, TokenKind, Tokenizer},
+};
+
+/// How many of the most common opening words and phrases are kept.
+pub const TOP_OPENINGS: usize = 5;
+
+/// What an opener is, see `OpenerKind::of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenerKind {
+    Question,
+    Statement,
+    /// a link in the text, or a shared post, reel or profile
+    Link,
+    /// a photo, video, voice message, gif or sticker without any text
+    Attachment,
+}
+
+impl OpenerKind {
+    pub const ALL: [Self; 4] = [
+        Self::Question,
+        Self::Statement,
+        Self::Link,
+        Self::Attachment,
+    ];
+
+    /// What kind of opener the message is: a link if it shares something or has a URL in its text,
+    /// otherwise an attachment if it has no text, otherwise a question if it has a question mark in it.
+    pub fn of(message: &Message) -> Self {
+        let text = message.text();
+        if message.attachment == Some(Attachment::Share)
+            || Tokenizer::default()
+                .tokens(text)
+                .any(|token| token.kind == TokenKind::Url)
+        {
+            Self::Link
+        } else if text.trim().is_empty() {
+            Self::Attachment
+        } else if text.contains('?') {
+            Self::Question
+        } else {
+            Self::Statement
+        }
+    }
+
+    /// The name of the kind in the report, in the plural.
+    pub fn plural(self) -> &'static str {
+        match self {
+            Self::Question => "questions",
+            Self::Statement => "statements",
+            Self::Link => "links",
+            Self::Attachment => "attachments",
+        }
+    }
+}
+
+/// An opening word or phrase, and how many openers start with it.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct Opening {
+    pub text: String,
+    pub count: usize,
+}
+
+/// How one participant opened sessions.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct OpenerSummary {
+    /// how many sessions they opened
+    pub sessions: usize,
+    /// how many of those sessions they opened with each kind of message (every kind is present)
+    pub kinds: BTreeMap<OpenerKind, usize>,
+    /// the most common first words of their openers (ignoring case), most common first, at most `TOP_OPENINGS`
+    pub top_words: Vec<Opening>,
+    /// the most common first two words of their openers, like `top_words`
+    pub top_phrases: Vec<Opening>,
+    /// the mean compound score of their scored openers, `None` if every opener was an attachment
+    pub mean_compound: Option<f64>,
+    /// the mean compound score of all their scored messages, to compare against, `None` if none were scored
+    pub baseline: Option<f64>,
+}
+
+impl OpenerSummary {
+    /// The fraction of their sessions they opened with the kind of message.
+    pub fn share(&self, kind: OpenerKind) -> f64 {
+        if self.sessions == 0 {
+            return 0.0;
+        }
+        self.kinds.get(&kind).copied().unwrap_or_default() as f64 / self.sessions as f64
+    }
+}
+
+/// How every participant opened sessions.
+#[derive(Debug, Clone, Default)]
+pub struct Openers {
+    summaries: HashMap<ParticipantId, OpenerSummary>,
+}
+
+impl Openers {
+    /// How the participant opened sessions, `None` if they never sent a message.
+    pub fn get(&self, participant: ParticipantId) -> Option<&OpenerSummary> {
+        self.summaries.get(&participant)
+    }
+}
+
+/// Accumulates the openers one message at a time, fed every message (scored or not) in the order they were sent.
+#[derive(Debug)]
+pub struct OpenerBuilder {
+    session_gap_ms: usize,
+    last_ms: Option<usize>,
+    counts: HashMap<ParticipantId, OpenerCounts>,
+}
+
+#[derive(Debug, Default)]
+struct OpenerCounts {
+    kinds: BTreeMap<OpenerKind, usize>,
+    words: HashMap<String, usize>,
+    phrases: HashMap<String, usize>,
+    /// (count, sum) of the compound scores of the scored openers
+    openers: (usize, f64),
+    /// (count, sum) of the compound scores of every scored message
+    messages: (usize, f64),
+}
+
+impl OpenerBuilder {
+    pub fn new(session_gap: TimeDelta) -> Self {
+        Self {
+            session_gap_ms: session_gap.num_milliseconds().max(0) as usize,
+            last_ms: None,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Adds the next message, with its compound score if it was scored.
+    pub fn add(&mut self, message: &Message, compound: Option<f64>) {
+        let opens = self.last_ms.is_none_or(|last_ms| {
+            message.timestamp_ms().saturating_sub(last_ms) >= self.session_gap_ms
+        });
+        self.last_ms = Some(message.timestamp_ms());
+
+        let counts = self.counts.entry(message.sender).or_default();
+        if let Some(compound) = compound {
+            counts.messages.0 += 1;
+            counts.messages.1 += compound;
+        }
+        if !opens {
+            return;
+        }
+
+        *counts.kinds.entry(OpenerKind::of(message)).or_default() += 1;
+        let words = tokenizer::words(message.text())
+            .map(normalize)
+            .collect::<Vec<_>>();
+        if let Some(first) = words.first() {
+            *counts.words.entry(first.clone()).or_default() += 1;
+        }
+        if let [first, second, ..] = words.as_slice() {
+            *counts
+                .phrases
+                .entry(format!("{first} {second}"))
+                .or_default() += 1;
+        }
+        if let Some(compound) = compound {
+            counts.openers.0 += 1;
+            counts.openers.1 += compound;
+        }
+    }
+
+    pub fn build(self) -> Openers {
+        let mean = |(count, sum): (usize, f64)| (count > 0).then(|| sum / count as f64);
+        let summaries = self
+            .counts
+            .into_iter()
+            .map(|(participant, counts)| {
+                let kinds = OpenerKind::ALL
+                    .into_iter()
+                    .map(|kind| (kind, counts.kinds.get(&kind).copied().unwrap_or_default()))
+                    .collect::<BTreeMap<_, _>>();
+                let summary = OpenerSummary {
+                    sessions: kinds.values().sum(),
+                    kinds,
+                    top_words: top(counts.words),
+                    top_phrases: top(counts.phrases),
+                    mean_compound: mean(counts.openers),
+                    baseline: mean(counts.messages),
+                };
+                (participant, summary)
+            })
+            .collect();
+        Openers { summaries }
+    }
+}
+
+/// The `TOP_OPENINGS` most common openings, ties broken alphabetically so the order is deterministic.
+fn top(counts: HashMap<String, usize>) -> Vec<Opening> {
+    let mut openings = counts
+        .into_iter()
+        .map(|(text, count)| Opening { text, count })
+        .collect::<Vec<_>>();
+    openings.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.text.cmp(&b.text)));
+    openings.truncate(TOP_OPENINGS);
+    openings
+}
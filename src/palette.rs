@@ -0,0 +1,234 @@
+//! Module responsible for how the series of the plots are told apart: the color, marker shape and line dash pattern
+//! each one is drawn with, depending on the palette chosen for the run.
+//!
+//! The palette can always be chosen, but it only does anything with the `plot` feature.
+
+#[cfg(feature = "plot")]
+use plotters::{
+    element::{ComposedElement, DashedPathElement},
+    prelude::*,
+    style::{Palette as _, Palette99},
+};
+
+/// The Okabe-Ito palette, distinguishable with the common kinds of color blindness,
+/// ordered so the low contrast yellow (on white) is picked last.
+#[cfg(feature = "plot")]
+const OKABE_ITO: [RGBColor; 8] = [
+    RGBColor(0, 114, 178),   // blue
+    RGBColor(230, 159, 0),   // orange
+    RGBColor(0, 158, 115),   // bluish green
+    RGBColor(213, 94, 0),    // vermillion
+    RGBColor(204, 121, 167), // reddish purple
+    RGBColor(86, 180, 233),  // sky blue
+    RGBColor(0, 0, 0),       // black
+    RGBColor(240, 228, 66),  // yellow
+];
+
+/// The grays of the mono palette, dark enough to print, series are told apart by their markers and dashes.
+#[cfg(feature = "plot")]
+const GRAYS: [RGBColor; 3] = [
+    RGBColor(0, 0, 0),
+    RGBColor(90, 90, 90),
+    RGBColor(150, 150, 150),
+];
+
+/// The dash patterns of the mono palette, in pixels.
+const DASHES: [Dash; 4] = [
+    Dash::Solid,
+    Dash::Dashed {
+        size: 8,
+        spacing: 4,
+    },
+    Dash::Dashed {
+        size: 2,
+        spacing: 3,
+    },
+    Dash::Dashed {
+        size: 14,
+        spacing: 4,
+    },
+];
+
+/// The marker shapes of the mono palette.
+const MARKERS: [MarkerShape; 4] = [
+    MarkerShape::Disc,
+    MarkerShape::Triangle,
+    MarkerShape::Ring,
+    MarkerShape::Cross,
+];
+
+/// Shapes other than discs are drawn at least this big (in pixels), so they're recognizable.
+#[cfg(feature = "plot")]
+const MIN_SHAPE_SIZE: i32 = 3;
+
+/// A position on a drawing area, in pixels (plotters' backend coordinates, which it doesn't export).
+#[cfg(feature = "plot")]
+type BackendCoord = (i32, i32);
+
+/// A legend entry drawn by `Palette::scatter_legend`.
+#[cfg(feature = "plot")]
+pub type ScatterLegend<DB> = ComposedElement<
+    BackendCoord,
+    DB,
+    DynElement<'static, DB, BackendCoord>,
+    DynElement<'static, DB, BackendCoord>,
+>;
+
+/// How the series of the plots are told apart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Palette {
+    /// plotters' 99 color palette
+    #[default]
+    Default,
+    /// the Okabe-Ito palette, which stays distinguishable with color blindness
+    Colorblind,
+    /// grays, with a marker shape and line dash pattern per series, for printing in grayscale
+    Mono,
+}
+
+/// How a line is stroked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dash {
+    Solid,
+    /// dashes of `size` pixels, `spacing` pixels apart
+    Dashed {
+        size: u32,
+        spacing: u32,
+    },
+}
+
+/// The shape of the points of a scatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerShape {
+    /// a filled circle
+    Disc,
+    Triangle,
+    /// an outlined circle
+    Ring,
+    Cross,
+}
+
+impl Palette {
+    /// The dash pattern of the `i`th series' lines of a kind usually stroked with `usual` (e.g. dashed smoothed curves),
+    /// which is kept unless the palette tells series apart by their dash pattern.
+    pub fn dash(self, i: usize, usual: Dash) -> Dash {
+        match self {
+            Self::Default | Self::Colorblind => usual,
+            Self::Mono => DASHES[i % DASHES.len()],
+        }
+    }
+
+    /// The marker shape of the `i`th series' points.
+    pub fn marker_shape(self, i: usize) -> MarkerShape {
+        match self {
+            Self::Default | Self::Colorblind => MarkerShape::Disc,
+            Self::Mono => MARKERS[i % MARKERS.len()],
+        }
+    }
+}
+
+#[cfg(feature = "plot")]
+impl Palette {
+    /// The color of the `i`th series.
+    pub fn color(self, i: usize) -> RGBAColor {
+        match self {
+            Self::Default => Palette99::pick(i).to_rgba(),
+            Self::Colorblind => OKABE_ITO[i % OKABE_ITO.len()].to_rgba(),
+            Self::Mono => GRAYS[i % GRAYS.len()].to_rgba(),
+        }
+    }
+
+    /// The color of a value from 0 to 1 on a color scale, like a cell of a heatmap: viridis (dark blue to yellow,
+    /// which stays readable with color blindness and in grayscale), or with the mono palette, light to dark gray.
+    pub fn ramp(self, value: f64) -> RGBColor {
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            Self::Default | Self::Colorblind => ViridisRGB::get_color(value),
+            Self::Mono => {
+                let gray = (235.0 - 175.0 * value).round() as u8;
+                RGBColor(gray, gray, gray)
+            }
+        }
+    }
+
+    /// A point of the `i`th series at `at`, of (about) `size` pixels radius.
+    pub fn marker<DB, Coord>(
+        self,
+        i: usize,
+        at: Coord,
+        size: i32,
+        style: ShapeStyle,
+    ) -> DynElement<'static, DB, Coord>
+    where
+        DB: DrawingBackend,
+        Coord: Clone + 'static,
+    {
+        let shape_size = size.max(MIN_SHAPE_SIZE);
+        match self.marker_shape(i) {
+            MarkerShape::Disc => Circle::new(at, size, style).into_dyn(),
+            MarkerShape::Triangle => TriangleMarker::new(at, shape_size, style).into_dyn(),
+            MarkerShape::Ring => Circle::new(
+                at,
+                shape_size,
+                ShapeStyle {
+                    filled: false,
+                    ..style
+                },
+            )
+            .into_dyn(),
+            MarkerShape::Cross => Cross::new(at, shape_size, style).into_dyn(),
+        }
+    }
+
+    /// The legend entry of the `i`th series of lines, usually stroked with `usual` (see `dash`): a short line.
+    pub fn line_legend<DB>(
+        self,
+        i: usize,
+        (x, y): BackendCoord,
+        usual: Dash,
+    ) -> DynElement<'static, DB, BackendCoord>
+    where
+        DB: DrawingBackend,
+    {
+        self.dash(i, usual)
+            .path(vec![(x, y), (x + 20, y)], self.color(i).stroke_width(2))
+    }
+
+    /// The legend entry of the `i`th series of points, with lines usually stroked with `usual` (see `dash`):
+    /// a short line, with one of the points in the middle.
+    pub fn scatter_legend<DB>(
+        self,
+        i: usize,
+        (x, y): BackendCoord,
+        usual: Dash,
+    ) -> ScatterLegend<DB>
+    where
+        DB: DrawingBackend,
+    {
+        let color = self.color(i);
+        EmptyElement::at((x, y))
+            + self.line_legend(i, (0, 0), usual)
+            + self.marker(i, (10, 0), MIN_SHAPE_SIZE, color.filled())
+    }
+}
+
+#[cfg(feature = "plot")]
+impl Dash {
+    /// A line through `points`, stroked with this dash pattern.
+    pub fn path<DB, Coord>(
+        self,
+        points: Vec<Coord>,
+        style: ShapeStyle,
+    ) -> DynElement<'static, DB, Coord>
+    where
+        DB: DrawingBackend,
+        Coord: Clone + 'static,
+    {
+        match self {
+            Self::Solid => PathElement::new(points, style).into_dyn(),
+            Self::Dashed { size, spacing } => {
+                DashedPathElement::new(points, size, spacing, style).into_dyn()
+            }
+        }
+    }
+}
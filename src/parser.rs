@@ -1,37 +1,1321 @@
-//! Module responsible for collecting and parsing exported instagram message data (json).
+//! Module responsible for collecting and parsing exported instagram message data (json),
+//! or facebook messenger message data, which facebook exports the same way,
+//! or the chats whatsapp exports (see `whatsapp`).
+
+pub mod discord;
+pub mod html;
+mod named;
+pub mod telegram;
+pub mod whatsapp;
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     fs::File,
-    io::{BufReader, Read},
-    path::PathBuf,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use rayon::prelude::*;
+
+use crate::{
+    diagnostics::{Diagnostics, ImplausibleTimestamp, MixedUnitTimestamp, UnreadableFile},
+    format::DAY_MS,
+    language::Language,
+    progress::{Phase, Progress},
+    quotes::QuoteReason,
+    transform::TransformPipeline,
+};
+use html::HtmlOptions;
+use whatsapp::WhatsAppOptions;
 
+/// The message files of a conversation: a folder of an export, a single message file (e.g. `message_1.json`),
+/// or a conversation folder in the zip archive of an export
+/// (named by the archive's path followed by the folder's path in it, e.g. `export.zip/messages/inbox/bob_1234`,
+/// which can be shortened to `export.zip/bob_1234`, or to `export.zip` if it's the only conversation in it).
+///
+/// Message files are read straight out of the archive (with the `zip` feature), archives inside it aren't looked into,
+/// and entries whose names aren't utf-8 are left out, listed in the diagnostics.
+///
+/// ```
+/// # #[cfg(feature = "zip")]
+/// # {
+/// use std::path::Path;
+///
+/// use igdm_sentiment_analysis::{
+///     parser::ConversationDirectory,
+///     sink::{OutputSink, ZipSink},
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-archive");
+/// let config = SyntheticConfig {
+///     messages: 50,
+///     ..SyntheticConfig::default()
+/// };
+/// let files = synthetic::write_export(&config, &dir.join("bob_1234"))?;
+///
+/// // an export with a single conversation, an archive inside it, and an entry whose name isn't utf-8
+/// let archive = dir.join("instagram-someone-2024-01-01.zip");
+/// let mut sink = ZipSink::create(&archive)?;
+/// let inbox = Path::new("your_instagram_activity/messages/inbox");
+/// for file in &files {
+///     sink.write(&inbox.join("bob_1234").join(file.file_name().unwrap()), &std::fs::read(file)?)?;
+/// }
+/// sink.write(&inbox.join("carol_5678.zip"), b"")?;
+/// sink.write(&inbox.join("bob_1234/message_9~.json"), b"")?;
+/// sink.finish()?;
+/// # // zip names without the utf-8 flag are read as cp437, except when they aren't utf-8 at all
+/// # let mut bytes = std::fs::read(&archive)?;
+/// # while let Some(i) = bytes.windows(4).position(|window| window == b"9~.j") {
+/// #     bytes[i + 1] = 0xff;
+/// # }
+/// # std::fs::write(&archive, bytes)?;
+///
+/// let conversation = ConversationDirectory::try_from(archive.clone())?;
+/// assert_eq!(conversation.path(), archive.join(inbox).join("bob_1234"));
+/// let parsed = conversation.parse()?;
+/// assert!(!parsed.messages.is_empty());
+/// assert_eq!(parsed.diagnostics.skipped_archive_entries.len(), 1);
+///
+/// // the folder can be named in full, or by its name
+/// assert!(ConversationDirectory::try_from(archive.join("bob_1234")).is_ok());
+/// assert!(ConversationDirectory::try_from(archive.join("carol_5678")).is_err());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// A single message file can be named by any name, whether it's one is only known once it's parsed:
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ConversationDirectory;
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-single");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(
+///     dir.join("pulled-out.json"),
+///     r#"{"participants": [{"name": "Ana"}], "messages": [
+///         {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hi"}]}"#,
+/// )?;
+/// std::fs::write(dir.join("notes.md"), "call Ana back")?;
+///
+/// let conversation = ConversationDirectory::try_from(dir.join("pulled-out.json"))?.parse()?;
+/// assert_eq!(conversation.messages.len(), 1);
+/// let error = ConversationDirectory::try_from(dir.join("notes.md"))?
+///     .parse()
+///     .err()
+///     .expect("notes aren't a conversation");
+/// assert!(format!("{error:#}").contains("notes.md: message file is not a conversation of an export"));
+/// assert!(ConversationDirectory::try_from(dir.join("missing.json")).is_err());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// The message files of a folder are those named `message_<number>.json` (or `.html`), in any case, read in the order
+/// of their numbers, other files are ignored. `ConversationDirectory::matching` reads files named some other way:
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ConversationDirectory;
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-names");
+/// std::fs::create_dir_all(&dir)?;
+/// let file = |text: &str, timestamp_ms: u64| {
+///     format!(
+///         r#"{{"participants": [{{"name": "Ana"}}], "messages": [
+///             {{"sender_name": "Ana", "timestamp_ms": {timestamp_ms}, "content": "{text}"}}]}}"#
+///     )
+/// };
+/// std::fs::write(dir.join("message_1.json"), file("first", 1700000000000))?;
+/// std::fs::write(dir.join("MESSAGE_02.JSON"), file("second", 1700000060000))?;
+/// // none of these are message files, and aren't read (they'd fail to parse)
+/// for name in ["message_.json", "message_x.json", "message_+3.json", "message_٣.json", "méssage_4.json", "message_5.jsön"] {
+///     std::fs::write(dir.join(name), "not a message file")?;
+/// }
+///
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["first", "second"]);
+///
+/// // files named another way are read by a pattern of their names, `*` standing for any text
+/// std::fs::write(dir.join("chat-part10.json"), file("third", 1700000120000))?;
+/// std::fs::write(dir.join("chat-part9.json"), file("fourth", 1700000180000))?;
+/// let conversation = ConversationDirectory::matching(&dir, "chat-part*.json")?.parse()?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["third", "fourth"]);
+/// assert!(ConversationDirectory::matching(&dir, "*.txt").is_err());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Message files can be gzip-compressed (`message_1.json.gz`), beside ones that aren't, with the `gzip` feature (on by
+/// default):
+///
+/// ```
+/// # #[cfg(feature = "gzip")]
+/// # {
+/// use std::io::Write;
+/// use igdm_sentiment_analysis::parser::ConversationDirectory;
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-gzip");
+/// std::fs::create_dir_all(&dir)?;
+/// let file = |text: &str, timestamp_ms: u64| {
+///     format!(
+///         r#"{{"participants": [{{"name": "Ana"}}], "messages": [
+///             {{"sender_name": "Ana", "timestamp_ms": {timestamp_ms}, "content": "{text}"}}]}}"#
+///     )
+/// };
+/// let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+/// encoder.write_all(file("compressed", 1700000000000).as_bytes())?;
+/// std::fs::write(dir.join("message_1.json.gz"), encoder.finish()?)?;
+/// std::fs::write(dir.join("message_2.json"), file("plain", 1700000060000))?;
+///
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["compressed", "plain"]);
+///
+/// // a broken gzip stream fails, naming the file
+/// std::fs::write(dir.join("message_3.json.gz"), b"\x1f\x8b not quite gzip")?;
+/// let error = ConversationDirectory::try_from(dir.clone())?.parse().err().expect("the stream is broken");
+/// assert!(format!("{error:#}").contains("message_3.json.gz"));
+/// # std::fs::remove_dir_all(&dir)?;
+/// # }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// A folder without message files of its own can be any folder above the conversation (up to `SEARCH_DEPTH` levels),
+/// like the root of an export, as long as there's only one conversation in it:
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::ConversationDirectory,
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// let export = std::env::temp_dir().join("igdm-sentiment-analysis-search");
+/// let inbox = export.join("your_instagram_activity/messages/inbox");
+/// let config = SyntheticConfig {
+///     messages: 50,
+///     ..SyntheticConfig::default()
+/// };
+/// synthetic::write_export(&config, &inbox.join("bob_1234"))?;
+/// std::fs::create_dir_all(export.join("media/posts"))?;
+///
+/// let conversation = ConversationDirectory::try_from(export.clone())?;
+/// assert_eq!(conversation.path(), inbox.join("bob_1234"));
+///
+/// // with more than one, the error lists them
+/// synthetic::write_export(&config, &inbox.join("alice_5678"))?;
+/// let error = ConversationDirectory::try_from(export.clone()).err().expect("there are two conversations");
+/// assert!(error.to_string().contains("alice_5678"));
+/// assert!(error.to_string().contains("bob_1234"));
+/// # std::fs::remove_dir_all(&export)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
 pub struct ConversationDirectory {
-    _path: PathBuf,
-    message_file_paths: Vec<PathBuf>,
+    path: PathBuf,
+    files: MessageFiles,
+    /// the entries of the archive in the conversation's folder whose names aren't utf-8 (decoded lossily)
+    skipped_entries: Vec<String>,
+    /// how many threads to parse the message files on, see `ConversationDirectory::threads`
+    threads: Option<usize>,
+    /// whether unreadable message files are skipped, see `ConversationDirectory::lenient`
+    lenient: bool,
+    /// see `ConversationDirectory::format`
+    format: InputFormat,
+    /// see `ConversationDirectory::whatsapp`
+    whatsapp: WhatsAppOptions,
+    /// see `ConversationDirectory::html`
+    html: HtmlOptions,
+    /// see `ConversationDirectory::aliases`
+    aliases: Aliases,
+    /// see `ConversationDirectory::system_messages`
+    system_messages: SystemMessages,
+    /// see `ConversationDirectory::cache`
+    cache: Option<PathBuf>,
+    /// the numbers missing from those of the message files, see `ConversationDirectory::strict_numbering`
+    missing_files: Vec<u32>,
+    /// the message files numbered the same as the one before them
+    duplicate_files: Vec<PathBuf>,
+    /// see `ConversationDirectory::strict_numbering`
+    strict_numbering: bool,
+    /// see `ConversationDirectory::strict_fields`
+    strict_fields: bool,
 }
 
-#[derive(serde::Deserialize)]
+/// A message file, as its path (for errors) and its contents.
+type MessageFile = (PathBuf, std::io::Result<Vec<u8>>);
+
+/// What a message file is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum InputFormat {
+    /// a `.txt` file is a whatsapp chat, an `.html` file is a message file of an html export, a json file with the
+    /// type of a telegram chat is one, anything else is a message file of an instagram or facebook export
+    #[default]
+    Auto,
+    /// a message file of an instagram or facebook export
+    Json,
+    /// a message file of an instagram or facebook export requested in html (`message_1.html`), see `html`
+    Html,
+    /// a chat exported from whatsapp, see `whatsapp`
+    Whatsapp,
+    /// a chat exported from telegram, see `telegram`
+    Telegram,
+    /// a channel exported by DiscordChatExporter, see `discord`, never guessed
+    Discord,
+}
+
+impl InputFormat {
+    /// The format of the file at `path`, whose contents are `buffer`.
+    fn of(self, path: &Path, buffer: &[u8]) -> Self {
+        match self {
+            Self::Auto if path.extension().is_some_and(|extension| extension == "txt") => {
+                Self::Whatsapp
+            }
+            Self::Auto
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("html")) =>
+            {
+                Self::Html
+            }
+            Self::Auto if telegram::is_export(buffer) => Self::Telegram,
+            Self::Auto => Self::Json,
+            format => format,
+        }
+    }
+}
+
+/// Where the message files of a conversation are, ordered by the number in their name.
+enum MessageFiles {
+    /// files on disk, those of a folder or a single one
+    Files(Vec<PathBuf>),
+    /// entries of a zip archive, by name and index, in `folder` (a path in the archive)
+    #[cfg(feature = "zip")]
+    Archive {
+        archive: PathBuf,
+        folder: String,
+        entries: Vec<(String, usize)>,
+    },
+}
+
+impl MessageFiles {
+    fn len(&self) -> usize {
+        match self {
+            Self::Files(paths) => paths.len(),
+            #[cfg(feature = "zip")]
+            Self::Archive { entries, .. } => entries.len(),
+        }
+    }
+}
+
+/// A conversation, merged from its message files.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ConversationDirectory;
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-title");
+/// std::fs::create_dir_all(&dir)?;
+/// for (file, title) in [(1, "Hiking crew"), (2, "Hiking crew 2023"), (10, ""), (11, "Hiking crew 2022")] {
+///     std::fs::write(
+///         dir.join(format!("message_{file}.json")),
+///         format!(
+///             r#"{{"participants": [{{"name": "Ana"}}], "title": "{title}", "thread_path": "inbox/hikingcrew_123",
+///                 "messages": [{{"sender_name": "Ana", "timestamp_ms": {file}000, "content": "hi"}}]}}"#
+///         ),
+///     )?;
+/// }
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+/// // the highest numbered file with a title wins, by number rather than by name
+/// assert_eq!(conversation.title.as_deref(), Some("Hiking crew 2022"));
+/// assert_eq!(conversation.thread_path.as_deref(), Some("inbox/hikingcrew_123"));
+/// // parsing on a single thread merges the files the same way
+/// let sequential = ConversationDirectory::try_from(dir.clone())?.threads(1).parse()?;
+/// assert_eq!(sequential.title, conversation.title);
+/// assert_eq!(sequential.messages.len(), conversation.messages.len());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// The files of an export can overlap, messages in more than one of them (the same sender, timestamp and text)
+/// are only kept once.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ConversationDirectory;
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-overlap");
+/// std::fs::create_dir_all(&dir)?;
+/// let message = |sender: &str, second: usize, content: &str| {
+///     let timestamp_ms = 1_700_000_000_000 + second * 1000;
+///     format!(r#"{{"sender_name": "{sender}", "timestamp_ms": {timestamp_ms}, "content": "{content}"}}"#)
+/// };
+/// let file = |messages: &[String]| {
+///     format!(r#"{{"participants": [{{"name": "Ana"}}, {{"name": "Ben"}}], "messages": [{}]}}"#, messages.join(","))
+/// };
+/// std::fs::write(
+///     dir.join("message_1.json"),
+///     file(&[message("Ana", 3, "see you"), message("Ben", 2, "ok"), message("Ana", 1, "lunch?")]),
+/// )?;
+/// std::fs::write(
+///     dir.join("message_2.json"),
+///     // sent at the same time, but not the same message
+///     file(&[message("Ben", 4, "bye"), message("Ana", 3, "see you"), message("Ben", 3, "see you")]),
+/// )?;
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["lunch?", "ok", "see you", "see you", "bye"]);
+/// assert_eq!(conversation.diagnostics.duplicate_messages, 1);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// Someone who left a group isn't listed as a participant anymore, but their messages still are, so every sender is a
+/// participant, and those who aren't listed are named in the diagnostics.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ParsedConversation;
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "miss you Cleo"},
+///     {"sender_name": "Cleo", "timestamp_ms": 1700000060000, "content": "bye everyone, leaving the group",
+///      "reactions": [{"reaction": "\u00f0\u009f\u0098\u00a2", "actor": "Dev"}]},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "trip is on for friday"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// assert_eq!(conversation.participants.len(), 3);
+/// let cleo = conversation.names.id("Cleo").unwrap();
+/// assert!(conversation.participants.contains(&cleo));
+/// assert_eq!(conversation.diagnostics.unlisted_senders, ["Cleo"]);
+/// // reacting doesn't make someone a participant
+/// assert!(!conversation.participants.contains(&conversation.names.id("Dev").unwrap()));
+///
+/// let analysis = conversation.analyze();
+/// assert_eq!(analysis.analysis[&cleo].len(), 1);
+/// assert!(analysis.diagnostics.unmatched_senders.is_empty());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+///
+/// It's serialized with its participants named rather than by their ids (see `ParticipantId`), which only mean something
+/// with its `ParticipantNames`.
+#[derive(Clone, PartialEq)]
 pub struct ParsedConversation {
-    pub participants: HashSet<Participant>,
+    /// the participants listed in the export, and anyone else who sent a message (see `Diagnostics::unlisted_senders`)
+    pub participants: HashSet<ParticipantId>,
     pub messages: Vec<Message>,
+    /// the names of every participant and sender in the conversation
+    pub names: ParticipantNames,
+    pub diagnostics: Diagnostics,
+    /// where one message file ends and the next begins, as the timestamps of the last message of a file
+    /// and the first message of the next one, ordered by time, see `ExportBoundaries`
+    pub export_boundaries: ExportBoundaries,
+    /// the participant who made the export, if known, see `ConversationDirectory::detect_me`
+    pub me: Option<ParticipantId>,
+    /// messages with an attachment and no text, ordered by time, kept out of the transforms and the analysis
+    /// (there's nothing to score) but used to tell how sessions are opened, see `openers`
+    pub attachment_only: Vec<Message>,
+    /// the records of voice and video calls (see `Message::call_duration_secs`), ordered by time, kept out of the transforms
+    /// and the analysis, their text is boilerplate like "Video call ended"
+    pub calls: Vec<Message>,
+    /// the notices instagram wrote into the conversation itself (see `SystemMessages`), ordered by time, kept out of
+    /// the transforms and the analysis
+    pub system: Vec<Message>,
+    /// the title of the conversation (the other participant's name, or the group's), from the highest numbered
+    /// message file that has one
+    pub title: Option<String>,
+    /// where the conversation is in the export, e.g. `inbox/alice_1234567890`, from the highest numbered message file
+    /// that has one
+    pub thread_path: Option<String>,
+}
+
+/// The boundaries between the message files of a conversation.
+///
+/// Nothing is known about what happened between two files (the export may have been split or merged there),
+/// so features looking at the time between messages shouldn't look across a boundary.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ExportBoundaries(Vec<(usize, usize)>);
+
+impl ExportBoundaries {
+    /// Finds the boundaries between files, given the (first, last) timestamps of the messages in each file.
+    /// Files that overlap in time have no boundary between them.
+    pub fn from_spans(mut spans: Vec<(usize, usize)>) -> Self {
+        spans.sort_unstable();
+        Self(
+            spans
+                .windows(2)
+                .map(|window| (window[0].1, window[1].0))
+                .filter(|(last, first)| last <= first)
+                .collect(),
+        )
+    }
+
+    /// Whether the time between consecutive messages sent at `from_ms` and `to_ms` spans a boundary.
+    pub fn spans(&self, from_ms: usize, to_ms: usize) -> bool {
+        let i = self.0.partition_point(|(last, _)| *last < from_ms);
+        // the first boundary at or after the earlier message, later ones start even later
+        self.0.get(i).is_some_and(|(_, first)| *first <= to_ms)
+    }
+}
+
+/// Whether the captions of shared posts and reels (`Share::text`) count toward sentiment.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::{ConversationDirectory, SharedCaptions, TimestampValidation},
+///     transform::TransformPipeline,
+/// };
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-shared-captions");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(
+///     dir.join("message_1.json"),
+///     r#"{
+///       "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///       "messages": [
+///         {"sender_name": "Ben", "timestamp_ms": 1700000120000, "share": {"link": "https://www.instagram.com/p/2"}},
+///         {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "look",
+///          "share": {"link": "https://www.instagram.com/p/1", "share_text": "what a lovely day"}},
+///         {"sender_name": "Ben", "timestamp_ms": 1700000000000,
+///          "share": {"link": "https://www.instagram.com/reel/3", "share_text": "worst commute ever"}}
+///       ]
+///     }"#,
+/// )?;
+/// let parse = |shared_captions| {
+///     ConversationDirectory::try_from(dir.clone())?.parse_with(
+///         &TransformPipeline::standard(),
+///         &TimestampValidation::default(),
+///         shared_captions,
+///     )
+/// };
+///
+/// // by default, only the message with text of its own is analyzed, the captions stay on the shares
+/// let ignored = parse(SharedCaptions::Ignore)?;
+/// let texts = ignored.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["look"]);
+/// let caption = ignored.messages[0].share.as_ref().and_then(|share| share.text.as_deref());
+/// assert_eq!(caption, Some("what a lovely day"));
+/// assert_eq!(ignored.attachment_only.len(), 2);
+///
+/// // scoring them makes a share with a caption analyzable, a share without one still isn't
+/// let scored = parse(SharedCaptions::Score)?;
+/// let texts = scored.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["worst commute ever", "look\nwhat a lovely day"]);
+/// assert_eq!(scored.attachment_only.len(), 1);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum SharedCaptions {
+    /// keep them on the message only, a share without a message of its own isn't scored
+    #[default]
+    Ignore,
+    /// score them as part of the message, after the sender's own text
+    Score,
+}
+
+/// What to do with messages whose timestamp is outside the plausible window, see `TimestampValidation`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum TimestampPolicy {
+    /// leave them out of the analysis
+    #[default]
+    Exclude,
+    /// move them to the nearest end of the window
+    Clamp,
 }
 
-#[derive(serde::Deserialize, Eq, PartialEq, Hash, Clone)]
-pub struct Participant {
-    pub name: String,
+/// The unit of the timestamps of the message files, `timestamp_ms` is in milliseconds, but some of the tools that
+/// write message files of their own put seconds in it, which would put every message in January 1970.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::{ConversationDirectory, TimestampUnit, TimestampValidation},
+///     transform::TransformPipeline,
+/// };
+///
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-timestamp-unit");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(
+///     dir.join("message_1.json"),
+///     r#"{
+///       "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///       "messages": [
+///         {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "sure"},
+///         {"sender_name": "Ana", "timestamp_ms": 1700000060, "content": "lunch?"},
+///         {"sender_name": "Ben", "timestamp_ms": 1700000000, "content": "hey"}
+///       ]
+///     }"#,
+/// )?;
+/// let parse = |unit| {
+///     ConversationDirectory::try_from(dir.clone())?.parse_with(
+///         &TransformPipeline::standard(),
+///         &TimestampValidation::default().unit(unit),
+///         Default::default(),
+///     )
+/// };
+///
+/// // as milliseconds, the timestamps in seconds are from 1970, and left out as implausible
+/// let milliseconds = parse(TimestampUnit::Milliseconds)?;
+/// assert_eq!(milliseconds.messages.len(), 1);
+///
+/// // told apart, they're converted, and since the file has both, listed in the diagnostics
+/// let auto = parse(TimestampUnit::Auto)?;
+/// let timestamps = auto.messages.iter().map(|message| message.timestamp_ms()).collect::<Vec<_>>();
+/// assert_eq!(timestamps, [1_700_000_000_000, 1_700_000_060_000, 1_700_000_120_000]);
+/// assert_eq!(auto.diagnostics.mixed_unit_timestamps.len(), 2);
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum TimestampUnit {
+    /// milliseconds, as instagram and facebook write them
+    #[default]
+    Milliseconds,
+    /// seconds, every timestamp is converted
+    Seconds,
+    /// seconds for the timestamps too small to be milliseconds (before 2002) that are plausible as seconds,
+    /// milliseconds for the rest, listing those in seconds of a message file that has both in the diagnostics
+    Auto,
 }
 
-#[derive(serde::Deserialize, Clone)]
+/// The timestamps below this (the start of 2002) are too small to be milliseconds, with `TimestampUnit::Auto`.
+const SECONDS_BELOW_MS: usize = 1_009_843_200_000;
+
+/// The window of plausible timestamps, anything outside it (like a timestamp of 0, or one decades in the future)
+/// is a corrupt export rather than a real message, and would otherwise stretch every time axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimestampValidation {
+    pub earliest_ms: usize,
+    pub latest_ms: usize,
+    pub policy: TimestampPolicy,
+    /// converted to milliseconds before the timestamps are validated
+    pub unit: TimestampUnit,
+}
+
+impl Default for TimestampValidation {
+    /// From the start of 2004 (instagram didn't exist yet) to a day from now.
+    fn default() -> Self {
+        Self::new(
+            NaiveDate::from_ymd_opt(2004, 1, 1).unwrap_or_default(),
+            None,
+            TimestampPolicy::default(),
+        )
+    }
+}
+
+impl TimestampValidation {
+    /// The window from the start of `since` to the end of `until` (UTC),
+    /// or if there's no `until`, to a day from now (leaving room for clock skew).
+    pub fn new(since: NaiveDate, until: Option<NaiveDate>, policy: TimestampPolicy) -> Self {
+        let earliest = since.and_time(NaiveTime::MIN).and_utc();
+        let latest = match until {
+            Some(until) => until.and_time(NaiveTime::MIN).and_utc() + TimeDelta::days(1),
+            None => Utc::now() + TimeDelta::days(1),
+        };
+        Self {
+            earliest_ms: earliest.timestamp_millis().max(0) as usize,
+            latest_ms: latest.timestamp_millis().max(0) as usize,
+            policy,
+            unit: TimestampUnit::default(),
+        }
+    }
+
+    /// The unit of the timestamps, see `TimestampUnit`.
+    #[must_use]
+    pub fn unit(mut self, unit: TimestampUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Converts the timestamps of a message file that are in seconds to milliseconds, as the unit says,
+    /// recording each of them in the diagnostics if the file has timestamps in both.
+    fn normalize_unit(
+        &self,
+        messages: &mut [Message],
+        names: &ParticipantNames,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let window = self.earliest_ms..=self.latest_ms;
+        let in_seconds = |timestamp: usize| match self.unit {
+            TimestampUnit::Milliseconds => false,
+            TimestampUnit::Seconds => true,
+            TimestampUnit::Auto => {
+                timestamp < SECONDS_BELOW_MS && window.contains(&timestamp.saturating_mul(1000))
+            }
+        };
+        let seconds = messages
+            .iter()
+            .filter(|message| in_seconds(message.timestamp_ms()))
+            .count();
+        let mixed = self.unit == TimestampUnit::Auto && seconds > 0 && seconds < messages.len();
+        for message in messages
+            .iter_mut()
+            .filter(|message| in_seconds(message.timestamp_ms()))
+        {
+            if mixed {
+                diagnostics.mixed_unit_timestamps.push(MixedUnitTimestamp {
+                    sender: names.name(message.sender).to_string(),
+                    timestamp_secs: message.timestamp_ms(),
+                });
+            }
+            message.timestamp = from_ms(message.timestamp.timestamp_millis().saturating_mul(1000));
+        }
+    }
+
+    /// Applies the policy to the messages with implausible timestamps, recording each of them in the diagnostics.
+    fn apply(
+        &self,
+        messages: &mut Vec<Message>,
+        names: &ParticipantNames,
+        diagnostics: &mut Diagnostics,
+    ) {
+        let window = self.earliest_ms..=self.latest_ms;
+        messages.retain_mut(|message| {
+            let timestamp_ms = message.timestamp_ms();
+            if message.timestamp.timestamp_millis() >= 0 && window.contains(&timestamp_ms) {
+                return true;
+            }
+            let clamped_to = match self.policy {
+                TimestampPolicy::Exclude => None,
+                TimestampPolicy::Clamp => {
+                    Some(timestamp_ms.clamp(self.earliest_ms, self.latest_ms))
+                }
+            };
+            diagnostics
+                .implausible_timestamps
+                .push(ImplausibleTimestamp {
+                    sender: names.name(message.sender).to_string(),
+                    timestamp_ms,
+                    clamped_to,
+                });
+            match clamped_to {
+                Some(timestamp_ms) => {
+                    message.timestamp = from_ms(timestamp_ms as i64);
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+}
+
+/// The time `ms` milliseconds after the epoch, or the latest time there is if that's out of range.
+fn from_ms(ms: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(ms).unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+/// Identifies a participant by the index of their name in a `ParticipantNames` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParticipantId(u32);
+
+/// Interned participant names, every distinct name is stored once and referred to by its `ParticipantId`,
+/// rather than every message owning a copy of its sender's name.
+///
+/// Only the names are serialized, as a list, since each one's id is its position in it.
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct ParticipantNames {
+    names: Vec<String>,
+    ids: HashMap<String, ParticipantId>,
+}
+
+#[derive(Clone, PartialEq)]
 pub struct Message {
-    pub sender_name: String,
-    pub timestamp_ms: usize,
+    pub sender: ParticipantId,
+    /// when the message was sent, see `Message::timestamp_ms` for it in milliseconds
+    pub timestamp: DateTime<Utc>,
+    /// `None` once dropped after scoring, see `AnalyzeOptions::drop_content`
+    pub content: Option<String>,
+    /// why the message is likely quoted rather than the sender's own words, flagged by the analysis, see `quotes`
+    pub quote: Option<QuoteReason>,
+    /// the language the message is written in, detected by the analysis when it's confident of it, see `language`
+    pub language: Option<Language>,
+    /// what kind of thing is attached to the message, if anything
+    pub attachment: Option<Attachment>,
+    /// the photos, videos, and voice messages attached to the message, in the order the export lists them
+    pub attachments: Vec<MessageAttachment>,
+    /// how many messages this one stands for, more than 1 if a run of repeats was collapsed into it (see `transform::CollapseRepeats`)
+    pub repeats: usize,
+    /// the reactions to the message, in the order the export lists them
+    pub reactions: Vec<Reaction>,
+    /// the post, reel, profile or link shared in the message, if any
+    pub share: Option<Share>,
+    /// how long the call was, if the message is the record of a voice or video call (0 if it was missed)
+    pub call_duration_secs: Option<u64>,
+    /// whether the sender unsent the message, see `transform::DropUnsent`
+    pub unsent: bool,
+    /// the message this one replies to, if it's a reply and the export says so
+    pub reply_to: Option<ReplyTo>,
+    /// whether instagram wrote the message itself rather than its sender, see `SystemMessages`
+    pub system: bool,
+}
+
+/// The message a reply responds to, as much of it as the export has: newer instagram exports can have all of it or
+/// only the quoted text, discord and telegram exports have all of it unless it's no longer in the export.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ParsedConversation;
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "no you're not",
+///      "reply_to": "I'm always on time"},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "I'm always on time",
+///      "reply_to": {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "you're late again"}},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "you're late again"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// let [first, second, third] = &conversation.messages[..] else { panic!() };
+/// assert!(first.reply_to.is_none());
+///
+/// let reply = second.reply_to.as_ref().unwrap();
+/// assert_eq!(reply.sender, Some(first.sender));
+/// assert_eq!(reply.timestamp, Some(first.timestamp));
+/// assert_eq!(reply.text.as_deref(), Some("you're late again"));
+///
+/// // only the quoted text
+/// let reply = third.reply_to.as_ref().unwrap();
+/// assert_eq!((reply.sender, reply.timestamp), (None, None));
+/// assert_eq!(reply.text.as_deref(), Some("I'm always on time"));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplyTo {
+    /// who sent the replied-to message, interned in the same table as the senders
+    pub sender: Option<ParticipantId>,
+    /// when the replied-to message was sent
+    pub timestamp: Option<DateTime<Utc>>,
+    /// the text of the replied-to message, or the snippet of it the export quotes,
+    /// `None` once dropped after scoring, see `AnalyzeOptions::drop_content`
+    pub text: Option<String>,
+}
+
+/// A post, reel, profile or link shared in a message.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Share {
+    pub link: Option<String>,
+    /// the caption of the shared post or reel, part of the message's text with `SharedCaptions::Score`
+    pub text: Option<String>,
+}
+
+/// A reaction to a message, e.g. a ❤️.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reaction {
+    /// who reacted, interned in the same table as the senders, since they needn't be a listed participant
+    pub actor: ParticipantId,
+    /// the emoji they reacted with
+    pub reaction: String,
+}
+
+/// A photo, video, or voice message attached to a message, by where the export keeps its file.
+///
+/// Messages with attachments and no text aren't scored, they're kept apart in `ParsedConversation::attachment_only`
+/// (and `AnalyzedConversation::attachment_only`, for the listed participants).
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::{MessageAttachment, ParsedConversation};
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "listen",
+///      "audio_files": [{"uri": "messages/inbox/ben_1/audio/1.mp4"}]},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000000000,
+///      "photos": [{"uri": "messages/inbox/ben_1/photos/1.jpg", "creation_timestamp": 1699999990}],
+///      "videos": [{"uri": "messages/inbox/ben_1/videos/2.mp4"}]}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// assert_eq!(
+///     conversation.attachment_only[0].attachments,
+///     [
+///         MessageAttachment::Photo {
+///             uri: "messages/inbox/ben_1/photos/1.jpg".to_string(),
+///             creation_timestamp: Some(1699999990),
+///         },
+///         MessageAttachment::Video { uri: "messages/inbox/ben_1/videos/2.mp4".to_string() },
+///     ]
+/// );
+/// assert_eq!(conversation.messages[0].text(), "listen");
+/// assert_eq!(
+///     conversation.messages[0].attachments,
+///     [MessageAttachment::Audio { uri: "messages/inbox/ben_1/audio/1.mp4".to_string() }]
+/// );
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum MessageAttachment {
+    Photo {
+        /// the path of the file, relative to the root of the export
+        uri: String,
+        /// when the photo was taken, in seconds since the epoch, if the export has it
+        creation_timestamp: Option<i64>,
+    },
+    Video {
+        uri: String,
+    },
+    Audio {
+        uri: String,
+    },
+}
+
+/// What kind of thing is attached to a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Attachment {
+    /// a shared post, reel, profile or link
+    Share,
+    /// a photo, video, voice message, gif or sticker
+    Media,
+}
+
+/// What a message is, which decides where a conversation keeps it, see `ParsedConversation::all_messages`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MessageKind {
+    /// a message with text, scored by the analysis, in `ParsedConversation::messages`
+    Text,
+    /// a message with an attachment and no text, in `ParsedConversation::attachment_only`
+    Attachment,
+    /// the record of a voice or video call, in `ParsedConversation::calls`
+    Call,
+    /// a notice instagram wrote itself, like "Liked a message", in `ParsedConversation::system`, see `SystemMessages`
+    System,
+}
+
+impl Message {
+    /// What the message is, see `MessageKind`.
+    pub fn kind(&self) -> MessageKind {
+        if self.call_duration_secs.is_some() {
+            MessageKind::Call
+        } else if self.system {
+            MessageKind::System
+        } else if self.attachment.is_some() && self.text().is_empty() {
+            MessageKind::Attachment
+        } else {
+            MessageKind::Text
+        }
+    }
+
+    /// The text of the message, empty if it has none (e.g. an image) or it was dropped.
+    pub fn text(&self) -> &str {
+        self.content.as_deref().unwrap_or_default()
+    }
+
+    /// When the message was sent, in milliseconds since the epoch (0 if it was before it).
+    pub fn timestamp_ms(&self) -> usize {
+        self.timestamp.timestamp_millis().max(0) as usize
+    }
+}
+
+/// A single message file, as it is laid out in the export.
+#[derive(serde::Deserialize)]
+struct RawConversation {
+    participants: Vec<RawParticipant>,
+    messages: Vec<RawMessage>,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    thread_path: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawParticipant {
+    name: String,
+}
+
+/// The `personal_information.json` file of an instagram export, or the `profile_information.json` of a facebook one,
+/// only as much of them as is needed to find the exporter's name.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawPersonalInformation {
+    Instagram { profile_user: Vec<RawProfileUser> },
+    Facebook { profile_v2: RawProfile },
+}
+
+#[derive(serde::Deserialize)]
+struct RawProfile {
+    name: RawProfileName,
+}
+
+#[derive(serde::Deserialize)]
+struct RawProfileName {
+    full_name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawProfileUser {
+    string_map_data: HashMap<String, RawStringValue>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawStringValue {
+    value: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawMessage {
+    sender_name: String,
+    #[serde(rename = "timestamp_ms", with = "chrono::serde::ts_milliseconds")]
+    timestamp: DateTime<Utc>,
     #[serde(default)]
     // some messages (e.g. images) do not have content, so we default to an empty string that we can ignore later
-    pub content: String,
+    content: String,
+    share: Option<RawShare>,
+    photos: Option<Vec<RawMedia>>,
+    videos: Option<Vec<RawMedia>>,
+    audio_files: Option<Vec<RawMedia>>,
+    // only whether these are present matters, not what's in them
+    gifs: Option<serde::de::IgnoredAny>,
+    sticker: Option<serde::de::IgnoredAny>,
+    #[serde(default)]
+    reactions: Vec<RawReaction>,
+    call_duration: Option<u64>,
+    #[serde(default)]
+    is_unsent: bool,
+    #[serde(default)]
+    reply_to: Option<RawReplyTo>,
+}
+
+/// What an export has of the message a reply responds to.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawReplyTo {
+    /// only the quoted text
+    Snippet(String),
+    Message {
+        #[serde(default)]
+        sender_name: Option<String>,
+        #[serde(
+            default,
+            rename = "timestamp_ms",
+            with = "chrono::serde::ts_milliseconds_option"
+        )]
+        timestamp: Option<DateTime<Utc>>,
+        #[serde(default)]
+        content: Option<String>,
+    },
+}
+
+impl RawReplyTo {
+    /// The reply to `message`, with everything but its reactions and media.
+    fn to(message: &RawMessage) -> Self {
+        Self::Message {
+            sender_name: Some(message.sender_name.clone()),
+            timestamp: Some(message.timestamp),
+            content: Some(message.content.clone()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct RawMedia {
+    #[serde(default)]
+    uri: String,
+    #[serde(default)]
+    creation_timestamp: Option<i64>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawShare {
+    #[serde(default)]
+    link: Option<String>,
+    #[serde(default)]
+    share_text: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawReaction {
+    reaction: String,
+    actor: String,
+}
+
+impl RawMessage {
+    /// The shared post, reel, profile or link, leaving out empty fields.
+    fn share(&self) -> Option<Share> {
+        let share = self.share.as_ref()?;
+        let non_empty = |field: &Option<String>| field.clone().filter(|field| !field.is_empty());
+        Some(Share {
+            link: non_empty(&share.link),
+            text: non_empty(&share.share_text),
+        })
+    }
+
+    /// The text of the message, followed by the caption of what it shares with `SharedCaptions::Score`.
+    fn content(&self, shared_captions: SharedCaptions) -> String {
+        let caption = match shared_captions {
+            SharedCaptions::Ignore => None,
+            SharedCaptions::Score => self
+                .share
+                .as_ref()
+                .and_then(|share| share.share_text.as_deref())
+                .filter(|caption| !caption.is_empty()),
+        };
+        match caption {
+            Some(caption) if self.content.is_empty() => caption.to_string(),
+            Some(caption) => format!("{}\n{caption}", self.content),
+            None => self.content.clone(),
+        }
+    }
+
+    /// The photos, videos, and voice messages, in that order.
+    fn attachments(&self) -> Vec<MessageAttachment> {
+        let photos = self
+            .photos
+            .iter()
+            .flatten()
+            .map(|photo| MessageAttachment::Photo {
+                uri: photo.uri.clone(),
+                creation_timestamp: photo.creation_timestamp,
+            });
+        let videos = self
+            .videos
+            .iter()
+            .flatten()
+            .map(|video| MessageAttachment::Video {
+                uri: video.uri.clone(),
+            });
+        let audio = self
+            .audio_files
+            .iter()
+            .flatten()
+            .map(|audio| MessageAttachment::Audio {
+                uri: audio.uri.clone(),
+            });
+        photos.chain(videos).chain(audio).collect()
+    }
+
+    fn attachment(&self) -> Option<Attachment> {
+        if self.share.is_some() {
+            Some(Attachment::Share)
+        } else if self.photos.is_some()
+            || self.videos.is_some()
+            || self.audio_files.is_some()
+            || self.gifs.is_some()
+            || self.sticker.is_some()
+        {
+            Some(Attachment::Media)
+        } else {
+            None
+        }
+    }
+}
+
+/// Names to read as other names, so someone who changed their display name (and appears under both) is one participant.
+///
+/// Each name is replaced by its alias wherever it's read (participants, senders, the actors of reactions), aliases
+/// aren't followed any further, and names aliased to the same name are all merged into it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(transparent)]
+pub struct Aliases(BTreeMap<String, String>);
+
+impl Aliases {
+    pub fn new(aliases: impl IntoIterator<Item = (String, String)>) -> Self {
+        Self(aliases.into_iter().collect())
+    }
+
+    /// Reads the aliases from a json file (by its extension) or a toml one, of names and the names to read them as
+    /// (`"old name" = "new name"`).
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read aliases from {}", path.display()))?;
+        let aliases = if path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+        {
+            serde_json::from_str(&contents).map_err(anyhow::Error::from)
+        } else {
+            toml::from_str(&contents).map_err(anyhow::Error::from)
+        };
+        aliases.with_context(|| format!("failed to parse aliases from {}", path.display()))
+    }
+
+    /// The name `name` is read as, itself unless it has an alias.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        self.0.get(name).map_or(name, String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// The notices instagram writes into conversations (in english), see `SystemMessages`.
+pub const SYSTEM_MESSAGE_PATTERNS: &[&str] = &[
+    "Liked a message",
+    "* liked a message",
+    "Reacted * to your message",
+    "* reacted * to your message",
+    "* changed the theme to *.",
+    "* changed the group name to *.",
+    "* named the group *.",
+    "* changed the group photo.",
+    "* added * to the group.",
+    "* removed * from the group.",
+    "* left the group.",
+    "* set the nickname for * to *.",
+    "* set your nickname to *.",
+    "* sent an attachment.",
+    "* wasn't notified about this message because they're in quiet mode.",
+];
+
+/// The patterns of the notices instagram writes into a conversation itself, rather than its participants, like
+/// "Liked a message" or "Ana changed the group name to Trip.". They say nothing about how anyone feels, but vader would
+/// score them like any other message ("liked" is strongly positive), so a message whose whole text matches one is a
+/// `MessageKind::System` message, kept in `ParsedConversation::system` and counted in `Diagnostics::system_messages`
+/// rather than scored.
+///
+/// A pattern is the text of a notice, with a `*` standing for any text (like a name). The standard ones are
+/// `SYSTEM_MESSAGE_PATTERNS`, exports in other languages need their own.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::{ConversationDirectory, MessageKind, ParsedConversation, SystemMessages};
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000240000, "content": "Ben a aim\u00c3\u00a9 un message"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000180000,
+///      "content": "Ben wasn't notified about this message because they're in quiet mode."},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "Ana changed the group name to Beach trip."},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "Liked a message"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "you up? I liked a message you sent"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["you up? I liked a message you sent", "Ben a aimé un message"]);
+/// assert_eq!(conversation.system.len(), 3);
+/// assert_eq!(conversation.system[0].kind(), MessageKind::System);
+/// assert_eq!(conversation.diagnostics.system_messages, 3);
+///
+/// // an export in french has notices of its own
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-system");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("message_1.json"), export)?;
+/// let french = SystemMessages::standard().with("* a aimé un message");
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.system_messages(french).parse()?;
+/// assert_eq!(conversation.messages.len(), 1);
+/// // and scoring every message is none of them
+/// let conversation = ConversationDirectory::try_from(dir.clone())?
+///     .system_messages(SystemMessages::none())
+///     .parse()?;
+/// assert_eq!(conversation.messages.len(), 5);
+/// assert!(conversation.system.is_empty());
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SystemMessages(Vec<String>);
+
+impl Default for SystemMessages {
+    fn default() -> Self {
+        Self::standard()
+    }
+}
+
+impl SystemMessages {
+    /// The notices of exports in english, `SYSTEM_MESSAGE_PATTERNS`.
+    pub fn standard() -> Self {
+        Self(
+            SYSTEM_MESSAGE_PATTERNS
+                .iter()
+                .map(|pattern| pattern.to_string())
+                .collect(),
+        )
+    }
+
+    /// No notices at all, so every message with text is scored.
+    pub fn none() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Adds a pattern, `*` standing for any text.
+    #[must_use]
+    pub fn with(mut self, pattern: impl Into<String>) -> Self {
+        self.0.push(pattern.into());
+        self
+    }
+
+    /// Whether the whole text matches one of the patterns.
+    pub fn matches(&self, text: &str) -> bool {
+        self.0.iter().any(|pattern| matches_pattern(pattern, text))
+    }
+}
+
+/// Whether the whole text matches the pattern, `*` standing for any text.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut pieces = pattern.split('*');
+    let Some(mut rest) = pieces.next().and_then(|first| text.strip_prefix(first)) else {
+        return false;
+    };
+    let pieces = pieces.collect::<Vec<_>>();
+    let Some((last, middle)) = pieces.split_last() else {
+        return rest.is_empty();
+    };
+    // the pieces between the stars are matched as early as they can be, which leaves the most text for the rest
+    for piece in middle {
+        let Some(start) = rest.find(piece) else {
+            return false;
+        };
+        rest = &rest[start + piece.len()..];
+    }
+    rest.ends_with(last)
+}
+
+impl From<Vec<String>> for ParticipantNames {
+    fn from(names: Vec<String>) -> Self {
+        let mut table = Self::default();
+        for name in &names {
+            table.intern(name);
+        }
+        table
+    }
+}
+
+impl From<ParticipantNames> for Vec<String> {
+    fn from(table: ParticipantNames) -> Self {
+        table.names
+    }
+}
+
+impl ParticipantNames {
+    /// Returns the id of the given name, adding it to the table if it isn't already there.
+    pub fn intern(&mut self, name: &str) -> ParticipantId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = ParticipantId(
+            u32::try_from(self.names.len()).expect("more than u32::MAX distinct participants"),
+        );
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        id
+    }
+
+    /// Looks up the id of the given name, if it's in the table.
+    pub fn id(&self, name: &str) -> Option<ParticipantId> {
+        self.ids.get(name).copied()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `id` wasn't handed out by this table.
+    pub fn name(&self, id: ParticipantId) -> &str {
+        &self.names[id.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
 }
 
 impl TryFrom<PathBuf> for ConversationDirectory {
@@ -41,132 +1325,1901 @@ impl TryFrom<PathBuf> for ConversationDirectory {
         if path.is_dir() {
             // ensure that the directory contains files named like `message_\d+.json`
             // and that the files are not empty
-            let message_file_paths = path
+            let mut message_files = path
                 .read_dir()?
                 // filter out non-files
                 .filter_map(|entry| entry.ok().map(|entry| entry.path()))
                 // filter out files with the wrong naming convention
                 .filter_map(|path| {
-                    let stem = path.file_stem().and_then(std::ffi::OsStr::to_str)?;
-                    let ext = path.extension().and_then(std::ffi::OsStr::to_str)?;
-                    match (
-                        stem.split_at(if stem.len() >= 8 { 8 } else { stem.len() }),
-                        ext,
-                    ) {
-                        (("message_", num), "json") if num.parse::<u32>().is_ok() => Some(path),
-                        _ => None,
-                    }
+                    let name = path.file_name().and_then(std::ffi::OsStr::to_str)?;
+                    Some((message_file_number(name)?, path))
                 })
                 .collect::<Vec<_>>();
+            message_files.sort_unstable();
+            let message_file_paths = message_files
+                .into_iter()
+                .map(|(_, path)| path)
+                .collect::<Vec<_>>();
 
-            if message_file_paths.is_empty() {
-                Err(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    "Directory does not contain message data",
-                ))
+            if message_file_paths.is_empty() && path.join(telegram::EXPORT_FILE).is_file() {
+                // the folder of a chat exported from telegram
+                let files = MessageFiles::Files(vec![path.join(telegram::EXPORT_FILE)]);
+                Ok(Self::from_files(path, files, Vec::new()))
+            } else if message_file_paths.is_empty() {
+                // the conversation may be further down, like in the inbox of an export
+                let mut candidates = conversation_folders(&path);
+                match candidates.len() {
+                    1 => Self::try_from(candidates.remove(0)),
+                    0 => Err(std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "Directory does not contain message data",
+                    )),
+                    found => {
+                        let mut listed = candidates
+                            .iter()
+                            .take(LISTED_CANDIDATES)
+                            .map(|folder| folder.display().to_string())
+                            .collect::<Vec<_>>();
+                        if found > LISTED_CANDIDATES {
+                            listed.push(format!("and {} more", found - LISTED_CANDIDATES));
+                        }
+                        Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            format!(
+                                "Directory contains {found} conversations, point the path at one of them: {}",
+                                listed.join(", ")
+                            ),
+                        ))
+                    }
+                }
             } else {
-                Ok(Self {
-                    _path: path,
-                    message_file_paths,
-                })
+                Ok(Self::from_files(
+                    path,
+                    MessageFiles::Files(message_file_paths),
+                    Vec::new(),
+                ))
             }
-        } else {
+        } else if let Some((archive, inside)) = find_archive(&path) {
+            ArchiveContents::read(archive)?.conversation(archive, &inside)
+        } else if path.is_file() {
+            // a single message file, whatever it's named, whether it's one is only known once it's parsed
+            let files = MessageFiles::Files(vec![path.clone()]);
+            Ok(Self::from_files(path, files, Vec::new()))
+        } else if path.exists() {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
-                "Path is not a directory",
+                "Path is not a directory or a file",
             ))
+        } else {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Path does not exist",
+            ))
+        }
+    }
+}
+
+/// How many levels below a folder without message files conversations are looked for, enough to reach a conversation
+/// from the root of an export (`your_instagram_activity/messages/inbox/<conversation>`).
+pub const SEARCH_DEPTH: usize = 6;
+
+/// How many of the conversations found below a folder are named when there's more than one.
+const LISTED_CANDIDATES: usize = 10;
+
+/// The folders below `path` (up to `SEARCH_DEPTH` levels down) with message files in them, or the `result.json` of a
+/// telegram export, ordered by path. Folders that can't be read are left out.
+fn conversation_folders(path: &Path) -> Vec<PathBuf> {
+    let is_conversation =
+        |name: &str| message_file_number(name).is_some() || name == telegram::EXPORT_FILE;
+    let mut folders = walkdir::WalkDir::new(path)
+        .min_depth(2)
+        .max_depth(SEARCH_DEPTH + 1)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_conversation))
+        .filter_map(|entry| entry.path().parent().map(Path::to_path_buf))
+        .collect::<Vec<_>>();
+    folders.sort_unstable();
+    folders.dedup();
+    folders
+}
+
+/// The numbers missing from those of the message files of the folder at `path` (which are numbered from 1), and the files
+/// numbered the same as the one before them. Nothing is missing from a single file, or from files that aren't numbered.
+fn check_numbering(path: &Path, files: &MessageFiles) -> (Vec<u32>, Vec<PathBuf>) {
+    let numbered = match files {
+        MessageFiles::Files(paths) if path.is_dir() => paths
+            .iter()
+            .map(|path| {
+                let name = path.file_name()?.to_str()?;
+                Some((message_file_number(name)?, path.clone()))
+            })
+            .collect::<Option<Vec<_>>>(),
+        #[cfg(feature = "zip")]
+        MessageFiles::Archive {
+            archive, entries, ..
+        } => entries
+            .iter()
+            .map(|(name, _)| {
+                let number = message_file_number(name.rsplit('/').next()?)?;
+                Some((number, archive.join(name)))
+            })
+            .collect(),
+        MessageFiles::Files(_) => None,
+    };
+    let (mut missing, mut duplicates) = (Vec::new(), Vec::new());
+    let mut previous = 0;
+    // the files are ordered by number
+    for (number, path) in numbered.unwrap_or_default() {
+        if number == previous {
+            duplicates.push(path);
+        } else {
+            missing.extend(previous + 1..number);
+        }
+        previous = number;
+    }
+    (missing, duplicates)
+}
+
+impl TryFrom<&Path> for ConversationDirectory {
+    type Error = std::io::Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::try_from(path.to_path_buf())
+    }
+}
+
+/// The number in the name of a message file (`message_<number>.json`, `.json.gz` when it's compressed, or `.html` in an
+/// html export, in any case), `None` if it isn't named like one.
+fn message_file_number(name: &str) -> Option<u32> {
+    const PREFIX: &str = "message_";
+    let (stem, extension) = name.rsplit_once('.')?;
+    let (stem, extension, known) = match stem.rsplit_once('.') {
+        Some((inner, inner_extension)) if extension.eq_ignore_ascii_case("gz") => {
+            (inner, inner_extension, &["json"][..])
+        }
+        _ => (stem, extension, &["json", "html"][..]),
+    };
+    if !known
+        .iter()
+        .any(|known| extension.eq_ignore_ascii_case(known))
+    {
+        return None;
+    }
+    // `get` rather than slicing, the prefix may end inside a character of a name that isn't a message file's
+    if !stem.get(..PREFIX.len())?.eq_ignore_ascii_case(PREFIX) {
+        return None;
+    }
+    // digits only, `parse` would take a sign too
+    let number = &stem[PREFIX.len()..];
+    if number.is_empty() || !number.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+/// The number in the name of a file matched by a pattern, the first run of digits in it, to order the files by.
+fn first_number(name: &str) -> Option<u64> {
+    let start = name.find(|c: char| c.is_ascii_digit())?;
+    let digits = &name[start..];
+    let end = digits
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(digits.len());
+    digits[..end].parse().ok()
+}
+
+/// The zip archive `path` is, or is in (as `<archive>.zip/<path in the archive>`), and the path in it.
+fn find_archive(path: &Path) -> Option<(&Path, String)> {
+    let archive = path.ancestors().find(|ancestor| {
+        ancestor.is_file()
+            && ancestor
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+    })?;
+    // zip entries always use forward slashes
+    let inside = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some((archive, inside))
+}
+
+/// The conversations in the zip archive of an export.
+#[cfg(feature = "zip")]
+struct ArchiveContents {
+    /// the message files (by name and index) of every folder of the inbox with any, by the folder's path,
+    /// ordered by the number in their name
+    conversations: std::collections::BTreeMap<String, Vec<(String, usize)>>,
+    /// the entries whose names aren't utf-8 (decoded lossily)
+    skipped_entries: Vec<String>,
+    /// whether there are archives in the archive, which aren't looked into
+    nested_archives: bool,
+}
+
+#[cfg(feature = "zip")]
+impl ArchiveContents {
+    /// Lists the message files in `messages/inbox/*/` of the archive, without reading them.
+    fn read(archive: &Path) -> std::io::Result<Self> {
+        let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+        let mut conversations = std::collections::BTreeMap::<String, Vec<_>>::new();
+        let mut skipped_entries = Vec::new();
+        let mut nested_archives = false;
+        for index in 0..zip.len() {
+            let entry = zip.by_index_raw(index)?;
+            let Ok(name) = std::str::from_utf8(entry.name_raw()) else {
+                skipped_entries.push(String::from_utf8_lossy(entry.name_raw()).into_owned());
+                continue;
+            };
+            nested_archives |= name.to_ascii_lowercase().ends_with(".zip");
+            let Some((folder, file)) = name.rsplit_once('/') else {
+                continue;
+            };
+            let in_inbox = folder.rsplit_once('/').is_some_and(|(parent, _)| {
+                parent == "messages/inbox" || parent.ends_with("/messages/inbox")
+            });
+            if let (true, Some(number)) = (in_inbox, message_file_number(file)) {
+                conversations.entry(folder.to_string()).or_default().push((
+                    number,
+                    name.to_string(),
+                    index,
+                ));
+            }
+        }
+        let conversations = conversations
+            .into_iter()
+            .map(|(folder, mut files)| {
+                files.sort_unstable();
+                let files = files.into_iter().map(|(_, name, index)| (name, index));
+                (folder, files.collect())
+            })
+            .collect();
+        Ok(Self {
+            conversations,
+            skipped_entries,
+            nested_archives,
+        })
+    }
+
+    /// The conversation in the folder `inside` the archive (its full path, or just its name),
+    /// or the only one in the archive if `inside` is empty.
+    fn conversation(
+        mut self,
+        archive: &Path,
+        inside: &str,
+    ) -> std::io::Result<ConversationDirectory> {
+        let mut matching = self
+            .conversations
+            .keys()
+            .filter(|folder| {
+                inside.is_empty() || *folder == inside || folder.ends_with(&format!("/{inside}"))
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        match matching.len() {
+            1 => {
+                let folder = matching.remove(0);
+                let entries = self.conversations.remove(&folder).unwrap_or_default();
+                Ok(self.conversation_in(archive, folder, entries))
+            }
+            0 => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                if self.nested_archives {
+                    "Archive does not contain message data (archives inside it aren't looked into)"
+                } else {
+                    "Archive does not contain message data"
+                },
+            )),
+            conversations => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "Archive contains {conversations} conversations, add the folder of one to the path (e.g. {})",
+                    archive.join(&matching[0]).display()
+                ),
+            )),
+        }
+    }
+
+    /// Every conversation in the folder `inside` the archive (the whole archive if it's empty), by folder name.
+    fn inbox(self, path: PathBuf, archive: &Path, inside: &str) -> std::io::Result<InboxDirectory> {
+        let prefix = format!("{inside}/");
+        let conversations = self
+            .conversations
+            .iter()
+            .filter(|(folder, _)| inside.is_empty() || folder.starts_with(&prefix))
+            .map(|(folder, entries)| {
+                let name = folder.rsplit('/').next().unwrap_or_default().to_string();
+                (
+                    name,
+                    self.conversation_in(archive, folder.clone(), entries.clone()),
+                )
+            })
+            .collect::<Vec<_>>();
+        if conversations.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Archive does not contain any conversation folders",
+            ));
+        }
+        // the skipped entries of a conversation are reported with it
+        let skipped = self
+            .skipped_entries
+            .into_iter()
+            .filter(|name| {
+                !conversations
+                    .iter()
+                    .any(|(_, conversation)| conversation.skipped_entries.contains(name))
+            })
+            .map(|name| (name, "its name isn't utf-8".to_string()))
+            .collect();
+        Ok(InboxDirectory {
+            path,
+            conversations,
+            skipped,
+        })
+    }
+
+    /// The conversation in `folder`, with the skipped entries in it.
+    fn conversation_in(
+        &self,
+        archive: &Path,
+        folder: String,
+        entries: Vec<(String, usize)>,
+    ) -> ConversationDirectory {
+        let prefix = format!("{folder}/");
+        let skipped_entries = self
+            .skipped_entries
+            .iter()
+            .filter(|name| name.starts_with(&prefix))
+            .cloned()
+            .collect();
+        ConversationDirectory::from_files(
+            archive.join(&folder),
+            MessageFiles::Archive {
+                archive: archive.to_path_buf(),
+                folder,
+                entries,
+            },
+            skipped_entries,
+        )
+    }
+}
+
+/// Archives can't be read without the `zip` feature.
+#[cfg(not(feature = "zip"))]
+enum ArchiveContents {}
+
+#[cfg(not(feature = "zip"))]
+impl ArchiveContents {
+    fn read(_: &Path) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Reading a zip archive needs the `zip` feature, which this build was made without",
+        ))
+    }
+
+    fn conversation(self, _: &Path, _: &str) -> std::io::Result<ConversationDirectory> {
+        match self {}
+    }
+
+    fn inbox(self, _: PathBuf, _: &Path, _: &str) -> std::io::Result<InboxDirectory> {
+        match self {}
+    }
+}
+
+/// The inbox of an export (`messages/inbox` or `your_instagram_activity/messages/inbox`), a folder per conversation.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::InboxDirectory,
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// let inbox = std::env::temp_dir().join("igdm-sentiment-analysis-inbox");
+/// let config = SyntheticConfig {
+///     messages: 50,
+///     ..SyntheticConfig::default()
+/// };
+/// synthetic::write_export(&config, &inbox.join("bob_1234"))?;
+/// synthetic::write_export(&config, &inbox.join("alice_5678"))?;
+/// std::fs::create_dir_all(inbox.join("empty_9012"))?;
+///
+/// let inbox = InboxDirectory::try_from(inbox)?;
+/// let names = inbox.conversations.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+/// assert_eq!(names, ["alice_5678", "bob_1234"]);
+/// assert_eq!(inbox.skipped[0].0, "empty_9012");
+/// assert!(!inbox.conversations[0].1.parse()?.messages.is_empty());
+/// # std::fs::remove_dir_all(inbox.path())?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct InboxDirectory {
+    path: PathBuf,
+    /// every folder with message files in it, by folder name, ordered by it
+    pub conversations: Vec<(String, ConversationDirectory)>,
+    /// every other folder, by folder name, and why it isn't a conversation
+    pub skipped: Vec<(String, String)>,
+}
+
+impl TryFrom<PathBuf> for InboxDirectory {
+    type Error = std::io::Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        if !path.is_dir() {
+            if let Some((archive, inside)) = find_archive(&path) {
+                let archive = archive.to_path_buf();
+                return ArchiveContents::read(&archive)?.inbox(path, &archive, &inside);
+            }
         }
+        let mut conversations = Vec::new();
+        let mut skipped = Vec::new();
+        for entry in path.read_dir()? {
+            let folder = entry?.path();
+            if !folder.is_dir() {
+                continue;
+            }
+            let name = folder
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            match ConversationDirectory::try_from(folder) {
+                Ok(conversation) => conversations.push((name, conversation)),
+                Err(error) => skipped.push((name, error.to_string())),
+            }
+        }
+        if conversations.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "Directory does not contain any conversation folders",
+            ));
+        }
+        conversations.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        skipped.sort_unstable();
+        Ok(Self {
+            path,
+            conversations,
+            skipped,
+        })
+    }
+}
+
+impl InboxDirectory {
+    pub fn path(&self) -> &Path {
+        &self.path
     }
 }
 
 impl ConversationDirectory {
+    /// The conversation at `path`, the same as `ConversationDirectory::try_from`, without taking the path.
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-open");
+    /// std::fs::create_dir_all(dir.join("empty"))?;
+    /// std::fs::write(dir.join("message_1.json"), "{}")?;
+    ///
+    /// let error = ConversationDirectory::new(dir.join("missing")).err().expect("nothing is there");
+    /// assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    /// let error = ConversationDirectory::new(dir.join("empty")).err().expect("there are no message files");
+    /// assert_eq!(error.to_string(), "Directory does not contain message data");
+    /// // a file is taken for a message file, and only parsed later
+    /// let path: &Path = &dir.join("message_1.json");
+    /// assert_eq!(ConversationDirectory::try_from(path)?.path(), path);
+    /// assert_eq!(ConversationDirectory::new(&dir)?.path(), dir);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn new(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Self::try_from(path.as_ref().to_path_buf())
+    }
+
+    /// The conversation in the folder at `path` whose message files are those named like `pattern`, where `*` stands
+    /// for any text (e.g. `chat-*.json`), for files that aren't named `message_<number>.json`. The files are read in
+    /// the order of the first number in their names, then of their names.
+    pub fn matching(path: impl AsRef<Path>, pattern: &str) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut files = path
+            .read_dir()?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter_map(|path| {
+                let name = path.file_name()?.to_str()?;
+                matches_pattern(pattern, name)
+                    .then(|| (first_number(name), name.to_owned(), path.clone()))
+            })
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("Directory does not contain files named like \"{pattern}\""),
+            ));
+        }
+        files.sort_unstable();
+        let files = MessageFiles::Files(files.into_iter().map(|(_, _, path)| path).collect());
+        Ok(Self::from_files(path, files, Vec::new()))
+    }
+
+    fn from_files(path: PathBuf, files: MessageFiles, skipped_entries: Vec<String>) -> Self {
+        let (missing_files, duplicate_files) = check_numbering(&path, &files);
+        Self {
+            path,
+            files,
+            skipped_entries,
+            threads: None,
+            lenient: false,
+            format: InputFormat::default(),
+            whatsapp: WhatsAppOptions::default(),
+            html: HtmlOptions::default(),
+            aliases: Aliases::default(),
+            system_messages: SystemMessages::default(),
+            cache: None,
+            missing_files,
+            duplicate_files,
+            strict_numbering: false,
+            strict_fields: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// What the message files are, by default a `.txt` file is a whatsapp chat, see `InputFormat`.
+    #[must_use]
+    pub fn format(mut self, format: InputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// How to read the timestamps of whatsapp chats.
+    #[must_use]
+    pub fn whatsapp(mut self, options: WhatsAppOptions) -> Self {
+        self.whatsapp = options;
+        self
+    }
+
+    /// How to read the timestamps of the message files of an html export.
+    #[must_use]
+    pub fn html(mut self, options: HtmlOptions) -> Self {
+        self.html = options;
+        self
+    }
+
+    /// Reads names as their aliases (see `Aliases`), merging the participants whose names are aliased to the same one.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::{Aliases, ConversationDirectory};
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-aliases");
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(
+    ///     dir.join("message_1.json"),
+    ///     r#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "benji"}],
+    ///   "messages": [
+    ///     {"sender_name": "benji", "timestamp_ms": 1700000300000, "content": "new name, who dis",
+    ///      "reactions": [{"reaction": "😂", "actor": "Ana"}]},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000240000, "content": "did you change your name?",
+    ///      "reactions": [{"reaction": "👍", "actor": "Ben"}]},
+    ///     {"sender_name": "B.", "timestamp_ms": 1700000180000, "content": "trying out a nickname"},
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "hey!"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hi Ben"}
+    ///   ]
+    /// }"#,
+    /// )?;
+    /// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+    /// assert_eq!(conversation.participants.len(), 4);
+    ///
+    /// // two old names for the same person are both merged into the new one
+    /// std::fs::write(dir.join("aliases.toml"), "Ben = \"benji\"\n\"B.\" = \"benji\"\n")?;
+    /// let aliases = Aliases::load(&dir.join("aliases.toml"))?;
+    /// let conversation = ConversationDirectory::try_from(dir.clone())?.aliases(aliases).parse()?;
+    /// assert_eq!(conversation.participants.len(), 2);
+    /// let benji = conversation.names.id("benji").unwrap();
+    /// assert_eq!(conversation.names.id("Ben"), None);
+    /// assert_eq!(conversation.messages[3].reactions[0].actor, benji);
+    ///
+    /// let analysis = conversation.analyze();
+    /// assert_eq!(analysis.analysis[&benji].len(), 1 + 1 + 1);
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn aliases(mut self, aliases: Aliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// The notices instagram writes into conversations itself, kept apart rather than scored, the standard ones by
+    /// default, see `SystemMessages`.
+    #[must_use]
+    pub fn system_messages(mut self, system_messages: SystemMessages) -> Self {
+        self.system_messages = system_messages;
+        self
+    }
+
+    /// Parses the conversation, applying the standard transforms and timestamp validation,
+    /// without scoring the captions of shared posts.
     pub fn parse(&self) -> Result<ParsedConversation> {
-        Ok(ParsedConversation::merge(
-            &self
-                .message_file_paths
+        self.parse_with(
+            &TransformPipeline::standard(),
+            &TimestampValidation::default(),
+            SharedCaptions::default(),
+        )
+    }
+
+    /// Parses the message files on `threads` threads, instead of rayon's global thread pool
+    /// (which uses a thread per cpu, unless it's been configured otherwise).
+    #[must_use]
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    /// Whether message files that can't be read or parsed are skipped (and listed in the diagnostics)
+    /// instead of failing the parse, which still fails if none of them can be.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-lenient");
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(
+    ///     dir.join("message_1.json"),
+    ///     r#"{"participants": [{"name": "Ana"}], "messages": [
+    ///         {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hi"}]}"#,
+    /// )?;
+    /// std::fs::write(dir.join("message_2.json"), "{\n  \"participants\": [\n    {\"name\": \"Ana\"},,")?;
+    ///
+    /// // the error names the file, and where in it the json is broken
+    /// let error = ConversationDirectory::try_from(dir.clone())?.parse().err().expect("a file is corrupt");
+    /// let error = format!("{error:#}");
+    /// assert!(error.contains("message_2.json"));
+    /// assert!(error.contains("line 3 column 21"));
+    ///
+    /// let conversation = ConversationDirectory::try_from(dir.clone())?.lenient(true).parse()?;
+    /// assert_eq!(conversation.messages.len(), 1);
+    /// let unreadable = &conversation.diagnostics.unreadable_files;
+    /// assert_eq!(unreadable[0].path, dir.join("message_2.json"));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Keeps the parsed conversation in the `cache` folder, and loads it from there instead of parsing it again as long
+    /// as none of its message files changed, and it's parsed the same way. A conversation is cached as
+    /// `<key>.json`, the key being a hash of the path, size and modification time of every message file (or of the
+    /// archive they're in), and of the settings of the parse. Transforms are told apart by their names.
+    ///
+    /// A cache file that can't be read is parsed over, and one that can't be written is left out.
+    ///
+    /// ```
+    /// use std::time::{Duration, SystemTime};
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-cache");
+    /// let cache = dir.join(".cache");
+    /// let export = dir.join("inbox/ana_1234");
+    /// std::fs::create_dir_all(&export)?;
+    /// let file = export.join("message_1.json");
+    /// let write = |content: &str, modified: SystemTime| {
+    ///     std::fs::write(
+    ///         &file,
+    ///         format!(r#"{{"participants": [{{"name": "Ana"}}], "messages": [
+    ///             {{"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "{content}"}}]}}"#),
+    ///     )?;
+    ///     std::fs::File::options().write(true).open(&file)?.set_modified(modified)
+    /// };
+    /// let parse = || ConversationDirectory::try_from(export.clone())?.cache(&cache).parse();
+    /// let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    ///
+    /// write("good morning", modified)?;
+    /// assert_eq!(parse()?.messages[0].text(), "good morning");
+    /// assert_eq!(std::fs::read_dir(&cache)?.count(), 1);
+    ///
+    /// // a file of the same size, modified at the same time, is taken to be the same file
+    /// write("good evening", modified)?;
+    /// assert_eq!(parse()?.messages[0].text(), "good morning");
+    /// // until it's modified at another time
+    /// write("good evening", modified + Duration::from_secs(60))?;
+    /// assert_eq!(parse()?.messages[0].text(), "good evening");
+    ///
+    /// // parsing it another way is cached apart
+    /// let lenient = ConversationDirectory::try_from(export.clone())?.cache(&cache).lenient(true).parse()?;
+    /// assert_eq!(lenient.messages[0].text(), "good evening");
+    /// assert_eq!(std::fs::read_dir(&cache)?.count(), 3);
+    ///
+    /// // and a cache file that can't be read is parsed over
+    /// for entry in std::fs::read_dir(&cache)? {
+    ///     std::fs::write(entry?.path(), "{")?;
+    /// }
+    /// assert_eq!(parse()?.messages[0].text(), "good evening");
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn cache(mut self, cache: impl Into<PathBuf>) -> Self {
+        self.cache = Some(cache.into());
+        self
+    }
+
+    /// Whether the parse fails when a message file is missing from the numbering of the conversation's message files
+    /// (`message_2.json`, of `message_1.json`, `message_3.json` and `message_4.json`), rather than only listing the
+    /// missing ones in the diagnostics. Files numbered the same as another are only listed either way.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-numbering");
+    /// std::fs::create_dir_all(&dir)?;
+    /// for (file, second) in [("message_1.json", 1), ("message_01.json", 1), ("message_3.json", 3), ("message_5.json", 5)] {
+    ///     std::fs::write(
+    ///         dir.join(file),
+    ///         format!(r#"{{"participants": [{{"name": "Ana"}}], "messages": [
+    ///             {{"sender_name": "Ana", "timestamp_ms": 170000000{second}000, "content": "hi"}}]}}"#),
+    ///     )?;
+    /// }
+    ///
+    /// let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+    /// assert_eq!(conversation.messages.len(), 3);
+    /// assert_eq!(conversation.diagnostics.missing_message_files, [2, 4]);
+    /// assert_eq!(conversation.diagnostics.duplicate_message_files, [dir.join("message_1.json")]);
+    ///
+    /// let error = ConversationDirectory::try_from(dir.clone())?.strict_numbering(true).parse().err();
+    /// assert!(error.expect("files are missing").to_string().contains("message_2.json, message_4.json"));
+    /// // a single message file is never missing any
+    /// assert!(ConversationDirectory::try_from(dir.join("message_3.json"))?.strict_numbering(true).parse().is_ok());
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn strict_numbering(mut self, strict: bool) -> Self {
+        self.strict_numbering = strict;
+        self
+    }
+
+    /// Whether a message file of an instagram or facebook export with fields this version doesn't know of, at its top
+    /// level or in its messages, fails to parse (naming them), rather than having them ignored. Exports change now and
+    /// then, this is for noticing when they have something new that might be worth analyzing.
+    ///
+    /// The fields exports are known to have that aren't analyzed (like `is_still_participant`) aren't unknown.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-strict-fields");
+    /// std::fs::create_dir_all(&dir)?;
+    /// std::fs::write(
+    ///     dir.join("message_1.json"),
+    ///     r#"{"participants": [{"name": "Ana"}], "is_still_participant": true, "theme": {"name": "ocean"},
+    ///         "messages": [
+    ///           {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "hi", "is_geoblocked_for_viewer": false,
+    ///            "edited": true, "reactions": [{"reaction": "x", "actor": "Ana", "timestamp": 1700000070}]},
+    ///           {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hey", "edited": false, "pinned": true}
+    ///         ]}"#,
+    /// )?;
+    ///
+    /// assert_eq!(ConversationDirectory::try_from(dir.clone())?.parse()?.messages.len(), 2);
+    /// let error = ConversationDirectory::try_from(dir.clone())?.strict_fields(true).parse().err();
+    /// let error = format!("{:#}", error.expect("there are unknown fields"));
+    /// assert!(error.contains("message_1.json"));
+    /// assert!(error.contains("at its top level: theme; in its messages: edited, pinned"));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn strict_fields(mut self, strict: bool) -> Self {
+        self.strict_fields = strict;
+        self
+    }
+
+    /// Parses the conversation, validating the timestamps of its messages as they're merged,
+    /// then applies the given transforms to its messages, or loads it from the cache (see `ConversationDirectory::cache`).
+    pub fn parse_with(
+        &self,
+        transforms: &TransformPipeline,
+        validation: &TimestampValidation,
+        shared_captions: SharedCaptions,
+    ) -> Result<ParsedConversation> {
+        self.parse_with_progress(transforms, validation, shared_captions, |_| {})
+    }
+
+    /// `ConversationDirectory::parse_with`, reporting to `progress` as each message file is merged, see `progress`.
+    pub fn parse_with_progress(
+        &self,
+        transforms: &TransformPipeline,
+        validation: &TimestampValidation,
+        shared_captions: SharedCaptions,
+        mut progress: impl FnMut(Progress),
+    ) -> Result<ParsedConversation> {
+        if self.strict_numbering && !self.missing_files.is_empty() {
+            let missing = self
+                .missing_files
                 .iter()
-                .map(|path| {
+                .map(|number| format!("message_{number}.json"))
+                .collect::<Vec<_>>();
+            anyhow::bail!(
+                "{} is missing {}, the conversation would have gaps",
+                self.path.display(),
+                missing.join(", ")
+            );
+        }
+        let cached = self.cache.as_ref().and_then(|cache| {
+            let key = self
+                .cache_key(transforms, validation, shared_captions)
+                .ok()?;
+            Some(cache.join(format!("{key:016x}.json")))
+        });
+        let total = self.files.len();
+        let mut conversation = match cached.as_deref().and_then(load_cached) {
+            Some(conversation) => {
+                progress(Progress {
+                    phase: Phase::Parse,
+                    done: total,
+                    total,
+                });
+                conversation
+            }
+            None => {
+                let conversation =
+                    self.parse_files(transforms, validation, shared_captions, &mut progress)?;
+                if let Some(cached) = &cached {
+                    // it's only parsed again next time
+                    let _ = store_cached(cached, &conversation);
+                }
+                conversation
+            }
+        };
+        // the personal information isn't part of the key, so who made the export is always detected anew
+        conversation.me = self
+            .detect_me()
+            .and_then(|name| conversation.names.id(self.aliases.resolve(&name)))
+            .filter(|me| conversation.participants.contains(me));
+        Ok(conversation)
+    }
+
+    /// The key of the conversation in the cache, parsed with these settings, see `ConversationDirectory::cache`.
+    fn cache_key(
+        &self,
+        transforms: &TransformPipeline,
+        validation: &TimestampValidation,
+        shared_captions: SharedCaptions,
+    ) -> std::io::Result<u64> {
+        let mut hasher = DefaultHasher::new();
+        // the layout of a cached conversation can change between versions
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        let mut fingerprint = |path: &Path| -> std::io::Result<()> {
+            let metadata = std::fs::metadata(path)?;
+            std::fs::canonicalize(path)?.hash(&mut hasher);
+            metadata.len().hash(&mut hasher);
+            metadata.modified()?.hash(&mut hasher);
+            Ok(())
+        };
+        match &self.files {
+            MessageFiles::Files(paths) => {
+                for path in paths {
+                    fingerprint(path)?;
+                }
+            }
+            #[cfg(feature = "zip")]
+            MessageFiles::Archive {
+                archive,
+                folder,
+                entries,
+            } => {
+                fingerprint(archive)?;
+                folder.hash(&mut hasher);
+                entries.hash(&mut hasher);
+            }
+        }
+        self.lenient.hash(&mut hasher);
+        self.strict_fields.hash(&mut hasher);
+        self.format.hash(&mut hasher);
+        self.whatsapp.hash(&mut hasher);
+        self.html.hash(&mut hasher);
+        self.aliases.hash(&mut hasher);
+        self.system_messages.hash(&mut hasher);
+        transforms.names().hash(&mut hasher);
+        validation.earliest_ms.hash(&mut hasher);
+        // by default the window ends a day from now, which would never be the same twice, so it's only told apart by
+        // the day (a message file can't have changed to put a message in the hours between without being modified)
+        (validation.latest_ms / DAY_MS as usize).hash(&mut hasher);
+        validation.policy.hash(&mut hasher);
+        validation.unit.hash(&mut hasher);
+        shared_captions.hash(&mut hasher);
+        Ok(hasher.finish())
+    }
+
+    /// Parses the conversation, validating the timestamps of its messages as they're merged,
+    /// then applies the given transforms to its messages.
+    ///
+    /// The message files are read in order, a batch of as many as there are threads at a time, and each batch is
+    /// decoded and deserialized in parallel, so the merge still takes them in the order they're numbered.
+    fn parse_files(
+        &self,
+        transforms: &TransformPipeline,
+        validation: &TimestampValidation,
+        shared_captions: SharedCaptions,
+        progress: &mut dyn FnMut(Progress),
+    ) -> Result<ParsedConversation> {
+        let pool = self
+            .threads
+            .map(|threads| rayon::ThreadPoolBuilder::new().num_threads(threads).build())
+            .transpose()?;
+        let threads = pool.as_ref().map_or_else(
+            rayon::current_num_threads,
+            rayon::ThreadPool::current_num_threads,
+        );
+        let parse_batch = |batch: Vec<MessageFile>| {
+            batch
+                .into_par_iter()
+                .map(|(path, buffer)| {
+                    let conversation = buffer
+                        .map_err(anyhow::Error::from)
+                        .and_then(|buffer| match self.format.of(&path, &buffer) {
+                            InputFormat::Whatsapp => whatsapp::parse(buffer, &path, &self.whatsapp),
+                            InputFormat::Html => html::parse(buffer, &self.html),
+                            InputFormat::Telegram => telegram::parse(buffer),
+                            InputFormat::Discord => discord::parse(buffer),
+                            _ => RawConversation::from_buffer(buffer, self.strict_fields),
+                        })
+                        .map(|mut conversation| {
+                            conversation.alias(&self.aliases);
+                            conversation
+                        })
+                        .with_context(|| format!("failed to parse {}", path.display()));
+                    (path, conversation)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut files = self.message_files()?;
+        let batches = std::iter::from_fn(|| {
+            let batch = files.by_ref().take(threads).collect::<Vec<_>>();
+            (!batch.is_empty()).then_some(batch)
+        });
+        let (mut parsed, mut unreadable) = (0, Vec::new());
+        let total = self.files.len();
+        let files = batches
+            .flat_map(|batch| match &pool {
+                Some(pool) => pool.install(|| parse_batch(batch)),
+                None => parse_batch(batch),
+            })
+            .enumerate()
+            .inspect(|(done, _)| {
+                progress(Progress {
+                    phase: Phase::Parse,
+                    done: done + 1,
+                    total,
+                })
+            })
+            .map(|(_, file)| file)
+            .filter_map(|(path, conversation)| match conversation {
+                Err(error) if self.lenient => {
+                    unreadable.push((path, error));
+                    None
+                }
+                conversation => {
+                    parsed += 1;
+                    Some(conversation)
+                }
+            });
+        let mut conversation = ParsedConversation::merge(
+            files,
+            transforms,
+            &self.system_messages,
+            validation,
+            shared_captions,
+        )?;
+        if parsed == 0 {
+            if let Some((_, error)) = unreadable.into_iter().next() {
+                return Err(error.context("none of the message files could be parsed"));
+            }
+        } else {
+            conversation.diagnostics.unreadable_files = unreadable
+                .into_iter()
+                .map(|(path, error)| UnreadableFile {
+                    path,
+                    // the path is already in the diagnostic
+                    error: format!("{:#}", error.root_cause()),
+                })
+                .collect();
+        }
+        conversation.diagnostics.skipped_archive_entries = self.skipped_entries.clone();
+        conversation.diagnostics.missing_message_files = self.missing_files.clone();
+        conversation.diagnostics.duplicate_message_files = self.duplicate_files.clone();
+        Ok(conversation)
+    }
+
+    /// The name of the participant who made the export, from the `personal_information` folder at the root of the export
+    /// (the conversation is a few folders down, in `messages/inbox` or `your_instagram_activity/messages/inbox`),
+    /// or of its zip archive.
+    ///
+    /// `None` if the conversation isn't inside a full export, or its personal information can't be read.
+    ///
+    /// Facebook exports (of messenger conversations) are laid out the same way, with the same message files:
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ConversationDirectory;
+    ///
+    /// let export = std::env::temp_dir().join("igdm-sentiment-analysis-messenger");
+    /// let profile = export.join("personal_information/profile_information");
+    /// std::fs::create_dir_all(&profile)?;
+    /// std::fs::write(
+    ///     profile.join("profile_information.json"),
+    ///     r#"{"profile_v2": {"name": {"full_name": "Ren\u00c3\u00a9e Lam", "first_name": "Ren\u00c3\u00a9e",
+    ///         "last_name": "Lam"}, "emails": {"emails": []}}}"#,
+    /// )?;
+    /// let conversation = export.join("your_facebook_activity/messages/inbox/joelmorin_10158812345");
+    /// std::fs::create_dir_all(&conversation)?;
+    /// std::fs::write(
+    ///     conversation.join("message_1.json"),
+    ///     r#"{
+    ///   "participants": [{"name": "Jo\u00c3\u00abl Morin"}, {"name": "Ren\u00c3\u00a9e Lam"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ren\u00c3\u00a9e Lam", "timestamp_ms": 1700000120000, "content": "see you there \u00f0\u009f\u0091\u008d",
+    ///      "is_geoblocked_for_viewer": false, "is_unsent_image_by_messenger_kid_parent": false},
+    ///     {"sender_name": "Jo\u00c3\u00abl Morin", "timestamp_ms": 1700000060000,
+    ///      "photos": [{"uri": "your_facebook_activity/messages/inbox/joelmorin_10158812345/photos/1.jpg",
+    ///                  "creation_timestamp": 1700000060}],
+    ///      "is_geoblocked_for_viewer": false, "is_unsent_image_by_messenger_kid_parent": false},
+    ///     {"sender_name": "Jo\u00c3\u00abl Morin", "timestamp_ms": 1700000000000, "content": "brunch on sunday?",
+    ///      "reactions": [{"reaction": "\u00e2\u009d\u00a4", "actor": "Ren\u00c3\u00a9e Lam"}],
+    ///      "is_geoblocked_for_viewer": false, "is_unsent_image_by_messenger_kid_parent": false}
+    ///   ],
+    ///   "title": "Jo\u00c3\u00abl Morin",
+    ///   "is_still_participant": true,
+    ///   "thread_path": "inbox/joelmorin_10158812345",
+    ///   "magic_words": [],
+    ///   "image": {"uri": "", "creation_timestamp": 0},
+    ///   "joinable_mode": {"mode": 1, "link": ""}
+    /// }"#,
+    /// )?;
+    ///
+    /// let directory = ConversationDirectory::try_from(conversation)?;
+    /// assert_eq!(directory.detect_me().as_deref(), Some("Renée Lam"));
+    /// let parsed = directory.parse()?;
+    /// assert_eq!(parsed.participants.len(), 2);
+    /// assert_eq!(parsed.names.name(parsed.me.unwrap()), "Renée Lam");
+    /// let texts = parsed.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+    /// assert_eq!(texts, ["brunch on sunday?", "see you there 👍"]);
+    /// assert_eq!(parsed.attachment_only.len(), 1);
+    /// # std::fs::remove_dir_all(&export)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn detect_me(&self) -> Option<String> {
+        match &self.files {
+            MessageFiles::Files(_) => self.path.ancestors().skip(1).find_map(|ancestor| {
+                let folder = ancestor.join("personal_information");
+                PERSONAL_INFORMATION
+                    .iter()
+                    .find_map(|file| exporter_name(&std::fs::read(folder.join(file)).ok()?))
+            }),
+            #[cfg(feature = "zip")]
+            MessageFiles::Archive {
+                archive, folder, ..
+            } => {
+                let mut zip = zip::ZipArchive::new(File::open(archive).ok()?).ok()?;
+                // the folders the conversation is in, up to the root of the archive
+                let ancestors = folder.match_indices('/').rev().map(|(i, _)| &folder[..=i]);
+                ancestors.chain([""]).find_map(|ancestor| {
+                    PERSONAL_INFORMATION.iter().find_map(|file| {
+                        let mut entry = zip
+                            .by_name(&format!("{ancestor}personal_information/{file}"))
+                            .ok()?;
+                        let mut buffer = Vec::new();
+                        entry.read_to_end(&mut buffer).ok()?;
+                        exporter_name(&buffer)
+                    })
+                })
+            }
+        }
+    }
+
+    /// The path (for errors) and contents of each message file, read one at a time as they're iterated over.
+    fn message_files(&self) -> Result<Box<dyn Iterator<Item = MessageFile> + '_>> {
+        match &self.files {
+            MessageFiles::Files(paths) => Ok(Box::new(paths.iter().map(|path| {
+                let read = || {
                     let file = File::open(path)?;
                     let mut reader = BufReader::new(file);
                     let mut buffer = Vec::new();
 
                     // read the file into a buffer
                     reader.read_to_end(&mut buffer)?;
+                    decompress(path, buffer)
+                };
+                (path.clone(), read())
+            }))),
+            #[cfg(feature = "zip")]
+            MessageFiles::Archive {
+                archive, entries, ..
+            } => {
+                let mut zip = zip::ZipArchive::new(File::open(archive)?)
+                    .with_context(|| format!("failed to open {}", archive.display()))?;
+                Ok(Box::new(entries.iter().map(move |(name, index)| {
+                    let mut read = || {
+                        let mut buffer = Vec::new();
+                        zip.by_index(*index)?.read_to_end(&mut buffer)?;
+                        decompress(Path::new(name), buffer)
+                    };
+                    (archive.join(name), read())
+                })))
+            }
+        }
+    }
+}
 
-                    // decode the buffer into a string
-                    let mut decoded_bytes = Vec::new();
-                    let mut to_skip = 0;
-                    for (i, c) in buffer.iter().enumerate() {
-                        if to_skip > 0 {
-                            to_skip -= 1;
-                            continue;
-                        }
+/// The contents of the message file at `path`, decompressed if it's gzip-compressed (named `.gz`).
+fn decompress(path: &Path, buffer: Vec<u8>) -> std::io::Result<Vec<u8>> {
+    if !path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("gz"))
+    {
+        return Ok(buffer);
+    }
+    #[cfg(feature = "gzip")]
+    {
+        let mut decompressed = Vec::new();
+        flate2::read::GzDecoder::new(buffer.as_slice()).read_to_end(&mut decompressed)?;
+        Ok(decompressed)
+    }
+    #[cfg(not(feature = "gzip"))]
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "reading a gzip-compressed message file needs the `gzip` feature, which this build was made without",
+    ))
+}
 
-                        // if we encounter an escaped character (format example: \u00f0), we need to decode it (example: 0xf0, which is ð)
-                        // if the next character is not a 'u', we just push the character as is
-                        // also ensure that the next 2 characters are 0's
-                        if *c == b'\\'
-                            && buffer[i + 1] == b'u'
-                            && buffer[i + 2] == b'0'
-                            && buffer[i + 3] == b'0'
-                            && buffer[i + 4].is_ascii_alphanumeric()
-                            && buffer[i + 5].is_ascii_alphanumeric()
-                        {
-                            let hex = u8::from_str_radix(
-                                std::str::from_utf8(&buffer[i + 4..i + 6]).unwrap(),
-                                16,
-                            )
-                            .unwrap();
-                            decoded_bytes.push(hex);
-                            to_skip = 5;
-                        } else {
-                            decoded_bytes.push(*c);
-                        }
-                    }
+/// The conversation cached at `path`, if it can be read.
+fn load_cached(path: &Path) -> Option<ParsedConversation> {
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file)).ok()
+}
 
-                    let decoded_string = String::from_utf8(decoded_bytes).unwrap();
+/// Caches the conversation at `path`, written beside it first so a run that's cut short doesn't leave half of it.
+fn store_cached(path: &Path, conversation: &ParsedConversation) -> Result<()> {
+    if let Some(folder) = path.parent() {
+        std::fs::create_dir_all(folder)?;
+    }
+    let partial = path.with_extension("json.partial");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    serde_json::to_writer(&mut writer, conversation)?;
+    writer.flush()?;
+    drop(writer);
+    std::fs::rename(partial, path)?;
+    Ok(())
+}
 
-                    let parsed_conversation: ParsedConversation =
-                        serde_json::from_str(&decoded_string)?;
-                    Ok(parsed_conversation)
-                })
-                .collect::<Result<Vec<ParsedConversation>>>()?,
-        ))
+/// Where the personal information of an export is, in its `personal_information` folder.
+const PERSONAL_INFORMATION: [&str; 3] = [
+    "personal_information.json",
+    "personal_information/personal_information.json",
+    // facebook's
+    "profile_information/profile_information.json",
+];
+
+/// The name of the exporter, from the contents of the personal information of an export, see `PERSONAL_INFORMATION`.
+fn exporter_name(buffer: &[u8]) -> Option<String> {
+    let decoded = String::from_utf8(decode_escapes(buffer)).ok()?;
+    match serde_json::from_str(&decoded).ok()? {
+        RawPersonalInformation::Instagram { profile_user } => {
+            let user = profile_user.into_iter().next()?;
+            user.string_map_data
+                .into_iter()
+                .find(|(key, _)| key == "Name")
+                .map(|(_, name)| name.value)
+        }
+        RawPersonalInformation::Facebook { profile_v2 } => Some(profile_v2.name.full_name),
+    }
+}
+
+/// Decodes the `\u00XX` escapes instagram uses to encode (the bytes of) non-ascii characters.
+///
+/// Instagram writes every utf-8 byte of a non-ascii character as its own escape, so a run of consecutive escapes is
+/// decoded into bytes and validated as utf-8 as a whole. The parts of a run that aren't valid utf-8 (e.g. a `\u00e9`
+/// written by something other than instagram, which means "é" itself) are left as escapes, for the json parser to
+/// decode as the characters they name. Escapes of ascii characters (e.g. `\u0022`, a quote) are left for the json
+/// parser to handle as well, since decoding them here could produce invalid json, and so are those of any other
+/// character (e.g. the surrogate pair `\ud83d\ude02`, json's own escape of 😂, left by tools that re-export a
+/// conversation), whole, even between instagram's escapes. Anything that isn't a complete, valid escape is passed
+/// through untouched.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::{decode_escapes, ParsedConversation};
+///
+/// let decode = |escaped: &str| String::from_utf8(decode_escapes(escaped.as_bytes())).unwrap();
+/// // accents, emoji, and both mixed with unescaped text
+/// assert_eq!(decode(r"caf\u00c3\u00a9"), "café");
+/// assert_eq!(decode(r"\u00f0\u009f\u0098\u0082"), "😂");
+/// assert_eq!(
+///     decode(r"\u00c3\u00a7a va \u00f0\u009f\u0098\u0082\u00f0\u009f\u0098\u0082 \u0022na\u00c3\u00afve\u0022"),
+///     r"ça va 😂😂 \u0022naïve\u0022"
+/// );
+/// // a latin-1 escape isn't valid utf-8 on its own, so it's left for the json parser
+/// assert_eq!(decode(r"caf\u00e9 \u00c3\u00a9"), r"caf\u00e9 é");
+/// // json's own escapes of characters are left whole, surrogate pairs included, wherever they are
+/// assert_eq!(
+///     decode(r"\u00c3\u00a7a va \ud83d\ude02\u00f0\u009f\u0098\u0082\uD83D\uDE02 na\u00c3\u00afve"),
+///     r"ça va \ud83d\ude02😂\uD83D\uDE02 naïve"
+/// );
+/// assert_eq!(decode(r"\u00c3\ud83d\ude02\u00a9"), r"\u00c3\ud83d\ude02\u00a9");
+/// let export = br#"{"participants": [{"name": "Sam"}], "messages": [{"sender_name": "Sam",
+///     "timestamp_ms": 1700000000000, "content": "caf\u00c3\u00a9 \ud83d\ude02 \u00f0\u009f\u0098\u0082"}]}"#;
+/// assert_eq!(ParsedConversation::from_bytes(export)?.messages[0].text(), "café 😂 😂");
+/// // and an escaped backslash doesn't start an escape, leaving the lone continuation byte after it
+/// assert_eq!(decode(r"\\u00c3\u00a9"), r"\\u00c3\u00a9");
+/// // hex digits can be upper or lower case, and an escape with anything else in it is passed through as is
+/// assert_eq!(decode(r"\u00C3\u00A9 \u00c3\u00A9"), "é é");
+/// assert_eq!(decode(r"\u00g1 \u00zz \u00c3\u00a9"), r"\u00g1 \u00zz é");
+/// assert_eq!(decode(r"\u00c3\u00g1"), r"\u00c3\u00g1");
+/// // which the json parser then rejects, saying where
+/// let error = ParsedConversation::from_bytes(br#"{"participants": [{"name": "Zo\u00g1"}], "messages": []}"#)
+///     .err()
+///     .expect("the escape is malformed");
+/// assert!(format!("{error:#}").contains("line 1 column 36"));
+/// // a file cut off in the middle of an escape ends with the start of it, as is
+/// for truncated in [r"\", r"\u", r"\u0", r"\u00", r"\u00c"] {
+///     assert_eq!(decode(&format!(r"caf\u00c3\u00a9{truncated}")), format!("café{truncated}"));
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn decode_escapes(buffer: &[u8]) -> Vec<u8> {
+    let mut decoded_bytes = Vec::with_capacity(buffer.len());
+    let mut run = Vec::new();
+    let mut i = 0;
+    while i < buffer.len() {
+        // an escaped backslash can't start an escape sequence, so copy it over as is
+        if buffer[i..].starts_with(b"\\\\") {
+            decoded_bytes.extend_from_slice(b"\\\\");
+            i += 2;
+            continue;
+        }
+
+        // if we encounter an escaped character (format example: \u00f0), we need to decode it (example: 0xf0),
+        // the run of escapes it starts is only utf-8 as a whole (0xf0 0x9f 0x98 0x82 is 😂),
+        // and an escape cut off by the end of the buffer isn't one
+        while let Some(byte) = buffer.get(i..i + 6).and_then(decode_escape) {
+            run.push(byte);
+            i += 6;
+        }
+        if run.is_empty() {
+            // any other escape is left for the json parser, all of it, so a surrogate pair stays one
+            let length = if buffer.get(i..i + 6).is_some_and(is_escape) {
+                6
+            } else {
+                1
+            };
+            decoded_bytes.extend_from_slice(&buffer[i..i + length]);
+            i += length;
+            continue;
+        }
+        for chunk in run.utf8_chunks() {
+            decoded_bytes.extend_from_slice(chunk.valid().as_bytes());
+            for byte in chunk.invalid() {
+                decoded_bytes.extend_from_slice(format!("\\u{byte:04x}").as_bytes());
+            }
+        }
+        run.clear();
+    }
+    decoded_bytes
+}
+
+/// Decodes the `\u00XX` escapes of `buffer` the same way as `decode_escapes`, but in place, which works since decoding
+/// never makes anything longer. Parsing a message file only takes about as much memory as the file this way,
+/// on top of the messages parsed out of it, rather than twice that.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::{decode_escapes, decode_escapes_in_place},
+///     synthetic::{self, SyntheticConfig},
+/// };
+///
+/// let config = SyntheticConfig {
+///     messages: 500,
+///     ..SyntheticConfig::default()
+/// };
+/// let conversation = synthetic::generate_conversation(&config);
+/// let mut inputs = vec![
+///     synthetic::encode_export(&conversation, &conversation.messages)?.into_bytes(),
+///     br"caf\u00e9 \u00c3\u00a9 \\u00c3\u00a9 \u00f0\u009f\u0098 \u00c3 \ud83d\ude02\u00f0\u009f\u0098\u0082 \u00c3\ud83d".to_vec(),
+/// ];
+/// // and everything the fuzzer has been seeded with, or found
+/// let fuzz = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz");
+/// for dir in ["corpus/parse", "regressions/parse"] {
+///     for entry in std::fs::read_dir(fuzz.join(dir))? {
+///         inputs.push(std::fs::read(entry?.path())?);
+///     }
+/// }
+/// for input in inputs {
+///     let mut in_place = input.clone();
+///     decode_escapes_in_place(&mut in_place);
+///     assert_eq!(in_place, decode_escapes(&input));
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn decode_escapes_in_place(buffer: &mut Vec<u8>) {
+    let mut run = Vec::new();
+    // what's been decoded is written over what's already been read, which is at least as long
+    let (mut read, mut written) = (0, 0);
+    while read < buffer.len() {
+        // an escaped backslash can't start an escape sequence, so copy it over as is
+        if buffer[read..].starts_with(b"\\\\") {
+            buffer.copy_within(read..read + 2, written);
+            read += 2;
+            written += 2;
+            continue;
+        }
+
+        // a run of escapes is only utf-8 as a whole, see `decode_escapes`
+        while let Some(byte) = buffer.get(read..read + 6).and_then(decode_escape) {
+            run.push(byte);
+            read += 6;
+        }
+        if run.is_empty() {
+            // any other escape is left whole, see `decode_escapes`
+            let length = if buffer.get(read..read + 6).is_some_and(is_escape) {
+                6
+            } else {
+                1
+            };
+            buffer.copy_within(read..read + length, written);
+            read += length;
+            written += length;
+            continue;
+        }
+        for chunk in run.utf8_chunks() {
+            let valid = chunk.valid().as_bytes();
+            buffer[written..written + valid.len()].copy_from_slice(valid);
+            written += valid.len();
+            for byte in chunk.invalid() {
+                buffer[written..written + 6].copy_from_slice(format!("\\u{byte:04x}").as_bytes());
+                written += 6;
+            }
+        }
+        run.clear();
     }
+    buffer.truncate(written);
+}
+
+/// Decodes a single `\u00XX` escape into the non-ascii byte it represents, `None` if it isn't one (including when
+/// `XX` isn't two hex digits).
+fn decode_escape(escape: &[u8]) -> Option<u8> {
+    let [b'\\', b'u', b'0', b'0', hi, lo] = escape else {
+        return None;
+    };
+    let digit = |byte: &u8| char::from(*byte).to_digit(16);
+    let byte = u8::try_from(digit(hi)? * 16 + digit(lo)?).ok()?;
+    (!byte.is_ascii()).then_some(byte)
+}
+
+/// Whether `escape` is a `\uXXXX` escape, of any character.
+fn is_escape(escape: &[u8]) -> bool {
+    matches!(escape, [b'\\', b'u', digits @ ..] if digits.len() == 4 && digits.iter().all(u8::is_ascii_hexdigit))
 }
 
 impl ParsedConversation {
-    fn merge(conversations: &[Self]) -> Self {
-        let participants = conversations
-            .iter()
-            .flat_map(|c| c.participants.iter())
-            .cloned()
-            .collect::<HashSet<_>>();
-        let mut messages = conversations
-            .iter()
-            .flat_map(|c| c.messages.iter())
-            // filter out empty messages
-            .filter(|message| !message.content.is_empty())
-            // // filter out messages that are just reacting to a message
-            // .filter(|message| {
-            //     !(message.content.starts_with("Reacted ")
-            //         && message.content.ends_with(" to your message "))
-            // })
-            // filter out the "__ wasn't notified about this message" messages
-            .filter(|message| {
-                !message
-                    .content
-                    .ends_with(" wasn't notified about this message because they're in quiet mode.")
-            })
-            .cloned()
-            .collect::<Vec<_>>();
+    /// Parses the (raw, still escaped) contents of a single message file, applying the standard transforms
+    /// and timestamp validation.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ParsedConversation;
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Zo\u00c3\u00ab"}, {"name": "Sam"}],
+    ///   "messages": [
+    ///     {"sender_name": "Sam", "timestamp_ms": 1700000900000, "content": "Video call ended", "call_duration": 754},
+    ///     {"sender_name": "Zo\u00c3\u00ab", "timestamp_ms": 1700000060000, "content": "caf\u00c3\u00a9 tonight? \u00e2\u0098\u0095",
+    ///      "reactions": [{"reaction": "\u00e2\u009d\u00a4\u00ef\u00b8\u008f", "actor": "Sam"}]},
+    ///     {"sender_name": "Sam", "timestamp_ms": 1700000000000, "content": "\u00f0\u009f\u0098\u0082\u00f0\u009f\u0098\u0082 \"okay\""}
+    ///   ]
+    /// }"#;
+    /// let conversation = ParsedConversation::from_bytes(export)?;
+    /// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+    /// assert_eq!(texts, ["😂😂 \"okay\"", "café tonight? ☕"]);
+    /// assert!(conversation.names.id("Zoë").is_some());
+    /// // messages without reactions have none
+    /// let reactions = conversation.messages.iter().map(|message| &message.reactions).collect::<Vec<_>>();
+    /// assert!(reactions[0].is_empty());
+    /// assert_eq!(reactions[1][0].reaction, "❤️");
+    /// assert_eq!(conversation.names.name(reactions[1][0].actor), "Sam");
+    /// // call records aren't messages to score
+    /// assert_eq!(conversation.calls.len(), 1);
+    /// assert_eq!(conversation.calls[0].call_duration_secs, Some(754));
+    ///
+    /// // raw bytes that aren't utf-8 are an error, not a panic
+    /// let error = ParsedConversation::from_bytes(b"{\"participants\": [], \"messages\": [\xff]}")
+    ///     .err()
+    ///     .expect("the file isn't utf-8");
+    /// assert!(error.to_string().contains("not valid utf-8"));
+    /// // and so is a file cut off in the middle of an escape
+    /// let cut_off = &export[..export.windows(2).position(|window| window == br"\u").unwrap() + 3];
+    /// assert!(ParsedConversation::from_bytes(cut_off).is_err());
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn from_bytes(buffer: &[u8]) -> Result<Self> {
+        Self::merge(
+            [RawConversation::from_buffer(buffer.to_vec(), false)],
+            &TransformPipeline::standard(),
+            &SystemMessages::standard(),
+            &TimestampValidation::default(),
+            SharedCaptions::default(),
+        )
+    }
+
+    /// Merges the message files of a conversation into one, taking them one at a time (in the order they're numbered),
+    /// so that only the raw files still being parsed need to be held in memory at once,
+    /// validating the timestamps of each file's messages as it's merged,
+    /// then runs the merged messages (but the notices of `system_messages`) through the transform pipeline.
+    fn merge(
+        conversations: impl IntoIterator<Item = Result<RawConversation>>,
+        transforms: &TransformPipeline,
+        system_messages: &SystemMessages,
+        validation: &TimestampValidation,
+        shared_captions: SharedCaptions,
+    ) -> Result<Self> {
+        let mut names = ParticipantNames::default();
+        let mut participants = HashSet::new();
+        let mut messages = Vec::new();
+        let mut spans = Vec::new();
+        let mut diagnostics = Diagnostics::default();
+        let (mut title, mut thread_path) = (None, None);
+
+        for conversation in conversations {
+            let conversation = conversation?;
+            // files can disagree (e.g. after a group is renamed), the higher numbered ones win
+            let non_empty = |field: Option<String>| field.filter(|field| !field.is_empty());
+            title = non_empty(conversation.title).or(title);
+            thread_path = non_empty(conversation.thread_path).or(thread_path);
+            participants.extend(
+                conversation
+                    .participants
+                    .iter()
+                    .map(|participant| names.intern(&participant.name)),
+            );
+            let mut file_messages = conversation
+                .messages
+                .into_iter()
+                .map(|message| Message {
+                    sender: names.intern(&message.sender_name),
+                    timestamp: message.timestamp,
+                    attachment: message.attachment(),
+                    attachments: message.attachments(),
+                    share: message.share(),
+                    call_duration_secs: message.call_duration,
+                    unsent: message.is_unsent,
+                    content: Some(message.content(shared_captions)),
+                    reactions: message
+                        .reactions
+                        .into_iter()
+                        .map(|reaction| Reaction {
+                            actor: names.intern(&reaction.actor),
+                            reaction: reaction.reaction,
+                        })
+                        .collect(),
+                    reply_to: message.reply_to.map(|reply_to| match reply_to {
+                        RawReplyTo::Snippet(text) => ReplyTo {
+                            sender: None,
+                            timestamp: None,
+                            text: Some(text),
+                        },
+                        RawReplyTo::Message {
+                            sender_name,
+                            timestamp,
+                            content,
+                        } => ReplyTo {
+                            sender: sender_name.map(|name| names.intern(&name)),
+                            timestamp,
+                            text: content,
+                        },
+                    }),
+                    quote: None,
+                    language: None,
+                    repeats: 1,
+                    system: false,
+                })
+                .collect::<Vec<_>>();
+            // before the file's span is taken, so a bogus timestamp can't stretch it
+            validation.normalize_unit(&mut file_messages, &names, &mut diagnostics);
+            validation.apply(&mut file_messages, &names, &mut diagnostics);
+
+            let timestamps = || file_messages.iter().map(Message::timestamp_ms);
+            if let (Some(first), Some(last)) = (timestamps().min(), timestamps().max()) {
+                spans.push((first, last));
+            }
+            messages.extend(file_messages);
+        }
 
         // sort messages by timestamp
-        messages.sort_by_key(|message| message.timestamp_ms);
+        messages.sort_by_key(|message| message.timestamp);
 
-        Self {
+        // the files of an export can overlap, a message in more than one of them is only kept once,
+        // and a copy can only be among the messages sharing its timestamp
+        let before = messages.len();
+        let mut deduplicated = Vec::with_capacity(messages.len());
+        let mut same_timestamp = 0;
+        for message in messages {
+            if deduplicated
+                .last()
+                .is_some_and(|last: &Message| last.timestamp != message.timestamp)
+            {
+                same_timestamp = deduplicated.len();
+            }
+            let copy = deduplicated[same_timestamp..].iter().any(|kept: &Message| {
+                kept.sender == message.sender && kept.content == message.content
+            });
+            if !copy {
+                deduplicated.push(message);
+            }
+        }
+        diagnostics.duplicate_messages = before - deduplicated.len();
+        let messages = deduplicated;
+
+        // the text of a call record is boilerplate, scoring it would only add neutral messages,
+        // and there's nothing in an attachment without text for the transforms to work on, or to score,
+        // a share only has something with its caption scored (see `SharedCaptions`), which is its text by now,
+        // and instagram's own notices say nothing about anyone
+        let (mut calls, mut attachment_only, mut text) = (Vec::new(), Vec::new(), Vec::new());
+        let mut system = Vec::new();
+        for mut message in messages {
+            if message.kind() == MessageKind::Text && system_messages.matches(message.text()) {
+                message.system = true;
+            }
+            match message.kind() {
+                MessageKind::Call => calls.push(message),
+                MessageKind::Attachment => attachment_only.push(message),
+                MessageKind::Text => text.push(message),
+                MessageKind::System => system.push(message),
+            }
+        }
+        diagnostics.system_messages = system.len();
+        let messages = transforms.apply(text, &names, &mut diagnostics);
+
+        let mut conversation = Self {
             participants,
             messages,
+            names,
+            diagnostics,
+            export_boundaries: ExportBoundaries::from_spans(spans),
+            me: None,
+            attachment_only,
+            calls,
+            system,
+            title,
+            thread_path,
+        };
+        // someone who left a group isn't listed anymore, but their messages are still there
+        let senders = conversation
+            .all_messages()
+            .iter()
+            .map(|message| message.sender)
+            .collect::<Vec<_>>();
+        for sender in senders {
+            if conversation.participants.insert(sender) {
+                let name = conversation.names.name(sender).to_string();
+                conversation.diagnostics.unlisted_senders.push(name);
+            }
+        }
+        Ok(conversation)
+    }
+
+    /// Every message of the conversation, of every kind (see `MessageKind`), ordered by time, for counting what was sent
+    /// rather than what's scored. Messages the transforms dropped (like unsent ones) aren't among them.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::{MessageKind, ParsedConversation};
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000300000, "content": "Video call ended", "call_duration": 120},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000240000, "content": "call me?"},
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000180000, "photos": [{"uri": "photos/1.jpg"}]},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "", "is_unsent": true},
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "look at this"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hi!"}
+    ///   ]
+    /// }"#;
+    /// let conversation = ParsedConversation::from_bytes(export)?;
+    /// let kinds = conversation.all_messages().iter().map(|message| message.kind()).collect::<Vec<_>>();
+    /// use MessageKind::*;
+    /// assert_eq!(kinds, [Text, Text, Attachment, Text, Call]);
+    ///
+    /// // the analysis only scores the messages with text, as it always has
+    /// let analysis = conversation.analyze();
+    /// assert_eq!(analysis.analysis.values().map(Vec::len).sum::<usize>(), 3);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn all_messages(&self) -> Vec<&Message> {
+        let mut all = self
+            .messages
+            .iter()
+            .chain(&self.attachment_only)
+            .chain(&self.calls)
+            .chain(&self.system)
+            .collect::<Vec<_>>();
+        all.sort_by_key(|message| message.timestamp);
+        all
+    }
+
+    /// The conversation as json, as `ParsedConversation::write_json` writes it.
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Writes the conversation to `path` as json, for other tools to read: the messages of every message file, merged,
+    /// decoded, transformed, and ordered by time, in plain utf-8. Senders, actors, and participants are written as their
+    /// names. The same conversation is always written the same way, and reads back the same with
+    /// `ParsedConversation::read_json`.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ParsedConversation;
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}, {"name": "Cleo"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "caf\u00c3\u00a9 at 5?",
+    ///      "reactions": [{"reaction": "\u00f0\u009f\u0091\u008d", "actor": "Ana"}]},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "photos": [{"uri": "photos/1.jpg"}]},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "hi!"}
+    ///   ]
+    /// }"#;
+    /// let conversation = ParsedConversation::from_bytes(export)?;
+    /// let path = std::env::temp_dir().join("igdm-sentiment-analysis-conversation.json");
+    /// conversation.write_json(&path)?;
+    ///
+    /// let written = std::fs::read_to_string(&path)?;
+    /// assert!(written.contains("café at 5?") && written.contains("👍"));
+    /// let json: serde_json::Value = serde_json::from_str(&written)?;
+    /// assert_eq!(json["participants"], serde_json::json!(["Ana", "Ben", "Cleo"]));
+    /// assert_eq!(json["messages"][1]["sender"], "Ben");
+    /// assert_eq!(json["messages"][1]["reactions"][0]["actor"], "Ana");
+    /// assert_eq!(json["attachment_only"][0]["sender"], "Ana");
+    /// let read = ParsedConversation::read_json(&path)?;
+    /// assert!(read == conversation);
+    /// assert_eq!(read.to_json()?, written.as_bytes());
+    /// # std::fs::remove_file(&path)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_json()?)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Reads a conversation written by `ParsedConversation::write_json`.
+    pub fn read_json(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file))
+            .with_context(|| format!("{} isn't a conversation written as json", path.display()))
+    }
+}
+
+/// The fields at the top level of a message file, those that are parsed and those exports are known to have that aren't
+/// analyzed, see `ConversationDirectory::strict_fields`.
+const CONVERSATION_FIELDS: [&str; 8] = [
+    "participants",
+    "messages",
+    "title",
+    "thread_path",
+    "is_still_participant",
+    "magic_words",
+    "image",
+    "joinable_mode",
+];
+
+/// The fields of a message in a message file, those that are parsed and those exports are known to have that aren't
+/// analyzed, see `ConversationDirectory::strict_fields`.
+const MESSAGE_FIELDS: [&str; 18] = [
+    "sender_name",
+    "timestamp_ms",
+    "content",
+    "share",
+    "photos",
+    "videos",
+    "audio_files",
+    "gifs",
+    "sticker",
+    "reactions",
+    "call_duration",
+    "is_unsent",
+    "reply_to",
+    "is_geoblocked_for_viewer",
+    "is_unsent_image_by_messenger_kid_parent",
+    "is_taken_down",
+    "bumped_message_metadata",
+    // facebook's older exports
+    "type",
+];
+
+/// Fails if the (decoded) message file has fields at its top level or in its messages that aren't in `CONVERSATION_FIELDS`
+/// or `MESSAGE_FIELDS`. A file that isn't a json object passes, parsing it fails on its own.
+fn check_fields(decoded: &str) -> Result<()> {
+    let Ok(serde_json::Value::Object(conversation)) = serde_json::from_str(decoded) else {
+        return Ok(());
+    };
+    let mut top_level = conversation
+        .keys()
+        .filter(|field| !CONVERSATION_FIELDS.contains(&field.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    let mut in_messages = conversation
+        .get("messages")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_object)
+        .flat_map(serde_json::Map::keys)
+        .filter(|field| !MESSAGE_FIELDS.contains(&field.as_str()))
+        .map(String::as_str)
+        .collect::<Vec<_>>();
+    if top_level.is_empty() && in_messages.is_empty() {
+        return Ok(());
+    }
+    top_level.sort_unstable();
+    in_messages.sort_unstable();
+    in_messages.dedup();
+    let mut unknown = Vec::new();
+    if !top_level.is_empty() {
+        unknown.push(format!("at its top level: {}", top_level.join(", ")));
+    }
+    if !in_messages.is_empty() {
+        unknown.push(format!("in its messages: {}", in_messages.join(", ")));
+    }
+    anyhow::bail!(
+        "message file has fields this version doesn't know of, {}",
+        unknown.join("; ")
+    )
+}
+
+impl RawConversation {
+    /// Parses the raw contents of a message file, decoding its escapes in place, failing on fields it doesn't know of
+    /// if `strict` (see `ConversationDirectory::strict_fields`).
+    fn from_buffer(mut buffer: Vec<u8>, strict: bool) -> Result<Self> {
+        // decode the buffer into a string
+        decode_escapes_in_place(&mut buffer);
+        let decoded_string = String::from_utf8(buffer).map_err(|error| {
+            let error = error.utf8_error();
+            anyhow::anyhow!(
+                "message file is not valid utf-8 once escapes are decoded, at byte {} of the decoded file",
+                error.valid_up_to()
+            )
+        })?;
+
+        if strict {
+            check_fields(&decoded_string)?;
+        }
+        serde_json::from_str(&decoded_string)
+            .context("message file is not a conversation of an export")
+    }
+
+    /// Replaces the names of the participants, the senders, and the actors of reactions by their aliases.
+    fn alias(&mut self, aliases: &Aliases) {
+        if aliases.is_empty() {
+            return;
+        }
+        let alias = |name: &mut String| {
+            if let Some(alias) = aliases.0.get(name.as_str()) {
+                name.clone_from(alias);
+            }
+        };
+        for participant in &mut self.participants {
+            alias(&mut participant.name);
+        }
+        for message in &mut self.messages {
+            alias(&mut message.sender_name);
+            for reaction in &mut message.reactions {
+                alias(&mut reaction.actor);
+            }
+            if let Some(RawReplyTo::Message {
+                sender_name: Some(sender_name),
+                ..
+            }) = &mut message.reply_to
+            {
+                alias(sender_name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes `escaped` with both decoders, asserting they agree.
+    fn decode(escaped: &[u8]) -> Vec<u8> {
+        let decoded = decode_escapes(escaped);
+        let mut in_place = escaped.to_vec();
+        decode_escapes_in_place(&mut in_place);
+        assert_eq!(in_place, decoded);
+        decoded
+    }
+
+    #[test]
+    fn escapes_cut_off_by_the_end_are_passed_through() {
+        for truncated in [&br"\"[..], br"\u", br"\u0", br"\u00", br"\u00c"] {
+            assert_eq!(decode(truncated), truncated);
+            // after a run of escapes too, which is still decoded
+            let after_run = [&br"\u00c3\u00a9"[..], truncated].concat();
+            assert_eq!(decode(&after_run), ["é".as_bytes(), truncated].concat());
+            // and an escape with no room left for its digits isn't one
+            assert_eq!(decode_escape(truncated), None);
+        }
+    }
+
+    #[test]
+    fn escapes_take_hex_digits_of_either_case() {
+        assert_eq!(decode_escape(br"\u00e9"), Some(0xe9));
+        assert_eq!(decode_escape(br"\u00E9"), Some(0xe9));
+        assert_eq!(decode_escape(br"\u00aB"), Some(0xab));
+        assert_eq!(
+            decode(br"\u00c3\u00a9\u00C3\u00A9\u00c3\u00A9"),
+            "ééé".as_bytes()
+        );
+    }
+
+    #[test]
+    fn escapes_with_other_digits_are_not_decoded() {
+        for escape in [br"\u00g1", br"\u001g", br"\u00 1", br"\u0-e9", br"\u+0e9"] {
+            assert_eq!(decode_escape(escape), None);
+        }
+        // nor are those of ascii characters, or beyond a byte
+        assert_eq!(decode_escape(br"\u0022"), None);
+        assert_eq!(decode_escape(br"\u0100"), None);
+        // and they're passed through as is, breaking a run without losing the escapes after them
+        assert_eq!(decode(br"\u00g1"), br"\u00g1");
+        assert_eq!(
+            decode(br"\u00c3\u00a9\u00g1\u00c3\u00a9"),
+            r"é\u00g1é".as_bytes()
+        );
+    }
+
+    /// The text of the one message of a conversation whose content is `content`, as written in the file.
+    fn parse_content(content: &str) -> String {
+        let export = format!(
+            r#"{{"participants": [{{"name": "Sam"}}], "messages": [{{"sender_name": "Sam",
+                "timestamp_ms": 1700000000000, "content": "{content}"}}]}}"#
+        );
+        let conversation = ParsedConversation::from_bytes(export.as_bytes()).unwrap();
+        conversation.messages[0].text().to_string()
+    }
+
+    #[test]
+    fn mojibake_and_surrogate_pairs_mix() {
+        // instagram's escapes of ❤️ right before json's of 👍, and after them
+        let mixed =
+            r"\u00e2\u009d\u00a4\u00ef\u00b8\u008f\ud83d\udc4d\u00e2\u009d\u00a4\u00ef\u00b8\u008f";
+        assert_eq!(decode(mixed.as_bytes()), r"❤️\ud83d\udc4d❤️".as_bytes());
+        assert_eq!(parse_content(mixed), "❤️👍❤️");
+        // with text around them, and upper case surrogates
+        let mixed = r"caf\u00c3\u00a9 \uD83D\uDE02 na\u00c3\u00afve \u00f0\u009f\u0098\u0082";
+        assert_eq!(parse_content(mixed), "café 😂 naïve 😂");
+    }
+
+    #[test]
+    fn surrogate_pairs_split_a_run_of_mojibake() {
+        // the bytes of 😂 on either side of a surrogate pair aren't utf-8 on their own, so they're left as latin-1
+        let split = r"\u00f0\u009f\ud83d\ude02\u0098\u0082";
+        assert_eq!(decode(split.as_bytes()), split.as_bytes());
+        // and the ones that are control characters are then removed by the standard transforms
+        assert_eq!(parse_content(split), "\u{f0}😂");
+    }
+
+    #[test]
+    fn files_cut_off_in_an_escape_are_errors() {
+        let export = br#"{"participants": [{"name": "Sam"}], "messages": [{"sender_name": "Sam", "timestamp_ms": 1700000000000, "content": "caf"#;
+        for truncated in [&br"\"[..], br"\u", br"\u0"] {
+            assert!(ParsedConversation::from_bytes(&[&export[..], truncated].concat()).is_err());
         }
     }
 }
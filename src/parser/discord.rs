@@ -0,0 +1,232 @@
+//! Module responsible for reading the json exports of DiscordChatExporter (of a DM, a group DM, or a channel) into the
+//! same raw conversation as a message file of an instagram export.
+//!
+//! A discord export looks enough like any other json that it's never guessed, it's read with `InputFormat::Discord`
+//! (`--input-format discord`). Only messages and replies are read, the rest (calls, pins, people joining) are left out.
+//! A reply keeps what it replies to, if that's still in the export.
+//!
+//! ```
+//! use igdm_sentiment_analysis::parser::{ConversationDirectory, InputFormat};
+//!
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-discord");
+//! std::fs::create_dir_all(&dir)?;
+//! let export = dir.join("Direct Messages - alice [1085312345678901234].json");
+//! std::fs::write(
+//!     &export,
+//!     r#"{
+//!   "guild": {"id": "0", "name": "Direct Messages", "iconUrl": ""},
+//!   "channel": {"id": "1085312345678901234", "type": "DirectTextChat", "name": "alice", "topic": null},
+//!   "messages": [
+//!     {"id": "1", "type": "Default", "timestamp": "2023-03-25T23:41:02.123+02:00", "timestampEdited": null,
+//!      "isPinned": false, "content": "are you coming tonight?",
+//!      "author": {"id": "11", "name": "alice", "discriminator": "0000", "nickname": "Alice", "isBot": false},
+//!      "attachments": [], "embeds": [], "stickers": [],
+//!      "reactions": [{"emoji": {"id": "", "name": "👍", "code": "thumbsup", "isAnimated": false}, "count": 1,
+//!                     "users": [{"id": "22", "name": "bob", "discriminator": "0000", "nickname": "Bob"}]}],
+//!      "mentions": []},
+//!     {"id": "2", "type": "ChannelPinnedMessage", "timestamp": "2023-03-25T21:42:00+00:00", "content": "Pinned a message.",
+//!      "author": {"id": "22", "name": "bob"}, "attachments": [], "stickers": [], "reactions": []},
+//!     {"id": "3", "type": "Reply", "timestamp": "2023-03-25T21:43:15.5+00:00", "content": "yes!",
+//!      "author": {"id": "22", "name": "bob"},
+//!      "attachments": [{"id": "31", "url": "https://cdn.discordapp.com/attachments/1/31/snacks.jpg",
+//!                       "fileName": "snacks.jpg", "fileSizeBytes": 48213}],
+//!      "stickers": [], "reactions": [], "reference": {"messageId": "1"}}
+//!   ],
+//!   "messageCount": 3
+//! }"#,
+//! )?;
+//!
+//! let conversation = ConversationDirectory::try_from(export.clone())?
+//!     .format(InputFormat::Discord)
+//!     .parse()?;
+//! assert_eq!(conversation.title.as_deref(), Some("alice"));
+//! assert_eq!(conversation.participants.len(), 2);
+//! let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+//! assert_eq!(texts, ["are you coming tonight?", "yes!"]);
+//! assert_eq!(conversation.messages[0].timestamp_ms(), 1_679_780_462_123);
+//! assert_eq!(conversation.messages[1].timestamp_ms(), 1_679_780_595_500);
+//! assert_eq!(conversation.messages[0].reactions[0].reaction, "👍");
+//! assert_eq!(conversation.messages[1].attachments.len(), 1);
+//! let reply_to = conversation.messages[1].reply_to.as_ref().unwrap();
+//! assert_eq!(reply_to.sender, Some(conversation.messages[0].sender));
+//! assert_eq!(reply_to.text.as_deref(), Some("are you coming tonight?"));
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::de::IgnoredAny;
+
+use super::{RawConversation, RawMedia, RawMessage, RawParticipant, RawReaction, RawReplyTo};
+
+/// The types of messages that are read, the rest are notices.
+const MESSAGE_TYPES: [&str; 2] = ["Default", "Reply"];
+
+/// An export of a channel, only as much of it as is analyzed.
+#[derive(serde::Deserialize)]
+struct RawExport {
+    #[serde(default)]
+    channel: Option<RawChannel>,
+    messages: Vec<RawExportMessage>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawChannel {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawExportMessage {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// an rfc 3339 date and time, with the offset of the computer that exported it
+    timestamp: String,
+    #[serde(default)]
+    content: String,
+    author: RawUser,
+    #[serde(default)]
+    attachments: Vec<RawAttachment>,
+    #[serde(default)]
+    stickers: Vec<IgnoredAny>,
+    #[serde(default)]
+    reactions: Vec<RawExportReaction>,
+    /// the message a reply replies to
+    #[serde(default)]
+    reference: Option<RawReference>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReference {
+    #[serde(default)]
+    message_id: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawUser {
+    name: String,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawAttachment {
+    url: String,
+    #[serde(default)]
+    file_name: String,
+}
+
+#[derive(serde::Deserialize)]
+struct RawExportReaction {
+    emoji: RawEmoji,
+    /// who reacted, only the first few of them for popular reactions
+    #[serde(default)]
+    users: Vec<RawUser>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawEmoji {
+    /// the emoji itself, or the name of a custom one
+    name: String,
+}
+
+/// Reads an export into a raw conversation, titled by its channel's name, with its participants in the order they
+/// first sent something.
+///
+/// Every string of the export is moved into the conversation, not copied, so a large export isn't held twice.
+pub(super) fn parse(buffer: Vec<u8>) -> Result<RawConversation> {
+    let export: RawExport = serde_json::from_slice(&buffer)
+        .context("message file is not a channel exported by DiscordChatExporter")?;
+    drop(buffer);
+
+    let mut participants = Vec::<RawParticipant>::new();
+    let mut seen = HashSet::<String>::new();
+    let mut messages = Vec::<RawMessage>::with_capacity(export.messages.len());
+    // the index in `messages` of each message by its id, for the replies to it
+    let mut indices = HashMap::<String, usize>::new();
+    for raw in export.messages {
+        if !MESSAGE_TYPES.contains(&raw.kind.as_str()) {
+            continue;
+        }
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&raw.timestamp)
+            .with_context(|| format!("{:?} isn't an rfc 3339 timestamp", raw.timestamp))?;
+        if !seen.contains(&raw.author.name) {
+            seen.insert(raw.author.name.clone());
+            participants.push(RawParticipant {
+                name: raw.author.name.clone(),
+            });
+        }
+
+        let mut message = RawMessage {
+            sender_name: raw.author.name,
+            timestamp: timestamp.with_timezone(&Utc),
+            content: raw.content,
+            share: None,
+            photos: None,
+            videos: None,
+            audio_files: None,
+            gifs: None,
+            sticker: (!raw.stickers.is_empty()).then_some(IgnoredAny),
+            reactions: raw
+                .reactions
+                .into_iter()
+                .flat_map(|reaction| {
+                    let emoji = reaction.emoji.name;
+                    reaction.users.into_iter().map(move |user| RawReaction {
+                        reaction: emoji.clone(),
+                        actor: user.name,
+                    })
+                })
+                .collect(),
+            call_duration: None,
+            is_unsent: false,
+            // a reply to a deleted message is still a reply, to nothing that's known
+            reply_to: raw.reference.map(|reference| {
+                match reference.message_id.and_then(|id| indices.get(&id)) {
+                    Some(&index) => RawReplyTo::to(&messages[index]),
+                    None => RawReplyTo::Message {
+                        sender_name: None,
+                        timestamp: None,
+                        content: None,
+                    },
+                }
+            }),
+        };
+        // discord doesn't say what an attachment is, only its file's name
+        for attachment in raw.attachments {
+            let extension = attachment
+                .file_name
+                .rsplit_once('.')
+                .map(|(_, extension)| extension.to_ascii_lowercase());
+            let media = match extension.as_deref() {
+                Some("mp4" | "mov" | "webm" | "mkv") => &mut message.videos,
+                Some("mp3" | "ogg" | "wav" | "m4a" | "flac") => &mut message.audio_files,
+                Some("gif") => {
+                    message.gifs = Some(IgnoredAny);
+                    continue;
+                }
+                Some("jpg" | "jpeg" | "png" | "webp" | "heic") => &mut message.photos,
+                // other files (documents, archives) are only the text they're sent with
+                _ => continue,
+            };
+            media.get_or_insert_with(Vec::new).push(RawMedia {
+                uri: attachment.url,
+                creation_timestamp: None,
+            });
+        }
+        indices.insert(raw.id, messages.len());
+        messages.push(message);
+    }
+
+    Ok(RawConversation {
+        participants,
+        messages,
+        title: export.channel.and_then(|channel| channel.name),
+        thread_path: None,
+    })
+}
@@ -0,0 +1,241 @@
+//! Module responsible for reading the message files of an instagram (or facebook) export requested in html rather
+//! than json (`message_1.html`), into the same raw conversation as a message file in json.
+//!
+//! Each message is a block of the page with its sender, its content (text, photos, videos, voice messages, shared
+//! links), and when it was sent, written out in the local time and the language the export was requested in, so
+//! the timestamps are read with a date format (see `HtmlOptions`). Only the senders are known, a participant who never
+//! sent anything isn't listed. Reading html needs the `html` feature (on by default).
+//!
+//! ```
+//! # #[cfg(feature = "html")]
+//! # {
+//! use igdm_sentiment_analysis::parser::{html::HtmlOptions, ConversationDirectory};
+//!
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-html/alice_1234");
+//! std::fs::create_dir_all(&dir)?;
+//! let block = |sender: &str, content: &str, time: &str| {
+//!     format!(
+//!         r#"<div class="pam _3-95 _2ph- _a6-g uiBoxWhite noborder">
+//!              <h2 class="_3-95 _2pim _a6-h _a6-i">{sender}</h2>
+//!              <div class="_3-95 _a6-p"><div><div></div><div>{content}</div><div></div><div></div></div></div>
+//!              <div class="_3-94 _a6-o">{time}</div>
+//!            </div>"#
+//!     )
+//! };
+//! let page = [
+//!     block("Bob", r#"<img src="messages/inbox/alice_1234/photos/1.jpg" />"#, "Mar 25, 2023 9:43 pm"),
+//!     block("Bob", "yes! &amp; bringing snacks", "Mar 25, 2023 9:43 pm"),
+//!     block("Alice", "are you coming tonight?", "Mar 25, 2023 9:41 pm"),
+//! ];
+//! std::fs::write(
+//!     dir.join("message_1.html"),
+//!     format!(
+//!         r#"<html><head><title>Alice</title></head><body><div class="_a706" role="main">{}</div></body></html>"#,
+//!         page.concat()
+//!     ),
+//! )?;
+//!
+//! let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+//! assert_eq!(conversation.title.as_deref(), Some("Alice"));
+//! assert_eq!(conversation.participants.len(), 2);
+//! let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+//! assert_eq!(texts, ["are you coming tonight?", "yes! & bringing snacks"]);
+//! assert_eq!(conversation.messages[0].timestamp_ms(), 1_679_780_460_000);
+//! // the photo is a message without text, like in json
+//! assert_eq!(conversation.attachment_only.len(), 1);
+//! assert_eq!(conversation.attachment_only[0].attachments.len(), 1);
+//!
+//! // exports requested in another language have their timestamps written another way
+//! std::fs::write(
+//!     dir.join("message_1.html"),
+//!     block("Alice", "bist du heute abend da?", "25.03.2023, 21:41"),
+//! )?;
+//! let options = HtmlOptions {
+//!     date_format: "%d.%m.%Y, %H:%M".to_string(),
+//!     time_zone: igdm_sentiment_analysis::timezone::parse("Europe/Paris")?,
+//! };
+//! let conversation = ConversationDirectory::try_from(dir.clone())?.html(options).parse()?;
+//! assert_eq!(conversation.messages[0].timestamp_ms(), 1_679_776_860_000);
+//! assert!(ConversationDirectory::try_from(dir.clone())?.parse().is_err());
+//! # std::fs::remove_dir_all(dir.parent().unwrap())?;
+//! # }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::Result;
+#[cfg(feature = "html")]
+use anyhow::{bail, Context};
+#[cfg(feature = "html")]
+use chrono::{NaiveDateTime, TimeDelta};
+#[cfg(feature = "html")]
+use scraper::{ElementRef, Html, Selector};
+
+use super::RawConversation;
+#[cfg(feature = "html")]
+use super::{RawMedia, RawMessage, RawParticipant, RawShare};
+#[cfg(feature = "html")]
+use crate::timezone;
+use crate::timezone::Tz;
+
+/// The format of the timestamps of an export requested in english, e.g. `Mar 25, 2023 9:41 pm`.
+pub const DEFAULT_DATE_FORMAT: &str = "%b %d, %Y %I:%M %p";
+
+/// How to read the timestamps of an html export.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HtmlOptions {
+    /// the format of the timestamps, in chrono's `strftime` syntax (only english month names can be read with `%b`,
+    /// exports in other languages need a format of numbers, if theirs has one)
+    pub date_format: String,
+    /// the time zone of the account that requested the export, whose local time the timestamps are in (see
+    /// `timezone::from_local` for the times daylight saving time makes ambiguous)
+    pub time_zone: Tz,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            date_format: DEFAULT_DATE_FORMAT.to_string(),
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+/// Reads a page of messages into a raw conversation, titled by the page's title, with its participants in the order
+/// they first sent something.
+#[cfg(feature = "html")]
+pub(super) fn parse(buffer: Vec<u8>, options: &HtmlOptions) -> Result<RawConversation> {
+    let selector = |selector: &str| Selector::parse(selector).expect("the selectors are valid");
+    let (block, sender, content, time) = (
+        selector("._a6-g"),
+        selector("._a6-h"),
+        selector("._a6-p"),
+        selector("._a6-o"),
+    );
+    let (photo, video, audio, link, title) = (
+        selector("img[src]"),
+        selector("video[src]"),
+        selector("audio[src]"),
+        selector("a[href]"),
+        selector("title"),
+    );
+
+    let text = String::from_utf8(buffer).context("message file is not valid utf-8")?;
+    let page = Html::parse_document(&text);
+    let text_of = |element: ElementRef| element.text().collect::<String>().trim().to_string();
+
+    let mut blocks = page.select(&block).collect::<Vec<_>>();
+    if blocks.is_empty() {
+        bail!(
+            "message file is not a page of messages of an html export, it has none of their blocks"
+        );
+    }
+    // newest first, like the json
+    blocks.reverse();
+
+    let mut participants = Vec::<RawParticipant>::new();
+    let mut messages = Vec::<RawMessage>::with_capacity(blocks.len());
+    let mut previous = None;
+    for block in blocks {
+        let sender_name = block
+            .select(&sender)
+            .next()
+            .map(text_of)
+            .context("a message has no sender")?;
+        let written = block
+            .select(&time)
+            .next()
+            .map(text_of)
+            .context("a message has no timestamp")?
+            .replace(['\u{202f}', '\u{a0}'], " ");
+        let local =
+            NaiveDateTime::parse_from_str(&written, &options.date_format).with_context(|| {
+                format!(
+                    "{written:?} isn't a timestamp in the date format {:?}",
+                    options.date_format
+                )
+            })?;
+        let sent = timezone::from_local(options.time_zone, local)
+            .context("the timestamp isn't a local time")?;
+        // timestamps only go down to the minute, so the messages sharing one are spread a millisecond apart,
+        // in order, which keeps them apart from the copies of a message the merge drops
+        let timestamp = match previous {
+            Some((previous_sent, previous)) if previous_sent == sent => {
+                previous + TimeDelta::milliseconds(1)
+            }
+            _ => sent,
+        };
+        previous = Some((sent, timestamp));
+
+        if !participants
+            .iter()
+            .any(|participant| participant.name == sender_name)
+        {
+            participants.push(RawParticipant {
+                name: sender_name.clone(),
+            });
+        }
+        let mut message = RawMessage {
+            sender_name,
+            timestamp,
+            content: String::new(),
+            share: None,
+            photos: None,
+            videos: None,
+            audio_files: None,
+            gifs: None,
+            sticker: None,
+            reactions: Vec::new(),
+            call_duration: None,
+            is_unsent: false,
+            reply_to: None,
+        };
+        let Some(body) = block.select(&content).next() else {
+            messages.push(message);
+            continue;
+        };
+        // the text is every piece of text in the content, except that of shared links and of the list of reactions
+        let pieces = body.descendants().filter_map(|node| {
+            let text = node.value().as_text()?.trim();
+            let excluded = node
+                .ancestors()
+                .filter_map(ElementRef::wrap)
+                .take_while(|ancestor| ancestor.id() != body.id())
+                .any(|ancestor| matches!(ancestor.value().name(), "a" | "ul"));
+            (!text.is_empty() && !excluded).then_some(text)
+        });
+        message.content = pieces.collect::<Vec<_>>().join("\n");
+        let media = |selector: &Selector| {
+            let files = body
+                .select(selector)
+                .filter_map(|element| element.value().attr("src"))
+                .map(|uri| RawMedia {
+                    uri: uri.to_string(),
+                    creation_timestamp: None,
+                })
+                .collect::<Vec<_>>();
+            (!files.is_empty()).then_some(files)
+        };
+        message.photos = media(&photo);
+        message.videos = media(&video);
+        message.audio_files = media(&audio);
+        message.share = body.select(&link).next().map(|link| RawShare {
+            link: link.value().attr("href").map(str::to_string),
+            share_text: None,
+        });
+        messages.push(message);
+    }
+
+    Ok(RawConversation {
+        participants,
+        messages,
+        title: page.select(&title).next().map(text_of),
+        thread_path: None,
+    })
+}
+
+#[cfg(not(feature = "html"))]
+pub(super) fn parse(_: Vec<u8>, _: &HtmlOptions) -> Result<RawConversation> {
+    anyhow::bail!(
+        "reading an html export needs the `html` feature, which this build was made without"
+    )
+}
@@ -0,0 +1,233 @@
+//! Module responsible for how a `ParsedConversation` is serialized: with its participants named, rather than by their
+//! ids, which only mean something with the table of names they were handed out by (see `ParticipantNames`). The senders
+//! of messages and of the messages they reply to, the actors of reactions, the participants and the exporter are
+//! written as names, and read back into the same ids, since the table of names is written too.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    Attachment, ExportBoundaries, Message, MessageAttachment, ParsedConversation, ParticipantNames,
+    Reaction, ReplyTo, Share,
+};
+use crate::{diagnostics::Diagnostics, language::Language, quotes::QuoteReason};
+
+/// A `ParsedConversation` with its participants named.
+#[derive(Serialize, Deserialize)]
+struct NamedConversation<'a> {
+    /// ordered by id, so the same conversation is always written the same way
+    participants: Vec<Cow<'a, str>>,
+    messages: Vec<NamedMessage<'a>>,
+    names: Cow<'a, ParticipantNames>,
+    diagnostics: Cow<'a, Diagnostics>,
+    export_boundaries: Cow<'a, ExportBoundaries>,
+    me: Option<Cow<'a, str>>,
+    attachment_only: Vec<NamedMessage<'a>>,
+    calls: Vec<NamedMessage<'a>>,
+    system: Vec<NamedMessage<'a>>,
+    title: Option<Cow<'a, str>>,
+    thread_path: Option<Cow<'a, str>>,
+}
+
+/// A `Message` with its sender, the actors of its reactions, and the sender of what it replies to named.
+#[derive(Serialize, Deserialize)]
+struct NamedMessage<'a> {
+    sender: Cow<'a, str>,
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    timestamp: DateTime<Utc>,
+    content: Option<Cow<'a, str>>,
+    quote: Option<QuoteReason>,
+    language: Option<Language>,
+    attachment: Option<Attachment>,
+    attachments: Cow<'a, [MessageAttachment]>,
+    repeats: usize,
+    reactions: Vec<NamedReaction<'a>>,
+    share: Option<Cow<'a, Share>>,
+    call_duration_secs: Option<u64>,
+    unsent: bool,
+    reply_to: Option<NamedReplyTo<'a>>,
+    system: bool,
+}
+
+/// A `Reaction` with its actor named.
+#[derive(Serialize, Deserialize)]
+struct NamedReaction<'a> {
+    actor: Cow<'a, str>,
+    reaction: Cow<'a, str>,
+}
+
+/// A `ReplyTo` with its sender named.
+#[derive(Serialize, Deserialize)]
+struct NamedReplyTo<'a> {
+    sender: Option<Cow<'a, str>>,
+    #[serde(with = "chrono::serde::ts_milliseconds_option")]
+    timestamp: Option<DateTime<Utc>>,
+    text: Option<Cow<'a, str>>,
+}
+
+impl<'a> NamedConversation<'a> {
+    fn new(conversation: &'a ParsedConversation) -> Self {
+        let ParsedConversation {
+            participants,
+            messages,
+            names,
+            diagnostics,
+            export_boundaries,
+            me,
+            attachment_only,
+            calls,
+            system,
+            title,
+            thread_path,
+        } = conversation;
+        let mut sorted = participants.iter().collect::<Vec<_>>();
+        sorted.sort_unstable();
+        let named = |messages: &'a [Message]| {
+            messages
+                .iter()
+                .map(|message| NamedMessage::new(message, names))
+                .collect()
+        };
+        Self {
+            participants: sorted
+                .into_iter()
+                .map(|participant| Cow::Borrowed(names.name(*participant)))
+                .collect(),
+            messages: named(messages),
+            names: Cow::Borrowed(names),
+            diagnostics: Cow::Borrowed(diagnostics),
+            export_boundaries: Cow::Borrowed(export_boundaries),
+            me: me.map(|me| Cow::Borrowed(names.name(me))),
+            attachment_only: named(attachment_only),
+            calls: named(calls),
+            system: named(system),
+            title: title.as_deref().map(Cow::Borrowed),
+            thread_path: thread_path.as_deref().map(Cow::Borrowed),
+        }
+    }
+
+    /// The conversation, its names interned in its table of names (any that aren't in it are added to it).
+    fn into_conversation(self) -> ParsedConversation {
+        let mut names = self.names.into_owned();
+        let mut messages = |messages: Vec<NamedMessage>| {
+            messages
+                .into_iter()
+                .map(|message| message.into_message(&mut names))
+                .collect::<Vec<_>>()
+        };
+        let (messages, attachment_only, calls, system) = (
+            messages(self.messages),
+            messages(self.attachment_only),
+            messages(self.calls),
+            messages(self.system),
+        );
+        ParsedConversation {
+            participants: self
+                .participants
+                .iter()
+                .map(|participant| names.intern(participant))
+                .collect::<HashSet<_>>(),
+            messages,
+            me: self.me.map(|me| names.intern(&me)),
+            names,
+            diagnostics: self.diagnostics.into_owned(),
+            export_boundaries: self.export_boundaries.into_owned(),
+            attachment_only,
+            calls,
+            system,
+            title: self.title.map(Cow::into_owned),
+            thread_path: self.thread_path.map(Cow::into_owned),
+        }
+    }
+}
+
+impl<'a> NamedMessage<'a> {
+    fn new(message: &'a Message, names: &'a ParticipantNames) -> Self {
+        let Message {
+            sender,
+            timestamp,
+            content,
+            quote,
+            language,
+            attachment,
+            attachments,
+            repeats,
+            reactions,
+            share,
+            call_duration_secs,
+            unsent,
+            reply_to,
+            system,
+        } = message;
+        Self {
+            sender: Cow::Borrowed(names.name(*sender)),
+            timestamp: *timestamp,
+            content: content.as_deref().map(Cow::Borrowed),
+            quote: *quote,
+            language: *language,
+            attachment: *attachment,
+            attachments: Cow::Borrowed(attachments),
+            repeats: *repeats,
+            reactions: reactions
+                .iter()
+                .map(|reaction| NamedReaction {
+                    actor: Cow::Borrowed(names.name(reaction.actor)),
+                    reaction: Cow::Borrowed(&reaction.reaction),
+                })
+                .collect(),
+            share: share.as_ref().map(Cow::Borrowed),
+            call_duration_secs: *call_duration_secs,
+            unsent: *unsent,
+            reply_to: reply_to.as_ref().map(|reply| NamedReplyTo {
+                sender: reply.sender.map(|sender| Cow::Borrowed(names.name(sender))),
+                timestamp: reply.timestamp,
+                text: reply.text.as_deref().map(Cow::Borrowed),
+            }),
+            system: *system,
+        }
+    }
+
+    fn into_message(self, names: &mut ParticipantNames) -> Message {
+        Message {
+            sender: names.intern(&self.sender),
+            timestamp: self.timestamp,
+            content: self.content.map(Cow::into_owned),
+            quote: self.quote,
+            language: self.language,
+            attachment: self.attachment,
+            attachments: self.attachments.into_owned(),
+            repeats: self.repeats,
+            reactions: self
+                .reactions
+                .into_iter()
+                .map(|reaction| Reaction {
+                    actor: names.intern(&reaction.actor),
+                    reaction: reaction.reaction.into_owned(),
+                })
+                .collect(),
+            share: self.share.map(Cow::into_owned),
+            call_duration_secs: self.call_duration_secs,
+            unsent: self.unsent,
+            reply_to: self.reply_to.map(|reply| ReplyTo {
+                sender: reply.sender.map(|sender| names.intern(&sender)),
+                timestamp: reply.timestamp,
+                text: reply.text.map(Cow::into_owned),
+            }),
+            system: self.system,
+        }
+    }
+}
+
+impl Serialize for ParsedConversation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        NamedConversation::new(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ParsedConversation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NamedConversation::deserialize(deserializer).map(NamedConversation::into_conversation)
+    }
+}
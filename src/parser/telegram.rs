@@ -0,0 +1,321 @@
+//! Module responsible for reading the chats Telegram Desktop exports ("Export chat history", a `result.json` in a
+//! `ChatExport_<date>` folder) into the same raw conversation as a message file of an instagram export.
+//!
+//! The text of a message is either a string or, once it has links or formatting, an array of strings and entities
+//! (`{"type": "bold", "text": "..."}`), which is flattened back into its plain text. Service messages (someone joining,
+//! a pinned message, a changed title) are left out, except for calls, which are kept
+//! as the records of calls instagram has. A reply keeps what it replies to, if that's still in the export.
+//!
+//! ```
+//! use igdm_sentiment_analysis::parser::ConversationDirectory;
+//!
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-telegram/ChatExport_2023-03-26");
+//! std::fs::create_dir_all(&dir)?;
+//! std::fs::write(
+//!     dir.join("result.json"),
+//!     r#"{
+//!  "name": "Alice",
+//!  "type": "personal_chat",
+//!  "id": 4815162342,
+//!  "messages": [
+//!   {"id": 1, "type": "service", "date": "2023-03-25T21:40:00", "date_unixtime": "1679780400",
+//!    "actor": "Bob", "actor_id": "user2", "action": "pin_message", "message_id": 2, "text": "", "text_entities": []},
+//!   {"id": 2, "type": "message", "date": "2023-03-25T21:41:02", "date_unixtime": "1679780462",
+//!    "from": "Alice", "from_id": "user1", "text": "are you coming tonight?",
+//!    "reactions": [{"type": "emoji", "count": 1, "emoji": "👍", "recent": [{"from": "Bob", "from_id": "user2"}]}]},
+//!   {"id": 3, "type": "message", "date": "2023-03-25T21:43:15", "date_unixtime": "1679780595",
+//!    "from": "Bob", "from_id": "user2", "reply_to_message_id": 2,
+//!    "text": ["yes, ", {"type": "bold", "text": "definitely"}, "!"]},
+//!   {"id": 4, "type": "message", "date": "2023-03-25T21:43:15", "date_unixtime": "1679780595",
+//!    "from": "Bob", "from_id": "user2", "photo": "photos/photo_1@25-03-2023_21-43-15.jpg", "text": ""},
+//!   {"id": 5, "type": "service", "date": "2023-03-25T22:00:00", "date_unixtime": "1679781600",
+//!    "actor": "Alice", "actor_id": "user1", "action": "phone_call", "duration_seconds": 754, "text": ""}
+//!  ]
+//! }"#,
+//! )?;
+//!
+//! // the export's folder can be named instead of its result.json
+//! let conversation = ConversationDirectory::try_from(dir.clone())?.parse()?;
+//! assert_eq!(conversation.title.as_deref(), Some("Alice"));
+//! assert_eq!(conversation.participants.len(), 2);
+//! let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+//! assert_eq!(texts, ["are you coming tonight?", "yes, definitely!"]);
+//! assert_eq!(conversation.messages[0].timestamp_ms(), 1_679_780_462_000);
+//! assert_eq!(conversation.messages[0].reactions[0].reaction, "👍");
+//! let reply_to = conversation.messages[1].reply_to.as_ref().unwrap();
+//! assert_eq!(reply_to.text.as_deref(), Some("are you coming tonight?"));
+//! assert_eq!(conversation.attachment_only.len(), 1);
+//! assert_eq!(conversation.calls[0].call_duration_secs, Some(754));
+//! # std::fs::remove_dir_all(dir.parent().unwrap())?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, TimeDelta};
+use serde::de::IgnoredAny;
+
+use super::{RawConversation, RawMedia, RawMessage, RawParticipant, RawReaction, RawReplyTo};
+
+/// The name of the file Telegram Desktop exports a chat to, in the export's folder.
+pub const EXPORT_FILE: &str = "result.json";
+
+/// The types of the chats Telegram Desktop exports, a chat with one person or a group.
+const CHAT_TYPES: [&str; 4] = [
+    "personal_chat",
+    "private_group",
+    "private_supergroup",
+    "public_supergroup",
+];
+
+/// What telegram writes in place of a media file that was left out of the export.
+const NOT_INCLUDED: &str = "(File not included.";
+
+/// The name telegram exports for the messages of a deleted account, when it exports none.
+const DELETED_ACCOUNT: &str = "Deleted Account";
+
+/// An exported chat, only as much of it as is analyzed.
+#[derive(serde::Deserialize)]
+struct RawChat {
+    #[serde(default)]
+    name: Option<String>,
+    messages: Vec<RawChatMessage>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawChatMessage {
+    #[serde(default)]
+    id: i64,
+    /// `message` or `service`
+    #[serde(rename = "type")]
+    kind: String,
+    date_unixtime: RawUnixTime,
+    /// the sender of a message, `null` for a deleted account
+    #[serde(default)]
+    from: Option<String>,
+    /// who did what a service message is about
+    #[serde(default)]
+    actor: Option<String>,
+    #[serde(default)]
+    action: Option<String>,
+    #[serde(default)]
+    duration_seconds: Option<u64>,
+    #[serde(default)]
+    text: RawText,
+    #[serde(default)]
+    photo: Option<String>,
+    #[serde(default)]
+    file: Option<String>,
+    /// what kind of media `file` is, e.g. `voice_message` or `sticker`, none for a document
+    #[serde(default)]
+    media_type: Option<String>,
+    #[serde(default)]
+    reactions: Vec<RawChatReaction>,
+    #[serde(default)]
+    reply_to_message_id: Option<i64>,
+}
+
+/// Seconds since the epoch, which telegram writes as a string.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawUnixTime {
+    Text(String),
+    Number(u64),
+}
+
+/// The text of a message, plain or split into entities.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawText {
+    Plain(String),
+    Entities(Vec<RawEntity>),
+}
+
+impl Default for RawText {
+    fn default() -> Self {
+        Self::Plain(String::new())
+    }
+}
+
+/// A piece of the text of a message, plain, or formatted (a link, bold text, a mention, ...).
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawEntity {
+    Plain(String),
+    Formatted { text: String },
+}
+
+#[derive(serde::Deserialize)]
+struct RawChatReaction {
+    /// `None` for custom emoji, which only have the id of their sticker
+    #[serde(default)]
+    emoji: Option<String>,
+    /// the latest of the people who reacted, telegram only exports a few of them
+    #[serde(default)]
+    recent: Vec<RawRecentReaction>,
+}
+
+#[derive(serde::Deserialize)]
+struct RawRecentReaction {
+    #[serde(default)]
+    from: Option<String>,
+}
+
+impl RawText {
+    /// The plain text, with the entities' text in place.
+    fn flatten(self) -> String {
+        match self {
+            Self::Plain(text) => text,
+            Self::Entities(entities) => entities
+                .into_iter()
+                .map(|entity| match entity {
+                    RawEntity::Plain(text) | RawEntity::Formatted { text } => text,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Whether `buffer` is a chat exported from telegram, by the type of chat telegram writes before its messages
+/// (`"type": "personal_chat"`), which a message file of an instagram or facebook export doesn't have.
+pub(super) fn is_export(buffer: &[u8]) -> bool {
+    let messages = br#""messages""#;
+    let Some(end) = buffer
+        .windows(messages.len())
+        .position(|window| window == messages)
+    else {
+        return false;
+    };
+    let header = buffer[..end]
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<_>>();
+    CHAT_TYPES.iter().any(|kind| {
+        let key = format!(r#""type":"{kind}""#);
+        header
+            .windows(key.len())
+            .any(|window| window == key.as_bytes())
+    })
+}
+
+/// Reads a chat into a raw conversation, titled by the chat's name, with its participants in the order they first
+/// sent something.
+pub(super) fn parse(buffer: Vec<u8>) -> Result<RawConversation> {
+    let chat: RawChat = serde_json::from_slice(&buffer)
+        .context("message file is not a chat exported from telegram")?;
+
+    let mut participants = Vec::<RawParticipant>::new();
+    let mut messages = Vec::<RawMessage>::with_capacity(chat.messages.len());
+    let mut previous = None;
+    // the index in `messages` of each message by its id, for the replies to it
+    let mut indices = HashMap::<i64, usize>::new();
+    for raw in chat.messages {
+        let call = raw.action.as_deref() == Some("phone_call");
+        if raw.kind != "message" && !call {
+            continue;
+        }
+        let sender = match (call, raw.actor, raw.from) {
+            (true, Some(actor), _) | (false, _, Some(actor)) => actor,
+            _ => DELETED_ACCOUNT.to_string(),
+        };
+        let seconds = match &raw.date_unixtime {
+            RawUnixTime::Text(seconds) => seconds
+                .parse::<i64>()
+                .with_context(|| format!("{seconds:?} isn't a timestamp"))?,
+            RawUnixTime::Number(seconds) => i64::try_from(*seconds)?,
+        };
+        let time = DateTime::from_timestamp(seconds, 0)
+            .with_context(|| format!("{seconds} is out of range"))?;
+        // timestamps only go down to the second, so the messages sharing one are spread a millisecond apart,
+        // in order, which keeps them apart from the copies of a message the merge drops
+        let timestamp = match previous {
+            Some((previous_time, previous)) if previous_time == time => {
+                previous + TimeDelta::milliseconds(1)
+            }
+            _ => time,
+        };
+        previous = Some((time, timestamp));
+
+        if !participants
+            .iter()
+            .any(|participant| participant.name == sender)
+        {
+            participants.push(RawParticipant {
+                name: sender.clone(),
+            });
+        }
+        let mut message = RawMessage {
+            sender_name: sender,
+            timestamp,
+            content: if call {
+                String::new()
+            } else {
+                raw.text.flatten()
+            },
+            share: None,
+            photos: None,
+            videos: None,
+            audio_files: None,
+            gifs: None,
+            sticker: None,
+            reactions: raw
+                .reactions
+                .into_iter()
+                .filter_map(|reaction| Some((reaction.emoji?, reaction.recent)))
+                .flat_map(|(emoji, recent)| {
+                    recent.into_iter().map(move |recent| RawReaction {
+                        reaction: emoji.clone(),
+                        actor: recent.from.unwrap_or_else(|| DELETED_ACCOUNT.to_string()),
+                    })
+                })
+                .collect(),
+            // missed calls have no duration
+            call_duration: call.then(|| raw.duration_seconds.unwrap_or(0)),
+            is_unsent: false,
+            // a reply to a deleted message is still a reply, to nothing that's known
+            reply_to: raw.reply_to_message_id.map(|id| match indices.get(&id) {
+                Some(&index) => RawReplyTo::to(&messages[index]),
+                None => RawReplyTo::Message {
+                    sender_name: None,
+                    timestamp: None,
+                    content: None,
+                },
+            }),
+        };
+        // the files of media left out of the export are replaced by a note saying so
+        let media = |uri: String| {
+            Some(if uri.starts_with(NOT_INCLUDED) {
+                Vec::new()
+            } else {
+                vec![RawMedia {
+                    uri,
+                    creation_timestamp: None,
+                }]
+            })
+        };
+        if let Some(photo) = raw.photo {
+            message.photos = media(photo);
+        }
+        if let Some(file) = raw.file {
+            match raw.media_type.as_deref() {
+                Some("video_file" | "video_message") => message.videos = media(file),
+                Some("voice_message" | "audio_file") => message.audio_files = media(file),
+                Some("sticker") => message.sticker = Some(IgnoredAny),
+                Some("animation") => message.gifs = Some(IgnoredAny),
+                // documents are only their caption
+                _ => {}
+            }
+        }
+        indices.insert(raw.id, messages.len());
+        messages.push(message);
+    }
+
+    Ok(RawConversation {
+        participants,
+        messages,
+        title: chat.name,
+        thread_path: None,
+    })
+}
@@ -0,0 +1,319 @@
+//! Module responsible for reading the chats whatsapp exports ("Export chat"), a text file with a line per message like
+//! `[12/03/23, 9:41:02 PM] Alice: message text` (from iOS) or `12/03/23, 21:41 - Alice: message text` (from Android),
+//! into the same raw conversation as a message file of an instagram export.
+//!
+//! ```
+//! use igdm_sentiment_analysis::parser::{
+//!     whatsapp::{DateOrder, WhatsAppOptions},
+//!     ConversationDirectory,
+//! };
+//!
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-whatsapp");
+//! std::fs::create_dir_all(&dir)?;
+//! let chat = dir.join("WhatsApp Chat with Alice.txt");
+//! std::fs::write(
+//!     &chat,
+//!     "[25/03/23, 9:41:02\u{202f}PM] Messages and calls are end-to-end encrypted.\n\
+//!      [25/03/23, 9:41:02\u{202f}PM] Alice: are you coming tonight?\n\
+//!      [25/03/23, 9:43:15\u{202f}PM] Bob: yes!\n\
+//!      bringing snacks: chips and salsa\n\
+//!      \u{200e}[25/03/23, 9:43:15\u{202f}PM] Bob: \u{200e}image omitted\n\
+//!      [25/03/23, 9:43:15\u{202f}PM] Bob: yes!\n\
+//!      [26/03/23, 12:05:40\u{202f}AM] Alice: \u{200e}This message was deleted.\n",
+//! )?;
+//!
+//! let conversation = ConversationDirectory::try_from(chat.clone())?.parse()?;
+//! assert_eq!(conversation.title.as_deref(), Some("Alice"));
+//! assert_eq!(conversation.participants.len(), 2);
+//! let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+//! // the same message twice in the same second is still two messages
+//! assert_eq!(texts, ["are you coming tonight?", "yes!\nbringing snacks: chips and salsa", "yes!"]);
+//! assert_eq!(conversation.messages[0].timestamp_ms(), 1_679_780_462_000);
+//! assert_eq!(conversation.attachment_only.len(), 1);
+//!
+//! // timestamps are in the local time of the phone, and Android leaves out the seconds
+//! std::fs::write(&chat, "3/12/23, 21:41 - Alice: hi\n3/12/23, 21:41 - Bob: hey\n")?;
+//! let options = WhatsAppOptions {
+//!     date_order: DateOrder::MonthFirst,
+//!     time_zone: igdm_sentiment_analysis::timezone::parse("Europe/Paris")?,
+//! };
+//! let conversation = ConversationDirectory::try_from(chat.clone())?.whatsapp(options).parse()?;
+//! let timestamps = conversation.messages.iter().map(|message| message.timestamp_ms()).collect::<Vec<_>>();
+//! assert_eq!(timestamps, [1_678_653_660_000, 1_678_653_660_001]);
+//! // without the date order, 3/12/23 could be either day
+//! assert!(ConversationDirectory::try_from(chat.clone())?.parse().is_err());
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use serde::de::IgnoredAny;
+
+use super::{RawConversation, RawMedia, RawMessage, RawParticipant};
+use crate::timezone::{self, Tz};
+
+/// The order of the day and month in the dates of a chat, which depends on the locale of the phone that exported it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum DateOrder {
+    /// day first if a date only makes sense that way (e.g. 25/03/23), month first if one only does the other way
+    #[default]
+    Auto,
+    /// e.g. 25/03/23
+    DayFirst,
+    /// e.g. 03/25/23
+    MonthFirst,
+}
+
+/// How to read the timestamps of a whatsapp chat.
+#[derive(Debug, Clone, Copy, Hash)]
+pub struct WhatsAppOptions {
+    pub date_order: DateOrder,
+    /// the time zone of the phone that exported the chat, whose local time the timestamps are in (see
+    /// `timezone::from_local` for the times daylight saving time makes ambiguous)
+    pub time_zone: Tz,
+}
+
+impl Default for WhatsAppOptions {
+    fn default() -> Self {
+        Self {
+            date_order: DateOrder::default(),
+            time_zone: Tz::UTC,
+        }
+    }
+}
+
+/// What whatsapp writes in place of a message that was deleted, by its sender and by someone else.
+const DELETED: [&str; 2] = ["This message was deleted", "You deleted this message"];
+/// What whatsapp appends to a message that was edited.
+const EDITED: &str = "<This message was edited>";
+
+/// A line starting a message, before its date is resolved.
+struct Line<'a> {
+    /// the numbers of the date, in the order they're written
+    date: [u32; 3],
+    time: NaiveTime,
+    sender: &'a str,
+    content: String,
+}
+
+/// Reads a chat into a raw conversation, titled by the chat's file name (`WhatsApp Chat with <title>.txt`).
+pub(super) fn parse(
+    buffer: Vec<u8>,
+    path: &Path,
+    options: &WhatsAppOptions,
+) -> Result<RawConversation> {
+    let text = String::from_utf8(buffer).context("chat is not valid utf-8")?;
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut in_message = false;
+    for line in text.lines() {
+        match header(line) {
+            Some((date, time, rest)) => {
+                // the lines without a sender are notices, e.g. about encryption or someone joining a group
+                in_message = false;
+                if let Some((sender, content)) = rest.split_once(": ") {
+                    lines.push(Line {
+                        date,
+                        time,
+                        sender,
+                        content: content.to_string(),
+                    });
+                    in_message = true;
+                }
+            }
+            // a line of a message that spans several
+            None if in_message => {
+                if let Some(last) = lines.last_mut() {
+                    last.content.push('\n');
+                    last.content.push_str(line);
+                }
+            }
+            None => {}
+        }
+    }
+    if lines.is_empty() {
+        bail!("chat is not a whatsapp chat, none of its lines start with a date and time and a sender");
+    }
+
+    let date_order = match options.date_order {
+        DateOrder::Auto => detect_date_order(&lines)?,
+        order => order,
+    };
+    let mut participants = Vec::<RawParticipant>::new();
+    let mut messages = Vec::<RawMessage>::with_capacity(lines.len());
+    let mut previous = None;
+    for line in lines {
+        let [first, second, third] = line.date;
+        let (year, month, day) = match date_order {
+            _ if first >= 1000 => (first, second, third),
+            DateOrder::MonthFirst => (third, first, second),
+            _ => (third, second, first),
+        };
+        // two digit years are this century's
+        let year = if year < 100 { year + 2000 } else { year };
+        let date = NaiveDate::from_ymd_opt(year as i32, month, day)
+            .with_context(|| format!("{:?} isn't a date", line.date))?;
+        let time = timezone::from_local(options.time_zone, NaiveDateTime::new(date, line.time))
+            .context("the timestamp isn't a local time")?;
+        // timestamps only go down to the minute (or second), so the messages sharing one are spread a millisecond
+        // apart, in order, which keeps them apart from the copies of a message the merge drops
+        let timestamp = match previous {
+            Some((previous_time, previous)) if previous_time == time => {
+                previous + TimeDelta::milliseconds(1)
+            }
+            _ => time,
+        };
+        previous = Some((time, timestamp));
+
+        if !participants
+            .iter()
+            .any(|participant| participant.name == line.sender)
+        {
+            participants.push(RawParticipant {
+                name: line.sender.to_string(),
+            });
+        }
+        messages.push(message(line.sender, timestamp, &line.content));
+    }
+
+    let title = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| {
+            stem.strip_prefix("WhatsApp Chat with ")
+                .or_else(|| stem.strip_prefix("WhatsApp Chat - "))
+        })
+        .map(str::to_string);
+    Ok(RawConversation {
+        participants,
+        messages,
+        title,
+        thread_path: None,
+    })
+}
+
+/// The date, time, and the rest of a line that starts a message (or a notice),
+/// `[date, time] rest` (iOS) or `date, time - rest` (Android), `None` if the line doesn't start one.
+fn header(line: &str) -> Option<([u32; 3], NaiveTime, &str)> {
+    let line = line.trim_start_matches('\u{200e}');
+    let (stamp, rest) = match line.strip_prefix('[') {
+        Some(line) => line.split_once("] ")?,
+        None => line.split_once(" - ")?,
+    };
+    let (date, time) = stamp.split_once(", ").or_else(|| stamp.split_once(' '))?;
+
+    let mut numbers = date
+        .split(['/', '.', '-'])
+        .map(|number| number.parse().ok());
+    let date = [numbers.next()??, numbers.next()??, numbers.next()??];
+    if numbers.next().is_some() {
+        return None;
+    }
+
+    // 12 hour clocks end with am or pm, written in a few ways (9:41 PM, 9:41 p.m., with a narrow space before it)
+    let time = time
+        .replace(['\u{202f}', '\u{a0}', '.'], "")
+        .to_ascii_lowercase();
+    let (time, afternoon) = match (time.strip_suffix("am"), time.strip_suffix("pm")) {
+        (Some(time), _) => (time.trim_end(), Some(false)),
+        (_, Some(time)) => (time.trim_end(), Some(true)),
+        _ => (time.as_str(), None),
+    };
+    let mut parts = time.split(':').map(|part| part.parse::<u32>().ok());
+    let (hour, minute, second) = (
+        parts.next()??,
+        parts.next()??,
+        parts.next().unwrap_or(Some(0))?,
+    );
+    let hour = match afternoon {
+        Some(afternoon) => hour % 12 + if afternoon { 12 } else { 0 },
+        None => hour,
+    };
+    Some((date, NaiveTime::from_hms_opt(hour, minute, second)?, rest))
+}
+
+/// Whether the dates of the chat are day first or month first, from the dates that only make sense one way.
+fn detect_date_order(lines: &[Line]) -> Result<DateOrder> {
+    let only = |position: usize| {
+        lines
+            .iter()
+            .any(|line| line.date[0] < 1000 && line.date[position] > 12)
+    };
+    match (only(0), only(1)) {
+        (true, false) => Ok(DateOrder::DayFirst),
+        (false, true) => Ok(DateOrder::MonthFirst),
+        // year first
+        _ if lines.iter().all(|line| line.date[0] >= 1000) => Ok(DateOrder::Auto),
+        (false, false) => bail!(
+            "the dates of the chat could be day/month or month/day, its date order has to be given"
+        ),
+        (true, true) => bail!("the dates of the chat are neither all day/month nor all month/day"),
+    }
+}
+
+/// The message `content` stands for, media whatsapp left out of the export are attachments (without their files,
+/// unless they were exported with them), and deleted messages are unsent.
+fn message(sender: &str, timestamp: DateTime<Utc>, content: &str) -> RawMessage {
+    let content = content.trim_start_matches('\u{200e}');
+    let content = content.strip_suffix(EDITED).map_or(content, |content| {
+        content.trim_end_matches([' ', '\u{200e}'])
+    });
+    let mut message = RawMessage {
+        sender_name: sender.to_string(),
+        timestamp,
+        content: String::new(),
+        share: None,
+        photos: None,
+        videos: None,
+        audio_files: None,
+        gifs: None,
+        sticker: None,
+        reactions: Vec::new(),
+        call_duration: None,
+        is_unsent: false,
+        reply_to: None,
+    };
+    let media = |uri: &str| {
+        Some(vec![RawMedia {
+            uri: uri.to_string(),
+            creation_timestamp: None,
+        }])
+    };
+    if let Some(file) = content
+        .strip_prefix("<attached: ")
+        .and_then(|content| content.strip_suffix('>'))
+    {
+        // files exported with the chat are named like `00000012-PHOTO-2023-03-12-21-41-02.jpg`
+        match file.split('-').nth(1) {
+            Some("VIDEO") => message.videos = media(file),
+            Some("AUDIO") => message.audio_files = media(file),
+            Some("STICKER") => message.sticker = Some(IgnoredAny),
+            Some("GIF") => message.gifs = Some(IgnoredAny),
+            _ => message.photos = media(file),
+        }
+    } else if content == "<Media omitted>" {
+        // there's no telling what kind of media it was
+        message.photos = Some(Vec::new());
+    } else if let Some(kind) = content.strip_suffix(" omitted") {
+        match kind {
+            "video" => message.videos = Some(Vec::new()),
+            "audio" => message.audio_files = Some(Vec::new()),
+            "sticker" => message.sticker = Some(IgnoredAny),
+            "GIF" => message.gifs = Some(IgnoredAny),
+            "image" => message.photos = Some(Vec::new()),
+            // e.g. "document omitted", "Contact card omitted"
+            _ => message.content = content.to_string(),
+        }
+    } else if DELETED
+        .iter()
+        .any(|deleted| content.trim_end_matches('.') == *deleted)
+    {
+        message.is_unsent = true;
+    } else {
+        message.content = content.to_string();
+    }
+    message
+}
@@ -0,0 +1,323 @@
+//! Module responsible for splitting a conversation into phases: contiguous periods with a dominant character,
+//! like "high volume, positive" or "sparse, declining".
+//!
+//! The conversation is bucketed into (UTC) days, then split in three steps:
+//! 1. silences, runs of at least `SILENCE_DAYS` days without a message, become phases of their own
+//! 2. the rest is split at change points, by binary segmentation over the daily message volume and daily mean compound score,
+//!    so bursts of volume and shifts in tone both start new phases
+//! 3. each phase is labeled by the rules in `Phase::label`
+//!
+//! The phases tile the conversation exactly, from the day of the first message to the day of the last.
+
+use chrono::{NaiveDate, TimeDelta};
+
+use crate::{
+    aggregate::Period,
+    analyzer::{AnalyzedConversation, Sentiment, Thresholds},
+    format::{format_count, format_delta, format_duration, DAY_MS},
+    stats::fit_line,
+};
+
+/// A run of at least this many days without a message is a silent phase.
+pub const SILENCE_DAYS: usize = 14;
+/// Change points are never placed closer than this many days to each other, or to the ends of an active period.
+pub const MIN_PHASE_DAYS: usize = 14;
+/// A split must reduce the (variance normalized) squared error by this much times the natural log of the period's length.
+const CHANGE_PENALTY: f64 = 4.0;
+/// A phase with at least this many times the conversation's overall messages per active day is "high volume".
+pub const HIGH_VOLUME: f64 = 1.5;
+/// A phase with at most this many times the conversation's overall messages per active day is "sparse".
+pub const SPARSE_VOLUME: f64 = 0.5;
+/// If the daily mean compound score drifts by more than this over a phase, the phase is labeled by the drift rather than its mean.
+pub const TREND_CHANGE: f64 = 0.1;
+
+/// A contiguous period of the conversation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Phase {
+    /// the first day of the phase
+    pub start: NaiveDate,
+    /// the day after the last day of the phase, which is also the start of the next phase
+    pub end: NaiveDate,
+    pub messages: usize,
+    /// the mean compound score of the phase's messages, `None` for silent phases
+    pub mean_compound: Option<f64>,
+    /// how much the daily mean compound score drifted over the phase, according to a fitted line,
+    /// `None` if there weren't at least 2 days with messages
+    pub drift: Option<f64>,
+    pub label: String,
+}
+
+impl Phase {
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days()
+    }
+
+    /// A one-line description of the phase, for the report.
+    pub fn description(&self) -> String {
+        let last = self.end.pred_opt().unwrap_or(self.end);
+        let mut description = format!(
+            "{} to {} ({}): {}",
+            self.start,
+            last,
+            format_duration(self.days() as f64 * DAY_MS),
+            self.label
+        );
+        if let Some(mean) = self.mean_compound {
+            description.push_str(&format!(
+                ", {} messages ({:.1}/day), mean compound {mean:+.2}",
+                format_count(self.messages),
+                self.messages as f64 / self.days() as f64
+            ));
+        }
+        if let Some(drift) = self.drift {
+            description.push_str(&format!(", drifting {}", format_delta(drift, 2)));
+        }
+        description
+    }
+
+    /// Labels a phase by two simple rules over its aggregates:
+    /// - volume: "silent" without messages, otherwise "high volume" or "sparse" relative to the conversation's
+    ///   overall messages per active day (see `HIGH_VOLUME` and `SPARSE_VOLUME`), and "steady" in between
+    /// - tone: "improving" or "declining" if the daily mean compound score drifted by more than `TREND_CHANGE`,
+    ///   otherwise the classification of the mean compound score
+    fn label(
+        messages_per_day: f64,
+        overall_per_day: f64,
+        mean_compound: Option<f64>,
+        drift: Option<f64>,
+        thresholds: &Thresholds,
+    ) -> String {
+        let Some(mean_compound) = mean_compound else {
+            return "silent".to_string();
+        };
+        let volume = if messages_per_day >= overall_per_day * HIGH_VOLUME {
+            "high volume"
+        } else if messages_per_day <= overall_per_day * SPARSE_VOLUME {
+            "sparse"
+        } else {
+            "steady"
+        };
+        let tone = match drift {
+            Some(drift) if drift > TREND_CHANGE => "improving",
+            Some(drift) if drift < -TREND_CHANGE => "declining",
+            _ => match thresholds.classify(mean_compound) {
+                Sentiment::Positive => "positive",
+                Sentiment::Neutral => "neutral",
+                Sentiment::Negative => "negative",
+            },
+        };
+        format!("{volume}, {tone}")
+    }
+}
+
+/// Messages and summed compound score of a day.
+#[derive(Debug, Clone, Copy, Default)]
+struct Day {
+    messages: usize,
+    /// the messages counted as `AnalyzedConversation::repeats` says
+    volume: usize,
+    compound: f64,
+}
+
+impl Day {
+    fn mean(&self) -> Option<f64> {
+        (self.messages > 0).then(|| self.compound / self.messages as f64)
+    }
+}
+
+impl AnalyzedConversation {
+    /// Splits the conversation into phases, see the module documentation. Empty if there are no messages.
+    pub fn phases(&self) -> Vec<Phase> {
+        let Some((first_ms, last_ms)) = self.time_range() else {
+            return Vec::new();
+        };
+        let first_day = Period::Day.bucket(first_ms);
+        let mut days = vec![Day::default(); Period::Day.bucket(last_ms) - first_day + 1];
+        for series in self.analysis.values() {
+            for (message, score) in series {
+                let day = &mut days[Period::Day.bucket(message.timestamp_ms()) - first_day];
+                day.messages += 1;
+                day.volume += self.repeats.volume(message);
+                day.compound += score.compound;
+            }
+        }
+        let Some(first_date) =
+            chrono::DateTime::from_timestamp_millis(Period::Day.start_ms(first_day) as i64)
+                .map(|date| date.date_naive())
+        else {
+            return Vec::new();
+        };
+
+        // 1. silences, every other period is split further
+        let mut boundaries = Vec::new();
+        let mut silent_days = 0;
+        let mut active_start = 0;
+        let mut i = 0;
+        while i < days.len() {
+            if days[i].messages > 0 {
+                i += 1;
+                continue;
+            }
+            let gap_start = i;
+            while i < days.len() && days[i].messages == 0 {
+                i += 1;
+            }
+            if i - gap_start >= SILENCE_DAYS {
+                // 2. change points within the active period before the silence
+                boundaries.extend(change_points(&days[active_start..gap_start], active_start));
+                boundaries.push(gap_start);
+                boundaries.push(i);
+                silent_days += i - gap_start;
+                active_start = i;
+            }
+        }
+        boundaries.extend(change_points(&days[active_start..], active_start));
+        boundaries.push(days.len());
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let total_messages = days.iter().map(|day| day.volume).sum::<usize>();
+        let overall_per_day = total_messages as f64 / (days.len() - silent_days).max(1) as f64;
+
+        // 3. aggregate and label each phase
+        let date = |day: usize| first_date + TimeDelta::days(day as i64);
+        std::iter::once(0)
+            .chain(boundaries)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .filter(|window| window[0] < window[1])
+            .map(|window| {
+                let (start, end) = (window[0], window[1]);
+                let phase_days = &days[start..end];
+                let messages = phase_days.iter().map(|day| day.volume).sum::<usize>();
+                let scored = phase_days.iter().map(|day| day.messages).sum::<usize>();
+                let mean_compound = (scored > 0).then(|| {
+                    phase_days.iter().map(|day| day.compound).sum::<f64>() / scored as f64
+                });
+                let drift = fit_line(
+                    phase_days
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, day)| Some((i as f64, day.mean()?))),
+                )
+                .map(|(slope, _)| slope * (end - start) as f64);
+                Phase {
+                    start: date(start),
+                    end: date(end),
+                    messages,
+                    mean_compound,
+                    drift,
+                    label: Phase::label(
+                        messages as f64 / (end - start) as f64,
+                        overall_per_day,
+                        mean_compound,
+                        drift,
+                        &self.thresholds,
+                    ),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Finds change points in a period by binary segmentation, returned as day indices offset by `offset`.
+///
+/// The cost of a segment is the squared error of its daily volume (`ln(1 + messages)`) and of its daily mean compound score
+/// (over the days with messages) about their means, each normalized by its variance over the whole period.
+fn change_points(days: &[Day], offset: usize) -> Vec<usize> {
+    let volume = days
+        .iter()
+        .map(|day| (day.volume as f64).ln_1p())
+        .collect::<Vec<_>>();
+    let sentiment = days.iter().map(Day::mean).collect::<Vec<_>>();
+    let sums = PrefixSums::new(&volume, &sentiment);
+
+    let (volume_var, sentiment_var) = sums.variances(0, days.len());
+    let cost = |start: usize, end: usize| {
+        let (volume_sse, sentiment_sse) = sums.squared_errors(start, end);
+        let normalize = |sse: f64, var: f64| if var > 0.0 { sse / var } else { 0.0 };
+        normalize(volume_sse, volume_var) + normalize(sentiment_sse, sentiment_var)
+    };
+
+    let mut points = Vec::new();
+    let mut pending = vec![(0, days.len())];
+    while let Some((start, end)) = pending.pop() {
+        if end - start < 2 * MIN_PHASE_DAYS {
+            continue;
+        }
+        let whole = cost(start, end);
+        let best = (start + MIN_PHASE_DAYS..=end - MIN_PHASE_DAYS)
+            .map(|split| (split, whole - cost(start, split) - cost(split, end)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b));
+        if let Some((split, gain)) = best {
+            if gain > CHANGE_PENALTY * ((end - start) as f64).ln() {
+                points.push(offset + split);
+                pending.push((start, split));
+                pending.push((split, end));
+            }
+        }
+    }
+    points
+}
+
+/// Prefix sums of the daily series, so the squared error of any segment takes constant time.
+struct PrefixSums {
+    /// (sum, sum of squares) of the volume
+    volume: Vec<(f64, f64)>,
+    /// (count, sum, sum of squares) of the days with a mean compound score
+    sentiment: Vec<(f64, f64, f64)>,
+}
+
+impl PrefixSums {
+    fn new(volume: &[f64], sentiment: &[Option<f64>]) -> Self {
+        let mut sums = Self {
+            volume: vec![(0.0, 0.0)],
+            sentiment: vec![(0.0, 0.0, 0.0)],
+        };
+        for (volume, sentiment) in volume.iter().zip(sentiment) {
+            let (sum, squares) = sums.volume[sums.volume.len() - 1];
+            sums.volume
+                .push((sum + volume, volume.mul_add(*volume, squares)));
+            let (count, sum, squares) = sums.sentiment[sums.sentiment.len() - 1];
+            sums.sentiment.push(match sentiment {
+                Some(value) => (count + 1.0, sum + value, value.mul_add(*value, squares)),
+                None => (count, sum, squares),
+            });
+        }
+        sums
+    }
+
+    /// The squared errors of the volume and sentiment of days `start..end` about their means.
+    fn squared_errors(&self, start: usize, end: usize) -> (f64, f64) {
+        let sse = |count: f64, sum: f64, squares: f64| {
+            if count > 0.0 {
+                (squares - sum * sum / count).max(0.0)
+            } else {
+                0.0
+            }
+        };
+        let (volume_sum, volume_squares) = (
+            self.volume[end].0 - self.volume[start].0,
+            self.volume[end].1 - self.volume[start].1,
+        );
+        let (count, sum, squares) = (
+            self.sentiment[end].0 - self.sentiment[start].0,
+            self.sentiment[end].1 - self.sentiment[start].1,
+            self.sentiment[end].2 - self.sentiment[start].2,
+        );
+        (
+            sse((end - start) as f64, volume_sum, volume_squares),
+            sse(count, sum, squares),
+        )
+    }
+
+    /// The variances of the volume and sentiment of days `start..end`.
+    fn variances(&self, start: usize, end: usize) -> (f64, f64) {
+        let (volume_sse, sentiment_sse) = self.squared_errors(start, end);
+        let count = self.sentiment[end].0 - self.sentiment[start].0;
+        (
+            volume_sse / (end - start).max(1) as f64,
+            sentiment_sse / count.max(1.0),
+        )
+    }
+}
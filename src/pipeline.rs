@@ -0,0 +1,699 @@
+//! Module containing the top-level entry point of the library: everything the cli does, as a builder.
+//!
+//! The cli in `main.rs` only translates its arguments into a `Pipeline`, so anything it can do,
+//! a library user can do the same way.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+#[cfg(feature = "plot")]
+use crate::plotter::PlotType;
+use crate::{
+    aggregate::Period,
+    analyzer::{AnalyzeOptions, AnalyzedConversation, SentimentBackend, Vader},
+    calendar::CalendarConfig,
+    compare::Comparison,
+    events::EventConfig,
+    manifest::Manifest,
+    palette::Palette,
+    parser::{
+        html::HtmlOptions, whatsapp::WhatsAppOptions, Aliases, ConversationDirectory, InputFormat,
+        ParsedConversation, SharedCaptions, SystemMessages, TimestampValidation,
+    },
+    progress::Progress,
+    quiz::QuizConfig,
+    rng::Seed,
+    settings::Profile,
+    sink::OutputSink,
+    summary::Bootstrap,
+    timezone::Tz,
+    transform::TransformPipeline,
+};
+
+/// Configures a run over a single conversation, see `Pipeline::run`.
+pub struct Pipeline {
+    path: PathBuf,
+    transforms: TransformPipeline,
+    timestamps: TimestampValidation,
+    shared_captions: SharedCaptions,
+    options: AnalyzeOptions,
+    backend: Option<Box<dyn SentimentBackend>>,
+    events: EventConfig,
+    palette: Palette,
+    time_zone: Tz,
+    seed: Seed,
+    bootstrap_resamples: Option<usize>,
+    me: Option<String>,
+    lenient: bool,
+    strict_numbering: bool,
+    strict_fields: bool,
+    format: InputFormat,
+    whatsapp: WhatsAppOptions,
+    html: HtmlOptions,
+    aliases: Aliases,
+    system_messages: SystemMessages,
+    glob: Option<String>,
+    cache: Option<PathBuf>,
+    record_timings: bool,
+    progress: Option<Box<dyn FnMut(Progress) + Send>>,
+    export_conversation: bool,
+}
+
+/// The results of a run.
+///
+/// The parsed conversation is consumed by the analysis (so its messages aren't held twice),
+/// the messages are available through `analysis` instead.
+pub struct PipelineOutput {
+    pub analysis: AnalyzedConversation,
+    /// describes the run, and every artifact written through this output so far
+    pub manifest: Manifest,
+    /// the parsed conversation as json, if it was kept with `Pipeline::export_conversation`
+    conversation: Option<Vec<u8>>,
+}
+
+/// The results of a comparison, see `Pipeline::compare`.
+pub struct ComparisonOutput {
+    pub comparison: Comparison,
+    pub manifest: Manifest,
+}
+
+impl Pipeline {
+    /// A pipeline over the conversation at `path`, with the default settings.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            transforms: TransformPipeline::standard(),
+            timestamps: TimestampValidation::default(),
+            shared_captions: SharedCaptions::default(),
+            options: AnalyzeOptions::default(),
+            backend: None,
+            events: EventConfig::default(),
+            palette: Palette::default(),
+            time_zone: Tz::UTC,
+            seed: Seed::default(),
+            bootstrap_resamples: None,
+            me: None,
+            lenient: false,
+            strict_numbering: false,
+            strict_fields: false,
+            format: InputFormat::default(),
+            whatsapp: WhatsAppOptions::default(),
+            html: HtmlOptions::default(),
+            aliases: Aliases::default(),
+            system_messages: SystemMessages::default(),
+            glob: None,
+            cache: None,
+            record_timings: false,
+            progress: None,
+            export_conversation: false,
+        }
+    }
+
+    /// The transforms to apply to the messages between parsing and analysis.
+    #[must_use]
+    pub fn transforms(mut self, transforms: TransformPipeline) -> Self {
+        self.transforms = transforms;
+        self
+    }
+
+    /// Which timestamps are plausible, and what to do with the messages whose aren't.
+    #[must_use]
+    pub fn timestamps(mut self, timestamps: TimestampValidation) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// Whether the captions of shared posts and reels count toward sentiment, see `SharedCaptions`.
+    #[must_use]
+    pub fn shared_captions(mut self, shared_captions: SharedCaptions) -> Self {
+        self.shared_captions = shared_captions;
+        self
+    }
+
+    #[must_use]
+    pub fn options(mut self, options: AnalyzeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// What scores the messages rather than vader (with `AnalyzeOptions::lexicon`), see `SentimentBackend`. It's
+    /// self-tested before parsing, see `SentimentBackend::self_test`.
+    ///
+    /// ```
+    /// use anyhow::Result;
+    /// use igdm_sentiment_analysis::{
+    ///     analyzer::{Score, SentimentBackend},
+    ///     pipeline::Pipeline,
+    ///     synthetic::{self, BenchFixture},
+    /// };
+    ///
+    /// struct Constant(Score);
+    ///
+    /// impl SentimentBackend for Constant {
+    ///     fn name(&self) -> &str {
+    ///         "constant"
+    ///     }
+    ///
+    ///     fn score(&self, _: &str) -> Result<Score> {
+    ///         Ok(self.0)
+    ///     }
+    /// }
+    ///
+    /// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-doc-backend");
+    /// synthetic::write_export(&BenchFixture::Small.config(), &dir)?;
+    ///
+    /// // scoring everything the same, it can't tell the self-test's sentences apart
+    /// let pleased = Score { pos: 1.0, neu: 0.0, neg: 0.0, compound: 0.5 };
+    /// let error = Pipeline::new(&dir).backend(Constant(pleased)).run().err().unwrap();
+    /// assert!(error.to_string().starts_with("constant self-test failed"));
+    ///
+    /// struct Untested(Constant);
+    ///
+    /// impl SentimentBackend for Untested {
+    ///     fn name(&self) -> &str {
+    ///         self.0.name()
+    ///     }
+    ///
+    ///     fn score(&self, text: &str) -> Result<Score> {
+    ///         self.0.score(text)
+    ///     }
+    ///
+    ///     fn self_test(&self) -> Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let output = Pipeline::new(&dir).backend(Untested(Constant(pleased))).run()?;
+    /// let mut scores = output.analysis.analysis.values().flatten().map(|(_, score)| *score);
+    /// assert!(scores.all(|score| score == pleased));
+    /// # std::fs::remove_dir_all(&dir)?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    #[must_use]
+    pub fn backend(mut self, backend: impl SentimentBackend + 'static) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// The events (holidays, birthdays) to mark on the plots and compare in the report.
+    #[must_use]
+    pub fn events(mut self, events: EventConfig) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// How the series of the plots are told apart, see `Palette`.
+    #[must_use]
+    pub fn palette(mut self, palette: Palette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// The time zone the times of the plots are shown in, utc by default, see `timezone`.
+    #[must_use]
+    pub fn time_zone(mut self, time_zone: Tz) -> Self {
+        self.time_zone = time_zone;
+        self
+    }
+
+    #[must_use]
+    pub fn seed(mut self, seed: Seed) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Compute confidence intervals for the summaries from this many bootstrap resamples, seeded by the pipeline's seed.
+    #[must_use]
+    pub fn bootstrap(mut self, resamples: usize) -> Self {
+        self.bootstrap_resamples = Some(resamples);
+        self
+    }
+
+    /// The name of the participant who made the export, overriding the one detected from the export (if any),
+    /// see `ConversationDirectory::detect_me`.
+    #[must_use]
+    pub fn me(mut self, name: impl Into<String>) -> Self {
+        self.me = Some(name.into());
+        self
+    }
+
+    /// Whether message files that can't be read or parsed are skipped, see `ConversationDirectory::lenient`.
+    #[must_use]
+    pub fn lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Whether a message file missing from the numbering fails the run, see `ConversationDirectory::strict_numbering`.
+    #[must_use]
+    pub fn strict_numbering(mut self, strict: bool) -> Self {
+        self.strict_numbering = strict;
+        self
+    }
+
+    /// Whether message files with fields this version doesn't know of fail to parse, see
+    /// `ConversationDirectory::strict_fields`.
+    #[must_use]
+    pub fn strict_fields(mut self, strict: bool) -> Self {
+        self.strict_fields = strict;
+        self
+    }
+
+    /// What the message files are, see `ConversationDirectory::format`.
+    #[must_use]
+    pub fn format(mut self, format: InputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// How to read the timestamps of a whatsapp chat.
+    #[must_use]
+    pub fn whatsapp(mut self, options: WhatsAppOptions) -> Self {
+        self.whatsapp = options;
+        self
+    }
+
+    /// Names to read as other names, see `ConversationDirectory::aliases`. `Pipeline::me` can be either name.
+    #[must_use]
+    pub fn aliases(mut self, aliases: Aliases) -> Self {
+        self.aliases = aliases;
+        self
+    }
+
+    /// The notices kept apart rather than scored, see `ConversationDirectory::system_messages`.
+    #[must_use]
+    pub fn system_messages(mut self, system_messages: SystemMessages) -> Self {
+        self.system_messages = system_messages;
+        self
+    }
+
+    /// Reads the files of the folder named like `pattern` as the message files, see `ConversationDirectory::matching`.
+    #[must_use]
+    pub fn glob(mut self, pattern: impl Into<String>) -> Self {
+        self.glob = Some(pattern.into());
+        self
+    }
+
+    /// How to read the timestamps of an html export, see `ConversationDirectory::html`.
+    #[must_use]
+    pub fn html(mut self, options: HtmlOptions) -> Self {
+        self.html = options;
+        self
+    }
+
+    /// Where to keep the parsed conversation between runs, see `ConversationDirectory::cache`.
+    #[must_use]
+    pub fn cache(mut self, cache: impl Into<PathBuf>) -> Self {
+        self.cache = Some(cache.into());
+        self
+    }
+
+    /// Whether to record how long each phase took in the manifest.
+    #[must_use]
+    pub fn timings(mut self, record_timings: bool) -> Self {
+        self.record_timings = record_timings;
+        self
+    }
+
+    /// Whether to keep the parsed conversation as json (before it's consumed by the analysis), to write it out with
+    /// `PipelineOutput::write_conversation`.
+    #[must_use]
+    pub fn export_conversation(mut self, export: bool) -> Self {
+        self.export_conversation = export;
+        self
+    }
+
+    /// Reports how far along parsing and analysis are to `progress`, see `progress`.
+    #[must_use]
+    pub fn progress(mut self, progress: impl FnMut(Progress) + Send + 'static) -> Self {
+        self.progress = Some(Box::new(progress));
+        self
+    }
+
+    /// Parses and analyzes the conversation.
+    pub fn run(mut self) -> Result<PipelineOutput> {
+        // fail fast if the sentiment analyzer doesn't behave as expected
+        self.self_test()?;
+
+        let mut manifest = Manifest::new(Some(self.path.clone()), self.seed, self.record_timings);
+
+        let mut progress = self.progress.take();
+        let mut report = |update| {
+            if let Some(progress) = &mut progress {
+                progress(update);
+            }
+        };
+        let conversation = manifest.time("parse", || self.parse(&mut report))?;
+        let json = self
+            .export_conversation
+            .then(|| conversation.to_json())
+            .transpose()?;
+        let mut analysis = manifest.time("analyze", || match &self.backend {
+            Some(backend) => {
+                conversation.into_analyzed_with_backend(&self.options, &**backend, &mut report)
+            }
+            None => conversation.into_analyzed_with_progress(&self.options, &mut report),
+        });
+        if let Some((first_ms, last_ms)) = analysis.time_range() {
+            analysis.events = self.events.expand(first_ms, last_ms);
+        }
+        analysis.palette = self.palette;
+        analysis.time_zone = self.time_zone;
+        analysis.set_bootstrap(self.bootstrap_resamples.map(|resamples| Bootstrap {
+            resamples,
+            seed: self.seed,
+        }));
+        manifest.diagnostics = analysis.diagnostics.clone();
+        manifest.fingerprint = Some(analysis.fingerprint.clone());
+
+        Ok(PipelineOutput {
+            analysis,
+            manifest,
+            conversation: json,
+        })
+    }
+
+    /// Parses the conversation once, and analyzes it with both profiles (in parallel) to compare them.
+    ///
+    /// The profiles' options are used instead of the pipeline's, events aren't marked, and only parsing reports its
+    /// progress.
+    pub fn compare(mut self, baseline: Profile, variant: Profile) -> Result<ComparisonOutput> {
+        self.self_test()?;
+
+        let mut manifest = Manifest::new(Some(self.path.clone()), self.seed, self.record_timings);
+
+        let mut progress = self.progress.take();
+        let mut report = |update| {
+            if let Some(progress) = &mut progress {
+                progress(update);
+            }
+        };
+        let conversation = manifest.time("parse", || self.parse(&mut report))?;
+        let mut comparison = manifest.time("analyze", || match &self.backend {
+            Some(backend) => {
+                Comparison::run_with_backend(&conversation, baseline, variant, &**backend)
+            }
+            None => Comparison::run(&conversation, baseline, variant),
+        });
+        comparison.baseline.analysis.palette = self.palette;
+        comparison.variant.analysis.palette = self.palette;
+        comparison.baseline.analysis.time_zone = self.time_zone;
+        comparison.variant.analysis.time_zone = self.time_zone;
+        manifest.diagnostics = comparison.baseline.analysis.diagnostics.clone();
+
+        Ok(ComparisonOutput {
+            comparison,
+            manifest,
+        })
+    }
+
+    /// Parses the conversation, with the participant named by `me` (if set) as the exporter.
+    /// Self-tests the backend the messages are scored with, vader unless `Pipeline::backend` set another.
+    fn self_test(&self) -> Result<()> {
+        match &self.backend {
+            Some(backend) => backend.self_test(),
+            None => Vader::default().self_test(),
+        }
+    }
+
+    fn parse(&self, progress: &mut dyn FnMut(Progress)) -> Result<ParsedConversation> {
+        let directory = match &self.glob {
+            Some(pattern) => ConversationDirectory::matching(&self.path, pattern),
+            None => ConversationDirectory::new(&self.path),
+        };
+        let mut directory = directory
+            .with_context(|| format!("failed to open {}", self.path.display()))?
+            .lenient(self.lenient)
+            .strict_numbering(self.strict_numbering)
+            .strict_fields(self.strict_fields)
+            .format(self.format)
+            .whatsapp(self.whatsapp)
+            .html(self.html.clone())
+            .aliases(self.aliases.clone())
+            .system_messages(self.system_messages.clone());
+        if let Some(cache) = &self.cache {
+            directory = directory.cache(cache);
+        }
+        let mut conversation = directory.parse_with_progress(
+            &self.transforms,
+            &self.timestamps,
+            self.shared_captions,
+            progress,
+        )?;
+        if let Some(name) = &self.me {
+            let me = conversation
+                .names
+                .id(self.aliases.resolve(name))
+                .filter(|me| conversation.participants.contains(me))
+                .with_context(|| format!("there is no participant named {name:?} to be \"me\""))?;
+            conversation.me = Some(me);
+        }
+        Ok(conversation)
+    }
+}
+
+impl ComparisonOutput {
+    /// Writes the comparison table into the sink as `comparison.txt`, and the overlay plot as `comparison.png`
+    /// (with the `plot` feature).
+    pub fn write_artifacts(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        let comparison = &self.comparison;
+        self.manifest.write_artifact(
+            sink,
+            "comparison table",
+            Path::new("comparison.txt"),
+            || Ok(comparison.table().into_bytes()),
+        );
+        #[cfg(feature = "plot")]
+        self.manifest
+            .write_artifact(sink, "plot comparison", Path::new("comparison.png"), || {
+                comparison.plot_png()
+            });
+        Ok(())
+    }
+
+    /// Writes the manifest into the sink as `manifest.json`.
+    pub fn write_manifest(&self, sink: &mut dyn OutputSink) -> Result<()> {
+        sink.write(Path::new("manifest.json"), &self.manifest.to_json()?)
+    }
+}
+
+impl PipelineOutput {
+    /// Renders the given plots into the sink, as `<plot type>.png`.
+    ///
+    /// One plot failing doesn't stop the others from being attempted, the outcome of each is recorded in the manifest,
+    /// see `Manifest::failed_artifacts`.
+    #[cfg(feature = "plot")]
+    pub fn write_plots(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        plot_types: &[PlotType],
+    ) -> Result<()> {
+        for plot_type in plot_types {
+            self.write_artifact(
+                sink,
+                &format!("plot {plot_type}"),
+                Path::new(&format!("{plot_type}.png")),
+                |analysis| analysis.plot_png(*plot_type),
+            );
+        }
+        Ok(())
+    }
+
+    /// Renders the scatter plot of replies against the messages they replied to into the sink, as `responsiveness.png`.
+    #[cfg(feature = "plot")]
+    pub fn write_responsiveness_plot(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        self.write_artifact(
+            sink,
+            "plot responsiveness",
+            Path::new("responsiveness.png"),
+            AnalyzedConversation::plot_responsiveness_png,
+        );
+        Ok(())
+    }
+
+    /// Writes the analysis into the sink as `analysis.json`, see `AnalyzedConversation::to_json`.
+    pub fn write_json_export(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        self.write_artifact(
+            sink,
+            "export json",
+            Path::new("analysis.json"),
+            AnalyzedConversation::to_json,
+        );
+        Ok(())
+    }
+
+    /// Renders the monthly sparkline sheet into the sink, as `sparklines.png`, see `AnalyzedConversation::draw_sparklines`.
+    #[cfg(feature = "plot")]
+    pub fn write_sparklines_plot(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        self.write_artifact(
+            sink,
+            "plot sparklines",
+            Path::new("sparklines.png"),
+            AnalyzedConversation::plot_sparklines_png,
+        );
+        Ok(())
+    }
+
+    /// Renders the weekly gaps between messages into the sink, as `gaps.png`.
+    #[cfg(feature = "plot")]
+    pub fn write_gaps_plot(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        self.write_artifact(
+            sink,
+            "plot gaps",
+            Path::new("gaps.png"),
+            AnalyzedConversation::plot_gaps_png,
+        );
+        Ok(())
+    }
+
+    /// Renders the daily message volume into the sink, as `volume.png`, with the decay fitted after each burst
+    /// if `overlay_decay` is set, see `AnalyzedConversation::draw_volume`.
+    #[cfg(feature = "plot")]
+    pub fn write_volume_plot(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        overlay_decay: bool,
+    ) -> Result<()> {
+        self.write_artifact(sink, "plot volume", Path::new("volume.png"), |analysis| {
+            analysis.plot_volume_png(overlay_decay)
+        });
+        Ok(())
+    }
+
+    /// Writes the scores into the sink at `path` as InfluxDB line protocol, see `AnalyzedConversation::to_line_protocol`.
+    pub fn write_line_protocol(&mut self, sink: &mut dyn OutputSink, path: &Path) -> Result<()> {
+        self.write_artifact(sink, "export line protocol", path, |analysis| {
+            Ok(analysis.to_line_protocol().into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Writes the parsed conversation into the sink at `path` as json, see `ParsedConversation::write_json`,
+    /// failing (in the manifest) unless it was kept with `Pipeline::export_conversation`.
+    pub fn write_conversation(&mut self, sink: &mut dyn OutputSink, path: &Path) -> Result<()> {
+        let json = self.conversation.take();
+        self.manifest
+            .write_artifact(sink, "export conversation", path, || {
+                json.context("the conversation wasn't kept, see `Pipeline::export_conversation`")
+            });
+        Ok(())
+    }
+
+    /// Writes the mean compound scores over each period into the sink at `path` as a csv, see
+    /// `AnalyzedConversation::to_csv_timeseries`.
+    pub fn write_csv_timeseries(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        path: &Path,
+        period: Period,
+    ) -> Result<()> {
+        self.write_artifact(sink, "export csv time series", path, |analysis| {
+            Ok(analysis.to_csv_timeseries(period).into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Writes the weekly digest into the sink at `path` as a csv, see `AnalyzedConversation::to_weekly_csv`.
+    pub fn write_weekly_csv(&mut self, sink: &mut dyn OutputSink, path: &Path) -> Result<()> {
+        self.write_artifact(sink, "export weekly digest", path, |analysis| {
+            Ok(analysis.to_weekly_csv().into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Writes the hour of the day and day of the week profiles into the sink at `path` as a csv, see
+    /// `AnalyzedConversation::to_rhythm_csv`.
+    pub fn write_rhythm_csv(&mut self, sink: &mut dyn OutputSink, path: &Path) -> Result<()> {
+        self.write_artifact(sink, "export rhythm", path, |analysis| {
+            Ok(analysis.to_rhythm_csv().into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Writes the activity calendar into the sink at `path` as iCalendar, see `AnalyzedConversation::to_ics`.
+    pub fn write_ics(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        path: &Path,
+        config: &CalendarConfig,
+    ) -> Result<()> {
+        self.write_artifact(sink, "export ics", path, |analysis| {
+            Ok(analysis.to_ics(config).into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Draws a who-said-it quiz (randomized by the run's seed) and writes it into the sink as `quiz.json` and `quiz.html`,
+    /// with the answers in `quiz_answers.json`, see `AnalyzedConversation::quiz`.
+    pub fn write_quiz(&mut self, sink: &mut dyn OutputSink, config: &QuizConfig) -> Result<()> {
+        let quiz = self
+            .analysis
+            .quiz(config, self.manifest.seed)
+            .map_err(|error| format!("{error:#}"));
+        let quiz = || quiz.as_ref().map_err(|error| anyhow!("{error}"));
+        self.manifest
+            .write_artifact(sink, "quiz", Path::new("quiz.json"), || quiz()?.to_json());
+        self.manifest
+            .write_artifact(sink, "quiz html", Path::new("quiz.html"), || {
+                Ok(quiz()?.to_html().into_bytes())
+            });
+        self.manifest
+            .write_artifact(sink, "quiz answers", Path::new("quiz_answers.json"), || {
+                quiz()?.answers_json()
+            });
+        Ok(())
+    }
+
+    /// Reviews the calendar year (see `AnalyzedConversation::year_review`) and writes the card into the sink as `year-review-<year>.png`
+    /// (with the `plot` feature), and the markdown section (embedding the card) as `year-review-<year>.md`, returning the markdown.
+    /// Fails if there are no messages from the year.
+    pub fn write_year_review(&mut self, sink: &mut dyn OutputSink, year: i32) -> Result<String> {
+        let review = self.analysis.year_review(year)?;
+        let image = format!("year-review-{year}.png");
+        #[cfg(feature = "plot")]
+        self.manifest
+            .write_artifact(sink, "plot year review", Path::new(&image), || {
+                review.plot_png()
+            });
+        let markdown = review.to_markdown(cfg!(feature = "plot").then_some(image.as_str()));
+        self.manifest.write_artifact(
+            sink,
+            "year review markdown",
+            Path::new(&format!("year-review-{year}.md")),
+            || Ok(markdown.clone().into_bytes()),
+        );
+        Ok(markdown)
+    }
+
+    /// Writes the report into the sink as `report.txt`, see `AnalyzedConversation::report`.
+    pub fn write_report(&mut self, sink: &mut dyn OutputSink) -> Result<()> {
+        self.write_artifact(sink, "report", Path::new("report.txt"), |analysis| {
+            Ok(analysis.report().into_bytes())
+        });
+        Ok(())
+    }
+
+    /// Writes the manifest into the sink as `manifest.json`.
+    pub fn write_manifest(&self, sink: &mut dyn OutputSink) -> Result<()> {
+        sink.write(Path::new("manifest.json"), &self.manifest.to_json()?)
+    }
+
+    /// Renders an artifact from the analysis and writes it into the sink, see `Manifest::write_artifact`,
+    /// adding whatever a plot left out to the manifest's diagnostics.
+    fn write_artifact(
+        &mut self,
+        sink: &mut dyn OutputSink,
+        phase: &str,
+        path: &Path,
+        render: impl FnOnce(&AnalyzedConversation) -> Result<Vec<u8>>,
+    ) {
+        let analysis = &self.analysis;
+        self.manifest
+            .write_artifact(sink, phase, path, || render(analysis));
+        self.manifest
+            .diagnostics
+            .plot_issues
+            .extend(analysis.take_plot_issues());
+    }
+}
@@ -1,17 +1,45 @@
-use std::{collections::HashMap, fmt::Display, path::PathBuf};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+    io::Cursor,
+    ops::Range,
+    path::PathBuf,
+};
 
-use anyhow::Result;
-use chrono::TimeDelta;
-use plotters::prelude::*;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeDelta, Utc};
+use image::{ImageFormat, RgbImage};
+use plotters::{
+    coord::Shift,
+    prelude::*,
+    style::text_anchor::{HPos, Pos, VPos},
+};
 
 use crate::{
-    analyzer::{AnalyzedConversation, Score},
-    parser::Participant,
+    aggregate::{self, Period, DAY_MS},
+    analyzer::{AnalyzedConversation, Score, Sentiment},
+    bursts::DECAY_DAYS,
+    compare::Comparison,
+    diagnostics::PlotIssue,
+    format::{format_count, format_duration, HOUR_MS},
+    palette::Dash,
+    parser::ParticipantId,
+    phases::Phase,
+    register::Month,
+    review::YearReview,
+    stats::{fit_line, least_squares_linear_regression, smoothen_wrt_time},
+    threads::{format_metric, ThreadComparison, THREAD_METRICS},
+    timeseries::EmptyBuckets,
 };
 
 const SHOW_SMOOTHED: bool = true;
 const SHOW_LSQR: bool = true;
 const REMOVE_OUTLIERS: bool = true;
+/// How the smoothed curves are stroked, unless the palette picks the dash pattern.
+const SMOOTHED_DASH: Dash = Dash::Dashed {
+    size: 6,
+    spacing: 2,
+};
 
 #[derive(Debug, Default, Clone, Copy)]
 pub enum PlotType {
@@ -22,6 +50,15 @@ pub enum PlotType {
     Compound,
 }
 
+impl PlotType {
+    pub const ALL: [Self; 4] = [
+        Self::Positive,
+        Self::Negative,
+        Self::Neutral,
+        Self::Compound,
+    ];
+}
+
 impl Display for PlotType {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
@@ -37,35 +74,161 @@ impl Display for PlotType {
     }
 }
 
+/// The size, in pixels, of the rendered plots.
+pub const PLOT_SIZE: (u32, u32) = (800, 600);
+/// How the time axes label their ticks.
+const X_LABEL_FORMAT: &str = "%Y-%m-%d";
+/// The height, in pixels, of the phase strip above the compound plot.
+const PHASE_STRIP_HEIGHT: u32 = 30;
+/// The width, in pixels, of the monthly sparkline sheet, which has a row of 12 panels per year.
+const SPARKLINE_SHEET_WIDTH: u32 = 1200;
+/// The height, in pixels, of a row of the sparkline sheet, and of the header above the rows.
+const SPARKLINE_ROW_HEIGHT: u32 = 90;
+const SPARKLINE_HEADER_HEIGHT: u32 = 40;
+/// Months with fewer days with messages than this get their daily means drawn as points, too few for a meaningful line.
+const SPARKLINE_MIN_LINE_DAYS: usize = 4;
+/// The size, in pixels, of the year in review card, and the height of its header and its row of tiles.
+const YEAR_REVIEW_SIZE: (u32, u32) = (800, 480);
+const YEAR_REVIEW_HEADER_HEIGHT: u32 = 70;
+const YEAR_REVIEW_TILES_HEIGHT: u32 = 250;
+/// The width, in pixels, of the thread heatmap, the height of each of its rows, and of the caption and axis around them.
+const HEATMAP_WIDTH: u32 = 900;
+const HEATMAP_ROW_HEIGHT: u32 = 28;
+const HEATMAP_FRAME_HEIGHT: u32 = 110;
+/// How many characters of the thread titles the heatmap labels its rows with, and the width, in pixels, of the labels.
+const HEATMAP_LABEL_CHARS: usize = 28;
+const HEATMAP_LABEL_WIDTH: u32 = 220;
+
+/// One month of the sparkline sheet.
+struct SparklinePanel {
+    messages: usize,
+    /// (day of the month, mean compound) for each participant, ordered by name
+    series: Vec<Vec<(u32, f64)>>,
+}
+
 impl AnalyzedConversation {
+    /// Renders the plot into a png (or other bitmap format, depending on the extension) at `output_file`.
     pub fn plot(&self, plot_type: PlotType, output_file: &PathBuf) -> Result<()> {
+        let root = BitMapBackend::new(&output_file, PLOT_SIZE).into_drawing_area();
+        self.draw(plot_type, &root)?;
+        // save the plot to the output file
+        root.present()?;
+        Ok(())
+    }
+
+    /// Renders the plot into an in-memory RGB buffer of `PLOT_SIZE`, without touching the disk.
+    pub fn plot_to_buffer(&self, plot_type: PlotType) -> Result<Vec<u8>> {
+        render_buffer(PLOT_SIZE, |root| self.draw(plot_type, root))
+    }
+
+    /// Renders the plot into an in-memory png.
+    ///
+    /// Sparse conversations plot too, a participant without a point to draw (who never sent anything, or whose every
+    /// message was left out) is left out of the plot, and a conversation of a single moment is drawn over the day around it.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{parser::ParsedConversation, plotter::PlotType};
+    ///
+    /// let notes_to_self = br#"{
+    ///   "participants": [{"name": "Ana"}],
+    ///   "messages": [{"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "remember the lovely bakery"}]
+    /// }"#;
+    /// // Ben only said something vader found no sentiment in, and Cleo never said anything
+    /// let quiet = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}, {"name": "Cleo"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "the table is in the kitchen"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "this is awful"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "remember the lovely bakery"}
+    ///   ]
+    /// }"#;
+    /// for export in [&notes_to_self[..], &quiet[..]] {
+    ///     let analysis = ParsedConversation::from_bytes(export)?.analyze();
+    ///     for plot_type in PlotType::ALL {
+    ///         analysis.plot_png(plot_type)?;
+    ///     }
+    ///     assert!(analysis.take_plot_issues().is_empty());
+    /// }
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn plot_png(&self, plot_type: PlotType) -> Result<Vec<u8>> {
+        render_png(|root| self.draw(plot_type, root))
+    }
+
+    /// Renders the scatter plot of replies against the messages they replied to into an in-memory png,
+    /// see `draw_responsiveness`.
+    pub fn plot_responsiveness_png(&self) -> Result<Vec<u8>> {
+        render_png(|root| self.draw_responsiveness(root))
+    }
+
+    /// Draws the plot onto the given drawing area, which can be backed by any plotters backend.
+    pub fn draw<DB>(&self, plot_type: PlotType, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
         // first, we need to extract the data we want to plot
-        // data should be HashMap<Participant, Vec<(timestamp, score)>>
-        let data = extract_data(self, plot_type);
-
-        let min_time: usize = *data
-            .values()
-            .flat_map(|v| v.iter().map(|(t, _)| t))
-            .min()
-            .unwrap();
-        let max_time: usize = *data
-            .values()
-            .flat_map(|v| v.iter().map(|(t, _)| t))
-            .max()
-            .unwrap();
+        // data should be Vec<(ParticipantId, Vec<(timestamp, score)>)>
+        // plotters fails on values that aren't finite, so those are left out (and reported) up front
+        let mut omissions = Omissions::new(plot_type.to_string());
+        let mut extracted = extract_data(self, plot_type);
+        // ordered by name, so every participant gets the same color in every run
+        let data = self
+            .participants_by_name()
+            .into_iter()
+            .filter_map(|participant| {
+                let scores = extracted.remove(&participant)?;
+                let name = self.names.name(participant);
+                Some((participant, omissions.finite_points(name, "", scores)))
+            })
+            // participants without a point to draw would only be a legend entry
+            .filter(|(_, points)| !points.is_empty())
+            .collect::<Vec<_>>();
+
+        let all_times = || data.iter().flat_map(|(_, v)| v.iter().map(|(t, _)| *t));
+        let (Some(min_time), Some(max_time)) = (all_times().min(), all_times().max()) else {
+            bail!("there is no data to plot");
+        };
+        // a single moment has no span to draw over, so it gets the day around it
+        let (min_time, max_time) = if min_time == max_time {
+            (
+                min_time - TimeDelta::hours(12),
+                max_time + TimeDelta::hours(12),
+            )
+        } else {
+            (min_time, max_time)
+        };
         let (min_score, max_score) = match plot_type {
             PlotType::Neutral | PlotType::Positive | PlotType::Negative => (0.0, 1.0),
             PlotType::Compound => (-1.0, 1.0),
         };
 
         // plot the data with the plotters crate
-        let root = BitMapBackend::new(&output_file, (800, 600)).into_drawing_area();
         root.fill(&WHITE)?;
         let root = root.margin(10, 10, 10, 10);
+        // the compound plot gets a strip showing the phases of the conversation above it
+        let phases = match plot_type {
+            PlotType::Compound => self.phases(),
+            _ => Vec::new(),
+        };
+        let root = if phases.is_empty() {
+            root
+        } else {
+            let (strip, rest) = root.split_vertically(PHASE_STRIP_HEIGHT);
+            self.draw_phase_strip(&strip, &phases, min_time..max_time)?;
+            rest
+        };
+        // the smoothing window is a hundredth of the time the data spans
+        let window = (max_time - min_time) / 100;
         // construct the chart context
         let mut chart = ChartBuilder::on(&root)
             .caption(
-                format!("Sentiment Analysis ({plot_type})"),
+                format!(
+                    "{} ({plot_type}, smoothed by {} over {})",
+                    self.title.as_deref().unwrap_or("Sentiment Analysis"),
+                    self.aggregate.describe(),
+                    format_duration(window.num_milliseconds() as f64)
+                ),
                 ("sans-serif", 30).into_font(),
             )
             .margin(5)
@@ -78,12 +241,10 @@ impl AnalyzedConversation {
             .configure_mesh()
             // customize the x labels
             .x_desc("Time")
-            // display the x labels as datetimes, currently is a timestamp in milliseconds
             .x_label_formatter(&|t| {
-                format!(
-                    "{}",
-                    chrono::DateTime::from_timestamp(*t as i64 / 1000, 0).unwrap()
-                )
+                t.with_timezone(&self.time_zone)
+                    .format(X_LABEL_FORMAT)
+                    .to_string()
             })
             // customize the y labels
             .y_desc("Score")
@@ -91,25 +252,56 @@ impl AnalyzedConversation {
             .y_label_formatter(&&|s: &f64| format!("{:.0}%", s * 100.0))
             .draw()?;
 
-        // draw the data, give each participant a different color
+        // draw the data, give each participant a different color (and with the mono palette, marker shape)
+        // a series that fails to draw is left out of the rest of the plot, rather than losing the whole plot
+        let palette = self.palette;
         for (i, (participant, scores)) in data.iter().enumerate() {
-            // pick a color from the palette, and use it for the line
-            let mut style = Palette99::pick(i).to_rgba();
-            style.3 = 0.3; // set the alpha channel to 0.5 to make the line transparent
+            // pick a color from the palette, and use it for the points
+            let mut style = palette.color(i);
+            style.3 = 0.3; // set the alpha channel to 0.3 to make the points transparent
 
             // draw the data points
-            chart
-                .draw_series(PointSeries::of_element(
-                    scores.iter().map(|(t, s)| (*t, *s)),
-                    1,
-                    style.filled(),
-                    &|c, s, st| {
-                        EmptyElement::at(c)    // We want to put the point at the position of (x, y)
-                    + Circle::new((0,0),s,st.filled()) // And a circle of (2*radius, color)
-                    },
-                ))?
-                .label(participant.name.clone())
-                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], style));
+            let name = self.names.name(*participant);
+            match chart.draw_series(
+                scores
+                    .iter()
+                    .map(|(t, s)| palette.marker(i, (*t, *s), 1, style.filled())),
+            ) {
+                Ok(series) => {
+                    series
+                        .label(name)
+                        .legend(move |point| palette.scatter_legend(i, point, Dash::Solid));
+                }
+                Err(error) => omissions.omit(i, name, error),
+            }
+        }
+
+        // mark event days (holidays, birthdays) on the compound plot, with a vertical line per day and one color per category
+        if matches!(plot_type, PlotType::Compound) {
+            let mut categories: BTreeMap<&str, Vec<DateTime<Utc>>> = BTreeMap::new();
+            for marker in &self.events {
+                let Some(start) = DateTime::from_timestamp_millis(marker.start_ms as i64) else {
+                    continue;
+                };
+                if (min_time..=max_time).contains(&start) {
+                    categories.entry(&marker.category).or_default().push(start);
+                }
+            }
+            for (i, (category, starts)) in categories.into_iter().enumerate() {
+                let i = data.len() + i;
+                let mut style = palette.color(i);
+                style.3 = 0.5;
+                let dash = palette.dash(i, Dash::Solid);
+                chart
+                    .draw_series(starts.into_iter().map(|start| {
+                        dash.path(
+                            vec![(start, min_score), (start, max_score)],
+                            style.stroke_width(1),
+                        )
+                    }))?
+                    .label(category)
+                    .legend(move |point| palette.line_legend(i, point, Dash::Solid));
+            }
         }
 
         // add a legend to the plot
@@ -120,76 +312,918 @@ impl AnalyzedConversation {
 
         // now, with a thicker line, let's draw a smoothed version of the data on top of the original data
         if SHOW_SMOOTHED {
-            for (i, (_, scores)) in data.iter().enumerate() {
+            for (i, (participant, scores)) in data.iter().enumerate() {
+                if omissions.omitted(i) {
+                    continue;
+                }
                 // pick a color from the palette, and use it for the line
-                let mut color = Palette99::pick(i).to_rgba();
+                let mut color = palette.color(i);
                 color.3 = 0.8; // set the alpha channel to 0.8 to make the line more visible
 
                 // draw the smoothed line
-                chart.draw_series(DashedLineSeries::new(
-                    smoothen_wrt_time(
-                        scores,
-                        TimeDelta::milliseconds((max_time as i64 - min_time as i64) / 100),
-                        // TimeDelta::days(3),
-                    ),
-                    6,
-                    2,
-                    color.stroke_width(2),
-                ))?;
+                let name = self.names.name(*participant);
+                let smoothed = omissions.finite_points(
+                    name,
+                    " smoothed",
+                    smoothen_wrt_time(scores, window, self.aggregate),
+                );
+                if let Err(error) = chart.draw_series(std::iter::once(
+                    palette
+                        .dash(i, SMOOTHED_DASH)
+                        .path(smoothed, color.stroke_width(2)),
+                )) {
+                    omissions.omit(i, name, error);
+                }
             }
         }
 
         // now, with an even thicker line, let's draw a least squares linear regression of the data on top of the original data
         if SHOW_LSQR {
-            for (i, (_, scores)) in data.iter().enumerate() {
+            for (i, (participant, scores)) in data.iter().enumerate() {
+                if omissions.omitted(i) {
+                    continue;
+                }
                 // pick a color from the palette, and use it for the line
-                let color = Palette99::pick(i).to_rgba();
+                let color = palette.color(i);
 
-                // draw the least squares linear regression line
-                chart.draw_series(LineSeries::new(
-                    least_squares_linear_regression(scores),
-                    color.stroke_width(2),
+                // draw the least squares linear regression line, if one can be fitted
+                let Some(fit) = least_squares_linear_regression(scores) else {
+                    continue;
+                };
+                let start = scores.iter().map(|(t, _)| *t).min().unwrap_or(min_time);
+                let end = scores.iter().map(|(t, _)| *t).max().unwrap_or(max_time);
+                if let Err(error) = chart.draw_series(std::iter::once(
+                    palette
+                        .dash(i, Dash::Solid)
+                        .path(fit.eval_range(start..end).to_vec(), color.stroke_width(2)),
+                )) {
+                    omissions.omit(i, self.names.name(*participant), error);
+                }
+            }
+        }
+
+        // say so under the caption if any series was left out, the points left out are only reported
+        if let Some(note) = omissions.note() {
+            root.draw(&Text::new(
+                note,
+                (5, 5),
+                ("sans-serif", 14).into_font().color(&RED),
+            ))?;
+        }
+        self.plot_issues
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .extend(omissions.issues);
+
+        Ok(())
+    }
+
+    /// Draws the median and 90th percentile of the gaps between messages, week by week, on a log scale in hours.
+    pub fn draw_gaps<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let weeks = self.weekly_gaps();
+        let (Some(first), Some(last)) = (weeks.first(), weeks.last()) else {
+            bail!("there are no gaps to plot");
+        };
+        let (min_time, max_time) = (
+            first.week_start_ms,
+            last.week_start_ms.max(first.week_start_ms + 1),
+        );
+        // gaps shorter than a minute (including messages sent at the same time) are drawn at a minute, log scales can't show 0
+        let floor = 1.0 / 60.0;
+        let max_hours = weeks.iter().map(|week| week.p90_hours).fold(1.0, f64::max);
+
+        root.fill(&WHITE)?;
+        let root = root.margin(10, 10, 10, 10);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                match self.longest_silence() {
+                    Some(silence) => format!(
+                        "Gaps between messages (longest {})",
+                        format_duration(silence.duration_ms())
+                    ),
+                    None => "Gaps between messages".to_string(),
+                },
+                ("sans-serif", 30).into_font(),
+            )
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(min_time..max_time, (floor..max_hours * 1.5).log_scale())?;
+        chart
+            .configure_mesh()
+            .x_desc("Week")
+            .x_label_formatter(&|t| {
+                chrono::DateTime::from_timestamp_millis(*t as i64)
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default()
+            })
+            .y_desc("Gap")
+            .y_label_formatter(&|hours| format_duration(hours * HOUR_MS))
+            .draw()?;
+
+        let palette = self.palette;
+        let medians = weeks.iter().map(|week| week.median_hours);
+        let p90s = weeks.iter().map(|week| week.p90_hours);
+        for (i, (label, values)) in [
+            ("median", medians.collect::<Vec<_>>()),
+            ("90th percentile", p90s.collect()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            chart
+                .draw_series(std::iter::once(
+                    palette.dash(i, Dash::Solid).path(
+                        weeks
+                            .iter()
+                            .zip(values)
+                            .map(|(week, hours)| (week.week_start_ms, hours.max(floor)))
+                            .collect(),
+                        palette.color(i).stroke_width(2),
+                    ),
+                ))?
+                .label(label)
+                .legend(move |point| palette.line_legend(i, point, Dash::Solid));
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+
+        Ok(())
+    }
+
+    /// Renders the weekly gap plot into an in-memory png, see `draw_gaps`.
+    pub fn plot_gaps_png(&self) -> Result<Vec<u8>> {
+        render_png(|root| self.draw_gaps(root))
+    }
+
+    /// Draws how many messages each participant sent every day, and with `overlay_decay`, the decay fitted after each burst
+    /// (see `AnalyzedConversation::bursts`) as a dashed curve over the days it was fitted to.
+    pub fn draw_volume<DB>(&self, root: &DrawingArea<DB, Shift>, overlay_decay: bool) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let participants = self.participants_by_name();
+        let daily = participants
+            .iter()
+            .map(|participant| {
+                aggregate::bucket(
+                    self.counted_messages(*participant)
+                        .map(|message| (message.timestamp_ms(), self.repeats.volume(message))),
+                    Period::Day,
+                )
+                .into_iter()
+                .map(|(day, volumes)| (day, volumes.into_iter().sum::<usize>()))
+                .collect::<BTreeMap<_, _>>()
+            })
+            .collect::<Vec<_>>();
+        let all_days = || daily.iter().flat_map(|days| days.keys().copied());
+        let (Some(first), Some(last)) = (all_days().min(), all_days().max()) else {
+            bail!("there is no data to plot");
+        };
+        let max_messages = daily
+            .iter()
+            .flat_map(|days| days.values().copied())
+            .max()
+            .unwrap_or(1);
+
+        root.fill(&WHITE)?;
+        let root = root.margin(10, 10, 10, 10);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                format!(
+                    "Messages per day ({} in total)",
+                    format_count(daily.iter().flat_map(|days| days.values()).sum())
+                ),
+                ("sans-serif", 30).into_font(),
+            )
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(
+                Period::Day.start_ms(first)..Period::Day.start_ms(last + 1),
+                0.0..max_messages as f64 * 1.1,
+            )?;
+        chart
+            .configure_mesh()
+            .x_desc("Day")
+            .x_label_formatter(&|t| {
+                chrono::DateTime::from_timestamp_millis(*t as i64)
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default()
+            })
+            .y_desc("Messages")
+            .draw()?;
+
+        let palette = self.palette;
+        for (i, (participant, days)) in participants.iter().zip(&daily).enumerate() {
+            // every day, so the line drops to 0 on days without messages
+            chart
+                .draw_series(std::iter::once(
+                    palette.dash(i, Dash::Solid).path(
+                        (first..=last)
+                            .map(|day| {
+                                (
+                                    Period::Day.start_ms(day),
+                                    days.get(&day).copied().unwrap_or_default() as f64,
+                                )
+                            })
+                            .collect(),
+                        palette.color(i).stroke_width(1),
+                    ),
+                ))?
+                .label(self.names.name(*participant))
+                .legend(move |point| palette.line_legend(i, point, Dash::Solid));
+        }
+
+        if overlay_decay {
+            for burst in self.bursts() {
+                for fit in &burst.fits {
+                    let Some(i) = participants.iter().position(|p| *p == fit.participant) else {
+                        continue;
+                    };
+                    chart.draw_series(std::iter::once(
+                        palette.dash(i, SMOOTHED_DASH).path(
+                            (0..DECAY_DAYS)
+                                .map(|t| (burst.peak_ms + t * DAY_MS, fit.eval(t as f64)))
+                                .collect(),
+                            palette.color(i).stroke_width(2),
+                        ),
+                    ))?;
+                }
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+
+        Ok(())
+    }
+
+    /// Renders the daily message volume plot into an in-memory png, see `draw_volume`.
+    pub fn plot_volume_png(&self, overlay_decay: bool) -> Result<Vec<u8>> {
+        render_png(|root| self.draw_volume(root, overlay_decay))
+    }
+
+    /// The daily compound score of each participant (see `AnalyzedConversation::resample`), grouped by month.
+    fn sparkline_panels(&self) -> BTreeMap<Month, SparklinePanel> {
+        let participants = self.participants_by_name();
+        let daily = self.resample(Period::Day, EmptyBuckets::Omit);
+        let mut panels: BTreeMap<Month, SparklinePanel> = BTreeMap::new();
+        for (i, participant) in participants.iter().enumerate() {
+            for day in daily.get(participant).into_iter().flatten() {
+                let (Some(month), Some(date), Some(compound)) = (
+                    Month::of_timestamp(day.start_ms),
+                    chrono::DateTime::from_timestamp_millis(day.start_ms as i64),
+                    day.compound,
+                ) else {
+                    continue;
+                };
+                let panel = panels.entry(month).or_insert_with(|| SparklinePanel {
+                    messages: 0,
+                    series: vec![Vec::new(); participants.len()],
+                });
+                panel.messages += day.messages;
+                panel.series[i].push((date.day(), compound));
+            }
+        }
+        panels
+    }
+
+    /// The size the sparkline sheet is rendered at: a row per year the conversation spans.
+    fn sparkline_sheet_size(&self) -> (u32, u32) {
+        let panels = self.sparkline_panels();
+        let years = match (panels.keys().next(), panels.keys().next_back()) {
+            (Some(first), Some(last)) => (last.year - first.year + 1) as u32,
+            _ => 1,
+        };
+        (
+            SPARKLINE_SHEET_WIDTH,
+            SPARKLINE_HEADER_HEIGHT + years * SPARKLINE_ROW_HEIGHT,
+        )
+    }
+
+    /// Renders the monthly sparkline sheet into an in-memory png, see `draw_sparklines`.
+    pub fn plot_sparklines_png(&self) -> Result<Vec<u8>> {
+        render_png_sized(self.sparkline_sheet_size(), |root| {
+            self.draw_sparklines(root)
+        })
+    }
+
+    /// Draws a sheet of small panels, a row per year and a column per month, each showing that month's daily mean compound score
+    /// of every participant (on the same y range in every panel), labeled with the month and its number of messages.
+    pub fn draw_sparklines<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let panels = self.sparkline_panels();
+        let (Some(first), Some(last)) = (panels.keys().next(), panels.keys().next_back()) else {
+            bail!("there is no data to plot");
+        };
+        let (first_year, years) = (first.year, (last.year - first.year + 1) as usize);
+
+        // the same range in every panel, always including 0 so it's clear which side of neutral a month is on
+        let means = || {
+            panels
+                .values()
+                .flat_map(|panel| panel.series.iter().flatten().map(|(_, mean)| *mean))
+        };
+        let min_mean = means().fold(0.0, f64::min);
+        let max_mean = means().fold(0.0, f64::max);
+        let y_range = if max_mean > min_mean {
+            min_mean..max_mean
+        } else {
+            -1.0..1.0
+        };
+
+        root.fill(&WHITE)?;
+        let (header, rows) = root.split_vertically(SPARKLINE_HEADER_HEIGHT);
+        header.draw(&Text::new(
+            format!(
+                "Monthly sentiment (daily {} compound)",
+                self.aggregate.describe()
+            ),
+            (10, 10),
+            ("sans-serif", 20).into_font(),
+        ))?;
+        let palette = self.palette;
+        let participants = self.participants_by_name();
+        for (i, participant) in participants.iter().enumerate() {
+            let x = 450 + 150 * i as i32;
+            header.draw(&palette.scatter_legend(i, (x, 20), Dash::Solid))?;
+            header.draw(&Text::new(
+                self.names.name(*participant).to_string(),
+                (x + 25, 13),
+                ("sans-serif", 14).into_font(),
+            ))?;
+        }
+
+        let label_style = ("sans-serif", 13).into_font();
+        let count_style = label_style
+            .clone()
+            .color(&RGBColor(100, 100, 100))
+            .pos(Pos::new(HPos::Right, VPos::Top));
+        for (area, i) in rows.split_evenly((years, 12)).iter().zip(0..) {
+            let month = Month {
+                year: first_year + i / 12,
+                month: (i % 12) as u32 + 1,
+            };
+            let (width, _) = area.dim_in_pixel();
+            let name = NaiveDate::from_ymd_opt(month.year, month.month, 1)
+                .map(|date| date.format("%b %Y").to_string())
+                .unwrap_or_else(|| month.to_string());
+            let Some(panel) = panels.get(&month) else {
+                area.draw(&Text::new(
+                    name,
+                    (8, 4),
+                    label_style.clone().color(&RGBColor(180, 180, 180)),
                 ))?;
+                continue;
+            };
+            area.draw(&Text::new(name, (8, 4), label_style.clone()))?;
+            area.draw(&Text::new(
+                panel.messages.to_string(),
+                (width as i32 - 8, 4),
+                count_style.clone(),
+            ))?;
+
+            let mut chart = ChartBuilder::on(&area.margin(18, 4, 4, 6))
+                .build_cartesian_2d(1.0..31.0, y_range.clone())?;
+            chart.plotting_area().fill(&RGBColor(248, 248, 248))?;
+            chart.draw_series(std::iter::once(PathElement::new(
+                vec![(1.0, 0.0), (31.0, 0.0)],
+                RGBColor(200, 200, 200),
+            )))?;
+            for (i, series) in panel.series.iter().enumerate() {
+                let color = palette.color(i);
+                let points = series.iter().map(|(day, mean)| (f64::from(*day), *mean));
+                if series.len() >= SPARKLINE_MIN_LINE_DAYS {
+                    chart.draw_series(std::iter::once(
+                        palette
+                            .dash(i, Dash::Solid)
+                            .path(points.collect(), color.stroke_width(1)),
+                    ))?;
+                } else {
+                    chart.draw_series(
+                        points.map(|point| palette.marker(i, point, 2, color.filled())),
+                    )?;
+                }
             }
         }
-        // save the plot to the output file
-        root.present()?;
 
         Ok(())
     }
+
+    /// Draws the phases as a horizontal band, each colored by the classification of its mean compound score
+    /// (silent phases in light gray), lined up with a chart over `time_range` drawn below it.
+    fn draw_phase_strip<DB>(
+        &self,
+        strip: &DrawingArea<DB, Shift>,
+        phases: &[Phase],
+        time_range: Range<DateTime<Utc>>,
+    ) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        // same margin and label area as the chart below, so the time axes line up
+        let mut chart = ChartBuilder::on(strip)
+            .margin(5)
+            .y_label_area_size(40)
+            .build_cartesian_2d(time_range.clone(), 0.0..1.0)?;
+
+        let start_of = |date: NaiveDate| date.and_time(NaiveTime::MIN).and_utc();
+        chart.draw_series(phases.iter().map(|phase| {
+            let color = match phase
+                .mean_compound
+                .map(|mean| self.thresholds.classify(mean))
+            {
+                None => RGBColor(220, 220, 220),
+                Some(Sentiment::Positive) => RGBColor(120, 200, 120),
+                Some(Sentiment::Neutral) => RGBColor(170, 170, 170),
+                Some(Sentiment::Negative) => RGBColor(220, 110, 110),
+            };
+            let start = start_of(phase.start).clamp(time_range.start, time_range.end);
+            let end = start_of(phase.end).clamp(time_range.start, time_range.end);
+            Rectangle::new([(start, 0.0), (end, 1.0)], color.filled())
+        }))?;
+        // outline each phase, so neighbouring phases of the same color stay distinguishable
+        chart.draw_series(phases.iter().map(|phase| {
+            let start = start_of(phase.start).clamp(time_range.start, time_range.end);
+            PathElement::new(vec![(start, 0.0), (start, 1.0)], WHITE.stroke_width(2))
+        }))?;
+
+        Ok(())
+    }
+
+    /// Draws a scatter plot of the compound score of every reply against the compound score of the message it replied to,
+    /// with a regression line for each direction (who replied to whom), see `AnalyzedConversation::reply_pairs`.
+    pub fn draw_responsiveness<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let mut directions: BTreeMap<(ParticipantId, ParticipantId), Vec<(f64, f64)>> =
+            BTreeMap::new();
+        for pair in self.reply_pairs() {
+            directions
+                .entry((pair.stimulus_sender, pair.responder))
+                .or_default()
+                .push((pair.stimulus_compound, pair.response_compound));
+        }
+        if directions.is_empty() {
+            bail!("there are no replies to plot");
+        }
+
+        root.fill(&WHITE)?;
+        let root = root.margin(10, 10, 10, 10);
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Responsiveness", ("sans-serif", 30).into_font())
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(-1.0..1.0, -1.0..1.0)?;
+
+        chart
+            .configure_mesh()
+            .x_desc("Compound score of the message")
+            .y_desc("Compound score of the reply")
+            .draw()?;
+
+        let palette = self.palette;
+        for (i, ((stimulus_sender, responder), points)) in directions.iter().enumerate() {
+            let mut style = palette.color(i);
+            style.3 = 0.3;
+
+            chart
+                .draw_series(
+                    points
+                        .iter()
+                        .map(|point| palette.marker(i, *point, 2, style.filled())),
+                )?
+                .label(format!(
+                    "{} replying to {}",
+                    self.names.name(*responder),
+                    self.names.name(*stimulus_sender)
+                ))
+                .legend(move |point| palette.scatter_legend(i, point, Dash::Solid));
+
+            if let Some((slope, intercept)) = fit_line(points.iter().copied()) {
+                let line = [-1.0, 1.0].map(|x: f64| (x, slope.mul_add(x, intercept)));
+                chart.draw_series(std::iter::once(
+                    palette
+                        .dash(i, Dash::Solid)
+                        .path(line.to_vec(), palette.color(i).stroke_width(2)),
+                ))?;
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+
+        Ok(())
+    }
+}
+
+impl YearReview {
+    /// Renders the card into an in-memory png, see `draw`.
+    pub fn plot_png(&self) -> Result<Vec<u8>> {
+        render_png_sized(YEAR_REVIEW_SIZE, |root| self.draw(root))
+    }
+
+    /// Draws the review as a card: the year, a tile per headline number (see `YearReview::lines`),
+    /// and the year's daily compound score as a sparkline, with a faint line at the start of each month.
+    pub fn draw<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let days_in_year = NaiveDate::from_ymd_opt(self.year, 12, 31)
+            .context("the year is out of range")?
+            .ordinal();
+
+        root.fill(&WHITE)?;
+        let (header, rest) = root.split_vertically(YEAR_REVIEW_HEADER_HEIGHT);
+        let (tiles, sparkline) = rest.split_vertically(YEAR_REVIEW_TILES_HEIGHT);
+        header.draw(&Text::new(
+            format!("{} in review", self.year),
+            (20, 18),
+            ("sans-serif", 32).into_font(),
+        ))?;
+
+        let label_style = ("sans-serif", 14)
+            .into_font()
+            .color(&RGBColor(100, 100, 100));
+        let value_style = ("sans-serif", 24).into_font();
+        for (tile, line) in tiles
+            .margin(0, 0, 14, 14)
+            .split_evenly((2, 3))
+            .iter()
+            .zip(self.lines())
+        {
+            let tile = tile.margin(6, 6, 6, 6);
+            tile.fill(&RGBColor(245, 245, 245))?;
+            tile.draw(&Text::new(line.label, (12, 12), label_style.clone()))?;
+            tile.draw(&Text::new(line.value, (12, 38), value_style.clone()))?;
+            if let Some(detail) = line.detail {
+                tile.draw(&Text::new(detail, (12, 74), label_style.clone()))?;
+            }
+        }
+
+        let mut chart = ChartBuilder::on(&sparkline.margin(10, 20, 20, 20))
+            .caption(
+                format!("Daily {} compound", self.aggregate.describe()),
+                ("sans-serif", 14).into_font(),
+            )
+            .build_cartesian_2d(0.0..f64::from(days_in_year), -1.0..1.0)?;
+        chart.plotting_area().fill(&RGBColor(248, 248, 248))?;
+        chart.draw_series((1..=12).filter_map(|month| {
+            let x = f64::from(NaiveDate::from_ymd_opt(self.year, month, 1)?.ordinal0());
+            Some(PathElement::new(
+                vec![(x, -1.0), (x, 1.0)],
+                RGBColor(225, 225, 225),
+            ))
+        }))?;
+        chart.draw_series(std::iter::once(PathElement::new(
+            vec![(0.0, 0.0), (f64::from(days_in_year), 0.0)],
+            RGBColor(200, 200, 200),
+        )))?;
+        let palette = self.palette;
+        let color = palette.color(0);
+        let points = self
+            .daily
+            .iter()
+            .map(|(date, compound)| (f64::from(date.ordinal0()), *compound));
+        if self.daily.len() >= SPARKLINE_MIN_LINE_DAYS {
+            chart.draw_series(std::iter::once(
+                palette
+                    .dash(0, Dash::Solid)
+                    .path(points.collect(), color.stroke_width(1)),
+            ))?;
+        } else {
+            chart.draw_series(points.map(|point| palette.marker(0, point, 3, color.filled())))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Comparison {
+    /// Renders the overlay of the two runs' smoothed compound curves into an in-memory png, see `draw`.
+    pub fn plot_png(&self) -> Result<Vec<u8>> {
+        render_png(|root| self.draw(root))
+    }
+
+    /// Draws the smoothed compound score of every participant's messages together, for each of the two runs, on top of each other.
+    pub fn draw<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        let curves = [&self.baseline, &self.variant].map(|run| {
+            let mut points = extract_data(&run.analysis, PlotType::Compound)
+                .into_values()
+                .flatten()
+                .collect::<Vec<_>>();
+            points.sort_by_key(|(t, _)| *t);
+            (
+                format!(
+                    "{} (smoothed by {})",
+                    run.profile.name,
+                    run.analysis.aggregate.describe()
+                ),
+                run.analysis.aggregate,
+                points,
+            )
+        });
+        let all_times = || {
+            curves
+                .iter()
+                .flat_map(|(_, _, points)| points.iter().map(|(t, _)| *t))
+        };
+        let (Some(min_time), Some(max_time)) = (all_times().min(), all_times().max()) else {
+            bail!("there is no data to plot");
+        };
+
+        root.fill(&WHITE)?;
+        let root = root.margin(10, 10, 10, 10);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                "Settings comparison (compound)",
+                ("sans-serif", 30).into_font(),
+            )
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(40)
+            .build_cartesian_2d(min_time..max_time, -1.0..1.0)?;
+        chart
+            .configure_mesh()
+            .x_desc("Time")
+            .x_label_formatter(&|t| {
+                t.with_timezone(&self.baseline.analysis.time_zone)
+                    .format(X_LABEL_FORMAT)
+                    .to_string()
+            })
+            .y_desc("Score")
+            .y_label_formatter(&&|s: &f64| format!("{:.0}%", s * 100.0))
+            .draw()?;
+
+        let window = (max_time - min_time) / 100;
+        let palette = self.baseline.analysis.palette;
+        for (i, (name, aggregate, points)) in curves.iter().enumerate() {
+            chart
+                .draw_series(std::iter::once(palette.dash(i, Dash::Solid).path(
+                    smoothen_wrt_time(points, window, *aggregate),
+                    palette.color(i).stroke_width(2),
+                )))?
+                .label(name)
+                .legend(move |point| palette.line_legend(i, point, Dash::Solid));
+        }
+        chart
+            .configure_series_labels()
+            .position(SeriesLabelPosition::UpperLeft)
+            .draw()?;
+
+        Ok(())
+    }
+}
+
+/// What a plot left out, see `Diagnostics::plot_issues`.
+struct Omissions {
+    plot: String,
+    issues: Vec<PlotIssue>,
+    /// the indices of the series that failed to draw, and were left out of the rest of the plot
+    series: BTreeSet<usize>,
 }
 
-fn extract_data(
+impl Omissions {
+    fn new(plot: String) -> Self {
+        Self {
+            plot,
+            issues: Vec::new(),
+            series: BTreeSet::new(),
+        }
+    }
+
+    /// The points of the participant's (`kind`, e.g. " smoothed") series that can be drawn,
+    /// recording an issue for every point that can't (one that isn't finite).
+    fn finite_points<T>(
+        &mut self,
+        participant: &str,
+        kind: &str,
+        points: Vec<(T, f64)>,
+    ) -> Vec<(T, f64)> {
+        points
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, (t, value))| {
+                if value.is_finite() {
+                    return Some((t, value));
+                }
+                self.issues.push(PlotIssue {
+                    plot: self.plot.clone(),
+                    participant: participant.to_string(),
+                    index: Some(index),
+                    problem: format!("its{kind} value is {value}"),
+                });
+                None
+            })
+            .collect()
+    }
+
+    /// Records that the `i`th series (the participant's) failed to draw, so the rest of it is left out.
+    fn omit(&mut self, i: usize, participant: &str, error: impl Display) {
+        self.series.insert(i);
+        self.issues.push(PlotIssue {
+            plot: self.plot.clone(),
+            participant: participant.to_string(),
+            index: None,
+            problem: format!("it failed to draw: {error}"),
+        });
+    }
+
+    fn omitted(&self, i: usize) -> bool {
+        self.series.contains(&i)
+    }
+
+    /// The note added to the plot if any series was left out.
+    fn note(&self) -> Option<String> {
+        match self.series.len() {
+            0 => None,
+            1 => Some("1 series omitted due to errors".to_string()),
+            omitted => Some(format!("{omitted} series omitted due to errors")),
+        }
+    }
+}
+
+impl ThreadComparison {
+    /// Renders the heatmap into an in-memory png, as tall as the threads need, see `draw`.
+    pub fn plot_png(&self) -> Result<Vec<u8>> {
+        let height = HEATMAP_FRAME_HEIGHT + HEATMAP_ROW_HEIGHT * self.threads.len().max(1) as u32;
+        render_png_sized((HEATMAP_WIDTH, height), |root| self.draw(root))
+    }
+
+    /// Draws the threads as a heatmap, with a row per thread (in rank order, labeled with `ThreadComparison::labels`)
+    /// and a column per metric (see `THREAD_METRICS`). Each cell is colored by its value normalized over its column
+    /// (see `ThreadComparison::normalized` and `Palette::ramp`) and has its value written in it, cells without a value
+    /// are left blank.
+    pub fn draw<DB>(&self, root: &DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB: DrawingBackend,
+        DB::ErrorType: 'static,
+    {
+        if self.threads.is_empty() {
+            bail!("there are no threads to plot");
+        }
+        let rows = self.threads.len();
+        let labels = self.labels(HEATMAP_LABEL_CHARS);
+        // a cell spans a unit on each axis, the first thread at the top
+        let top = |row: usize| (rows - 1 - row) as f64;
+
+        root.fill(&WHITE)?;
+        let root = root.margin(10, 10, 10, 10);
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Threads", ("sans-serif", 30).into_font())
+            .margin(5)
+            .x_label_area_size(30)
+            .y_label_area_size(HEATMAP_LABEL_WIDTH)
+            .build_cartesian_2d(0.0..THREAD_METRICS.len() as f64, 0.0..rows as f64)?;
+        chart
+            .configure_mesh()
+            .disable_mesh()
+            .x_labels(0)
+            .y_labels(0)
+            .draw()?;
+
+        // the labels are drawn at the centers of the cells, which plotters' key points don't land on
+        let (base_x, base_y) = root.get_base_pixel();
+        let label = |text: String, (x, y): (i32, i32), pos| {
+            Text::new(
+                text,
+                (x - base_x, y - base_y),
+                ("sans-serif", 12).into_font().color(&BLACK).pos(pos),
+            )
+        };
+        for (column, metric) in THREAD_METRICS.iter().enumerate() {
+            let (x, y) = chart.backend_coord(&(column as f64 + 0.5, 0.0));
+            root.draw(&label(
+                metric.to_string(),
+                (x, y + 8),
+                Pos::new(HPos::Center, VPos::Top),
+            ))?;
+        }
+        for (row, text) in labels.into_iter().enumerate() {
+            let (x, y) = chart.backend_coord(&(0.0, top(row) + 0.5));
+            root.draw(&label(
+                text,
+                (x - 8, y),
+                Pos::new(HPos::Right, VPos::Center),
+            ))?;
+        }
+
+        let palette = self.palette;
+        for (row, (thread, normalized)) in self.threads.iter().zip(self.normalized()).enumerate() {
+            let y = top(row);
+            for (column, (value, normalized)) in
+                thread.values().into_iter().zip(normalized).enumerate()
+            {
+                let Some(normalized) = normalized else {
+                    continue;
+                };
+                let x = column as f64;
+                let color = palette.ramp(normalized);
+                chart.draw_series(std::iter::once(Rectangle::new(
+                    [(x, y), (x + 1.0, y + 1.0)],
+                    color.filled(),
+                )))?;
+                // dark cells get light text
+                let RGBColor(r, g, b) = color;
+                let luminance = 0.299 * f64::from(r) + 0.587 * f64::from(g) + 0.114 * f64::from(b);
+                let text_color = if luminance < 128.0 { WHITE } else { BLACK };
+                chart.draw_series(std::iter::once(Text::new(
+                    format_metric(column, value),
+                    (x + 0.5, y + 0.5),
+                    ("sans-serif", 14)
+                        .into_font()
+                        .color(&text_color)
+                        .pos(Pos::new(HPos::Center, VPos::Center)),
+                )))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders whatever `draw` draws into an in-memory RGB buffer of the given size.
+fn render_buffer(
+    size: (u32, u32),
+    draw: impl FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<()>,
+) -> Result<Vec<u8>> {
+    let (width, height) = size;
+    let mut buffer = vec![0; width as usize * height as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut buffer, size).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    Ok(buffer)
+}
+
+/// Renders whatever `draw` draws into an in-memory png of `PLOT_SIZE`.
+fn render_png(
+    draw: impl FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<()>,
+) -> Result<Vec<u8>> {
+    render_png_sized(PLOT_SIZE, draw)
+}
+
+/// Renders whatever `draw` draws into an in-memory png of the given size.
+fn render_png_sized(
+    size: (u32, u32),
+    draw: impl FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<()>,
+) -> Result<Vec<u8>> {
+    let (width, height) = size;
+    let image = RgbImage::from_raw(width, height, render_buffer(size, draw)?)
+        .context("plot buffer has the wrong size")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+pub fn extract_data(
     analysis: &AnalyzedConversation,
     plot_type: PlotType,
-) -> HashMap<Participant, Vec<(usize, f64)>> {
+) -> HashMap<ParticipantId, Vec<(DateTime<Utc>, f64)>> {
     analysis
         .analysis
         .iter()
         .map(|(participant, messages)| {
-            // remove outliers from the data
+            // remove outliers (messages vader found no sentiment in) from the data
             let filter = if REMOVE_OUTLIERS {
-                |score| {
-                    score
-                        != Score {
-                            pos: 0.0,
-                            neu: 1.0,
-                            neg: 0.0,
-                            compound: 0.0,
-                        }
-                }
+                |score: Score| !score.is_neutral()
             } else {
                 |_| true
             };
 
             (
-                participant.clone(),
+                *participant,
                 messages
                     .iter()
                     .filter(|(_, score)| filter(*score))
                     .map(|(message, score)| {
                         (
-                            message.timestamp_ms,
+                            message.timestamp,
                             match plot_type {
                                 PlotType::Positive => score.pos,
                                 PlotType::Negative => score.neg,
@@ -203,51 +1237,3 @@ fn extract_data(
         })
         .collect()
 }
-
-/// Smoothens the given data (timestamp, score) by averaging scores within a window of `window_size`,
-/// data is assumed to be sorted by timestamp in ascending order.
-fn smoothen_wrt_time(data: &[(usize, f64)], window_size: TimeDelta) -> Vec<(usize, f64)> {
-    let window_size = window_size.num_milliseconds() as usize;
-    let mut smoothed_scores = Vec::new();
-    let mut window_start = data[0].0;
-    let mut window_sum = 0.0;
-    let mut window_count = 0;
-    for (time, score) in data {
-        if window_count > 0 && time - window_start >= window_size {
-            smoothed_scores.push((window_start, window_sum / window_count as f64));
-            window_start = *time;
-            window_sum = 0.0;
-            window_count = 0;
-        } else {
-            window_sum += score;
-            window_count += 1;
-        }
-    }
-    if window_count > 0 {
-        smoothed_scores.push((window_start, window_sum / window_count as f64));
-    }
-    smoothed_scores
-}
-
-/// Calculates the least squares linear regression of the given data (timestamp, score),
-fn least_squares_linear_regression(data: &[(usize, f64)]) -> Vec<(usize, f64)> {
-    let x = data.iter().map(|(t, _)| *t as f64);
-    let y = data.iter().map(|(_, s)| *s);
-    let n = x.len() as f64;
-
-    let (sum_x, sum_y, sum_x_squared, sum_xy) = x.clone().zip(y).fold(
-        (0.0, 0.0, 0.0, 0.0),
-        |(sum_x, sum_y, sum_x_squared, sum_xy), (x, y)| {
-            (
-                sum_x + x,
-                sum_y + y,
-                x.mul_add(x, sum_x_squared),
-                x.mul_add(y, sum_xy),
-            )
-        },
-    );
-
-    let m = n.mul_add(sum_xy, -(sum_x * sum_y)) / n.mul_add(sum_x_squared, -(sum_x * sum_x));
-    let b = m.mul_add(-sum_x, sum_y) / n;
-    x.map(|x| (x as usize, x.mul_add(m, b))).collect()
-}
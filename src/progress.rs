@@ -0,0 +1,53 @@
+//! Module containing the progress of the slow phases of a run, parsing the message files and scoring the messages,
+//! reported to a callback so whoever runs them can show it however they like (the cli shows a progress bar).
+//!
+//! ```
+//! use igdm_sentiment_analysis::{
+//!     parser::{ConversationDirectory, SharedCaptions, TimestampValidation},
+//!     progress::{Phase, Progress},
+//!     synthetic::{self, SyntheticConfig},
+//!     transform::TransformPipeline,
+//! };
+//!
+//! let dir = std::env::temp_dir().join("igdm-sentiment-analysis-progress");
+//! let config = SyntheticConfig {
+//!     messages: 120,
+//!     messages_per_file: 50,
+//!     ..SyntheticConfig::default()
+//! };
+//! synthetic::write_export(&config, &dir)?;
+//!
+//! let mut reported = Vec::new();
+//! let conversation = ConversationDirectory::try_from(dir.clone())?.parse_with_progress(
+//!     &TransformPipeline::standard(),
+//!     &TimestampValidation::default(),
+//!     SharedCaptions::default(),
+//!     |progress| reported.push(progress),
+//! )?;
+//! assert_eq!(reported.len(), 3);
+//! assert_eq!(reported[2], Progress { phase: Phase::Parse, done: 3, total: 3 });
+//!
+//! let scored = conversation.messages.len();
+//! let mut last = None;
+//! conversation.into_analyzed_with_progress(&Default::default(), |progress| last = Some(progress));
+//! assert_eq!(last, Some(Progress { phase: Phase::Analyze, done: scored, total: scored }));
+//! # std::fs::remove_dir_all(&dir)?;
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+/// The phase a `Progress` is of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// parsing the message files, counted in files (all at once when the conversation is cached)
+    Parse,
+    /// scoring the messages, counted in messages
+    Analyze,
+}
+
+/// How far along a phase is, `done` of its `total` files or messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: Phase,
+    pub done: usize,
+    pub total: usize,
+}
@@ -0,0 +1,202 @@
+//! Module responsible for the who-said-it quiz: a random selection of strongly scored messages with the sender hidden,
+//! and a separate answer key, to share with the people in the conversation.
+//!
+//! Questions are drawn from each participant in turn, so no one dominates the quiz however much more they write,
+//! and near-identical messages (the same tokens, ignoring case and punctuation) only appear once.
+
+use std::{collections::HashSet, fmt::Write};
+
+use anyhow::{bail, Result};
+use rand::seq::SliceRandom;
+
+use crate::{analyzer::AnalyzedConversation, rng::Seed, text, tokenizer::Tokenizer};
+
+/// Which messages can make it into the quiz, and how many.
+#[derive(Debug, Clone)]
+pub struct QuizConfig {
+    pub questions: usize,
+    /// only messages with a compound score at least this far from 0
+    pub min_magnitude: f64,
+    /// only messages at most this many characters long
+    pub max_chars: usize,
+}
+
+impl Default for QuizConfig {
+    fn default() -> Self {
+        Self {
+            questions: 20,
+            min_magnitude: 0.5,
+            max_chars: 200,
+        }
+    }
+}
+
+/// Written as `quiz.json` and `quiz.html`, the answers are written separately as `quiz_answers.json`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Quiz {
+    /// the participants, ordered by name
+    pub choices: Vec<String>,
+    pub questions: Vec<Question>,
+    #[serde(skip)]
+    pub answers: Vec<Answer>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Question {
+    /// starting from 1, matches the question's answer
+    pub number: usize,
+    pub timestamp_ms: usize,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Answer {
+    pub number: usize,
+    pub sender: String,
+}
+
+impl AnalyzedConversation {
+    /// Draws a quiz from the messages, randomized by the seed.
+    ///
+    /// Fails if the content was dropped, or if no message qualifies.
+    pub fn quiz(&self, config: &QuizConfig, seed: Seed) -> Result<Quiz> {
+        if self.content_dropped {
+            bail!("the quiz needs the text of the messages, which was dropped after scoring");
+        }
+        let mut rng = seed.rng("quiz");
+
+        let mut pools = self
+            .participants_by_name()
+            .into_iter()
+            .map(|participant| {
+                let mut pool = self.analysis[&participant]
+                    .iter()
+                    .filter(|(message, score)| {
+                        score.compound.abs() >= config.min_magnitude
+                            && message.text().chars().count() <= config.max_chars
+                    })
+                    .map(|(message, _)| message)
+                    .collect::<Vec<_>>();
+                pool.shuffle(&mut rng);
+                pool.into_iter()
+            })
+            .collect::<Vec<_>>();
+        // so whoever sorts first by name doesn't always get the extra question
+        pools.shuffle(&mut rng);
+
+        let mut seen = HashSet::new();
+        let mut picked = Vec::new();
+        'rounds: loop {
+            let mut drew = false;
+            for pool in &mut pools {
+                if picked.len() == config.questions {
+                    break 'rounds;
+                }
+                let next = pool.find(|message| {
+                    let words = Tokenizer::default()
+                        .tokens(message.text())
+                        .map(|token| text::normalize(token.text))
+                        .collect::<Vec<_>>();
+                    !words.is_empty() && seen.insert(words)
+                });
+                if let Some(message) = next {
+                    picked.push(message);
+                    drew = true;
+                }
+            }
+            if !drew {
+                break;
+            }
+        }
+        if picked.is_empty() {
+            bail!(
+                "no message is both strongly scored (compound at least {} from 0) and at most {} characters long",
+                config.min_magnitude,
+                config.max_chars
+            );
+        }
+        picked.shuffle(&mut rng);
+
+        let (questions, answers) = picked
+            .into_iter()
+            .enumerate()
+            .map(|(i, message)| {
+                (
+                    Question {
+                        number: i + 1,
+                        timestamp_ms: message.timestamp_ms(),
+                        content: message.text().to_string(),
+                    },
+                    Answer {
+                        number: i + 1,
+                        sender: self.names.name(message.sender).to_string(),
+                    },
+                )
+            })
+            .unzip();
+        Ok(Quiz {
+            choices: self
+                .participants_by_name()
+                .into_iter()
+                .map(|participant| self.names.name(participant).to_string())
+                .collect(),
+            questions,
+            answers,
+        })
+    }
+}
+
+impl Quiz {
+    /// The quiz, without the answers, as pretty-printed json.
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// The answer key as pretty-printed json.
+    pub fn answers_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(&self.answers)?)
+    }
+
+    /// The quiz, without the answers, as a self-contained html page.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Who said it?</title>\n<style>blockquote { white-space: pre-wrap; }</style>\n</head>\n<body>\n<h1>Who said it?</h1>\n",
+        );
+        // writing to a string can't fail
+        let choices = self
+            .choices
+            .iter()
+            .map(|choice| escape_html(choice))
+            .collect::<Vec<_>>();
+        let _ = writeln!(html, "<p>Choices: {}</p>\n<ol>", choices.join(", "));
+        for question in &self.questions {
+            let date = chrono::DateTime::from_timestamp_millis(question.timestamp_ms as i64)
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<li value=\"{}\"><blockquote>{}</blockquote><small>{date}</small></li>",
+                question.number,
+                escape_html(&question.content)
+            );
+        }
+        html.push_str("</ol>\n</body>\n</html>\n");
+        html
+    }
+}
+
+/// Escapes text for use in html element content or a quoted attribute.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,260 @@
+//! Module responsible for flagging messages that are likely quoted rather than the sender's own words
+//! (pasted lyrics, headlines, or someone else's message), whose sentiment isn't really the sender's.
+//!
+//! A message is flagged by the first of these heuristics that applies:
+//! 1. it's a near-duplicate of an earlier message from someone else: at least `DUPLICATE_SIMILARITY` of its shingles
+//!    (runs of `SHINGLE_WORDS` words, see `similarity`) appear in that message, and it's at least `DUPLICATE_MIN_WORDS` words long
+//! 2. it's wrapped in quotation marks
+//! 3. it's at least `IMPERSONAL_MIN_WORDS` words long, without a single first or second person pronoun
+//!
+//! Flagged messages are always scored and flagged in the exports, `QuotePolicy` decides how much they count in aggregates.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    fmt::Display,
+    hash::{Hash, Hasher},
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    parser::{Message, ParticipantId},
+    text::normalize,
+    tokenizer::words,
+};
+
+/// How many consecutive words make up a shingle.
+pub const SHINGLE_WORDS: usize = 3;
+/// A message is a near-duplicate of an earlier one if at least this share of its shingles appear in it.
+pub const DUPLICATE_SIMILARITY: f64 = 0.6;
+/// Shorter messages are never near-duplicates, it's no paste when both sides say "see you tomorrow then".
+pub const DUPLICATE_MIN_WORDS: usize = 6;
+/// Shorter messages are never flagged for lacking first and second person pronouns.
+pub const IMPERSONAL_MIN_WORDS: usize = 15;
+
+/// First and second person pronouns (and their chat spellings and contractions), normalized.
+const PERSONAL_PRONOUNS: &[&str] = &[
+    "i",
+    "me",
+    "my",
+    "mine",
+    "myself",
+    "i'm",
+    "im",
+    "i'd",
+    "i'll",
+    "i've",
+    "we",
+    "us",
+    "our",
+    "ours",
+    "ourselves",
+    "we're",
+    "we'd",
+    "we'll",
+    "we've",
+    "let's",
+    "lets",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+    "you're",
+    "you'd",
+    "you'll",
+    "you've",
+    "u",
+    "ur",
+    "ya",
+    "y'all",
+    "yall",
+];
+
+/// Pairs of quotation marks a quoted message can be wrapped in.
+const QUOTATION_MARKS: &[(char, char)] = &[('"', '"'), ('“', '”'), ('„', '“'), ('«', '»')];
+
+/// Why a message was flagged as likely quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteReason {
+    /// it's mostly the same as an earlier message from someone else
+    NearDuplicate,
+    /// it's wrapped in quotation marks
+    QuotationMarks,
+    /// it's long, and never refers to the sender or who they're talking to
+    Impersonal,
+}
+
+impl QuoteReason {
+    /// The name of the reason in identifiers, like the exports.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::NearDuplicate => "near_duplicate",
+            Self::QuotationMarks => "quotation_marks",
+            Self::Impersonal => "impersonal",
+        }
+    }
+}
+
+/// How much flagged messages count in aggregates (the summaries, and daily aggregates like the time series exports and the calendar),
+/// they're always scored and flagged in the exports regardless.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QuotePolicy {
+    /// count them like every other message
+    #[default]
+    Keep,
+    /// count them this much (0 to 1) as much as every other message, see `AggregateStat::apply_weighted`
+    DownWeight(f64),
+    /// leave them out
+    Exclude,
+}
+
+impl QuotePolicy {
+    /// How much the message counts in aggregates.
+    pub fn weight(self, message: &Message) -> f64 {
+        match (message.quote, self) {
+            (None, _) | (Some(_), Self::Keep) => 1.0,
+            (Some(_), Self::DownWeight(weight)) => weight,
+            (Some(_), Self::Exclude) => 0.0,
+        }
+    }
+}
+
+impl FromStr for QuotePolicy {
+    type Err = anyhow::Error;
+
+    /// Parses `keep`, `exclude`, or `down-weight:WEIGHT`, with a weight from 0 to 1.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.split_once(':') {
+            None if s == "keep" => Ok(Self::Keep),
+            None if s == "exclude" => Ok(Self::Exclude),
+            None if s == "down-weight" => bail!("down-weight needs a weight, like down-weight:0.5"),
+            Some(("down-weight", weight)) => {
+                let weight = weight
+                    .parse::<f64>()
+                    .with_context(|| format!("invalid weight in \"{s}\""))?;
+                if !(0.0..=1.0).contains(&weight) {
+                    bail!("the weight in \"{s}\" should be from 0 to 1");
+                }
+                Ok(Self::DownWeight(weight))
+            }
+            _ => bail!("expected keep, exclude, or down-weight:WEIGHT, got \"{s}\""),
+        }
+    }
+}
+
+impl Display for QuotePolicy {
+    /// The form `from_str` parses.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Keep => write!(f, "keep"),
+            Self::DownWeight(weight) => write!(f, "down-weight:{weight}"),
+            Self::Exclude => write!(f, "exclude"),
+        }
+    }
+}
+
+/// The hashed shingles of the text: every run of `SHINGLE_WORDS` consecutive (normalized) words,
+/// or the whole text as one shingle if it has fewer words than that.
+fn shingles(words: &[String]) -> HashSet<u64> {
+    words
+        .windows(SHINGLE_WORDS.min(words.len()).max(1))
+        .map(|shingle| {
+            let mut hasher = DefaultHasher::new();
+            shingle.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn normalized_words(text: &str) -> Vec<String> {
+    words(text)
+        .map(|word| normalize(&word.replace('’', "'")))
+        .collect()
+}
+
+/// The share (0 to 1) of the shingles of `text` that also appear in `earlier`, 0 if `text` has no words.
+///
+/// ```
+/// use igdm_sentiment_analysis::quotes::similarity;
+///
+/// let original = "the quick brown fox jumps over the lazy dog by the river";
+/// // an exact paste
+/// assert_eq!(similarity(original, original), 1.0);
+/// // a partial paste, with a bit of commentary
+/// assert!(similarity("lmao the quick brown fox jumps over the lazy dog", original) > 0.6);
+/// // something else entirely
+/// assert_eq!(similarity("what are we having for dinner tonight", original), 0.0);
+/// ```
+pub fn similarity(text: &str, earlier: &str) -> f64 {
+    let text = shingles(&normalized_words(text));
+    let earlier = shingles(&normalized_words(earlier));
+    if text.is_empty() {
+        return 0.0;
+    }
+    text.intersection(&earlier).count() as f64 / text.len() as f64
+}
+
+/// Whether the text is wrapped in a pair of quotation marks, with something in between.
+fn wrapped_in_quotation_marks(text: &str) -> bool {
+    let text = text.trim();
+    QUOTATION_MARKS.iter().any(|(open, close)| {
+        text.strip_prefix(*open)
+            .and_then(|rest| rest.strip_suffix(*close))
+            .is_some_and(|inner| !inner.trim().is_empty())
+    })
+}
+
+/// Flags the messages of a conversation as they're fed to it in order, remembering the shingles of every message
+/// so later ones can be checked against them.
+#[derive(Debug, Default)]
+pub struct QuoteDetector {
+    /// the earlier messages each shingle appears in, as indices into `senders`
+    index: HashMap<u64, Vec<usize>>,
+    /// the sender of every earlier message
+    senders: Vec<ParticipantId>,
+}
+
+impl QuoteDetector {
+    /// Checks the next message of the conversation, see the module documentation.
+    pub fn check(&mut self, message: &Message) -> Option<QuoteReason> {
+        let words = normalized_words(message.text());
+        let shingles = shingles(&words);
+
+        // how many of this message's shingles each earlier message from someone else shares
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for shingle in &shingles {
+            for earlier in self.index.get(shingle).into_iter().flatten() {
+                if self.senders[*earlier] != message.sender {
+                    *shared.entry(*earlier).or_default() += 1;
+                }
+            }
+        }
+        let near_duplicate = words.len() >= DUPLICATE_MIN_WORDS
+            && shared
+                .values()
+                .any(|shared| *shared as f64 >= DUPLICATE_SIMILARITY * shingles.len() as f64);
+
+        let id = self.senders.len();
+        self.senders.push(message.sender);
+        for shingle in shingles {
+            self.index.entry(shingle).or_default().push(id);
+        }
+
+        if near_duplicate {
+            Some(QuoteReason::NearDuplicate)
+        } else if wrapped_in_quotation_marks(message.text()) {
+            Some(QuoteReason::QuotationMarks)
+        } else if words.len() >= IMPERSONAL_MIN_WORDS
+            && !words
+                .iter()
+                .any(|word| PERSONAL_PRONOUNS.contains(&word.as_str()))
+        {
+            Some(QuoteReason::Impersonal)
+        } else {
+            None
+        }
+    }
+}
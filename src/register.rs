@@ -0,0 +1,225 @@
+//! Module containing the linguistic register metrics: how long and varied each participant's sentences are,
+//! and how formally they write, per calendar month.
+//!
+//! These need the text of the messages, so they're accumulated while the conversation is analyzed
+//! (before the text can be dropped, see `AnalyzeOptions::drop_content`).
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
+
+use chrono::Datelike;
+
+use crate::{
+    parser::{Message, ParticipantId},
+    stats::fit_line,
+    text, tokenizer,
+};
+
+/// Chat abbreviations counted by the formality proxy, compared against lowercased words.
+const ABBREVIATIONS: [&str; 30] = [
+    "u", "ur", "r", "y", "k", "idk", "tbh", "imo", "lol", "lmao", "omg", "btw", "rn", "ngl", "thx",
+    "pls", "plz", "ya", "gonna", "wanna", "cuz", "bc", "brb", "ttyl", "smh", "nvm", "ik", "wyd",
+    "hbu", "ty",
+];
+
+/// How many months the trend in `RegisterSeries::trend` looks back over.
+pub const TREND_MONTHS: i64 = 12;
+
+/// A calendar month, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Month {
+    pub year: i32,
+    /// 1 to 12
+    pub month: u32,
+}
+
+impl Month {
+    /// The month the timestamp (in milliseconds since the epoch) falls in,
+    /// `None` if it's too far from the epoch to be a date.
+    pub fn of_timestamp(timestamp_ms: usize) -> Option<Self> {
+        let date = chrono::DateTime::from_timestamp_millis(i64::try_from(timestamp_ms).ok()?)?;
+        Some(Self {
+            year: date.year(),
+            month: date.month(),
+        })
+    }
+
+    /// Months since year 0, so that consecutive months are consecutive numbers.
+    pub fn index(&self) -> i64 {
+        i64::from(self.year) * 12 + i64::from(self.month) - 1
+    }
+}
+
+impl Display for Month {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}", self.year, self.month)
+    }
+}
+
+impl serde::Serialize for Month {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The register metrics of one participant's messages in one month.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct MonthlyRegister {
+    pub month: Month,
+    pub messages: usize,
+    /// mean number of words per sentence
+    pub mean_sentence_length: f64,
+    /// distinct words over total words (ignoring case), this shrinks as the number of words grows,
+    /// so it's only comparable between months with a similar amount of text
+    pub type_token_ratio: f64,
+    /// fraction of sentences that end with punctuation
+    pub punctuation: f64,
+    /// fraction of sentences starting with a letter that start with a capital one
+    pub capitalization: f64,
+    /// fraction of words that are chat abbreviations like "u" or "idk"
+    pub abbreviation_rate: f64,
+    /// a crude formality proxy in [0, 1]: the mean of `punctuation`, `capitalization`, and 1 - `abbreviation_rate`
+    pub formality: f64,
+}
+
+/// The slopes, per month, of the register metrics over the last `TREND_MONTHS` months, see `RegisterSeries::trend`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegisterTrend {
+    pub mean_sentence_length: f64,
+    pub type_token_ratio: f64,
+    pub formality: f64,
+}
+
+/// The monthly register metrics of every participant, ordered by month.
+#[derive(Debug, Clone, Default)]
+pub struct RegisterSeries {
+    series: HashMap<ParticipantId, Vec<MonthlyRegister>>,
+}
+
+impl RegisterSeries {
+    /// The participant's monthly metrics, months in which they wrote no sentences are left out.
+    pub fn get(&self, participant: ParticipantId) -> &[MonthlyRegister] {
+        self.series.get(&participant).map_or(&[], Vec::as_slice)
+    }
+
+    /// The participant's most recent monthly metrics.
+    pub fn current(&self, participant: ParticipantId) -> Option<&MonthlyRegister> {
+        self.get(participant).last()
+    }
+
+    /// How the participant's metrics changed over the `TREND_MONTHS` months up to (and including) their most recent one,
+    /// `None` if fewer than 2 of those months have metrics.
+    pub fn trend(&self, participant: ParticipantId) -> Option<RegisterTrend> {
+        let series = self.get(participant);
+        let latest = series.last()?.month.index();
+        let window = series
+            .iter()
+            .filter(|register| latest - register.month.index() < TREND_MONTHS)
+            .collect::<Vec<_>>();
+        let slope = |metric: fn(&MonthlyRegister) -> f64| {
+            fit_line(
+                window
+                    .iter()
+                    .map(|register| (register.month.index() as f64, metric(register))),
+            )
+            .map(|(slope, _)| slope)
+        };
+        Some(RegisterTrend {
+            mean_sentence_length: slope(|register| register.mean_sentence_length)?,
+            type_token_ratio: slope(|register| register.type_token_ratio)?,
+            formality: slope(|register| register.formality)?,
+        })
+    }
+}
+
+/// Accumulates the register metrics one message at a time.
+#[derive(Debug, Default)]
+pub struct RegisterBuilder {
+    counts: BTreeMap<(ParticipantId, Month), RegisterCounts>,
+}
+
+#[derive(Debug, Default)]
+struct RegisterCounts {
+    messages: usize,
+    sentences: usize,
+    words: usize,
+    types: HashSet<String>,
+    terminated: usize,
+    letter_starts: usize,
+    capitalized: usize,
+    abbreviations: usize,
+}
+
+impl RegisterBuilder {
+    pub fn add(&mut self, message: &Message) {
+        let Some(month) = Month::of_timestamp(message.timestamp_ms()) else {
+            return;
+        };
+        let counts = self.counts.entry((message.sender, month)).or_default();
+        counts.messages += 1;
+
+        for sentence in text::sentences(message.text()) {
+            counts.sentences += 1;
+            if sentence.ends_with(text::is_terminal) {
+                counts.terminated += 1;
+            }
+            if let Some(first) = sentence.chars().find(|c| c.is_alphanumeric()) {
+                if first.is_alphabetic() {
+                    counts.letter_starts += 1;
+                    if first.is_uppercase() {
+                        counts.capitalized += 1;
+                    }
+                }
+            }
+            for word in tokenizer::words(sentence) {
+                let word = text::normalize(word);
+                counts.words += 1;
+                if ABBREVIATIONS.contains(&word.as_str()) {
+                    counts.abbreviations += 1;
+                }
+                counts.types.insert(word);
+            }
+        }
+    }
+
+    pub fn build(self) -> RegisterSeries {
+        let mut series: HashMap<ParticipantId, Vec<MonthlyRegister>> = HashMap::new();
+        // the counts are ordered by participant then month, so each series comes out ordered by month
+        for ((participant, month), counts) in self.counts {
+            if let Some(register) = counts.metrics(month) {
+                series.entry(participant).or_default().push(register);
+            }
+        }
+        RegisterSeries { series }
+    }
+}
+
+impl RegisterCounts {
+    fn metrics(&self, month: Month) -> Option<MonthlyRegister> {
+        if self.sentences == 0 || self.words == 0 {
+            return None;
+        }
+        let ratio = |part: usize, whole: usize| part as f64 / whole as f64;
+
+        let punctuation = ratio(self.terminated, self.sentences);
+        // a month where no sentence starts with a letter gives no evidence either way
+        let capitalization = if self.letter_starts == 0 {
+            0.5
+        } else {
+            ratio(self.capitalized, self.letter_starts)
+        };
+        let abbreviation_rate = ratio(self.abbreviations, self.words);
+        Some(MonthlyRegister {
+            month,
+            messages: self.messages,
+            mean_sentence_length: ratio(self.words, self.sentences),
+            type_token_ratio: ratio(self.types.len(), self.words),
+            punctuation,
+            capitalization,
+            abbreviation_rate,
+            formality: (punctuation + capitalization + (1.0 - abbreviation_rate)) / 3.0,
+        })
+    }
+}
@@ -0,0 +1,70 @@
+//! Module containing metrics about how the participants of a conversation respond to each other,
+//! rather than about each participant on their own.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    analyzer::{AnalyzedConversation, ReplyPair},
+    parser::ParticipantId,
+    stats::fit_line,
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct RelationshipMetrics {
+    /// one entry per direction (who replied to whom) with at least one reply, ordered by participant id
+    pub responsiveness: Vec<Responsiveness>,
+}
+
+/// How closely one participant's replies track the tone of the messages they reply to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Responsiveness {
+    pub stimulus_sender: ParticipantId,
+    pub responder: ParticipantId,
+    /// how many replies the slope was fitted to
+    pub pairs: usize,
+    /// the slope of the regression of the reply's compound score against the compound score of the message replied to,
+    /// near 1 if the responder mirrors the stimulus sender's tone, near 0 if their replies don't depend on it.
+    /// `None` if no line could be fitted (e.g. every message replied to had the same score)
+    pub slope: Option<f64>,
+}
+
+impl RelationshipMetrics {
+    pub fn from_pairs(pairs: &[ReplyPair]) -> Self {
+        let mut directions: BTreeMap<(ParticipantId, ParticipantId), Vec<&ReplyPair>> =
+            BTreeMap::new();
+        for pair in pairs {
+            directions
+                .entry((pair.stimulus_sender, pair.responder))
+                .or_default()
+                .push(pair);
+        }
+
+        let responsiveness = directions
+            .into_iter()
+            .map(|((stimulus_sender, responder), pairs)| Responsiveness {
+                stimulus_sender,
+                responder,
+                pairs: pairs.len(),
+                slope: fit_line(
+                    pairs
+                        .iter()
+                        .map(|pair| (pair.stimulus_compound, pair.response_compound)),
+                )
+                .map(|(slope, _)| slope),
+            })
+            .collect();
+
+        Self { responsiveness }
+    }
+}
+
+impl AnalyzedConversation {
+    /// The relationship metrics, with the directions in which the exporter (`me`, if known) responds first.
+    pub fn relationship_metrics(&self) -> RelationshipMetrics {
+        let mut metrics = RelationshipMetrics::from_pairs(&self.reply_pairs());
+        metrics
+            .responsiveness
+            .sort_by_key(|responsiveness| Some(responsiveness.responder) != self.me);
+        metrics
+    }
+}
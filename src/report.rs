@@ -0,0 +1,277 @@
+//! Module responsible for the plain text report of a run: the headline numbers, for reading rather than for other tools.
+
+use std::fmt::Write;
+
+use crate::{
+    analyzer::AnalyzedConversation,
+    format::{
+        format_count, format_date_time, format_delta, format_duration, format_duration_delta,
+        DAY_MS, MINUTE_MS,
+    },
+    openers::{OpenerKind, Opening},
+    parser::ParticipantId,
+    quotes::QuotePolicy,
+    register::TREND_MONTHS,
+    stats::{Estimate, CONFIDENCE},
+};
+
+impl AnalyzedConversation {
+    /// Renders the report, participants are listed by name.
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        self.write_summary_section(&mut report);
+        self.write_register_section(&mut report);
+        self.write_openers_section(&mut report);
+        self.write_events_section(&mut report);
+        self.write_phases_section(&mut report);
+        self.write_gaps_section(&mut report);
+        self.write_decay_section(&mut report);
+        report
+    }
+
+    /// The participants, ordered by name.
+    pub fn participants_by_name(&self) -> Vec<ParticipantId> {
+        let mut participants = self.analysis.keys().copied().collect::<Vec<_>>();
+        participants.sort_unstable_by_key(|participant| self.names.name(*participant));
+        participants
+    }
+
+    /// The participants, ordered by name, except the exporter (`me`, if known) comes first,
+    /// so asymmetric metrics always read from their side.
+    pub fn participants_oriented(&self) -> Vec<ParticipantId> {
+        let mut participants = self.participants_by_name();
+        participants.sort_by_key(|participant| Some(*participant) != self.me);
+        participants
+    }
+
+    /// The name of the participant, marked if they're the exporter.
+    pub(crate) fn display_name(&self, participant: ParticipantId) -> String {
+        let name = self.names.name(participant);
+        if Some(participant) == self.me {
+            format!("{name} (me)")
+        } else {
+            name.to_string()
+        }
+    }
+
+    fn write_summary_section(&self, report: &mut String) {
+        // writing to a string can't fail
+        match self.bootstrap() {
+            Some(bootstrap) => {
+                let _ = writeln!(
+                    report,
+                    "Summary ({:.0}% confidence intervals from {} bootstrap resamples in brackets)",
+                    CONFIDENCE * 100.0,
+                    bootstrap.resamples
+                );
+            }
+            None => {
+                let _ = writeln!(report, "Summary");
+            }
+        }
+        let estimate = |estimate: Option<Estimate>, format: &dyn Fn(f64) -> String| match estimate {
+            Some(Estimate {
+                value,
+                interval: Some((low, high)),
+            }) => format!("{} [{}, {}]", format(value), format(low), format(high)),
+            Some(Estimate { value, .. }) => format(value),
+            None => "n/a".to_string(),
+        };
+        for summary in self.summaries() {
+            let quoted = match (summary.quoted, self.quotes) {
+                (0, _) => String::new(),
+                (quoted, QuotePolicy::Keep) => format!(" ({} likely quoted)", format_count(quoted)),
+                (quoted, policy) => {
+                    format!(" ({} likely quoted, {policy})", format_count(quoted))
+                }
+            };
+            let _ = writeln!(
+                report,
+                "  {}: {} messages{quoted}, {} compound {}, {}% positive, median reply {}",
+                self.display_name(summary.participant),
+                format_count(summary.messages),
+                self.aggregate.describe(),
+                estimate(summary.compound, &|compound| format!("{compound:.3}")),
+                estimate(summary.positive_share, &|share| format!(
+                    "{:.1}",
+                    share * 100.0
+                )),
+                estimate(summary.median_reply_minutes, &|minutes| {
+                    format_duration(minutes * MINUTE_MS)
+                }),
+            );
+        }
+    }
+
+    fn write_register_section(&self, report: &mut String) {
+        let _ = writeln!(
+            report,
+            "Register (most recent month, change per month over the last {TREND_MONTHS} months in parentheses)"
+        );
+        for participant in self.participants_oriented() {
+            let name = self.display_name(participant);
+            let Some(current) = self.register.current(participant) else {
+                let _ = writeln!(report, "  {name}: no text to measure");
+                continue;
+            };
+            let trend = self.register.trend(participant);
+            let change = |slope: Option<f64>| match slope {
+                Some(slope) => format!(" ({})", format_delta(slope, 3)),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                report,
+                "  {name} ({}): {:.1} words/sentence{}, type-token ratio {:.2}{}, formality {:.2}{}",
+                current.month,
+                current.mean_sentence_length,
+                change(trend.map(|trend| trend.mean_sentence_length)),
+                current.type_token_ratio,
+                change(trend.map(|trend| trend.type_token_ratio)),
+                current.formality,
+                change(trend.map(|trend| trend.formality)),
+            );
+        }
+    }
+
+    fn write_openers_section(&self, report: &mut String) {
+        let _ = writeln!(
+            report,
+            "Conversation starters (the first message of each session, mean compound of all their messages in parentheses)"
+        );
+        let mean = |mean: Option<f64>| match mean {
+            Some(mean) => format!("{mean:.3}"),
+            None => "n/a".to_string(),
+        };
+        let openings = |openings: &[Opening]| {
+            openings
+                .iter()
+                .map(|opening| format!("\"{}\" ({})", opening.text, opening.count))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        for participant in self.participants_oriented() {
+            let name = self.display_name(participant);
+            let Some(summary) = self
+                .openers
+                .get(participant)
+                .filter(|summary| summary.sessions > 0)
+            else {
+                let _ = writeln!(report, "  {name}: no sessions opened");
+                continue;
+            };
+            let kinds = OpenerKind::ALL
+                .into_iter()
+                .map(|kind| format!("{:.0}% {}", summary.share(kind) * 100.0, kind.plural()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let _ = writeln!(
+                report,
+                "  {name}: {} sessions opened, {kinds}, mean compound {} ({})",
+                summary.sessions,
+                mean(summary.mean_compound),
+                mean(summary.baseline),
+            );
+            if !summary.top_words.is_empty() {
+                let phrases = match summary.top_phrases.as_slice() {
+                    [] => String::new(),
+                    phrases => format!("; phrases: {}", openings(phrases)),
+                };
+                let _ = writeln!(
+                    report,
+                    "    opening words: {}{phrases}",
+                    openings(&summary.top_words),
+                );
+            }
+        }
+    }
+
+    fn write_events_section(&self, report: &mut String) {
+        let comparisons = self.event_comparisons();
+        if comparisons.is_empty() {
+            return;
+        }
+        let mean = |mean: Option<f64>| match mean {
+            Some(mean) => format!("{mean:+.2}"),
+            None => "n/a".to_string(),
+        };
+        let _ = writeln!(
+            report,
+            "Events (mean compound on those days, against every other day)"
+        );
+        for comparison in comparisons {
+            let _ = writeln!(
+                report,
+                "  {}: {} over {} days ({} messages) vs baseline {}",
+                comparison.category,
+                mean(comparison.mean_compound),
+                format_count(comparison.days),
+                format_count(comparison.messages),
+                mean(comparison.baseline),
+            );
+        }
+    }
+
+    fn write_phases_section(&self, report: &mut String) {
+        let phases = self.phases();
+        if phases.is_empty() {
+            return;
+        }
+        let _ = writeln!(report, "Phases");
+        for phase in phases {
+            let _ = writeln!(report, "  {}", phase.description());
+        }
+    }
+
+    fn write_gaps_section(&self, report: &mut String) {
+        let Some(silence) = self.longest_silence() else {
+            return;
+        };
+        let _ = writeln!(
+            report,
+            "Longest silence: {}, from {} to {}",
+            format_duration(silence.duration_ms()),
+            format_date_time(silence.from_ms),
+            format_date_time(silence.to_ms)
+        );
+    }
+
+    fn write_decay_section(&self, report: &mut String) {
+        let bursts = self.bursts();
+        if bursts.is_empty() {
+            return;
+        }
+        let excluded = bursts
+            .iter()
+            .filter(|burst| burst.excluded.is_some())
+            .count();
+        let _ = writeln!(
+            report,
+            "Cooling off after {} bursts (median half-life of daily messages, change per year in parentheses)",
+            format_count(bursts.len())
+        );
+        for summary in self.decay_summaries(&bursts) {
+            let name = self.display_name(summary.participant);
+            let Some(median) = summary.median_half_life_days else {
+                let _ = writeln!(report, "  {name}: no decay measured");
+                continue;
+            };
+            let change = match summary.change_per_year {
+                Some(change) => format!(" ({} per year)", format_duration_delta(change * DAY_MS)),
+                None => String::new(),
+            };
+            let _ = writeln!(
+                report,
+                "  {name}: {} over {} bursts{change}",
+                format_duration(median * DAY_MS),
+                format_count(summary.bursts)
+            );
+        }
+        if excluded > 0 {
+            let _ = writeln!(
+                report,
+                "  {} bursts were too close to the next one or the end of the data to fit",
+                format_count(excluded)
+            );
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Module responsible for the year in review: a handful of headline numbers for one calendar year of the conversation,
+//! for sharing, as a card (see `YearReview::plot_png`, with the `plot` feature) and as a markdown section.
+//!
+//! Every number comes from the same day and week buckets (see `aggregate`) and aggregate statistic as everything else,
+//! so days, weeks, and years are in UTC. The review is about the conversation as a whole, it names nobody.
+
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike, NaiveDate};
+
+use crate::{
+    aggregate::{self, Period},
+    analyzer::{AnalyzedConversation, Score},
+    format::{format_count, format_delta},
+    palette::Palette,
+    parser::Message,
+    stats::AggregateStat,
+    tokenizer,
+};
+
+/// Weeks with fewer messages than this can't be the most positive week, a couple of kind words aren't a good week.
+pub const MIN_WEEK_MESSAGES: usize = 10;
+
+/// A run of consecutive days with messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Streak {
+    pub start: NaiveDate,
+    pub days: usize,
+}
+
+impl Streak {
+    /// The last day of the streak.
+    pub fn end(&self) -> NaiveDate {
+        self.start + chrono::Days::new(self.days as u64 - 1)
+    }
+}
+
+/// One headline number of a review, e.g. "Busiest day: Sat Oct 14 (412 messages)".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewLine {
+    pub label: String,
+    pub value: String,
+    /// context for the value, shown smaller (or in parentheses) after it
+    pub detail: Option<String>,
+}
+
+/// The headline numbers of one calendar year of the conversation.
+#[derive(Debug, Clone)]
+pub struct YearReview {
+    pub year: i32,
+    pub messages: usize,
+    /// the day with the most messages (the earliest, if there's a tie), and how many it had
+    pub busiest_day: (NaiveDate, usize),
+    /// the year's compound scores aggregated with `aggregate`, counting quoted messages as `AnalyzedConversation::quotes` says
+    pub compound: Option<f64>,
+    /// the same for the year before, `None` if there were no messages that year
+    pub prior_compound: Option<f64>,
+    /// `None` if no emoji were used, or the message text was dropped
+    pub top_emoji: Option<String>,
+    /// the longest streak of the year (the earliest, if there's a tie), cut off at the ends of the year
+    pub longest_streak: Streak,
+    /// the start of the week with the highest compound score (of the weeks with at least `MIN_WEEK_MESSAGES` messages), and its score
+    pub most_positive_week: Option<(NaiveDate, f64)>,
+    /// the compound score of every day of the year with messages, in order
+    pub daily: Vec<(NaiveDate, f64)>,
+    /// the statistic the compound scores are aggregated with, carried over from the analysis
+    pub aggregate: AggregateStat,
+    /// how the card is colored, carried over from the analysis
+    pub palette: Palette,
+}
+
+/// The (UTC) date of the timestamp.
+fn date_of(timestamp_ms: usize) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(timestamp_ms as i64).map(|time| time.date_naive())
+}
+
+impl AnalyzedConversation {
+    /// Reviews the calendar year, failing if there are no messages from it.
+    pub fn year_review(&self, year: i32) -> Result<YearReview> {
+        let timeline = self.timeline();
+        let of_year = |year: i32| {
+            timeline
+                .iter()
+                .copied()
+                .filter(move |(message, _)| {
+                    date_of(message.timestamp_ms()).is_some_and(|date| date.year() == year)
+                })
+                .collect::<Vec<_>>()
+        };
+        let messages = of_year(year);
+        if messages.is_empty() {
+            match self
+                .time_range()
+                .and_then(|(first, last)| Some((date_of(first)?.year(), date_of(last)?.year())))
+            {
+                Some((first, last)) => {
+                    bail!("there are no messages from {year}, the conversation runs from {first} to {last}")
+                }
+                None => {
+                    bail!("there are no messages from {year}, the conversation has none at all")
+                }
+            }
+        }
+
+        let weighted = |messages: &[(&Message, &Score)]| {
+            messages
+                .iter()
+                .map(|(message, score)| {
+                    (
+                        message.timestamp_ms(),
+                        (score.compound, self.quotes.weight(message)),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let compound = |messages: &[(&Message, &Score)]| {
+            self.aggregate.apply_weighted(
+                &mut weighted(messages)
+                    .into_iter()
+                    .map(|(_, weighted)| weighted)
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        let days = aggregate::bucket(weighted(&messages), Period::Day);
+        let volume = |messages: &[(&Message, &Score)]| {
+            messages
+                .iter()
+                .map(|(message, _)| self.repeats.volume(message))
+                .sum::<usize>()
+        };
+        // max_by_key keeps the last of the maxima, so go in reverse to get the earliest
+        let (busiest_day, busiest_messages) = aggregate::bucket(
+            messages
+                .iter()
+                .map(|&(message, score)| (message.timestamp_ms(), (message, score))),
+            Period::Day,
+        )
+        .iter()
+        .rev()
+        .map(|(day, messages)| (*day, volume(messages)))
+        .max_by_key(|(_, volume)| *volume)
+        .unwrap_or_default();
+
+        let mut longest_streak = (0, 0);
+        let mut streak = (0, 0);
+        for day in days.keys() {
+            streak = match streak {
+                (start, length) if length > 0 && start + length == *day => (start, length + 1),
+                _ => (*day, 1),
+            };
+            if streak.1 > longest_streak.1 {
+                longest_streak = streak;
+            }
+        }
+
+        let most_positive_week = aggregate::bucket(weighted(&messages), Period::Week)
+            .into_iter()
+            .filter(|(_, scores)| scores.len() >= MIN_WEEK_MESSAGES)
+            .filter_map(|(week, mut scores)| {
+                Some((week, self.aggregate.apply_weighted(&mut scores)?))
+            })
+            .rev()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .and_then(|(week, compound)| Some((date_of(Period::Week.start_ms(week))?, compound)));
+
+        let day_date = |day: usize| date_of(Period::Day.start_ms(day));
+        Ok(YearReview {
+            year,
+            messages: volume(&messages),
+            busiest_day: (day_date(busiest_day).unwrap_or_default(), busiest_messages),
+            compound: compound(&messages),
+            prior_compound: compound(&of_year(year - 1)),
+            top_emoji: tokenizer::top_emoji(messages.iter().map(|(message, _)| message.text())),
+            longest_streak: Streak {
+                start: day_date(longest_streak.0).unwrap_or_default(),
+                days: longest_streak.1,
+            },
+            most_positive_week,
+            daily: days
+                .into_iter()
+                .filter_map(|(day, mut scores)| {
+                    Some((day_date(day)?, self.aggregate.apply_weighted(&mut scores)?))
+                })
+                .collect(),
+            aggregate: self.aggregate,
+            palette: self.palette,
+        })
+    }
+}
+
+impl YearReview {
+    /// The change in compound score from the year before, `None` if either year has none.
+    pub fn compound_change(&self) -> Option<f64> {
+        Some(self.compound? - self.prior_compound?)
+    }
+
+    /// The lines of the card and the markdown section, in order.
+    pub fn lines(&self) -> Vec<ReviewLine> {
+        let line = |label: &str, value: String, detail: Option<String>| ReviewLine {
+            label: label.to_string(),
+            value,
+            detail,
+        };
+        let (busiest_day, busiest_messages) = self.busiest_day;
+        let prior_year = self.year - 1;
+        let streak = &self.longest_streak;
+        vec![
+            line("Messages", format_count(self.messages), None),
+            line(
+                "Busiest day",
+                busiest_day.format("%a %b %-d").to_string(),
+                Some(format!("{} messages", format_count(busiest_messages))),
+            ),
+            line(
+                &format!("{} compound", capitalize(&self.aggregate.describe())),
+                match self.compound {
+                    Some(compound) => format!("{compound:+.3}"),
+                    None => "n/a".to_string(),
+                },
+                match (self.compound_change(), self.prior_compound) {
+                    (Some(change), _) => {
+                        Some(format!("{} from {prior_year}", format_delta(change, 3)))
+                    }
+                    (None, None) => Some(format!("no messages in {prior_year}")),
+                    (None, Some(_)) => None,
+                },
+            ),
+            line(
+                "Top emoji",
+                self.top_emoji.clone().unwrap_or_else(|| "n/a".to_string()),
+                None,
+            ),
+            line(
+                "Longest streak",
+                match streak.days {
+                    1 => "1 day".to_string(),
+                    days => format!("{days} days"),
+                },
+                Some(match streak.days {
+                    1 => streak.start.format("%b %-d").to_string(),
+                    _ => format!(
+                        "{} to {}",
+                        streak.start.format("%b %-d"),
+                        streak.end().format("%b %-d")
+                    ),
+                }),
+            ),
+            match self.most_positive_week {
+                Some((start, compound)) => line(
+                    "Most positive week",
+                    format!("week of {}", start.format("%b %-d")),
+                    Some(format!("compound {compound:+.3}")),
+                ),
+                None => line(
+                    "Most positive week",
+                    "n/a".to_string(),
+                    Some(format!("no week with {MIN_WEEK_MESSAGES} messages")),
+                ),
+            },
+        ]
+    }
+
+    /// Renders the review as a markdown section, embedding the card from `image` if there is one.
+    pub fn to_markdown(&self, image: Option<&str>) -> String {
+        let mut markdown = String::new();
+        // writing to a string can't fail
+        let _ = writeln!(markdown, "## {} in review", self.year);
+        let _ = writeln!(markdown);
+        if let Some(image) = image {
+            let _ = writeln!(markdown, "![{} in review]({image})", self.year);
+            let _ = writeln!(markdown);
+        }
+        for line in self.lines() {
+            let _ = match &line.detail {
+                Some(detail) => {
+                    writeln!(markdown, "- **{}:** {} ({detail})", line.label, line.value)
+                }
+                None => writeln!(markdown, "- **{}:** {}", line.label, line.value),
+            };
+        }
+        markdown
+    }
+}
+
+/// The text with its first letter in upper case.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
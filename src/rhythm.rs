@@ -0,0 +1,168 @@
+//! Module responsible for the rhythm of each participant's sentiment: their scored messages by hour of the day and by
+//! day of the week, to see whether they're grumpier at 2am or on Mondays.
+//!
+//! Hours and days are local to `AnalyzedConversation::time_zone` (utc by default), following its daylight saving time.
+//! Every hour and day has a bucket, those without messages are counted as 0 with no score, so a heatmap of the
+//! profiles is a full grid.
+
+use std::fmt::Write;
+
+use chrono::{Datelike, Timelike, Weekday};
+
+use crate::{
+    analyzer::AnalyzedConversation, parser::ParticipantId, stats, timeseries::escape_csv_field,
+};
+
+/// The columns of the csv, in order.
+pub const RHYTHM_COLUMNS: &[&str] = &[
+    "participant",
+    "profile",
+    "bucket",
+    "messages",
+    "mean_compound",
+];
+
+/// The scored messages a participant sent in an hour of the day, or on a day of the week.
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize)]
+pub struct RhythmBucket {
+    /// how many scored messages they sent (counting collapsed repeats as `AnalyzedConversation::repeats` says)
+    pub messages: usize,
+    /// the mean compound score of those messages, `None` if there are none
+    pub mean_compound: Option<f64>,
+}
+
+/// A participant's hour of the day and day of the week profiles.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParticipantRhythm {
+    #[serde(skip)]
+    pub participant: ParticipantId,
+    /// by local hour, from midnight (0) to 11pm (23)
+    pub hours: [RhythmBucket; 24],
+    /// by local day of the week, from Monday (0) to Sunday (6)
+    pub weekdays: [RhythmBucket; 7],
+}
+
+impl ParticipantRhythm {
+    /// The bucket of the day of the week.
+    pub fn weekday(&self, weekday: Weekday) -> &RhythmBucket {
+        &self.weekdays[weekday.num_days_from_monday() as usize]
+    }
+}
+
+impl AnalyzedConversation {
+    /// The hour of the day and day of the week profiles of each participant, ordered by name, see the module docs.
+    ///
+    /// ```
+    /// use chrono::Weekday;
+    /// use igdm_sentiment_analysis::{parser::ParsedConversation, timezone};
+    ///
+    /// // Monday the 3rd of July 2023 at 1:00 and 1:30 utc, and Saturday the 8th at 12:00 utc
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ana", "timestamp_ms": 1688817600000, "content": "what a great day, I love it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1688347800000, "content": "this is terrible, I hate it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1688346000000, "content": "ugh I can't sleep, awful"}
+    ///   ]
+    /// }"#;
+    /// let mut analysis = ParsedConversation::from_bytes(export)?.analyze();
+    /// let rhythm = analysis.rhythm();
+    /// let (ana, ben) = (&rhythm[0], &rhythm[1]);
+    /// assert_eq!(ana.hours[1].messages, 2);
+    /// assert!(ana.hours[1].mean_compound.unwrap() < 0.0);
+    /// assert!(ana.hours[12].mean_compound.unwrap() > 0.0);
+    /// assert_eq!(ana.weekday(Weekday::Mon).messages, 2);
+    /// assert_eq!(ana.weekday(Weekday::Sat).messages, 1);
+    /// // the hours and days without messages are still there
+    /// assert_eq!(ana.hours[2].messages, 0);
+    /// assert_eq!(ana.hours[2].mean_compound, None);
+    /// assert!(ben.hours.iter().chain(&ben.weekdays).all(|bucket| bucket.messages == 0));
+    ///
+    /// // in new york it was still Sunday, at 9pm
+    /// analysis.time_zone = timezone::parse("America/New_York")?;
+    /// let rhythm = analysis.rhythm();
+    /// assert_eq!(rhythm[0].hours[21].messages, 2);
+    /// assert_eq!(rhythm[0].weekday(Weekday::Sun).messages, 2);
+    ///
+    /// let csv = analysis.to_rhythm_csv();
+    /// assert_eq!(csv.lines().count(), 1 + 2 * (24 + 7));
+    /// assert!(csv.lines().any(|line| line.starts_with("Ana,weekday,sunday,2,-0.")));
+    /// assert!(csv.lines().any(|line| line == "Ben,hour,0,0,"));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn rhythm(&self) -> Vec<ParticipantRhythm> {
+        self.participants_by_name()
+            .into_iter()
+            .map(|participant| {
+                let mut hours = vec![Vec::new(); 24];
+                let mut weekdays = vec![Vec::new(); 7];
+                for (message, score) in self.analysis.get(&participant).into_iter().flatten() {
+                    let local = message.timestamp.with_timezone(&self.time_zone);
+                    let volume = self.repeats.volume(message);
+                    hours[local.hour() as usize].push((score.compound, volume));
+                    weekdays[local.weekday().num_days_from_monday() as usize]
+                        .push((score.compound, volume));
+                }
+                let bucket = |scores: &Vec<(f64, usize)>| RhythmBucket {
+                    messages: scores.iter().map(|(_, volume)| volume).sum(),
+                    mean_compound: stats::mean(
+                        &scores
+                            .iter()
+                            .map(|(compound, _)| *compound)
+                            .collect::<Vec<_>>(),
+                    ),
+                };
+                ParticipantRhythm {
+                    participant,
+                    hours: std::array::from_fn(|hour| bucket(&hours[hour])),
+                    weekdays: std::array::from_fn(|day| bucket(&weekdays[day])),
+                }
+            })
+            .collect()
+    }
+
+    /// Renders the profiles as a csv with a header row (see `RHYTHM_COLUMNS`), a row per participant per hour
+    /// (`hour`, 0 to 23) and per day of the week (`weekday`, monday to sunday), the mean compound empty without messages.
+    pub fn to_rhythm_csv(&self) -> String {
+        let mut csv = RHYTHM_COLUMNS.join(",");
+        csv.push('\n');
+        for rhythm in self.rhythm() {
+            let name = escape_csv_field(self.names.name(rhythm.participant));
+            let hours = rhythm
+                .hours
+                .iter()
+                .enumerate()
+                .map(|(hour, bucket)| ("hour", hour.to_string(), bucket));
+            let weekdays = rhythm.weekdays.iter().enumerate().map(|(day, bucket)| {
+                let weekday = Weekday::try_from(day as u8).unwrap_or(Weekday::Mon);
+                ("weekday", weekday_name(weekday).to_string(), bucket)
+            });
+            for (profile, key, bucket) in hours.chain(weekdays) {
+                // writing to a string can't fail
+                let _ = writeln!(
+                    csv,
+                    "{name},{profile},{key},{},{}",
+                    bucket.messages,
+                    bucket
+                        .mean_compound
+                        .map(|compound| compound.to_string())
+                        .unwrap_or_default(),
+                );
+            }
+        }
+        csv
+    }
+}
+
+/// The lowercase english name of the day of the week.
+fn weekday_name(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
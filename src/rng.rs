@@ -0,0 +1,49 @@
+//! Module responsible for every source of randomness, so that runs are reproducible.
+//!
+//! Randomized components never create their own rng, they ask the run's `Seed` for one instead,
+//! each passing a different `stream` name so they don't perturb each other's sequences.
+
+use std::{fmt::Display, str::FromStr};
+
+use rand::{rngs::SmallRng, SeedableRng};
+
+/// The seed of a run, fixed (to 0) unless asked otherwise.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    /// A seed drawn from the operating system's entropy source.
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    /// Creates the rng for the component named `stream`.
+    pub fn rng(self, stream: &str) -> SmallRng {
+        // FNV-1a, so the derived seeds don't depend on the standard library's hasher
+        let stream_hash = stream
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        SmallRng::seed_from_u64(self.0 ^ stream_hash)
+    }
+}
+
+impl FromStr for Seed {
+    type Err = std::num::ParseIntError;
+
+    /// Parses either a `u64`, or `random` for a seed drawn from entropy.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("random") {
+            Ok(Self::random())
+        } else {
+            s.parse().map(Self)
+        }
+    }
+}
+
+impl Display for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,93 @@
+//! Module responsible for settings profiles: toml files overriding some of the analysis options,
+//! so that alternative settings can be kept around and compared (see `compare`).
+//!
+//! ```toml
+//! positive_threshold = 0.1
+//! negative_threshold = -0.1
+//! session_gap_minutes = 120
+//! min_tokens = 3
+//! aggregate_stat = "trimmed:5"
+//! quoted = "down-weight:0.5"
+//! strip_urls_and_mentions = true
+//! ```
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::TimeDelta;
+
+use crate::analyzer::{AnalyzeOptions, Thresholds};
+
+/// The options a profile can override, anything left out keeps the value it's applied over.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub positive_threshold: Option<f64>,
+    pub negative_threshold: Option<f64>,
+    pub session_gap_minutes: Option<u32>,
+    pub min_tokens: Option<usize>,
+    /// parsed like `--aggregate-stat`, see `AggregateStat::from_str`
+    pub aggregate_stat: Option<String>,
+    /// parsed like `--quoted`, see `QuotePolicy::from_str`
+    pub quoted: Option<String>,
+    pub strip_urls_and_mentions: Option<bool>,
+}
+
+/// Analysis options under a name, for presenting them side by side.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub options: AnalyzeOptions,
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read settings from {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("failed to parse settings from {}", path.display()))
+    }
+
+    /// The options with this profile's overrides applied.
+    pub fn apply(&self, options: &AnalyzeOptions) -> Result<AnalyzeOptions> {
+        let mut options = options.clone();
+        options.thresholds = Thresholds::new(
+            self.positive_threshold
+                .unwrap_or(options.thresholds.positive),
+            self.negative_threshold
+                .unwrap_or(options.thresholds.negative),
+        )
+        .context("invalid thresholds")?;
+        if let Some(minutes) = self.session_gap_minutes {
+            options.session_gap = TimeDelta::minutes(minutes.into());
+        }
+        if let Some(min_tokens) = self.min_tokens {
+            options.min_tokens = min_tokens;
+        }
+        if let Some(stat) = &self.aggregate_stat {
+            options.aggregate = stat.parse().context("invalid aggregate_stat")?;
+        }
+        if let Some(policy) = &self.quoted {
+            options.quotes = policy.parse().context("invalid quoted")?;
+        }
+        if let Some(strip) = self.strip_urls_and_mentions {
+            options.strip_urls_and_mentions = strip;
+        }
+        Ok(options)
+    }
+}
+
+impl Profile {
+    /// Loads the settings at `path` over `options`, named after the file.
+    pub fn load(path: &Path, options: &AnalyzeOptions) -> Result<Self> {
+        Ok(Self {
+            name: path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+            options: Settings::load(path)?
+                .apply(options)
+                .with_context(|| format!("invalid settings in {}", path.display()))?,
+        })
+    }
+}
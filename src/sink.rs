@@ -0,0 +1,129 @@
+//! Module responsible for where the artifacts of a run end up.
+//!
+//! Everything a run produces (plots, exports, the manifest) is written through an `OutputSink`,
+//! as a path relative to the sink plus the file's contents, so every new kind of output works with every sink.
+
+#[cfg(feature = "zip")]
+use std::fs::File;
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "zip")]
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+pub trait OutputSink {
+    /// Writes an artifact to `path`, relative to the root of the sink.
+    fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()>;
+
+    /// Where an artifact written to `path` ends up, for reporting.
+    fn location(&self, path: &Path) -> PathBuf;
+
+    /// Flushes anything still pending, must be called once every artifact has been written.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes artifacts as files under a directory, which is created if needed.
+pub struct DirectorySink {
+    root: PathBuf,
+}
+
+impl DirectorySink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let path = self.location(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        self.root.join(path)
+    }
+}
+
+/// Writes every artifact into a single zip archive, handy for sharing a complete run (needs the `zip` feature).
+#[cfg(feature = "zip")]
+pub struct ZipSink {
+    archive: PathBuf,
+    writer: Option<ZipWriter<File>>,
+}
+
+#[cfg(feature = "zip")]
+impl ZipSink {
+    pub fn create(archive: impl Into<PathBuf>) -> Result<Self> {
+        let archive = archive.into();
+        let file =
+            File::create(&archive).with_context(|| format!("creating {}", archive.display()))?;
+        Ok(Self {
+            archive,
+            writer: Some(ZipWriter::new(file)),
+        })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl OutputSink for ZipSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        let Some(writer) = &mut self.writer else {
+            bail!("{} has already been finished", self.archive.display());
+        };
+        // zip entries always use forward slashes
+        let name = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        writer.start_file(name, SimpleFileOptions::default())?;
+        writer.write_all(contents)?;
+        Ok(())
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        self.archive.join(path)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.take() {
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+/// Streams a single artifact to stdout, for piping one export into another tool.
+#[derive(Default)]
+pub struct StdoutSink {
+    written: Option<PathBuf>,
+}
+
+impl OutputSink for StdoutSink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        if let Some(written) = &self.written {
+            bail!(
+                "only one artifact can be written to stdout, but {} was already written before {}",
+                written.display(),
+                path.display()
+            );
+        }
+        self.written = Some(path.to_path_buf());
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(contents)?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    fn location(&self, _: &Path) -> PathBuf {
+        PathBuf::from("-")
+    }
+}
@@ -0,0 +1,612 @@
+//! Module containing the statistics helpers used to summarize sentiment over time.
+//!
+//! Everything in here is pure math over `(timestamp, score)` series, kept apart from the plotter
+//! so it can be used (and checked) without drawing anything.
+
+use std::{fmt::Display, ops::Range, str::FromStr};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rayon::prelude::*;
+
+/// Confidence level of the bootstrap intervals, see `bootstrap_interval`.
+pub const CONFIDENCE: f64 = 0.95;
+
+/// Smoothens the given data (timestamp, score) by aggregating scores within consecutive windows of `window_size`
+/// with `stat`, each window starting at the first point not in the previous one.
+/// data is assumed to be sorted by timestamp in ascending order.
+pub fn smoothen_wrt_time(
+    data: &[(DateTime<Utc>, f64)],
+    window_size: TimeDelta,
+    stat: AggregateStat,
+) -> Vec<(DateTime<Utc>, f64)> {
+    let mut smoothed_scores = Vec::new();
+    let Some((mut window_start, _)) = data.first().copied() else {
+        return smoothed_scores;
+    };
+    let mut window = Vec::new();
+    for (time, score) in data {
+        // close the current window once a point falls outside of it, that point starts the next window
+        if !window.is_empty() && *time - window_start >= window_size {
+            smoothed_scores.extend(stat.apply(&mut window).map(|value| (window_start, value)));
+            window_start = *time;
+            window.clear();
+        }
+        window.push(*score);
+    }
+    smoothed_scores.extend(stat.apply(&mut window).map(|value| (window_start, value)));
+    smoothed_scores
+}
+
+/// A straight line fitted to (timestamp, score) data.
+///
+/// Timestamps are fitted as the milliseconds since `origin` (the earliest timestamp in the data)
+/// so that the fit is computed on small values rather than raw epoch milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearFit {
+    pub origin: DateTime<Utc>,
+    /// the change in score per millisecond
+    pub slope: f64,
+    pub intercept: f64,
+}
+
+impl LinearFit {
+    /// Evaluates the fitted line at the given timestamp.
+    pub fn eval(&self, t: DateTime<Utc>) -> f64 {
+        ((t - self.origin).num_milliseconds() as f64).mul_add(self.slope, self.intercept)
+    }
+
+    /// Returns the two endpoints of the fitted line over the given time range,
+    /// which is all that's needed to draw it.
+    pub fn eval_range(&self, range: Range<DateTime<Utc>>) -> [(DateTime<Utc>, f64); 2] {
+        [
+            (range.start, self.eval(range.start)),
+            (range.end, self.eval(range.end)),
+        ]
+    }
+}
+
+/// Calculates the least squares linear regression of the given data (timestamp, score).
+///
+/// Returns `None` if there are fewer than 2 points, or if every point has the same timestamp,
+/// since no line can be fitted in those cases.
+pub fn least_squares_linear_regression(data: &[(DateTime<Utc>, f64)]) -> Option<LinearFit> {
+    let origin = data.iter().map(|(t, _)| *t).min()?;
+    let (slope, intercept) = fit_line(
+        data.iter()
+            .map(|(t, s)| ((*t - origin).num_milliseconds() as f64, *s)),
+    )?;
+    Some(LinearFit {
+        origin,
+        slope,
+        intercept,
+    })
+}
+
+/// Fits a line `y = slope * x + intercept` to the given points by least squares, returning `(slope, intercept)`.
+///
+/// Uses the two-pass (centered) covariance formula: the naive single-pass sums of `x * x`
+/// cancel catastrophically for large x values (like epoch milliseconds), to the point of flipping the sign of the slope.
+///
+/// Returns `None` if there are fewer than 2 points, or if every point has the same x value,
+/// since no line can be fitted in those cases.
+pub fn fit_line(points: impl Iterator<Item = (f64, f64)> + Clone) -> Option<(f64, f64)> {
+    // first pass: the means
+    let (n, sum_x, sum_y) = points
+        .clone()
+        .fold((0usize, 0.0, 0.0), |(n, sum_x, sum_y), (x, y)| {
+            (n + 1, sum_x + x, sum_y + y)
+        });
+    if n < 2 {
+        return None;
+    }
+    let (mean_x, mean_y) = (sum_x / n as f64, sum_y / n as f64);
+
+    // second pass: the (unnormalized) variance of x and covariance of x and y, about the means
+    let (var_x, cov_xy) = points.fold((0.0, 0.0), |(var_x, cov_xy), (x, y)| {
+        let dx = x - mean_x;
+        (dx.mul_add(dx, var_x), dx.mul_add(y - mean_y, cov_xy))
+    });
+
+    if var_x == 0.0 || !var_x.is_finite() {
+        return None;
+    }
+
+    let slope = cov_xy / var_x;
+    let intercept = slope.mul_add(-mean_x, mean_y);
+    Some((slope, intercept))
+}
+
+/// The `q`th quantile (0 to 1) of already sorted values, linearly interpolating between the closest ranks.
+///
+/// Returns `None` if there are no values.
+pub fn quantile(sorted: &[f64], q: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = q.clamp(0.0, 1.0) * last as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    let fraction = rank - lower as f64;
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+}
+
+/// The mean of the values, `None` if there are none.
+pub fn mean(values: &[f64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// The median of the values (sorting them in place), `None` if there are none.
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    values.sort_unstable_by(f64::total_cmp);
+    quantile(values, 0.5)
+}
+
+/// The (population) standard deviation of the values, `None` if there are none.
+pub fn std_dev(values: &[f64]) -> Option<f64> {
+    let mean = mean(values)?;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / values.len() as f64;
+    Some(variance.sqrt())
+}
+
+/// How many values `trimmed_mean` and `winsorized_mean` treat as extreme at each end:
+/// `percent` of them (rounded down), but never so many that nothing is left in the middle.
+fn extreme_count(len: usize, percent: f64) -> usize {
+    let count = (len as f64 * percent.clamp(0.0, 50.0) / 100.0).floor() as usize;
+    count.min(len.saturating_sub(1) / 2)
+}
+
+/// The mean of the values (sorting them in place) without the lowest and highest `percent` (0 to 50) of them,
+/// `None` if there are none. When that would leave nothing, as many are left out as still leaves the middle one (or two).
+pub fn trimmed_mean(values: &mut [f64], percent: f64) -> Option<f64> {
+    values.sort_unstable_by(f64::total_cmp);
+    let trim = extreme_count(values.len(), percent);
+    mean(&values[trim..values.len() - trim])
+}
+
+/// The mean of the values (sorting them in place) after replacing the lowest and highest `percent` (0 to 50) of them
+/// with the closest value that isn't replaced, `None` if there are none. The same number are replaced as `trimmed_mean` leaves out.
+pub fn winsorized_mean(values: &mut [f64], percent: f64) -> Option<f64> {
+    values.sort_unstable_by(f64::total_cmp);
+    let replace = extreme_count(values.len(), percent);
+    let last = values.len().checked_sub(1)?;
+    let (low, high) = (values[replace], values[last - replace]);
+    let sum = values[replace..=last - replace].iter().sum::<f64>() + (low + high) * replace as f64;
+    Some(sum / values.len() as f64)
+}
+
+/// The weighted median of `(value, weight)` pairs sorted by value, all with a positive weight: the value at which half the weight
+/// is reached, or the midpoint of the values on either side if it's reached exactly between them. `None` if there are none.
+fn weighted_median(sorted: &[(f64, f64)]) -> Option<f64> {
+    let half = sorted.iter().map(|(_, weight)| weight).sum::<f64>() / 2.0;
+    let mut reached: f64 = 0.0;
+    for (i, (value, weight)) in sorted.iter().enumerate() {
+        reached += weight;
+        if reached == half {
+            return Some(
+                sorted
+                    .get(i + 1)
+                    .map_or(*value, |(next, _)| (value + next) / 2.0),
+            );
+        }
+        if reached > half {
+            return Some(*value);
+        }
+    }
+    sorted.last().map(|(value, _)| *value)
+}
+
+/// The weighted mean of `(value, weight)` pairs sorted by value, all with a positive weight, without the lowest and highest
+/// `percent` (0 to 50) of the weight, or with it moved onto the closest value left if `winsorize`. Falls back to the weighted median
+/// if trimming leaves no weight. `None` if there are none.
+fn weighted_trimmed_mean(sorted: &[(f64, f64)], percent: f64, winsorize: bool) -> Option<f64> {
+    let total = sorted.iter().map(|(_, weight)| weight).sum::<f64>();
+    let cut = total * percent.clamp(0.0, 50.0) / 100.0;
+    let (mut kept, mut sum, mut lowest, mut highest) = (0.0, 0.0, None, None);
+    let mut start: f64 = 0.0;
+    for (value, weight) in sorted {
+        // the part of this value's weight between the two cuts
+        let part = ((start + weight).min(total - cut) - start.max(cut)).max(0.0);
+        start += weight;
+        if part > 0.0 {
+            kept += part;
+            sum += value * part;
+            lowest = lowest.or(Some(*value));
+            highest = Some(*value);
+        }
+    }
+    match (lowest, highest) {
+        (Some(lowest), Some(highest)) if winsorize => {
+            Some((sum + (lowest + highest) * cut) / total)
+        }
+        _ if kept > 0.0 => Some(sum / kept),
+        _ => weighted_median(sorted),
+    }
+}
+
+/// The statistic compound scores are aggregated with (daily, over smoothing windows, and in the summaries),
+/// the robust ones keep a few extreme messages from dragging the aggregate around.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AggregateStat {
+    #[default]
+    Mean,
+    Median,
+    /// the mean without the lowest and highest this percent of values, see `trimmed_mean`
+    Trimmed(f64),
+    /// the mean with the lowest and highest this percent of values replaced, see `winsorized_mean`
+    Winsorized(f64),
+}
+
+impl AggregateStat {
+    /// The statistic over the values (which may be reordered), `None` if there are none.
+    pub fn apply(self, values: &mut [f64]) -> Option<f64> {
+        match self {
+            Self::Mean => mean(values),
+            Self::Median => median(values),
+            Self::Trimmed(percent) => trimmed_mean(values, percent),
+            Self::Winsorized(percent) => winsorized_mean(values, percent),
+        }
+    }
+
+    /// The statistic over `(value, weight)` pairs (which may be reordered), where a value with weight 0.5 counts half as much
+    /// as one with weight 1, and one with weight 0 not at all. `None` if no value has a positive weight.
+    ///
+    /// With every weight 0 or 1, it's exactly `apply` over the values with weight 1. Otherwise the median is the value at which
+    /// half the weight is reached, and the trimmed and winsorized means trim (or replace) that percent of the weight at each end.
+    pub fn apply_weighted(self, values: &mut [(f64, f64)]) -> Option<f64> {
+        let mut weighted = values
+            .iter()
+            .copied()
+            .filter(|(_, weight)| *weight > 0.0)
+            .collect::<Vec<_>>();
+        if weighted.iter().all(|(_, weight)| *weight == 1.0) {
+            return self.apply(
+                &mut weighted
+                    .into_iter()
+                    .map(|(value, _)| value)
+                    .collect::<Vec<_>>(),
+            );
+        }
+        weighted.sort_unstable_by(|(a, _), (b, _)| a.total_cmp(b));
+        match self {
+            Self::Mean => weighted_trimmed_mean(&weighted, 0.0, false),
+            Self::Median => weighted_median(&weighted),
+            Self::Trimmed(percent) => weighted_trimmed_mean(&weighted, percent, false),
+            Self::Winsorized(percent) => weighted_trimmed_mean(&weighted, percent, true),
+        }
+    }
+
+    /// A short name for the statistic in identifiers, like line protocol field names.
+    pub fn key(self) -> String {
+        match self {
+            Self::Mean => "mean".to_string(),
+            Self::Median => "median".to_string(),
+            Self::Trimmed(percent) => format!("trimmed{percent}"),
+            Self::Winsorized(percent) => format!("winsorized{percent}"),
+        }
+    }
+
+    /// The name of the statistic for captions and reports, e.g. "5% trimmed mean".
+    pub fn describe(self) -> String {
+        match self {
+            Self::Mean => "mean".to_string(),
+            Self::Median => "median".to_string(),
+            Self::Trimmed(percent) => format!("{percent}% trimmed mean"),
+            Self::Winsorized(percent) => format!("{percent}% winsorized mean"),
+        }
+    }
+}
+
+impl FromStr for AggregateStat {
+    type Err = anyhow::Error;
+
+    /// Parses `mean`, `median`, `trimmed:PERCENT`, or `winsorized:PERCENT`, with a percent from 0 up to (not including) 50.
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let (name, given) = match s.split_once(':') {
+            Some((name, percent)) => (name, Some(percent)),
+            None => (s, None),
+        };
+        let percent = || -> anyhow::Result<f64> {
+            let percent = given
+                .with_context(|| format!("{name} needs a percent, like {name}:5"))?
+                .parse::<f64>()
+                .with_context(|| format!("invalid percent in \"{s}\""))?;
+            if !(0.0..50.0).contains(&percent) {
+                bail!("the percent in \"{s}\" should be at least 0 and less than 50");
+            }
+            Ok(percent)
+        };
+        match name {
+            "mean" | "median" if given.is_some() => bail!("{name} takes no percent"),
+            "mean" => Ok(Self::Mean),
+            "median" => Ok(Self::Median),
+            "trimmed" => Ok(Self::Trimmed(percent()?)),
+            "winsorized" => Ok(Self::Winsorized(percent()?)),
+            _ => {
+                bail!("expected mean, median, trimmed:PERCENT, or winsorized:PERCENT, got \"{s}\"")
+            }
+        }
+    }
+}
+
+impl Display for AggregateStat {
+    /// The form `from_str` parses.
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Mean | Self::Median => write!(f, "{}", self.key()),
+            Self::Trimmed(percent) => write!(f, "trimmed:{percent}"),
+            Self::Winsorized(percent) => write!(f, "winsorized:{percent}"),
+        }
+    }
+}
+
+/// A statistic, and optionally a confidence interval `(low, high)` around it.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct Estimate {
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<(f64, f64)>,
+}
+
+/// A percentile bootstrap confidence interval (at the `CONFIDENCE` level) of `statistic` over the values:
+/// the statistic is computed over `resamples` resamples of the values (drawn with replacement, in parallel),
+/// and the interval spans the middle of those.
+///
+/// Each resample gets its own rng, seeded from `rng` up front, so the interval doesn't depend on how the resamples
+/// are spread over threads. Returns `None` if there are no values or no resamples.
+pub fn bootstrap_interval<T: Copy + Send + Sync>(
+    values: &[T],
+    statistic: impl Fn(&mut [T]) -> Option<f64> + Sync,
+    resamples: usize,
+    rng: &mut impl Rng,
+) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let seeds = (0..resamples).map(|_| rng.gen()).collect::<Vec<u64>>();
+    let mut statistics = seeds
+        .into_par_iter()
+        .map_init(
+            || Vec::with_capacity(values.len()),
+            |resample, seed| {
+                let mut rng = SmallRng::seed_from_u64(seed);
+                resample.clear();
+                resample.extend((0..values.len()).map(|_| values[rng.gen_range(0..values.len())]));
+                statistic(resample)
+            },
+        )
+        .flatten()
+        .collect::<Vec<_>>();
+    statistics.sort_unstable_by(f64::total_cmp);
+
+    let tail = (1.0 - CONFIDENCE) / 2.0;
+    Some((
+        quantile(&statistics, tail)?,
+        quantile(&statistics, 1.0 - tail)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::*;
+
+    /// `count` points every `step`, starting at `start_ms`, on the line `intercept + slope * ms since start_ms`.
+    fn line(
+        start_ms: i64,
+        step: TimeDelta,
+        count: i32,
+        slope: f64,
+        intercept: f64,
+    ) -> Vec<(DateTime<Utc>, f64)> {
+        let start = DateTime::from_timestamp_millis(start_ms).unwrap();
+        (0..count)
+            .map(|i| {
+                let t = start + step * i;
+                (t, intercept + slope * (t - start).num_milliseconds() as f64)
+            })
+            .collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64, relative: f64) {
+        assert!(
+            (actual - expected).abs() <= relative * expected.abs(),
+            "{actual} isn't within {relative} of {expected}"
+        );
+    }
+
+    #[test]
+    fn regression_recovers_a_perfect_line() {
+        let data = line(0, TimeDelta::minutes(1), 100, 0.002, -0.5);
+        let fit = least_squares_linear_regression(&data).unwrap();
+        assert_eq!(fit.origin, data[0].0);
+        assert_close(fit.slope, 0.002, 1e-12);
+        assert_close(fit.intercept, -0.5, 1e-12);
+        for (t, score) in &data {
+            assert_close(fit.eval(*t), *score, 1e-9);
+        }
+    }
+
+    #[test]
+    fn regression_through_two_points() {
+        let data = line(1_000, TimeDelta::hours(2), 2, -1e-7, 0.25);
+        let fit = least_squares_linear_regression(&data).unwrap();
+        assert_close(fit.slope, -1e-7, 1e-12);
+        let [start, end] = fit.eval_range(data[0].0..data[1].0);
+        assert_eq!(start, data[0]);
+        assert_eq!(end.0, data[1].0);
+        assert_close(end.1, data[1].1, 1e-12);
+    }
+
+    #[test]
+    fn regression_of_degenerate_data_is_none() {
+        assert_eq!(least_squares_linear_regression(&[]), None);
+        let one = line(1_700_000_000_000, TimeDelta::zero(), 1, 0.0, 0.5);
+        assert_eq!(least_squares_linear_regression(&one), None);
+        // every point at the same time: no line through them
+        let constant_x = [0.1, -0.4, 0.9].map(|score| (one[0].0, score));
+        assert_eq!(least_squares_linear_regression(&constant_x), None);
+        assert_eq!(fit_line([(3.0, 1.0), (3.0, 2.0)].into_iter()), None);
+    }
+
+    #[test]
+    fn regression_over_epoch_millisecond_timestamps() {
+        // three years of daily points from late 2023, gaining 0.3 a year
+        let per_ms = 0.3 / (365.0 * 24.0 * 3_600_000.0);
+        let data = line(1_700_000_000_000, TimeDelta::days(1), 3 * 365, per_ms, -0.2);
+        let fit = least_squares_linear_regression(&data).unwrap();
+        assert_close(fit.slope, per_ms, 1e-9);
+        assert_close(fit.intercept, -0.2, 1e-9);
+        let last = data.last().unwrap();
+        assert_close(fit.eval(last.0), last.1, 1e-9);
+    }
+
+    #[test]
+    fn fit_line_keeps_the_slope_of_raw_epoch_milliseconds() {
+        // five years of daily points from late 2023, gaining 0.1 a year from -0.2, with x in raw epoch milliseconds
+        let (start, day) = (1.7e12, 86_400_000.0);
+        let slope = 0.1 / (365.0 * day);
+        let intercept = -0.2 - slope * start;
+        let points = (0..5 * 365)
+            .map(|i| {
+                let x = start + f64::from(i) * day;
+                (x, slope * x + intercept)
+            })
+            .collect::<Vec<_>>();
+
+        let (fitted_slope, fitted_intercept) = fit_line(points.iter().copied()).unwrap();
+        assert_close(fitted_slope, slope, 1e-9);
+        assert_close(fitted_intercept, intercept, 1e-9);
+    }
+
+    #[test]
+    fn smoothing_counts_the_point_closing_a_window_in_the_next() {
+        let start = DateTime::from_timestamp_millis(0).unwrap();
+        let at = |hours, score| (start + TimeDelta::hours(hours), score);
+        let data = [at(0, 0.0), at(1, 1.0), at(3, 0.5), at(4, -0.5)];
+        assert_eq!(
+            smoothen_wrt_time(&data, TimeDelta::hours(2), AggregateStat::Mean),
+            [at(0, 0.5), at(3, 0.0)]
+        );
+        assert!(smoothen_wrt_time(&[], TimeDelta::hours(2), AggregateStat::Mean).is_empty());
+    }
+
+    #[test]
+    fn medians_of_a_few_values() {
+        assert_eq!(median(&mut []), None);
+        assert_eq!(median(&mut [3.0]), Some(3.0));
+        assert_eq!(median(&mut [5.0, 1.0]), Some(3.0));
+        assert_eq!(median(&mut [5.0, 1.0, 3.0]), Some(3.0));
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn trimmed_means_of_a_few_values() {
+        assert_eq!(trimmed_mean(&mut [], 20.0), None);
+        // one value off each end
+        assert_eq!(
+            trimmed_mean(&mut [100.0, 1.0, 4.0, 2.0, 3.0], 20.0),
+            Some(3.0)
+        );
+        // 10% of 5 values is less than one, so none
+        assert_eq!(
+            trimmed_mean(&mut [100.0, 1.0, 4.0, 2.0, 3.0], 10.0),
+            Some(22.0)
+        );
+        assert_eq!(trimmed_mean(&mut [1.0, 2.0, 3.0, 10.0], 25.0), Some(2.5));
+    }
+
+    #[test]
+    fn trimming_everything_leaves_the_middle() {
+        assert_eq!(trimmed_mean(&mut [7.0], 49.0), Some(7.0));
+        assert_eq!(trimmed_mean(&mut [3.0, 1.0], 49.0), Some(2.0));
+        assert_eq!(trimmed_mean(&mut [9.0, 1.0, 2.0], 49.0), Some(2.0));
+        let mut values = (1..=10).map(f64::from).collect::<Vec<_>>();
+        assert_eq!(trimmed_mean(&mut values, 49.0), Some(5.5));
+        // out of range percents are clamped
+        assert_eq!(trimmed_mean(&mut values, 80.0), Some(5.5));
+        assert_eq!(trimmed_mean(&mut values, -5.0), Some(5.5));
+    }
+
+    #[test]
+    fn winsorized_means_of_a_few_values() {
+        assert_eq!(winsorized_mean(&mut [], 20.0), None);
+        assert_eq!(winsorized_mean(&mut [7.0], 49.0), Some(7.0));
+        // 1 and 100 become 2 and 4: (2 + 2 + 3 + 4 + 4) / 5
+        assert_eq!(
+            winsorized_mean(&mut [100.0, 1.0, 4.0, 2.0, 3.0], 20.0),
+            Some(3.0)
+        );
+        // all but the middle two would be replaced, only one at each end is: (2 + 2 + 3 + 3) / 4
+        assert_eq!(winsorized_mean(&mut [4.0, 1.0, 3.0, 2.0], 49.0), Some(2.5));
+        assert_eq!(winsorized_mean(&mut [3.0, 1.0], 49.0), Some(2.0));
+    }
+
+    #[test]
+    fn weighted_aggregates_of_a_few_values() {
+        let apply =
+            |stat: AggregateStat, values: &[(f64, f64)]| stat.apply_weighted(&mut values.to_vec());
+        assert_eq!(apply(AggregateStat::Median, &[]), None);
+        assert_eq!(apply(AggregateStat::Mean, &[(1.0, 0.0)]), None);
+        // half the weight is reached exactly between 2 and 3
+        assert_eq!(
+            apply(AggregateStat::Median, &[(3.0, 1.0), (1.0, 0.5), (2.0, 0.5)]),
+            Some(2.5)
+        );
+        assert_eq!(
+            apply(AggregateStat::Mean, &[(1.0, 0.5), (4.0, 1.0)]),
+            Some(3.0)
+        );
+        // a quarter of the weight (0.75) is cut off each end: 0 entirely, and a quarter of 1
+        let values = [(0.0, 0.5), (1.0, 1.0), (2.0, 1.0), (10.0, 0.5)];
+        assert_eq!(apply(AggregateStat::Trimmed(25.0), &values), Some(1.5));
+        // moved onto 1 and 2 instead: (0.75 * 1 + 0.75 * 2 + 0.75 * 1 + 0.75 * 2) / 3
+        assert_eq!(apply(AggregateStat::Winsorized(25.0), &values), Some(1.5));
+        // trimming all the weight leaves the weighted median
+        let values = [(1.0, 0.5), (2.0, 1.0), (3.0, 0.5)];
+        assert_eq!(apply(AggregateStat::Trimmed(50.0), &values), Some(2.0));
+    }
+
+    #[test]
+    fn bootstrap_intervals_cover_the_true_mean() {
+        // samples of 50 values uniform around a known mean, each with its interval of the mean
+        let (trials, true_mean) = (400, 0.3);
+        let mut rng = SmallRng::seed_from_u64(229);
+        let covered = (0..trials)
+            .filter(|_| {
+                let sample = (0..50)
+                    .map(|_| true_mean + rng.gen_range(-1.0..1.0))
+                    .collect::<Vec<f64>>();
+                let (low, high) =
+                    bootstrap_interval(&sample, |values| mean(values), 1000, &mut rng).unwrap();
+                assert!(low <= high);
+                low <= true_mean && true_mean <= high
+            })
+            .count();
+        // percentile intervals run a little narrow on samples this small, so roughly, not exactly, 95%
+        let coverage = covered as f64 / f64::from(trials);
+        assert!(
+            (0.9..=0.99).contains(&coverage),
+            "the intervals covered the mean {coverage} of the time"
+        );
+    }
+
+    #[test]
+    fn bootstrap_intervals_need_values_and_resamples() {
+        let mut rng = SmallRng::seed_from_u64(0);
+        let statistic = |values: &mut [f64]| mean(values);
+        assert_eq!(bootstrap_interval(&[], statistic, 1000, &mut rng), None);
+        assert_eq!(
+            bootstrap_interval(&[0.5, 1.0], statistic, 0, &mut rng),
+            None
+        );
+        // a single value is its own interval
+        assert_eq!(
+            bootstrap_interval(&[0.5], statistic, 100, &mut rng),
+            Some((0.5, 0.5))
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Module responsible for the headline numbers of each participant, optionally with bootstrap confidence intervals,
+//! so that a mean over 200 messages isn't presented with the same confidence as one over 20,000.
+//!
+//! `ConversationStats` are plainer numbers of each participant, without weighting or intervals, tabulated as they are.
+
+use std::{fmt::Write, sync::OnceLock};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    analyzer::{AnalyzedConversation, Sentiment},
+    format::format_count,
+    parser::ParticipantId,
+    rng::Seed,
+    stats::{self, AggregateStat, Estimate},
+};
+
+/// How to compute the confidence intervals of the summaries, see `stats::bootstrap_interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bootstrap {
+    pub resamples: usize,
+    pub seed: Seed,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParticipantSummary {
+    #[serde(skip)]
+    pub participant: ParticipantId,
+    pub messages: usize,
+    /// how many of their messages were flagged as likely quoted, see `quotes`
+    pub quoted: usize,
+    /// their compound scores aggregated with `AnalyzedConversation::aggregate` (the mean by default)
+    pub compound: Option<Estimate>,
+    /// the share (0 to 1) of their messages classified as positive
+    /// (both this and `compound` count quoted messages as `AnalyzedConversation::quotes` says)
+    pub positive_share: Option<Estimate>,
+    /// how long they took to reply, over the reply pairs they responded in
+    pub median_reply_minutes: Option<Estimate>,
+}
+
+/// Descriptive statistics of each participant's messages, see `AnalyzedConversation::stats`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConversationStats {
+    /// ordered by name with the exporter first (see `AnalyzedConversation::participants_oriented`)
+    pub participants: Vec<ParticipantStats>,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ParticipantStats {
+    #[serde(skip)]
+    pub participant: ParticipantId,
+    pub name: String,
+    /// their messages, scored or not (too short, or in another language), as `ParticipantSummary::messages` counts them
+    pub messages: usize,
+    /// how many of their messages were scored
+    pub scored: usize,
+    /// the mean, median, and (population) standard deviation of their compound scores, `None` without scored messages
+    pub mean_compound: Option<f64>,
+    pub median_compound: Option<f64>,
+    pub std_dev_compound: Option<f64>,
+    /// the shares (0 to 1) of their scored messages classified as positive, neutral, and negative by the thresholds
+    /// of the analysis, `None` without scored messages
+    pub positive_share: Option<f64>,
+    pub neutral_share: Option<f64>,
+    pub negative_share: Option<f64>,
+    /// when they sent their first and last messages, `None` without messages
+    pub first_message: Option<DateTime<Utc>>,
+    pub last_message: Option<DateTime<Utc>>,
+}
+
+impl ConversationStats {
+    /// The statistics as a table, a row per participant, "n/a" where there's nothing to compute them over.
+    pub fn table(&self) -> String {
+        let percent = |share: Option<f64>| match share {
+            Some(share) => format!("{:.1}%", share * 100.0),
+            None => "n/a".to_string(),
+        };
+        let compound = |compound: Option<f64>| match compound {
+            Some(compound) => format!("{compound:.3}"),
+            None => "n/a".to_string(),
+        };
+        let date = |time: Option<DateTime<Utc>>| match time {
+            Some(time) => time.format("%Y-%m-%d").to_string(),
+            None => "n/a".to_string(),
+        };
+        let mut rows = vec![[
+            "participant",
+            "messages",
+            "scored",
+            "mean",
+            "median",
+            "std dev",
+            "positive",
+            "neutral",
+            "negative",
+            "first",
+            "last",
+        ]
+        .map(str::to_string)];
+        rows.extend(self.participants.iter().map(|stats| {
+            [
+                stats.name.clone(),
+                format_count(stats.messages),
+                format_count(stats.scored),
+                compound(stats.mean_compound),
+                compound(stats.median_compound),
+                compound(stats.std_dev_compound),
+                percent(stats.positive_share),
+                percent(stats.neutral_share),
+                percent(stats.negative_share),
+                date(stats.first_message),
+                date(stats.last_message),
+            ]
+        }));
+        let widths: [usize; 11] = std::array::from_fn(|column| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .max()
+                .unwrap_or_default()
+        });
+
+        let mut table = String::new();
+        // writing to a string can't fail
+        for row in &rows {
+            let mut line = format!("{:<w$}", row[0], w = widths[0]);
+            for (value, width) in row.iter().zip(widths).skip(1) {
+                let _ = write!(line, "  {value:>width$}");
+            }
+            let _ = writeln!(table, "{}", line.trim_end());
+        }
+        table
+    }
+}
+
+impl AnalyzedConversation {
+    /// Descriptive statistics of each participant: how many messages they sent and how many were scored, the mean,
+    /// median, and standard deviation of their compound scores, how their scored messages are classified, and when
+    /// they sent their first and last messages. Unlike the summaries, quoted messages count like any other.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{analyzer::AnalyzeOptions, parser::ParsedConversation};
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700100000000, "content": "ok"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "this is terrible, I hate it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "the train leaves at noon"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "what a great day, I love it"}
+    ///   ]
+    /// }"#;
+    /// let options = AnalyzeOptions { min_tokens: 2, ..AnalyzeOptions::default() };
+    /// let analysis = ParsedConversation::from_bytes(export)?.analyze_with(&options);
+    /// let stats = analysis.stats();
+    /// let (ana, ben) = (&stats.participants[0], &stats.participants[1]);
+    ///
+    /// assert_eq!((ana.name.as_str(), ana.messages, ana.scored), ("Ana", 3, 3));
+    /// assert_eq!(ana.median_compound, Some(0.0));
+    /// assert!(ana.std_dev_compound.unwrap() > 0.5);
+    /// let third = 1.0 / 3.0;
+    /// assert_eq!((ana.positive_share, ana.neutral_share, ana.negative_share), (Some(third), Some(third), Some(third)));
+    /// assert_eq!(ana.first_message.unwrap().timestamp_millis(), 1700000000000);
+    /// assert_eq!(ana.last_message.unwrap().timestamp_millis(), 1700000120000);
+    ///
+    /// // too short to be scored, but still counted
+    /// assert_eq!((ben.messages, ben.scored), (1, 0));
+    /// assert_eq!((ben.mean_compound, ben.std_dev_compound, ben.positive_share), (None, None, None));
+    /// assert_eq!(ben.first_message, ben.last_message);
+    ///
+    /// let table = stats.table();
+    /// assert!(table.starts_with("participant  messages  scored"));
+    /// assert!(table.lines().nth(2).unwrap().contains("n/a"));
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn stats(&self) -> ConversationStats {
+        let participants = self
+            .participants_oriented()
+            .into_iter()
+            .map(|participant| {
+                let series = self
+                    .analysis
+                    .get(&participant)
+                    .map_or(&[][..], Vec::as_slice);
+                let mut compounds = series
+                    .iter()
+                    .map(|(_, score)| score.compound)
+                    .collect::<Vec<_>>();
+                let share = |sentiment: Sentiment| {
+                    let classified = series
+                        .iter()
+                        .filter(|(_, score)| score.classify(&self.thresholds) == sentiment)
+                        .count();
+                    (!series.is_empty()).then(|| classified as f64 / series.len() as f64)
+                };
+                let times = || {
+                    self.counted_messages(participant)
+                        .map(|message| message.timestamp)
+                };
+
+                ParticipantStats {
+                    participant,
+                    name: self.names.name(participant).to_string(),
+                    messages: self
+                        .counted_messages(participant)
+                        .map(|message| self.repeats.volume(message))
+                        .sum(),
+                    scored: series.len(),
+                    mean_compound: stats::mean(&compounds),
+                    std_dev_compound: stats::std_dev(&compounds),
+                    median_compound: stats::median(&mut compounds),
+                    positive_share: share(Sentiment::Positive),
+                    neutral_share: share(Sentiment::Neutral),
+                    negative_share: share(Sentiment::Negative),
+                    first_message: times().min(),
+                    last_message: times().max(),
+                }
+            })
+            .collect();
+        ConversationStats { participants }
+    }
+
+    /// Sets (or unsets) how to compute the confidence intervals of the summaries, without it they have none.
+    pub fn set_bootstrap(&mut self, bootstrap: Option<Bootstrap>) {
+        self.bootstrap = bootstrap;
+        self.summaries = OnceLock::new();
+    }
+
+    pub fn bootstrap(&self) -> Option<Bootstrap> {
+        self.bootstrap
+    }
+
+    /// The headline numbers of each participant, ordered by name with the exporter first (see `participants_oriented`),
+    /// with confidence intervals if bootstrapping (see `AnalyzedConversation::set_bootstrap`).
+    ///
+    /// Computed once, on first use.
+    pub fn summaries(&self) -> &[ParticipantSummary] {
+        self.summaries.get_or_init(|| self.compute_summaries())
+    }
+
+    fn compute_summaries(&self) -> Vec<ParticipantSummary> {
+        let pairs = self.reply_pairs();
+        self.participants_oriented()
+            .into_iter()
+            .map(|participant| {
+                let name = self.names.name(participant);
+                let series = &self.analysis[&participant];
+                let compounds = series
+                    .iter()
+                    .map(|(message, score)| (score.compound, self.quotes.weight(message)))
+                    .collect::<Vec<_>>();
+                let positives = series
+                    .iter()
+                    .map(|(message, score)| {
+                        (
+                            f64::from(u8::from(
+                                score.classify(&self.thresholds) == Sentiment::Positive,
+                            )),
+                            self.quotes.weight(message),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let reply_minutes = pairs
+                    .iter()
+                    .filter(|pair| pair.responder == participant)
+                    .map(|pair| {
+                        (pair.response_timestamp_ms - pair.stimulus_timestamp_ms) as f64 / 60_000.0
+                    })
+                    .collect::<Vec<_>>();
+
+                ParticipantSummary {
+                    participant,
+                    messages: self
+                        .counted_messages(participant)
+                        .map(|message| self.repeats.volume(message))
+                        .sum(),
+                    quoted: series
+                        .iter()
+                        .filter(|(message, _)| message.quote.is_some())
+                        .count(),
+                    compound: self.estimate(
+                        &compounds,
+                        |values| self.aggregate.apply_weighted(values),
+                        &format!("{} compound {name}", self.aggregate.key()),
+                    ),
+                    positive_share: self.estimate(
+                        &positives,
+                        |values| AggregateStat::Mean.apply_weighted(values),
+                        &format!("positive share {name}"),
+                    ),
+                    median_reply_minutes: self.estimate(
+                        &reply_minutes,
+                        stats::median,
+                        &format!("median reply time {name}"),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    /// The statistic over the values, with a confidence interval if bootstrapping,
+    /// resampled with an rng of its own (named `stream`) so that each estimate is reproducible on its own.
+    fn estimate<T: Copy + Send + Sync>(
+        &self,
+        values: &[T],
+        statistic: impl Fn(&mut [T]) -> Option<f64> + Sync,
+        stream: &str,
+    ) -> Option<Estimate> {
+        let value = statistic(&mut values.to_vec())?;
+        let interval = self.bootstrap.and_then(|bootstrap| {
+            stats::bootstrap_interval(
+                values,
+                statistic,
+                bootstrap.resamples,
+                &mut bootstrap.seed.rng(&format!("bootstrap {stream}")),
+            )
+        });
+        Some(Estimate { value, interval })
+    }
+}
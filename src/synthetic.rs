@@ -0,0 +1,253 @@
+//! Module responsible for generating synthetic conversations, in the same format as instagram's exports.
+//!
+//! Used to produce fixtures for benchmarks and for trying the tool out without a real export,
+//! generation is driven by a seeded rng so the same config always produces the same conversation.
+
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rand::{rngs::SmallRng, Rng};
+
+use crate::{
+    diagnostics::Diagnostics,
+    parser::{ExportBoundaries, Message, ParsedConversation, ParticipantNames},
+    rng::Seed,
+};
+
+const NAMES: [&str; 16] = [
+    "Alice", "Bob", "Chloé", "Dmitri", "Eve", "Farah", "Gus", "Hana", "Iñigo", "Jules", "Kai",
+    "Lena", "Mateo", "Noor", "Olu", "Priya",
+];
+const POSITIVE_WORDS: [&str; 10] = [
+    "love", "great", "happy", "awesome", "nice", "good", "amazing", "fun", "thanks", "lol",
+];
+const NEGATIVE_WORDS: [&str; 10] = [
+    "hate", "bad", "sad", "awful", "annoying", "terrible", "ugh", "worst", "tired", "angry",
+];
+const FILLER_WORDS: [&str; 20] = [
+    "i", "you", "the", "that", "was", "is", "really", "so", "we", "should", "go", "today",
+    "tomorrow", "what", "about", "it", "this", "movie", "café", "food",
+];
+const EMOJI: [&str; 6] = ["😂", "❤️", "😭", "👍", "🙃", "🔥"];
+
+/// Parameters of a synthetic conversation.
+#[derive(Debug, Clone, Copy)]
+pub struct SyntheticConfig {
+    pub participants: usize,
+    pub messages: usize,
+    /// how many messages go in each `message_N.json` file (instagram uses 10,000)
+    pub messages_per_file: usize,
+    /// timestamp of the first message, in milliseconds since the epoch
+    pub start_ms: usize,
+    /// mean gap between consecutive messages, in milliseconds
+    pub mean_gap_ms: usize,
+    pub seed: Seed,
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            participants: 2,
+            messages: 10_000,
+            messages_per_file: 10_000,
+            // 2021-01-01T00:00:00Z
+            start_ms: 1_609_459_200_000,
+            // 20 minutes
+            mean_gap_ms: 20 * 60 * 1000,
+            seed: Seed::default(),
+        }
+    }
+}
+
+/// Standard datasets, so benchmark results are comparable across machines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum BenchFixture {
+    /// 2 participants, 1,000 messages
+    Small,
+    /// 2 participants, 50,000 messages
+    Medium,
+    /// 2 participants, 400,000 messages
+    Large,
+}
+
+impl BenchFixture {
+    pub fn config(self) -> SyntheticConfig {
+        let messages = match self {
+            Self::Small => 1_000,
+            Self::Medium => 50_000,
+            Self::Large => 400_000,
+        };
+        SyntheticConfig {
+            messages,
+            seed: Seed(0x1d_6d5),
+            ..SyntheticConfig::default()
+        }
+    }
+}
+
+/// Generates a conversation in memory, messages are sorted by timestamp.
+pub fn generate_conversation(config: &SyntheticConfig) -> ParsedConversation {
+    let mut rng = config.seed.rng("synthetic");
+    let mut names = ParticipantNames::default();
+    let participants = (0..config.participants.max(1))
+        .map(|i| match NAMES.get(i) {
+            Some(name) => names.intern(name),
+            None => names.intern(&format!("Participant {i}")),
+        })
+        .collect::<Vec<_>>();
+
+    let mut timestamp_ms = config.start_ms;
+    let messages = (0..config.messages)
+        .map(|_| {
+            timestamp_ms += rng.gen_range(0..=config.mean_gap_ms * 2);
+            Message {
+                sender: participants[rng.gen_range(0..participants.len())],
+                timestamp: DateTime::from_timestamp_millis(timestamp_ms as i64)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC),
+                content: Some(generate_content(&mut rng)),
+                quote: None,
+                language: None,
+                attachment: None,
+                attachments: Vec::new(),
+                repeats: 1,
+                system: false,
+                reactions: Vec::new(),
+                share: None,
+                call_duration_secs: None,
+                unsent: false,
+                reply_to: None,
+            }
+        })
+        .collect();
+
+    ParsedConversation {
+        participants: participants.into_iter().collect::<HashSet<_>>(),
+        messages,
+        names,
+        diagnostics: Diagnostics::default(),
+        export_boundaries: ExportBoundaries::default(),
+        me: None,
+        attachment_only: Vec::new(),
+        calls: Vec::new(),
+        system: Vec::new(),
+        title: None,
+        thread_path: None,
+    }
+}
+
+/// Generates the text of a single message, roughly 1 in 20 are empty (like a photo would be).
+fn generate_content(rng: &mut SmallRng) -> String {
+    if rng.gen_ratio(1, 20) {
+        return String::new();
+    }
+
+    let len = rng.gen_range(1..=15);
+    let mut words = Vec::with_capacity(len);
+    for _ in 0..len {
+        let word = match rng.gen_range(0..100) {
+            0..=9 => POSITIVE_WORDS[rng.gen_range(0..POSITIVE_WORDS.len())],
+            10..=17 => NEGATIVE_WORDS[rng.gen_range(0..NEGATIVE_WORDS.len())],
+            18..=21 => EMOJI[rng.gen_range(0..EMOJI.len())],
+            _ => FILLER_WORDS[rng.gen_range(0..FILLER_WORDS.len())],
+        };
+        words.push(word);
+    }
+    words.join(" ")
+}
+
+/// Serializes (part of) a conversation the way instagram does: newest message first,
+/// with every non-ascii byte written as a `\u00XX` escape.
+pub fn encode_export(conversation: &ParsedConversation, messages: &[Message]) -> Result<String> {
+    let mut participants = conversation
+        .participants
+        .iter()
+        .map(|participant| conversation.names.name(*participant))
+        .collect::<Vec<_>>();
+    participants.sort_unstable();
+
+    let mut json = serde_json::json!({
+        "participants": participants
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect::<Vec<_>>(),
+        "messages": messages
+            .iter()
+            .rev()
+            .map(|message| {
+                let mut value = serde_json::json!({
+                    "sender_name": conversation.names.name(message.sender),
+                    "timestamp_ms": message.timestamp_ms(),
+                });
+                if !message.text().is_empty() {
+                    value["content"] = message.text().into();
+                }
+                if message.unsent {
+                    value["is_unsent"] = true.into();
+                }
+                if let Some(duration) = message.call_duration_secs {
+                    value["call_duration"] = duration.into();
+                }
+                if let Some(share) = &message.share {
+                    let mut shared = serde_json::json!({});
+                    if let Some(link) = &share.link {
+                        shared["link"] = link.as_str().into();
+                    }
+                    if let Some(text) = &share.text {
+                        shared["share_text"] = text.as_str().into();
+                    }
+                    value["share"] = shared;
+                }
+                if !message.reactions.is_empty() {
+                    value["reactions"] = message
+                        .reactions
+                        .iter()
+                        .map(|reaction| {
+                            serde_json::json!({
+                                "reaction": reaction.reaction,
+                                "actor": conversation.names.name(reaction.actor),
+                            })
+                        })
+                        .collect();
+                }
+                value
+            })
+            .collect::<Vec<_>>(),
+    });
+    if let Some(title) = &conversation.title {
+        json["title"] = title.as_str().into();
+    }
+    if let Some(thread_path) = &conversation.thread_path {
+        json["thread_path"] = thread_path.as_str().into();
+    }
+    let json = serde_json::to_string_pretty(&json)?;
+
+    // non-ascii bytes can only occur inside of strings, so escaping them byte by byte is safe
+    let mut encoded = String::with_capacity(json.len());
+    for byte in json.bytes() {
+        if byte.is_ascii() {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("\\u00{byte:02x}"));
+        }
+    }
+    Ok(encoded)
+}
+
+/// Writes a generated conversation into `output_dir` as `message_N.json` files,
+/// `message_1.json` holding the newest messages like in a real export.
+pub fn write_export(config: &SyntheticConfig, output_dir: &PathBuf) -> Result<Vec<PathBuf>> {
+    let conversation = generate_conversation(config);
+
+    std::fs::create_dir_all(output_dir)?;
+    conversation
+        .messages
+        .rchunks(config.messages_per_file.max(1))
+        .enumerate()
+        .map(|(i, chunk)| {
+            let path = output_dir.join(format!("message_{}.json", i + 1));
+            std::fs::write(&path, encode_export(&conversation, chunk)?)?;
+            Ok(path)
+        })
+        .collect()
+}
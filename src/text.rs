@@ -0,0 +1,141 @@
+//! Module containing the text machinery shared by every feature that looks at what was written,
+//! rather than how it was scored: cleaning up the text of messages, and splitting them into sentences
+//! (words are split by the `tokenizer`).
+//!
+//! Sentence splitting is deliberately simple, chat messages rarely follow the rules a proper sentence splitter expects anyway.
+
+use unicode_normalization::UnicodeNormalization;
+
+use crate::tokenizer::is_emoji;
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const ZERO_WIDTH_NON_JOINER: char = '\u{200C}';
+/// Asks for the text (U+FE0E) or emoji (U+FE0F) presentation of the character before it.
+const VARIATION_SELECTORS: [char; 2] = ['\u{FE0E}', '\u{FE0F}'];
+/// Makes the character before it, and its variation selector, a keycap (1️⃣).
+const COMBINING_KEYCAP: char = '\u{20E3}';
+/// The black flag that starts the flags of subdivisions (🏴󠁧󠁢󠁳󠁣󠁴󠁿), whose tag characters follow it.
+const BLACK_FLAG: char = '\u{1F3F4}';
+
+/// The text in its composed form (NFC), so text typed with combining accents is the same as text typed without,
+/// without the invisible characters messages pick up from keyboards and copy-pasting (zero-width spaces,
+/// direction marks, soft hyphens, byte order marks, and control characters other than line breaks and tabs),
+/// and with non-breaking spaces turned into plain ones.
+///
+/// The invisible characters that are part of an emoji (the joiners of 👨‍👩‍👧, the variation selector of ❤️, the tags
+/// of a subdivision's flag) are kept, and so are the joiners between letters, which some scripts need.
+///
+/// ```
+/// use igdm_sentiment_analysis::text::clean;
+///
+/// assert_eq!(clean("see\u{200B} you\u{00A0}soon\u{FEFF}"), "see you soon");
+/// assert_eq!(clean("cafe\u{0301}"), "café");
+/// assert_eq!(clean("\u{2066}hi\u{2069}\u{00AD}\r\nthere\u{0007}"), "hi\nthere");
+/// // a variation selector after anything but an emoji selects nothing
+/// assert_eq!(clean("ok\u{FE0F}"), "ok");
+/// for emoji in ["❤\u{FE0F}", "👨\u{200D}👩\u{200D}👧", "👍🏽", "1\u{FE0F}\u{20E3}", "🏳\u{FE0F}\u{200D}🌈"] {
+///     assert_eq!(clean(&format!("love it {emoji}")), format!("love it {emoji}"));
+/// }
+/// assert_eq!(clean("a\u{200D} b"), "a b");
+/// assert_eq!(clean("می\u{200C}خواهم"), "می\u{200C}خواهم");
+/// ```
+pub fn clean(text: &str) -> String {
+    let chars = text.nfc().collect::<Vec<_>>();
+    let mut cleaned = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let previous = cleaned.chars().next_back();
+        let next = chars.get(i + 1).copied();
+        let keep = match c {
+            '\n' | '\t' => true,
+            '\u{00A0}' | '\u{2007}' | '\u{202F}' => {
+                cleaned.push(' ');
+                continue;
+            }
+            ZERO_WIDTH_JOINER => {
+                let emoji = |c: char| is_emoji(c) || VARIATION_SELECTORS.contains(&c);
+                (previous.is_some_and(emoji) && next.is_some_and(is_emoji))
+                    || (previous.is_some_and(char::is_alphabetic)
+                        && next.is_some_and(char::is_alphabetic))
+            }
+            ZERO_WIDTH_NON_JOINER => {
+                previous.is_some_and(char::is_alphabetic) && next.is_some_and(char::is_alphabetic)
+            }
+            c if VARIATION_SELECTORS.contains(&c) => {
+                previous.is_some_and(is_emoji) || next == Some(COMBINING_KEYCAP)
+            }
+            // the tags of a flag, after its black flag or the tags before them
+            '\u{E0020}'..='\u{E007F}' => previous.is_some_and(|previous| {
+                previous == BLACK_FLAG || ('\u{E0020}'..='\u{E007F}').contains(&previous)
+            }),
+            c => !(c.is_control() || is_invisible(c)),
+        };
+        if keep {
+            cleaned.push(c);
+        }
+    }
+    cleaned
+}
+
+/// Whether `c` is a character that takes no space and has no glyph of its own, besides the control characters.
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{180E}'
+            | '\u{200B}'
+            | '\u{200E}'
+            | '\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FE00}'..='\u{FE0D}'
+            | '\u{FEFF}'
+    )
+}
+
+/// Splits text into sentences, each ending at (and including) a run of `.`, `!`, or `?` followed by whitespace,
+/// or at the end of the text. Sentences are trimmed, and empty ones are skipped.
+pub fn sentences(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || loop {
+        if rest.is_empty() {
+            return None;
+        }
+        let end = sentence_end(rest);
+        let (sentence, remainder) = rest.split_at(end);
+        rest = remainder;
+        let sentence = sentence.trim();
+        if !sentence.is_empty() {
+            return Some(sentence);
+        }
+    })
+}
+
+/// The byte index just past the first sentence of `text`.
+fn sentence_end(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if !is_terminal(c) {
+            continue;
+        }
+        // include the whole run of terminal punctuation, e.g. "?!" or "..."
+        while chars.next_if(|(_, c)| is_terminal(*c)).is_some() {}
+        match chars.peek() {
+            Some((i, c)) if c.is_whitespace() => return *i,
+            None => return text.len(),
+            // e.g. "3.5" or "a.m." mid-word, not the end of a sentence
+            Some(_) => {}
+        }
+    }
+    text.len()
+}
+
+/// Whether `c` can end a sentence.
+pub fn is_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+/// The form of a word used to tell whether two words are the same, ignoring case.
+pub fn normalize(word: &str) -> String {
+    word.to_lowercase()
+}
@@ -0,0 +1,301 @@
+//! Module responsible for comparing the threads of an inbox side by side (see `InboxDirectory`): the headline numbers of
+//! each thread, as a table ranked by volume and, with the `plot` feature, as a heatmap with a row per thread, to see
+//! every relationship at once.
+//!
+//! The columns are on different scales (compound scores, a message count, a duration), so each is normalized over its
+//! own range for the heatmap's colors: from its lowest value across the threads (0) to its highest (1).
+
+use std::fmt::Write;
+
+use crate::{
+    analyzer::AnalyzedConversation,
+    format::{format_count, format_duration},
+    palette::Palette,
+    stats,
+};
+
+/// The columns of the table and the heatmap, in order.
+pub const THREAD_METRICS: [&str; 5] = [
+    "my compound",
+    "their compound",
+    "messages",
+    "median reply",
+    "trend",
+];
+
+/// How many milliseconds the trend is given over.
+const TREND_PERIOD_MS: f64 = 30.0 * 86_400_000.0;
+
+/// The headline numbers of a thread, see `AnalyzedConversation::thread_profile`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ThreadProfile {
+    /// the title of the conversation, or the name of its folder if it has none
+    pub title: String,
+    /// the compound scores of the exporter's messages, aggregated with `AnalyzedConversation::aggregate` (counting
+    /// quoted messages as `AnalyzedConversation::quotes` says), `None` without any or if the exporter isn't known
+    pub my_compound: Option<f64>,
+    /// the compound scores of everyone else's messages, aggregated the same way, `None` without any or if the exporter
+    /// isn't known (there'd be no telling their messages apart from everyone else's)
+    pub their_compound: Option<f64>,
+    /// every participant's messages, counted as `ParticipantSummary::messages` counts them
+    pub messages: usize,
+    /// how long a reply took (in either direction), over the reply pairs, in milliseconds
+    pub median_reply_ms: Option<f64>,
+    /// the slope of the least squares regression of every compound score over time, in compound per 30 days
+    pub trend: Option<f64>,
+}
+
+impl ThreadProfile {
+    /// The values of the columns, in the order of `THREAD_METRICS`.
+    pub fn values(&self) -> [Option<f64>; 5] {
+        [
+            self.my_compound,
+            self.their_compound,
+            Some(self.messages as f64),
+            self.median_reply_ms,
+            self.trend,
+        ]
+    }
+}
+
+/// The value of a column as text, "n/a" without one.
+pub fn format_metric(column: usize, value: Option<f64>) -> String {
+    let Some(value) = value else {
+        return "n/a".to_string();
+    };
+    match column {
+        2 => format_count(value as usize),
+        3 => format_duration(value),
+        _ => format!("{value:+.3}"),
+    }
+}
+
+impl AnalyzedConversation {
+    /// The headline numbers of the conversation as a thread of an inbox, titled `folder` if it has no title.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::parser::ParsedConversation;
+    ///
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700002400000, "content": "this is awful, I hate it"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1700000600000, "content": "I love it, it's great"},
+    ///     {"sender_name": "Ben", "timestamp_ms": 1700000000000, "content": "what a wonderful day"}
+    ///   ]
+    /// }"#;
+    /// let mut analysis = ParsedConversation::from_bytes(export)?.analyze();
+    /// analysis.me = analysis.names.id("Ana");
+    /// let profile = analysis.thread_profile("ana_1234");
+    /// assert_eq!(profile.title, "ana_1234");
+    /// assert_eq!(profile.messages, 3);
+    /// assert!(profile.my_compound.unwrap() > 0.5);
+    /// assert!(profile.their_compound.unwrap().abs() < 0.5);
+    /// // Ana replied after 10 minutes, and Ben after 30
+    /// assert_eq!(profile.median_reply_ms, Some(1_200_000.0));
+    /// assert!(profile.trend.unwrap() < 0.0);
+    ///
+    /// // without knowing who made the export, there's no telling whose messages are whose
+    /// analysis.me = None;
+    /// let profile = analysis.thread_profile("ana_1234");
+    /// assert_eq!((profile.my_compound, profile.their_compound), (None, None));
+    /// assert_eq!(profile.messages, 3);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn thread_profile(&self, folder: &str) -> ThreadProfile {
+        let mut mine = Vec::new();
+        let mut theirs = Vec::new();
+        let mut points = Vec::new();
+        for (message, score) in self.timeline() {
+            let compound = (score.compound, self.quotes.weight(message));
+            if Some(message.sender) == self.me {
+                mine.push(compound);
+            } else {
+                theirs.push(compound);
+            }
+            points.push((message.timestamp, score.compound));
+        }
+        let mut reply_ms = self
+            .reply_pairs()
+            .iter()
+            .map(|pair| (pair.response_timestamp_ms - pair.stimulus_timestamp_ms) as f64)
+            .collect::<Vec<_>>();
+
+        ThreadProfile {
+            title: self.title.clone().unwrap_or_else(|| folder.to_string()),
+            my_compound: self.aggregate.apply_weighted(&mut mine),
+            their_compound: self
+                .me
+                .and_then(|_| self.aggregate.apply_weighted(&mut theirs)),
+            messages: self
+                .summaries()
+                .iter()
+                .map(|summary| summary.messages)
+                .sum(),
+            median_reply_ms: stats::median(&mut reply_ms),
+            trend: stats::least_squares_linear_regression(&points)
+                .map(|fit| fit.slope * TREND_PERIOD_MS),
+        }
+    }
+}
+
+/// The threads of an inbox, ranked by how many messages they have (most first, then by title).
+#[derive(Debug, Clone)]
+pub struct ThreadComparison {
+    pub threads: Vec<ThreadProfile>,
+    /// how the cells of the heatmap are colored, see `Palette::ramp`
+    pub palette: Palette,
+}
+
+impl ThreadComparison {
+    /// Ranks the threads, see the type's docs.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::threads::{ThreadComparison, ThreadProfile};
+    ///
+    /// let thread = |title: &str, messages, my_compound| ThreadProfile {
+    ///     title: title.to_string(),
+    ///     my_compound,
+    ///     their_compound: Some(0.1),
+    ///     messages,
+    ///     median_reply_ms: None,
+    ///     trend: Some(0.0),
+    /// };
+    /// let comparison = ThreadComparison::new(vec![
+    ///     thread("Weekend plans with the whole family", 10, Some(0.25)),
+    ///     thread("Ana", 30, Some(0.75)),
+    ///     thread("Weekend plans with the whole family", 20, None),
+    ///     thread("Weekend plans with the whole team", 5, Some(0.5)),
+    /// ]);
+    /// let ranked = comparison.threads.iter().map(|thread| thread.messages).collect::<Vec<_>>();
+    /// assert_eq!(ranked, [30, 20, 10, 5]);
+    ///
+    /// // each column over its own range, 0 at its lowest value and 1 at its highest
+    /// let normalized = comparison.normalized();
+    /// assert_eq!(normalized.iter().map(|row| row[0]).collect::<Vec<_>>(), [Some(1.0), None, Some(0.0), Some(0.5)]);
+    /// assert_eq!(normalized[3][2], Some(0.0));
+    /// // a column without a range is in the middle, and one without values is empty
+    /// assert!(normalized.iter().all(|row| row[1] == Some(0.5) && row[3].is_none()));
+    ///
+    /// // titles are cut, and those the same once cut are numbered
+    /// assert_eq!(
+    ///     comparison.labels(16),
+    ///     ["Ana", "Weekend plans w…", "Weekend plan (2)", "Weekend plan (3)"]
+    /// );
+    /// let table = comparison.table(16);
+    /// assert!(table.lines().nth(1).unwrap().starts_with("   #  thread"));
+    /// assert!(table.lines().any(|line| line.starts_with("   2  Weekend plans w…") && line.contains("n/a")));
+    /// ```
+    pub fn new(mut threads: Vec<ThreadProfile>) -> Self {
+        threads.sort_by(|a, b| {
+            b.messages
+                .cmp(&a.messages)
+                .then_with(|| a.title.cmp(&b.title))
+        });
+        Self {
+            threads,
+            palette: Palette::default(),
+        }
+    }
+
+    /// The values of each thread (see `ThreadProfile::values`), each column normalized from its lowest value (0) to its
+    /// highest (1), or 0.5 if they're all the same, `None` where a thread has no value.
+    pub fn normalized(&self) -> Vec<[Option<f64>; 5]> {
+        let rows = self
+            .threads
+            .iter()
+            .map(ThreadProfile::values)
+            .collect::<Vec<_>>();
+        let mut normalized = rows.clone();
+        for column in 0..THREAD_METRICS.len() {
+            let values = rows.iter().filter_map(|row| row[column]);
+            let low = values.clone().fold(f64::INFINITY, f64::min);
+            let high = values.fold(f64::NEG_INFINITY, f64::max);
+            for row in &mut normalized {
+                row[column] = row[column].map(|value| {
+                    if high > low {
+                        (value - low) / (high - low)
+                    } else {
+                        0.5
+                    }
+                });
+            }
+        }
+        normalized
+    }
+
+    /// The titles of the threads as row labels: on one line, cut to `width` characters (with an ellipsis), and those
+    /// the same as an earlier one once cut numbered from 2 (still within `width`), so every row is told apart.
+    pub fn labels(&self, width: usize) -> Vec<String> {
+        let mut labels: Vec<String> = Vec::with_capacity(self.threads.len());
+        for thread in &self.threads {
+            let title = thread
+                .title
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            let label = truncate(&title, width);
+            let label = if labels.contains(&label) {
+                (2..)
+                    .map(|n| {
+                        let suffix = format!(" ({n})");
+                        let base = title
+                            .chars()
+                            .take(width.saturating_sub(suffix.chars().count()))
+                            .collect::<String>();
+                        format!("{}{suffix}", base.trim_end())
+                    })
+                    .find(|label| !labels.contains(label))
+                    .expect("there's always a number not taken yet")
+            } else {
+                label
+            };
+            labels.push(label);
+        }
+        labels
+    }
+
+    /// The threads as a table, a row per thread in rank order, labeled as `ThreadComparison::labels` cuts them.
+    pub fn table(&self, width: usize) -> String {
+        let labels = self.labels(width);
+        let label_width = labels
+            .iter()
+            .map(|label| label.chars().count())
+            .chain(["thread".len()])
+            .max()
+            .unwrap_or_default();
+        let mut table = String::new();
+        // writing to a string can't fail
+        let _ = writeln!(
+            table,
+            "Threads, ranked by messages (trend in compound per 30 days)"
+        );
+        let _ = write!(table, "{:>4}  {:<label_width$}", "#", "thread");
+        for metric in THREAD_METRICS {
+            let _ = write!(table, "  {metric:>14}");
+        }
+        table.push('\n');
+        for (rank, (thread, label)) in self.threads.iter().zip(&labels).enumerate() {
+            let padding = label_width - label.chars().count();
+            let _ = write!(table, "{:>4}  {label}{:padding$}", rank + 1, "");
+            for (column, value) in thread.values().into_iter().enumerate() {
+                let _ = write!(table, "  {:>14}", format_metric(column, value));
+            }
+            table.push('\n');
+        }
+        table
+    }
+}
+
+/// The text cut to `width` characters, with an ellipsis if it's longer.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut cut = text
+        .chars()
+        .take(width.saturating_sub(1))
+        .collect::<String>();
+    cut.push('…');
+    cut
+}
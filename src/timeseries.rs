@@ -0,0 +1,363 @@
+//! Module responsible for exporting the sentiment series for time series databases and dashboards:
+//! InfluxDB line protocol, and a csv on a regular grid of days, weeks, or months.
+//!
+//! The series are resampled into calendar periods (see `aggregate::Period`), timestamped at their start.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    aggregate::{self, Period},
+    analyzer::AnalyzedConversation,
+    parser::ParticipantId,
+};
+
+/// Measurement of the per-message rows.
+const MESSAGE_MEASUREMENT: &str = "sentiment";
+/// Measurement of the daily aggregate rows.
+const DAILY_MEASUREMENT: &str = "sentiment_daily";
+/// Measurement of the weekly gap rows.
+const GAPS_MEASUREMENT: &str = "gaps_weekly";
+
+/// Escapes a tag value for line protocol: commas, equals signs, spaces, and backslashes are escaped with a backslash.
+/// Line protocol has no way of escaping newlines, so they're replaced with spaces, and (since tag values can't be empty)
+/// an empty value becomes `_`.
+pub fn escape_tag_value(value: &str) -> String {
+    if value.is_empty() {
+        return "_".to_string();
+    }
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            ',' | '=' | ' ' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quotes a string field value for line protocol, escaping the quotes and backslashes in it with a backslash.
+fn quote_field_value(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if matches!(c, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// What to do with the periods a participant has no scored messages in, between the first and last periods of the conversation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyBuckets {
+    /// leave them out
+    #[default]
+    Omit,
+    /// include them, without a compound score
+    Fill,
+}
+
+/// A participant's scored messages over one period, see `AnalyzedConversation::resample`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize)]
+pub struct Bucket {
+    /// the start of the period, in milliseconds since the epoch
+    pub start_ms: usize,
+    /// their compound scores over the period, `None` for an empty period
+    pub compound: Option<f64>,
+    /// how many scored messages they sent in the period
+    pub messages: usize,
+}
+
+/// Quotes a csv field if it needs to be, doubling any quotes inside it.
+pub(crate) fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl AnalyzedConversation {
+    /// Each participant's scored messages resampled into calendar periods (see `aggregate::Period`), in order: how many
+    /// they sent (counting collapsed repeats as `AnalyzedConversation::repeats` says) and their compound score (aggregated
+    /// with `AnalyzedConversation::aggregate`, the mean by default, counting quoted messages as
+    /// `AnalyzedConversation::quotes` says) over each period. The periods without scored messages are left out, or with
+    /// `EmptyBuckets::Fill` included without a score, from the first period of the conversation to its last.
+    ///
+    /// ```
+    /// use igdm_sentiment_analysis::{aggregate::Period, parser::ParsedConversation, timeseries::EmptyBuckets};
+    ///
+    /// // Tuesday the 31st of January 2023 at 23:00, then Wednesday at 01:00, and Monday the 13th of February
+    /// let export = br#"{
+    ///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+    ///   "messages": [
+    ///     {"sender_name": "Ben", "timestamp_ms": 1676282400000, "content": "this is awful news"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1675213200000, "content": "what a great day"},
+    ///     {"sender_name": "Ana", "timestamp_ms": 1675206000000, "content": "I love this song"}
+    ///   ]
+    /// }"#;
+    /// let analysis = ParsedConversation::from_bytes(export)?.analyze();
+    /// let (ana, ben) = (analysis.names.id("Ana").unwrap(), analysis.names.id("Ben").unwrap());
+    /// let starts = |period, empty| {
+    ///     let resampled = analysis.resample(period, empty);
+    ///     let starts = |participant| resampled[&participant].iter().map(|bucket| bucket.start_ms).collect::<Vec<_>>();
+    ///     (starts(ana), starts(ben))
+    /// };
+    /// let (day, week, month) = (24 * 60 * 60 * 1000, 7 * 24 * 60 * 60 * 1000, 1675209600000);
+    ///
+    /// // split at midnight
+    /// assert_eq!(starts(Period::Day, EmptyBuckets::Omit), (vec![month - day, month], vec![1676246400000]));
+    /// // together in the week starting on Monday the 30th, then the week of the 13th after an empty one
+    /// let monday = month - 2 * day;
+    /// assert_eq!(starts(Period::Week, EmptyBuckets::Omit), (vec![monday], vec![monday + 2 * week]));
+    /// assert_eq!(
+    ///     starts(Period::Week, EmptyBuckets::Fill),
+    ///     (vec![monday, monday + week, monday + 2 * week], vec![monday, monday + week, monday + 2 * week])
+    /// );
+    /// // split across the 1st of February
+    /// assert_eq!(starts(Period::Month, EmptyBuckets::Omit), (vec![1672531200000, month], vec![month]));
+    ///
+    /// let weeks = analysis.resample(Period::Week, EmptyBuckets::Fill);
+    /// assert_eq!(weeks[&ana][0].messages, 2);
+    /// assert!(weeks[&ana][0].compound.unwrap() > 0.0);
+    /// assert_eq!((weeks[&ana][1].messages, weeks[&ana][1].compound), (0, None));
+    /// assert_eq!((weeks[&ben][0].messages, weeks[&ben][0].compound), (0, None));
+    /// assert!(weeks[&ben][2].compound.unwrap() < 0.0);
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn resample(
+        &self,
+        period: Period,
+        empty: EmptyBuckets,
+    ) -> BTreeMap<ParticipantId, Vec<Bucket>> {
+        let buckets = self
+            .analysis
+            .iter()
+            .map(|(participant, series)| {
+                let buckets = aggregate::bucket(
+                    series.iter().map(|(message, score)| {
+                        (
+                            message.timestamp_ms(),
+                            (
+                                (score.compound, self.quotes.weight(message)),
+                                self.repeats.volume(message),
+                            ),
+                        )
+                    }),
+                    period,
+                )
+                .into_iter()
+                .map(|(bucket, messages)| {
+                    let volume = messages.iter().map(|(_, volume)| volume).sum();
+                    let mut scores = messages
+                        .into_iter()
+                        .map(|(score, _)| score)
+                        .collect::<Vec<_>>();
+                    (bucket, (volume, self.aggregate.apply_weighted(&mut scores)))
+                })
+                .collect::<BTreeMap<_, _>>();
+                (*participant, buckets)
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let all_buckets = || buckets.values().flat_map(|buckets| buckets.keys().copied());
+        let range = match (empty, all_buckets().min(), all_buckets().max()) {
+            (EmptyBuckets::Fill, Some(first), Some(last)) => Some(first..=last),
+            _ => None,
+        };
+        buckets
+            .into_iter()
+            .map(|(participant, mut buckets)| {
+                if let Some(range) = range.clone() {
+                    for bucket in range {
+                        buckets.entry(bucket).or_insert((0, None));
+                    }
+                }
+                let buckets = buckets
+                    .into_iter()
+                    .map(|(bucket, (messages, compound))| Bucket {
+                        start_ms: period.start_ms(bucket),
+                        compound,
+                        messages,
+                    })
+                    .collect();
+                (participant, buckets)
+            })
+            .collect()
+    }
+
+    /// Renders every message's score, and each participant's daily compound score, as InfluxDB line protocol
+    /// with millisecond timestamps (so it should be written with `precision=ms`), e.g.
+    /// `sentiment,participant=alice compound=0.42,pos=0.1,neu=0.9,neg=0 1672531200000`,
+    /// with a `quote` field naming the reason (see `QuoteReason::key`) on messages flagged as likely quoted.
+    /// The daily field is named after the statistic, e.g. `compound_mean` or `compound_trimmed5`.
+    /// (InfluxDB keeps only one of the messages a participant sent within the same millisecond.)
+    /// The weekly gaps between messages follow, as `gaps_weekly` rows.
+    pub fn to_line_protocol(&self) -> String {
+        let mut lines = String::new();
+        // writing to a string can't fail
+        let participants = self.participants_by_name();
+        for participant in &participants {
+            let tag = escape_tag_value(self.names.name(*participant));
+            for (message, score) in &self.analysis[participant] {
+                let quote = match message.quote {
+                    Some(reason) => format!(",quote={}", quote_field_value(reason.key())),
+                    None => String::new(),
+                };
+                let _ = writeln!(
+                    lines,
+                    "{MESSAGE_MEASUREMENT},participant={tag} compound={},pos={},neu={},neg={}{quote} {}",
+                    score.compound, score.pos, score.neu, score.neg, message.timestamp_ms()
+                );
+            }
+        }
+        let daily = self.resample(Period::Day, EmptyBuckets::Omit);
+        let field = format!("compound_{}", self.aggregate.key());
+        for participant in &participants {
+            let tag = escape_tag_value(self.names.name(*participant));
+            for day in &daily[participant] {
+                let Some(compound) = day.compound else {
+                    continue;
+                };
+                let _ = writeln!(
+                    lines,
+                    "{DAILY_MEASUREMENT},participant={tag} {field}={compound},messages={}i {}",
+                    day.messages, day.start_ms
+                );
+            }
+        }
+        for week in self.weekly_gaps() {
+            let _ = writeln!(
+                lines,
+                "{GAPS_MEASUREMENT} median_hours={},p90_hours={},gaps={}i {}",
+                week.median_hours, week.p90_hours, week.gaps, week.week_start_ms
+            );
+        }
+        lines
+    }
+
+    /// Renders each participant's compound score over each day, week, or month (see `AnalyzedConversation::resample`)
+    /// as a csv with a row for every period from the first message to the last, timestamped at its start, and a column
+    /// per participant (ordered by name), left empty in the periods they sent nothing.
+    pub fn to_csv_timeseries(&self, period: Period) -> String {
+        let resampled = self.resample(period, EmptyBuckets::Fill);
+        let participants = self.participants_by_name();
+        let starts = resampled
+            .values()
+            .flat_map(|buckets| buckets.iter().map(|bucket| bucket.start_ms))
+            .collect::<BTreeSet<_>>();
+        let compounds = participants
+            .iter()
+            .map(|participant| {
+                resampled
+                    .get(participant)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|bucket| Some((bucket.start_ms, bucket.compound?)))
+                    .collect::<BTreeMap<_, _>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut csv = String::from("time");
+        for participant in &participants {
+            csv.push(',');
+            csv.push_str(&escape_csv_field(self.names.name(*participant)));
+        }
+        csv.push('\n');
+
+        for start_ms in starts {
+            let time = chrono::DateTime::from_timestamp_millis(start_ms as i64)
+                .map(|time| time.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .unwrap_or_default();
+            csv.push_str(&time);
+            for compounds in &compounds {
+                csv.push(',');
+                if let Some(compound) = compounds.get(&start_ms) {
+                    let _ = write!(csv, "{compound}");
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ParsedConversation;
+
+    /// Splits a line of line protocol into its measurement and tags, fields, and timestamp, at the unescaped spaces
+    /// outside of quoted field values, unescaping the rest.
+    fn split_line(line: &str) -> Vec<String> {
+        let (mut parts, mut part) = (Vec::new(), String::new());
+        let (mut chars, mut quoted) = (line.chars(), false);
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => part.extend(chars.next()),
+                '"' => quoted = !quoted,
+                ' ' if !quoted => parts.push(std::mem::take(&mut part)),
+                c => part.push(c),
+            }
+        }
+        parts.push(part);
+        parts
+    }
+
+    #[test]
+    fn tag_values_escape_spaces_commas_and_equals_signs() {
+        assert_eq!(escape_tag_value("Ana Lima"), r"Ana\ Lima");
+        assert_eq!(escape_tag_value("Lima, Ana"), r"Lima\,\ Ana");
+        assert_eq!(escape_tag_value("a=b"), r"a\=b");
+        assert_eq!(escape_tag_value(r"back\slash"), r"back\\slash");
+        assert_eq!(escape_tag_value("two\nlines"), "two lines");
+        assert_eq!(escape_tag_value(""), "_");
+        // escaping is undone by reading the line back
+        assert_eq!(split_line(&escape_tag_value(r"a b,c=d\e")), [r"a b,c=d\e"]);
+    }
+
+    #[test]
+    fn field_values_escape_quotes_and_backslashes() {
+        assert_eq!(quote_field_value("near_duplicate"), r#""near_duplicate""#);
+        assert_eq!(quote_field_value("a b,c=d"), r#""a b,c=d""#);
+        assert_eq!(quote_field_value(r#"say "hi"\n"#), r#""say \"hi\"\\n""#);
+    }
+
+    #[test]
+    fn lines_split_into_measurement_fields_and_timestamp() {
+        let export = br#"{
+          "participants": [{"name": "Ana Lima, Jr."}, {"name": "x=y"}],
+          "messages": [
+            {"sender_name": "x=y", "timestamp_ms": 1700000100000, "content": "\"what a great day, you deserve it\""},
+            {"sender_name": "Ana Lima, Jr.", "timestamp_ms": 1700000000000, "content": "I love this song"}
+          ]
+        }"#;
+        let analysis = ParsedConversation::from_bytes(export).unwrap().analyze();
+        let lines = analysis.to_line_protocol();
+        let messages = lines
+            .lines()
+            .filter(|line| line.starts_with("sentiment,"))
+            .map(split_line)
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2);
+        for parts in &messages {
+            assert_eq!(parts.len(), 3, "{parts:?}");
+            assert!(parts[1].starts_with("compound="));
+            assert!(parts[2].parse::<usize>().is_ok());
+        }
+        assert_eq!(messages[0][0], "sentiment,participant=Ana Lima, Jr.");
+        assert_eq!(messages[1][0], "sentiment,participant=x=y");
+        assert!(messages[1][1].ends_with(",quote=quotation_marks"));
+        assert!(lines.contains(r"participant=Ana\ Lima\,\ Jr. "));
+        assert!(lines.contains(r"participant=x\=y "));
+    }
+}
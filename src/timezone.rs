@@ -0,0 +1,87 @@
+//! Module containing the time zone times are shown in, like the labels of the time axes of the plots.
+//!
+//! Timestamps are kept in utc everywhere, they're only turned into local time to be shown, in an IANA time zone
+//! (see `chrono_tz::Tz`), so the shown times follow the zone's daylight saving time. Times are shown in utc by default.
+//!
+//! ```
+//! use igdm_sentiment_analysis::timezone;
+//!
+//! let zone = timezone::parse("Europe/London")?;
+//! let summer = chrono::DateTime::from_timestamp_millis(1_688_212_800_000).unwrap();
+//! assert_eq!(summer.with_timezone(&zone).format("%Y-%m-%d %H:%M").to_string(), "2023-07-01 13:00");
+//!
+//! let error = timezone::parse("Europe/Londn").unwrap_err().to_string();
+//! assert!(error.contains("America/New_York"));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeDelta, TimeZone, Utc};
+pub use chrono_tz::Tz;
+
+/// Names of time zones, given as examples when a name isn't one.
+const EXAMPLES: [&str; 4] = ["UTC", "Europe/London", "America/New_York", "Asia/Tokyo"];
+
+/// Reads the IANA name of a time zone, e.g. `Europe/London`.
+pub fn parse(name: &str) -> Result<Tz> {
+    name.parse().map_err(|_| {
+        anyhow!(
+            "unknown time zone \"{name}\", expected an IANA name like {}",
+            EXAMPLES.join(", ")
+        )
+    })
+}
+
+/// The time zone of a fixed utc offset, for the `Etc/GMT` zones that stand for them, which only exist for whole hours
+/// (from -12:00 to +14:00).
+///
+/// ```
+/// use chrono::FixedOffset;
+/// use igdm_sentiment_analysis::timezone::{self, Tz};
+///
+/// assert_eq!(timezone::from_offset(FixedOffset::west_opt(5 * 3600).unwrap())?, Tz::Etc__GMTPlus5);
+/// assert_eq!(timezone::from_offset(FixedOffset::east_opt(0).unwrap())?, Tz::UTC);
+/// assert!(timezone::from_offset(FixedOffset::east_opt(5 * 3600 + 1800).unwrap()).is_err());
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn from_offset(offset: FixedOffset) -> Result<Tz> {
+    let seconds = offset.local_minus_utc();
+    if seconds == 0 {
+        return Ok(Tz::UTC);
+    }
+    if seconds % 3600 != 0 || !(-12..=14).contains(&(seconds / 3600)) {
+        bail!("no time zone has the fixed utc offset {offset}, pass the IANA name of the time zone instead, e.g. Asia/Kolkata");
+    }
+    // the signs of the Etc/GMT zones are the other way around, Etc/GMT+5 is 5 hours behind utc
+    parse(&format!("Etc/GMT{:+}", -seconds / 3600))
+}
+
+/// The moment a local time happened in the time zone: the first of the two if the clocks went back over it, and as if
+/// they hadn't gone forward yet if they skipped it.
+///
+/// ```
+/// use chrono::NaiveDate;
+/// use igdm_sentiment_analysis::timezone;
+///
+/// let zone = timezone::parse("America/New_York")?;
+/// let at = |day, hour, minute| NaiveDate::from_ymd_opt(2023, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap();
+/// let utc = |day, hour, minute| timezone::from_local(zone, at(day, hour, minute)).unwrap().format("%d %H:%M").to_string();
+/// assert_eq!(utc(11, 21, 41), "12 02:41");
+/// // the clocks went from 2am to 3am on the 12th, 2:30 was 3:30 in daylight saving time
+/// assert_eq!(utc(12, 2, 30), "12 07:30");
+/// assert_eq!(utc(12, 3, 30), "12 07:30");
+/// // the day the clocks went forward only had 23 hours
+/// assert_eq!(timezone::from_local(zone, at(13, 0, 0)).unwrap() - timezone::from_local(zone, at(12, 0, 0)).unwrap(),
+///     chrono::TimeDelta::hours(23));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub fn from_local(zone: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    zone.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            // the clocks go forward by an hour, so an hour later is the same moment in the offset after the change
+            zone.from_local_datetime(&(local + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+}
@@ -0,0 +1,235 @@
+//! Module containing the tokenizer used by every feature that counts or compares what was written
+//! (the register metrics, the `min_tokens` filter, the quiz, the top emoji of the weekly digest and the year in review), so they all agree on what a word is.
+//! Scoring is the exception, vader tokenizes the text itself.
+//!
+//! Words are found with the Unicode word boundary rules (UAX #29), so contractions like "don't" and numbers like "3.5"
+//! are one word, emoji sequences (skin tones, ZWJ sequences like 👨‍👩‍👧, flags) are one token,
+//! and combining marks stay with the letter they modify, so composed and decomposed forms of the same text
+//! (NFC and NFD) tokenize the same. URLs and @mentions are recognized before the boundary rules are applied,
+//! so they aren't broken up at their punctuation.
+//!
+//! Where the counts differ from vader's internal view: vader splits on whitespace alone and strips the punctuation
+//! around each piece, so "hello,world" is one token to it and two here, emoticons like ":)" are tokens to it and
+//! not here, and text without spaces between words (e.g. Chinese or Japanese) is one token to it,
+//! where the boundary rules make each ideograph or kana a word of its own (except runs of katakana).
+
+use std::collections::BTreeMap;
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Word,
+    Emoji,
+    Url,
+    Mention,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub kind: TokenKind,
+}
+
+/// How the tokenizer treats the things that aren't plain words.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tokenizer {
+    /// emit emoji as tokens, otherwise they're skipped like punctuation
+    pub emoji: bool,
+    /// keep URLs whole, otherwise they're split into words at their punctuation
+    pub urls: bool,
+    /// keep @mentions whole, otherwise the name is a word and the @ is skipped
+    pub mentions: bool,
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self {
+            emoji: true,
+            urls: true,
+            mentions: true,
+        }
+    }
+}
+
+/// Characters trimmed off the end of a URL or mention, since they're far more likely to be
+/// the punctuation of the sentence around it.
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', '!', '?', ';', ':', ')', '\'', '"'];
+
+impl Tokenizer {
+    pub fn tokens<'a>(self, text: &'a str) -> impl Iterator<Item = Token<'a>> {
+        text.split_whitespace()
+            .flat_map(move |chunk| self.chunk_tokens(chunk))
+    }
+
+    /// The tokens of a run of text without whitespace.
+    fn chunk_tokens(self, chunk: &str) -> Vec<Token<'_>> {
+        let mut tokens = Vec::new();
+        let mut rest = chunk;
+
+        let is_url = ["http://", "https://", "www."].iter().any(|prefix| {
+            chunk
+                .as_bytes()
+                .get(..prefix.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(prefix.as_bytes()))
+        });
+        if self.urls && is_url {
+            let url = chunk.trim_end_matches(TRAILING_PUNCTUATION);
+            tokens.push(Token {
+                text: url,
+                kind: TokenKind::Url,
+            });
+            rest = &chunk[url.len()..];
+        } else if self.mentions && chunk.starts_with('@') {
+            let end = chunk[1..]
+                // the combining marks of a decomposed (NFD) name are part of it
+                .find(|c: char| {
+                    !(c.is_alphanumeric() || is_combining_mark(c) || c == '_' || c == '.')
+                })
+                .map_or(chunk.len(), |i| i + 1);
+            let mention = chunk[..end].trim_end_matches('.');
+            if mention.len() > 1 {
+                tokens.push(Token {
+                    text: mention,
+                    kind: TokenKind::Mention,
+                });
+                rest = &chunk[mention.len()..];
+            }
+        }
+
+        for segment in rest.split_word_bounds() {
+            if segment.chars().any(char::is_alphanumeric) {
+                tokens.push(Token {
+                    text: segment,
+                    kind: TokenKind::Word,
+                });
+            } else if self.emoji && segment.starts_with(is_emoji) {
+                tokens.push(Token {
+                    text: segment,
+                    kind: TokenKind::Emoji,
+                });
+            }
+        }
+        tokens
+    }
+}
+
+/// The text without the URLs and @mentions the default tokenizer finds in it (and the punctuation trimmed off their end,
+/// if that's all there is after them), with what's left separated by single spaces.
+pub fn strip_urls_and_mentions(text: &str) -> String {
+    let tokenizer = Tokenizer::default();
+    let mut kept = Vec::new();
+    for chunk in text.split_whitespace() {
+        let rest = match tokenizer.chunk_tokens(chunk).first() {
+            Some(token) if matches!(token.kind, TokenKind::Url | TokenKind::Mention) => {
+                &chunk[token.text.len()..]
+            }
+            _ => chunk,
+        };
+        if !rest.chars().all(|c| TRAILING_PUNCTUATION.contains(&c)) {
+            kept.push(rest);
+        }
+    }
+    kept.join(" ")
+}
+
+/// The words of the text, with the default tokenizer.
+pub fn words(text: &str) -> impl Iterator<Item = &str> {
+    Tokenizer::default()
+        .tokens(text)
+        .filter(|token| token.kind == TokenKind::Word)
+        .map(|token| token.text)
+}
+
+/// The emoji used most in the texts (the first of them in code point order on ties), `None` if there are none.
+pub fn top_emoji<'a>(texts: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let mut emoji: BTreeMap<&str, usize> = BTreeMap::new();
+    for text in texts {
+        for token in Tokenizer::default().tokens(text) {
+            if token.kind == TokenKind::Emoji {
+                *emoji.entry(token.text).or_default() += 1;
+            }
+        }
+    }
+    // max_by_key keeps the last of the maxima, so go in reverse to get the first
+    emoji
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(emoji, _)| emoji.to_string())
+}
+
+/// Whether `c` starts an emoji, going by the blocks emoji are allocated in
+/// (an approximation of the `Extended_Pictographic` property, plus the regional indicators flags are made of).
+pub(crate) fn is_emoji(c: char) -> bool {
+    matches!(
+        u32::from(c),
+        0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2300..=0x23FF
+            | 0x2B00..=0x2BFF
+            | 0x2190..=0x21FF
+            | 0x203C
+            | 0x2049
+            | 0x2122
+            | 0x2139
+            | 0x3030
+            | 0x303D
+            | 0x3297
+            | 0x3299
+            | 0x00A9
+            | 0x00AE
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use unicode_normalization::UnicodeNormalization;
+
+    use super::*;
+
+    /// The kinds of the tokens, and their text composed (NFC).
+    fn composed_tokens(text: &str) -> Vec<(TokenKind, String)> {
+        Tokenizer::default()
+            .tokens(text)
+            .map(|token| (token.kind, token.text.nfc().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn composed_and_decomposed_text_tokenize_the_same() {
+        for text in [
+            "café crème brûlée",
+            "ñandú, pingüino",
+            "Tiếng Việt có dấu",
+            "Ελληνικά ΐ",
+            "한국어 문장",
+            "naïve @zoë https://example.com/é 👍🏽",
+        ] {
+            let (nfc, nfd) = (
+                text.nfc().collect::<String>(),
+                text.nfd().collect::<String>(),
+            );
+            assert_ne!(nfc, nfd, "{text} has nothing to decompose");
+            assert_eq!(composed_tokens(&nfc), composed_tokens(&nfd), "{text}");
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn normalization_does_not_change_the_token_count(text in "\\PC{0,40}") {
+            let (nfc, nfd) = (text.nfc().collect::<String>(), text.nfd().collect::<String>());
+            prop_assert_eq!(Tokenizer::default().tokens(&nfc).count(), Tokenizer::default().tokens(&nfd).count());
+        }
+
+        #[test]
+        fn normalization_does_not_change_the_tokens(
+            text in "([a-zA-Z]|[\u{C0}-\u{17F}]|[\u{300}-\u{36F}]|[\u{AC00}-\u{AC30}]| |[.,!?@]|👍|🏽){0,40}",
+        ) {
+            let (nfc, nfd) = (text.nfc().collect::<String>(), text.nfd().collect::<String>());
+            prop_assert_eq!(composed_tokens(&nfc), composed_tokens(&nfd));
+        }
+    }
+}
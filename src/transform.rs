@@ -0,0 +1,460 @@
+//! Module containing the per-message transforms applied to a conversation between parsing and analysis,
+//! e.g. filtering out messages that shouldn't be scored.
+//!
+//! Transforms are composed into a `TransformPipeline`, which applies them in the order they were added,
+//! so adding a new transform never requires touching the parser.
+
+use crate::{
+    diagnostics::{CollapsedRun, Diagnostics},
+    parser::{Message, ParticipantNames},
+    quotes::similarity,
+    text,
+};
+
+/// Consecutive messages from the same sender are repeats of each other if at least this share of their shingles match
+/// (both ways, see `quotes::similarity`).
+pub const REPEAT_SIMILARITY: f64 = 0.95;
+/// How many of the largest collapsed runs are listed in the diagnostics.
+pub const LISTED_RUNS: usize = 10;
+
+pub trait MessageTransform {
+    /// A short name for the transform, used when reporting what it did.
+    fn name(&self) -> &str;
+
+    /// Transforms the (timestamp sorted) messages of a conversation, keeping them sorted.
+    fn apply(&self, messages: Vec<Message>) -> Vec<Message>;
+
+    /// Like `apply`, also recording what the transform did besides dropping messages (which the pipeline records itself),
+    /// with senders named from `names`.
+    fn apply_recorded(
+        &self,
+        messages: Vec<Message>,
+        _names: &ParticipantNames,
+        _diagnostics: &mut Diagnostics,
+    ) -> Vec<Message> {
+        self.apply(messages)
+    }
+}
+
+/// Drops the messages their sender unsent, the other side may never have read them (or not for long).
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::ParsedConversation,
+///     transform::{DropUnsent, MessageTransform, TransformPipeline},
+/// };
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000180000, "content": "sorry, wrong chat", "is_unsent": false},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "you're the worst", "is_unsent": true},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "see you at 8"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "is_unsent": true}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["see you at 8", "sorry, wrong chat"]);
+/// assert!(conversation
+///     .diagnostics
+///     .dropped_by_transform
+///     .contains(&("drop unsent".to_string(), 2)));
+///
+/// // keeping them is the standard pipeline without it
+/// let keeping = TransformPipeline::standard().without(DropUnsent.name());
+/// assert!(!keeping.names().contains(&"drop unsent"));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropUnsent;
+
+impl MessageTransform for DropUnsent {
+    fn name(&self) -> &str {
+        "drop unsent"
+    }
+
+    fn apply(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        messages.retain(|message| !message.unsent);
+        messages
+    }
+}
+
+/// Cleans up the text of messages and the captions of what they share, see `text::clean`, so what looks the same is
+/// the same. Drops nothing, but comes before `DropEmpty` in the standard transforms, so a message with nothing but
+/// invisible characters is dropped as empty.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     parser::{ConversationDirectory, ParsedConversation, SharedCaptions, TimestampValidation},
+///     transform::{CleanText, MessageTransform, TransformPipeline},
+/// };
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000120000, "content": "\u00e2\u0080\u008b"},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "caf\u0065\u00cc\u0081\u00e2\u0080\u008b?"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "coffee \u00e2\u0098\u0095\u00ef\u00b8\u008f"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// let texts = conversation.messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["coffee ☕\u{FE0F}", "café?"]);
+///
+/// // keeping the text as exported is the standard pipeline without it
+/// let dir = std::env::temp_dir().join("igdm-sentiment-analysis-clean");
+/// std::fs::create_dir_all(&dir)?;
+/// std::fs::write(dir.join("message_1.json"), export)?;
+/// let raw = TransformPipeline::standard().without(CleanText.name());
+/// let conversation = ConversationDirectory::try_from(dir.clone())?.parse_with(
+///     &raw,
+///     &TimestampValidation::default(),
+///     SharedCaptions::default(),
+/// )?;
+/// assert_eq!(conversation.messages.len(), 3);
+/// assert_eq!(conversation.messages[1].text(), "cafe\u{301}\u{200B}?");
+/// # std::fs::remove_dir_all(&dir)?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CleanText;
+
+impl MessageTransform for CleanText {
+    fn name(&self) -> &str {
+        "clean text"
+    }
+
+    fn apply(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        for message in &mut messages {
+            if let Some(content) = &mut message.content {
+                *content = text::clean(content);
+            }
+            if let Some(caption) = message.share.as_mut().and_then(|share| share.text.as_mut()) {
+                *caption = text::clean(caption);
+            }
+        }
+        messages
+    }
+}
+
+/// Drops messages with no text, e.g. images.
+///
+/// ```
+/// use igdm_sentiment_analysis::parser::ParsedConversation;
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "lol"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": ""}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+/// assert_eq!(conversation.messages.len(), 1);
+/// assert!(conversation.diagnostics.dropped_by_transform.contains(&("drop empty".to_string(), 1)));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DropEmpty;
+
+impl MessageTransform for DropEmpty {
+    fn name(&self) -> &str {
+        "drop empty"
+    }
+
+    fn apply(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        messages.retain(|message| !message.text().is_empty());
+        messages
+    }
+}
+
+/// Drops the messages a predicate matches, for exclusions of your own, recorded under `name` in the diagnostics.
+///
+/// ```
+/// use igdm_sentiment_analysis::{
+///     diagnostics::Diagnostics,
+///     parser::ParsedConversation,
+///     transform::{DropMatching, TransformPipeline},
+/// };
+///
+/// let export = br#"{
+///   "participants": [{"name": "Ana"}, {"name": "Ben"}],
+///   "messages": [
+///     {"sender_name": "Ben", "timestamp_ms": 1700000180000, "content": "\u00f0\u009f\u0098\u0082"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000120000, "content": "OK"},
+///     {"sender_name": "Ben", "timestamp_ms": 1700000060000, "content": "ok, 8 works"},
+///     {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "dinner at 8?"}
+///   ]
+/// }"#;
+/// let conversation = ParsedConversation::from_bytes(export)?;
+///
+/// // messages that are only "ok", or only emoji
+/// let filler = DropMatching::new("drop filler", |message| {
+///     let text = message.text().trim();
+///     text.eq_ignore_ascii_case("ok") || !text.chars().any(char::is_alphanumeric)
+/// });
+/// let mut diagnostics = Diagnostics::default();
+/// let messages = TransformPipeline::standard().with(filler).apply(
+///     conversation.messages,
+///     &conversation.names,
+///     &mut diagnostics,
+/// );
+/// let texts = messages.iter().map(|message| message.text()).collect::<Vec<_>>();
+/// assert_eq!(texts, ["dinner at 8?", "ok, 8 works"]);
+/// assert!(diagnostics.dropped_by_transform.contains(&("drop filler".to_string(), 2)));
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct DropMatching {
+    name: String,
+    predicate: Box<dyn Fn(&Message) -> bool>,
+}
+
+impl DropMatching {
+    pub fn new(name: impl Into<String>, predicate: impl Fn(&Message) -> bool + 'static) -> Self {
+        Self {
+            name: name.into(),
+            predicate: Box::new(predicate),
+        }
+    }
+}
+
+impl MessageTransform for DropMatching {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn apply(&self, mut messages: Vec<Message>) -> Vec<Message> {
+        messages.retain(|message| !(self.predicate)(message));
+        messages
+    }
+}
+
+/// Collapses runs of at least `min_run` consecutive repeats (see `REPEAT_SIMILARITY`) from the same sender,
+/// like chain messages and copypasta spammed over and over, into the first message of the run,
+/// which keeps how many messages it stands for in `Message::repeats` (see `RepeatCount`).
+///
+/// Not one of the standard transforms, the largest runs it collapsed are listed in the diagnostics to check what was folded.
+#[derive(Debug, Clone, Copy)]
+pub struct CollapseRepeats {
+    pub min_run: usize,
+}
+
+impl Default for CollapseRepeats {
+    fn default() -> Self {
+        Self { min_run: 3 }
+    }
+}
+
+impl CollapseRepeats {
+    /// Whether the message repeats the first message of the run.
+    fn repeats(first: &Message, message: &Message) -> bool {
+        first.sender == message.sender
+            && similarity(message.text(), first.text()) >= REPEAT_SIMILARITY
+            && similarity(first.text(), message.text()) >= REPEAT_SIMILARITY
+    }
+
+    /// Collapses the messages, returning them with every run that was collapsed.
+    fn collapse(&self, messages: Vec<Message>) -> (Vec<Message>, Vec<Vec<Message>>) {
+        let mut kept = Vec::with_capacity(messages.len());
+        let mut collapsed = Vec::new();
+        let mut run: Vec<Message> = Vec::new();
+        let mut end_run = |run: &mut Vec<Message>, kept: &mut Vec<Message>| {
+            if run.len() >= self.min_run.max(2) {
+                let mut first = run[0].clone();
+                first.repeats = run.iter().map(|message| message.repeats).sum();
+                // the reactions to any of the copies were to the message
+                first.reactions = run
+                    .iter()
+                    .flat_map(|message| message.reactions.iter().cloned())
+                    .collect();
+                kept.push(first);
+                collapsed.push(std::mem::take(run));
+            } else {
+                kept.append(run);
+            }
+        };
+        for message in messages {
+            if run
+                .first()
+                .is_some_and(|first| !Self::repeats(first, &message))
+            {
+                end_run(&mut run, &mut kept);
+            }
+            run.push(message);
+        }
+        end_run(&mut run, &mut kept);
+        (kept, collapsed)
+    }
+}
+
+impl MessageTransform for CollapseRepeats {
+    fn name(&self) -> &str {
+        "collapse repeats"
+    }
+
+    fn apply(&self, messages: Vec<Message>) -> Vec<Message> {
+        self.collapse(messages).0
+    }
+
+    fn apply_recorded(
+        &self,
+        messages: Vec<Message>,
+        names: &ParticipantNames,
+        diagnostics: &mut Diagnostics,
+    ) -> Vec<Message> {
+        let (messages, collapsed) = self.collapse(messages);
+        let listed = &mut diagnostics.collapsed_repeats;
+        listed.extend(collapsed.iter().map(|run| CollapsedRun {
+            sender: names.name(run[0].sender).to_string(),
+            first_ms: run[0].timestamp_ms(),
+            last_ms: run[run.len() - 1].timestamp_ms(),
+            messages: run.iter().map(|message| message.repeats).sum(),
+        }));
+        // stable, so ties stay in order
+        listed.sort_by_key(|run| std::cmp::Reverse(run.messages));
+        listed.truncate(LISTED_RUNS);
+        messages
+    }
+}
+
+/// How a message standing for a collapsed run of repeats (see `CollapseRepeats`) counts in message volumes:
+/// the message counts of the summaries, weekly digests and year review, and the daily counts of the time series exports, phases, bursts and volume plot.
+///
+/// Sentiment aggregates always count it once, the repeats say nothing new.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RepeatCount {
+    /// as one message
+    #[default]
+    Once,
+    /// as every message of the run
+    Each,
+}
+
+impl RepeatCount {
+    /// How many messages the message counts as.
+    pub fn volume(self, message: &Message) -> usize {
+        match self {
+            Self::Once => 1,
+            Self::Each => message.repeats.max(1),
+        }
+    }
+}
+
+/// An ordered list of transforms.
+#[derive(Default)]
+pub struct TransformPipeline {
+    transforms: Vec<Box<dyn MessageTransform>>,
+}
+
+impl TransformPipeline {
+    /// A pipeline that doesn't change anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The transforms applied when nothing else is asked for.
+    pub fn standard() -> Self {
+        Self::new().with(DropUnsent).with(CleanText).with(DropEmpty)
+    }
+
+    /// Adds a transform to the end of the pipeline.
+    #[must_use]
+    pub fn with(mut self, transform: impl MessageTransform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Removes the transforms named `name` from the pipeline, e.g. to keep what one of the standard transforms drops.
+    #[must_use]
+    pub fn without(mut self, name: &str) -> Self {
+        self.transforms.retain(|transform| transform.name() != name);
+        self
+    }
+
+    /// The names of the transforms, in the order they're applied.
+    pub fn names(&self) -> Vec<&str> {
+        self.transforms
+            .iter()
+            .map(|transform| transform.name())
+            .collect()
+    }
+
+    /// Applies every transform in order, recording how many messages each one that dropped (or collapsed) any dropped,
+    /// and anything else they record, see `MessageTransform::apply_recorded`.
+    pub fn apply(
+        &self,
+        mut messages: Vec<Message>,
+        names: &ParticipantNames,
+        diagnostics: &mut Diagnostics,
+    ) -> Vec<Message> {
+        for transform in &self.transforms {
+            let before = messages.len();
+            messages = transform.apply_recorded(messages, names, diagnostics);
+            let dropped = before.saturating_sub(messages.len());
+            if dropped > 0 {
+                diagnostics
+                    .dropped_by_transform
+                    .push((transform.name().to_string(), dropped));
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ParsedConversation;
+
+    /// Applies the transforms to Ana's messages: one with nothing but a zero-width space, and "hi".
+    fn apply(transforms: &TransformPipeline) -> (Vec<String>, Diagnostics) {
+        let export = br#"{
+          "participants": [{"name": "Ana"}],
+          "messages": [
+            {"sender_name": "Ana", "timestamp_ms": 1700000060000, "content": "hi"},
+            {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "placeholder"}
+          ]
+        }"#;
+        let conversation = ParsedConversation::from_bytes(export).unwrap();
+        let mut messages = conversation.messages;
+        messages[0].content = Some("\u{200B}".to_string());
+        let mut diagnostics = Diagnostics::default();
+        let messages = transforms.apply(messages, &conversation.names, &mut diagnostics);
+        let texts = messages
+            .iter()
+            .map(|message| message.text().to_string())
+            .collect();
+        (texts, diagnostics)
+    }
+
+    #[test]
+    fn transforms_that_drop_nothing_record_nothing() {
+        let transforms = TransformPipeline::new()
+            .with(DropUnsent)
+            .with(CollapseRepeats::default());
+        let (texts, diagnostics) = apply(&transforms);
+        assert_eq!(texts, ["\u{200B}", "hi"]);
+        assert!(diagnostics.dropped_by_transform.is_empty());
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn cleaning_before_dropping_empty_messages_drops_invisible_ones() {
+        let (texts, diagnostics) = apply(&TransformPipeline::standard());
+        assert_eq!(texts, ["hi"]);
+        assert_eq!(
+            diagnostics.dropped_by_transform,
+            [("drop empty".to_string(), 1)]
+        );
+
+        // cleaned only once it's too late to drop it
+        let transforms = TransformPipeline::new()
+            .with(DropUnsent)
+            .with(DropEmpty)
+            .with(CleanText);
+        let (texts, diagnostics) = apply(&transforms);
+        assert_eq!(texts, ["", "hi"]);
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,153 @@
+//! Module responsible for the weekly digest: a compact csv with a row per week per participant,
+//! derived from the same weekly buckets (see `aggregate`) and reply pairs as everything else.
+//!
+//! Weeks are ISO weeks, in UTC.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use crate::{
+    aggregate::{self, Period},
+    analyzer::{AnalyzedConversation, Score, Sentiment},
+    parser::{Message, ParticipantId},
+    stats,
+    timeseries::escape_csv_field,
+    tokenizer,
+};
+
+/// The columns of the digest, in order. Columns are only ever added to the end.
+pub const WEEKLY_COLUMNS: &[&str] = &[
+    "week_start",
+    "participant",
+    "messages",
+    "mean_compound",
+    "median_compound",
+    "positive_share",
+    "mean_reply_minutes",
+    "top_emoji",
+    "quoted",
+];
+
+/// One participant's week.
+#[derive(Debug, Clone)]
+pub struct WeeklyDigest {
+    pub week_start_ms: usize,
+    pub participant: ParticipantId,
+    pub messages: usize,
+    pub mean_compound: f64,
+    pub median_compound: f64,
+    /// the share (0 to 1) of their messages classified as positive
+    pub positive_share: f64,
+    /// how long they took to reply, over the reply pairs they responded in that week, if any
+    pub mean_reply_minutes: Option<f64>,
+    /// the emoji they used most that week (the first of them in code point order on ties),
+    /// `None` if they used none, or the message text was dropped
+    pub top_emoji: Option<String>,
+    /// how many of their messages that week were flagged as likely quoted, see `quotes`
+    pub quoted: usize,
+}
+
+impl AnalyzedConversation {
+    /// The digest of every week each participant sent a message in, ordered by week, then participant
+    /// (as in `participants_oriented`).
+    pub fn weekly_digests(&self) -> Vec<WeeklyDigest> {
+        let mut reply_minutes: BTreeMap<(usize, ParticipantId), Vec<f64>> = BTreeMap::new();
+        for pair in self.reply_pairs() {
+            reply_minutes
+                .entry((
+                    Period::Week.bucket(pair.response_timestamp_ms),
+                    pair.responder,
+                ))
+                .or_default()
+                .push((pair.response_timestamp_ms - pair.stimulus_timestamp_ms) as f64 / 60_000.0);
+        }
+
+        let mut digests = self
+            .participants_oriented()
+            .into_iter()
+            .flat_map(|participant| {
+                let weeks = aggregate::bucket(
+                    self.analysis[&participant]
+                        .iter()
+                        .map(|(message, score)| (message.timestamp_ms(), (message, score))),
+                    Period::Week,
+                );
+                let reply_minutes = &reply_minutes;
+                weeks.into_iter().map(move |(week, messages)| {
+                    self.weekly_digest(
+                        participant,
+                        week,
+                        &messages,
+                        reply_minutes.get(&(week, participant)),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        // stable, so participants stay in order within each week
+        digests.sort_by_key(|digest| digest.week_start_ms);
+        digests
+    }
+
+    fn weekly_digest(
+        &self,
+        participant: ParticipantId,
+        week: usize,
+        messages: &[(&Message, &Score)],
+        reply_minutes: Option<&Vec<f64>>,
+    ) -> WeeklyDigest {
+        let mut compounds = messages
+            .iter()
+            .map(|(_, score)| score.compound)
+            .collect::<Vec<_>>();
+        let positives = messages
+            .iter()
+            .filter(|(_, score)| score.classify(&self.thresholds) == Sentiment::Positive)
+            .count();
+
+        WeeklyDigest {
+            week_start_ms: Period::Week.start_ms(week),
+            participant,
+            messages: messages
+                .iter()
+                .map(|(message, _)| self.repeats.volume(message))
+                .sum(),
+            mean_compound: stats::mean(&compounds).unwrap_or_default(),
+            median_compound: stats::median(&mut compounds).unwrap_or_default(),
+            positive_share: positives as f64 / messages.len() as f64,
+            mean_reply_minutes: reply_minutes.and_then(|minutes| stats::mean(minutes)),
+            top_emoji: tokenizer::top_emoji(messages.iter().map(|(message, _)| message.text())),
+            quoted: messages
+                .iter()
+                .filter(|(message, _)| message.quote.is_some())
+                .count(),
+        }
+    }
+
+    /// Renders the weekly digests as a csv with a header row, see `WEEKLY_COLUMNS`.
+    /// Weeks are given by the date they start on, empty fields have no value.
+    pub fn to_weekly_csv(&self) -> String {
+        let mut csv = WEEKLY_COLUMNS.join(",");
+        csv.push('\n');
+        for digest in self.weekly_digests() {
+            let week_start = chrono::DateTime::from_timestamp_millis(digest.week_start_ms as i64)
+                .map(|time| time.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            // writing to a string can't fail
+            let _ = writeln!(
+                csv,
+                "{week_start},{},{},{},{},{},{},{},{}",
+                escape_csv_field(self.names.name(digest.participant)),
+                digest.messages,
+                digest.mean_compound,
+                digest.median_compound,
+                digest.positive_share,
+                digest
+                    .mean_reply_minutes
+                    .map(|minutes| minutes.to_string())
+                    .unwrap_or_default(),
+                escape_csv_field(digest.top_emoji.as_deref().unwrap_or_default()),
+                digest.quoted,
+            );
+        }
+        csv
+    }
+}
@@ -0,0 +1,58 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use igdm_sentiment_analysis::{
+    rng::Seed,
+    sink::OutputSink,
+    synthetic::{self, SyntheticConfig},
+};
+
+/// Keeps the artifacts of a run in memory, by path.
+#[derive(Debug, Default)]
+pub struct MemorySink {
+    pub files: BTreeMap<PathBuf, Vec<u8>>,
+    /// the paths writing to fails, as if the disk had refused them
+    pub failing: BTreeSet<PathBuf>,
+}
+
+impl OutputSink for MemorySink {
+    fn write(&mut self, path: &Path, contents: &[u8]) -> Result<()> {
+        if self.failing.contains(path) {
+            bail!("refusing to write {}", path.display());
+        }
+        self.files.insert(path.to_path_buf(), contents.to_vec());
+        Ok(())
+    }
+
+    fn location(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+}
+
+/// A fresh directory for a test named `name`, under the system's temporary directory.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("igdm-sentiment-analysis-{name}"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes a synthetic conversation of `messages` messages, generated from `seed`, into a fresh directory for the test
+/// named `name`, returning the directory.
+pub fn synthetic_export(name: &str, messages: usize, seed: u64) -> PathBuf {
+    let dir = temp_dir(name);
+    let config = SyntheticConfig {
+        messages,
+        seed: Seed(seed),
+        ..SyntheticConfig::default()
+    };
+    synthetic::write_export(&config, &dir).unwrap();
+    dir
+}
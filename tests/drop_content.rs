@@ -0,0 +1,171 @@
+//! With `AnalyzeOptions::drop_content`, none of the text of the conversation makes it into any artifact.
+
+mod common;
+
+use std::{collections::HashSet, path::Path};
+
+use common::{temp_dir, MemorySink};
+use igdm_sentiment_analysis::{
+    aggregate::Period,
+    analyzer::AnalyzeOptions,
+    calendar::CalendarConfig,
+    diagnostics::Diagnostics,
+    parser::{ExportBoundaries, Message, ParsedConversation},
+    pipeline::{Pipeline, PipelineOutput},
+    quiz::QuizConfig,
+};
+
+/// Written in every piece of text the conversation has: scored, too short, link only, attachment, in another
+/// language, replied to, shared captions and links, and calls.
+const PHRASE: &str = "zebrapancake";
+
+const EXPORT: &str = r#"{
+  "participants": [{"name": "Ana"}, {"name": "Ben"}],
+  "messages": [
+    {"sender_name": "Ben", "timestamp_ms": 1700000800000, "content": "Ben started a video chat zebrapancake", "call_duration": 60},
+    {"sender_name": "Ana", "timestamp_ms": 1700000700000, "content": "me encanta la receta zebrapancake de tu abuela, es muy rica y deliciosa"},
+    {"sender_name": "Ben", "timestamp_ms": 1700000600000, "content": "zebrapancake",
+     "photos": [{"uri": "photos/1.jpg"}]},
+    {"sender_name": "Ana", "timestamp_ms": 1700000500000, "content": "https://zebrapancake.example.com"},
+    {"sender_name": "Ben", "timestamp_ms": 1700000400000, "content": "zebrapancake!",
+     "reply_to": "what a great zebrapancake recipe"},
+    {"sender_name": "Ana", "timestamp_ms": 1700000300000, "content": "what a great zebrapancake recipe",
+     "share": {"link": "https://example.com/zebrapancake", "share_text": "the best zebrapancake ever"}},
+    {"sender_name": "Ben", "timestamp_ms": 1700000200000, "content": "I love the zebrapancake, it's wonderful",
+     "reply_to": "I made the zebrapancake for you"},
+    {"sender_name": "Ana", "timestamp_ms": 1700000100000, "content": "I made the zebrapancake for you",
+     "share": {"link": "https://example.com/zebrapancake/reel", "share_text": "zebrapancake tutorial"}}
+  ]
+}"#;
+
+fn run(drop_content: bool) -> PipelineOutput {
+    let input = temp_dir(&format!("drop-content-{drop_content}"));
+    std::fs::write(input.join("message_1.json"), EXPORT).unwrap();
+    Pipeline::new(&input)
+        .options(AnalyzeOptions {
+            drop_content,
+            min_tokens: 2,
+            strip_urls_and_mentions: true,
+            english_only: true,
+            ..AnalyzeOptions::default()
+        })
+        .run()
+        .unwrap()
+}
+
+/// Every artifact the run can write.
+fn artifacts(output: &mut PipelineOutput) -> MemorySink {
+    let mut sink = MemorySink::default();
+    output.write_json_export(&mut sink).unwrap();
+    output.write_report(&mut sink).unwrap();
+    output
+        .write_line_protocol(&mut sink, Path::new("sentiment.lp"))
+        .unwrap();
+    output
+        .write_csv_timeseries(&mut sink, Path::new("timeseries.csv"), Period::Day)
+        .unwrap();
+    output
+        .write_weekly_csv(&mut sink, Path::new("weekly.csv"))
+        .unwrap();
+    output
+        .write_rhythm_csv(&mut sink, Path::new("rhythm.csv"))
+        .unwrap();
+    output
+        .write_ics(
+            &mut sink,
+            Path::new("calendar.ics"),
+            &CalendarConfig::default(),
+        )
+        .unwrap();
+    output
+        .write_quiz(&mut sink, &QuizConfig::default())
+        .unwrap();
+    output.write_year_review(&mut sink, 2023).unwrap();
+    #[cfg(feature = "plot")]
+    {
+        output
+            .write_plots(&mut sink, &igdm_sentiment_analysis::plotter::PlotType::ALL)
+            .unwrap();
+        output.write_sparklines_plot(&mut sink).unwrap();
+        output.write_volume_plot(&mut sink, true).unwrap();
+    }
+    output.write_manifest(&mut sink).unwrap();
+    sink
+}
+
+/// The messages the analysis keeps, scored or not.
+fn messages(output: &PipelineOutput) -> Vec<&Message> {
+    let analysis = &output.analysis;
+    let scored = analysis
+        .analysis
+        .values()
+        .flatten()
+        .map(|(message, _)| message);
+    scored
+        .chain(&analysis.attachment_only)
+        .chain(&analysis.too_short)
+        .chain(&analysis.other_language)
+        .chain(&analysis.calls)
+        .collect()
+}
+
+/// The message as json, as part of a conversation of only it.
+fn to_json(message: &Message, output: &PipelineOutput) -> String {
+    let conversation = ParsedConversation {
+        participants: HashSet::new(),
+        messages: vec![message.clone()],
+        names: output.analysis.names.clone(),
+        diagnostics: Diagnostics::default(),
+        export_boundaries: ExportBoundaries::default(),
+        me: None,
+        attachment_only: Vec::new(),
+        calls: Vec::new(),
+        system: Vec::new(),
+        title: None,
+        thread_path: None,
+    };
+    String::from_utf8(conversation.to_json().unwrap()).unwrap()
+}
+
+/// The artifacts that mention the phrase, and the texts of the messages left, as their json.
+fn leaks(output: &mut PipelineOutput) -> Vec<String> {
+    let mut leaks = artifacts(output)
+        .files
+        .into_iter()
+        .filter(|(_, contents)| String::from_utf8_lossy(contents).contains(PHRASE))
+        .map(|(path, _)| path.display().to_string())
+        .collect::<Vec<_>>();
+    let messages = messages(output)
+        .into_iter()
+        .map(|message| to_json(message, output))
+        .filter(|json| json.contains(PHRASE))
+        .collect::<Vec<_>>();
+    leaks.extend(messages);
+    leaks
+}
+
+#[test]
+fn dropped_content_is_in_no_artifact() {
+    let mut output = run(true);
+    let analysis = &output.analysis;
+    // every kind of message is there to leak its text
+    assert!(analysis.analysis.values().flatten().count() > 0);
+    for (kind, messages) in [
+        ("attachment only", &analysis.attachment_only),
+        ("too short", &analysis.too_short),
+        ("other language", &analysis.other_language),
+        ("calls", &analysis.calls),
+    ] {
+        assert!(!messages.is_empty(), "no {kind} messages");
+    }
+    assert_eq!(leaks(&mut output), Vec::<String>::new());
+}
+
+#[test]
+fn kept_content_is_found() {
+    // so the test above would notice a leak
+    let mut output = run(false);
+    let leaks = leaks(&mut output);
+    assert!(leaks.contains(&"analysis.json".to_string()), "{leaks:?}");
+    assert!(leaks.len() > 8, "{leaks:?}");
+}
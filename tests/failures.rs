@@ -0,0 +1,90 @@
+//! An artifact that can't be written doesn't stop the others, and fails the run unless it's best effort.
+
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::{synthetic_export, temp_dir, MemorySink};
+use igdm_sentiment_analysis::pipeline::Pipeline;
+
+#[test]
+fn a_failing_artifact_is_recorded_and_the_others_are_still_written() {
+    let input = synthetic_export("failures-input", 500, 218);
+    let mut sink = MemorySink {
+        failing: [PathBuf::from("analysis.json")].into(),
+        ..MemorySink::default()
+    };
+
+    let mut output = Pipeline::new(&input).run().unwrap();
+    output.write_report(&mut sink).unwrap();
+    output.write_json_export(&mut sink).unwrap();
+    output
+        .write_rhythm_csv(&mut sink, Path::new("rhythm.csv"))
+        .unwrap();
+    output.write_manifest(&mut sink).unwrap();
+
+    assert_eq!(
+        sink.files.keys().collect::<Vec<_>>(),
+        ["manifest.json", "report.txt", "rhythm.csv"]
+    );
+    let failed = output.manifest.failed_artifacts().collect::<Vec<_>>();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].path, Path::new("analysis.json"));
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&sink.files[Path::new("manifest.json")]).unwrap();
+    let artifacts = manifest["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts.len(), 3);
+    for artifact in artifacts {
+        let failed = artifact["path"] == "analysis.json";
+        assert_eq!(artifact.get("error").is_some(), failed, "{artifact}");
+    }
+    assert!(artifacts[1]["error"]
+        .as_str()
+        .unwrap()
+        .contains("refusing to write analysis.json"));
+}
+
+#[test]
+fn a_failing_artifact_fails_the_run_unless_it_is_best_effort() {
+    let input = synthetic_export("failures-cli-input", 500, 218);
+    let run = |name: &str, best_effort: bool| {
+        let output = temp_dir(name);
+        // a directory where the csv should go can't be written over
+        std::fs::create_dir_all(output.join("rhythm.csv")).unwrap();
+        let mut command = Command::new(env!("CARGO_BIN_EXE_igdm-sentiment-analysis"));
+        command
+            .arg("--path")
+            .arg(&input)
+            .arg("--output-dir")
+            .arg(&output)
+            .args(["--no-cache", "--export-rhythm", "rhythm.csv"]);
+        if best_effort {
+            command.arg("--best-effort");
+        }
+        let status = command.output().unwrap().status;
+        (status, output)
+    };
+
+    let (status, output) = run("failures-cli-output", false);
+    assert!(!status.success());
+    assert!(output.join("report.txt").is_file());
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(output.join("manifest.json")).unwrap()).unwrap();
+    let failed = manifest["artifacts"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|artifact| artifact.get("error").is_some())
+        .map(|artifact| PathBuf::from(artifact["path"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(failed, [output.join("rhythm.csv")]);
+
+    let (status, output) = run("failures-cli-best-effort-output", true);
+    assert!(status.success());
+    assert!(output.join("report.txt").is_file());
+    assert!(output.join("manifest.json").is_file());
+}
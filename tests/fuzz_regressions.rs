@@ -0,0 +1,43 @@
+//! Replays the inputs of previously found crashes (`fuzz/regressions/`) and the fuzzing corpus (`fuzz/corpus/`)
+//! through the same checks as the `parse` fuzz target, so they stay fixed without a nightly toolchain.
+
+use std::{fs, path::Path};
+
+use igdm_sentiment_analysis::parser::{
+    decode_escapes, decode_escapes_in_place, ParsedConversation,
+};
+
+fn replay(directory: &Path) -> usize {
+    let mut replayed = 0;
+    for entry in fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        let data = fs::read(&path).unwrap();
+        let decoded = decode_escapes(&data);
+        let mut in_place = data.clone();
+        decode_escapes_in_place(&mut in_place);
+        assert_eq!(in_place, decoded, "{}", path.display());
+        let _ = ParsedConversation::from_bytes(&data);
+        replayed += 1;
+    }
+    replayed
+}
+
+#[test]
+fn regressions_do_not_panic() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/regressions/parse");
+    assert!(
+        replay(&directory) > 0,
+        "no regressions in {}",
+        directory.display()
+    );
+}
+
+#[test]
+fn corpus_does_not_panic() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/parse");
+    assert!(
+        replay(&directory) > 0,
+        "no corpus in {}",
+        directory.display()
+    );
+}
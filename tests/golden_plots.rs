@@ -0,0 +1,116 @@
+//! The plots of a small synthetic conversation, compared against the reference images in `tests/golden/`, so a change
+//! to how a plot is drawn fails a test rather than going unnoticed.
+//!
+//! Text is drawn with the system's sans-serif font (DejaVu Sans where the references were rendered, as on the ubuntu
+//! CI runners), so a few pixels may differ elsewhere. After changing a plot on purpose, render the references again
+//! with `UPDATE_GOLDEN=1 cargo test --test golden_plots`, and look at them before committing them.
+
+#![cfg(feature = "plot")]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use igdm_sentiment_analysis::{
+    analyzer::AnalyzedConversation,
+    palette::Palette,
+    plotter::PlotType,
+    synthetic::{self, SyntheticConfig},
+};
+use image::RgbImage;
+
+/// How far apart a channel of two pixels can be before they count as different.
+const CHANNEL_TOLERANCE: u8 = 16;
+/// The share of the pixels that can differ, for the antialiasing of text to vary a little.
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+/// Three participants over about two months.
+fn analysis() -> AnalyzedConversation {
+    let config = SyntheticConfig {
+        participants: 3,
+        messages: 240,
+        mean_gap_ms: 6 * 60 * 60 * 1000,
+        ..SyntheticConfig::default()
+    };
+    synthetic::generate_conversation(&config).analyze()
+}
+
+/// Compares the png against the reference image `name`, or replaces the reference with it if `UPDATE_GOLDEN` is set.
+fn check(name: &str, png: &[u8]) -> Result<()> {
+    let path = golden_dir().join(format!("{name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(golden_dir())?;
+        std::fs::write(&path, png)?;
+        return Ok(());
+    }
+
+    let actual = image::load_from_memory(png)?.to_rgb8();
+    let expected = image::open(&path)
+        .with_context(|| format!("no reference image at {path:?}, render it with UPDATE_GOLDEN=1"))?
+        .to_rgb8();
+    let differing = differing_pixels(&actual, &expected);
+    if differing > (PIXEL_TOLERANCE * expected.len() as f64 / 3.0) as usize {
+        let failed =
+            std::env::temp_dir().join(format!("igdm-sentiment-analysis-golden-{name}.png"));
+        std::fs::write(&failed, png)?;
+        anyhow::bail!(
+            "{name} doesn't look like {path:?} ({differing} pixels differ), it was rendered to {failed:?}"
+        );
+    }
+    Ok(())
+}
+
+/// How many pixels differ, every pixel if the images aren't the same size.
+fn differing_pixels(actual: &RgbImage, expected: &RgbImage) -> usize {
+    if actual.dimensions() != expected.dimensions() {
+        return actual.len().max(expected.len()) / 3;
+    }
+    actual
+        .pixels()
+        .zip(expected.pixels())
+        .filter(|(a, b)| {
+            a.0.iter()
+                .zip(b.0)
+                .any(|(a, b)| a.abs_diff(b) > CHANNEL_TOLERANCE)
+        })
+        .count()
+}
+
+#[test]
+fn plots_match_their_references() -> Result<()> {
+    let analysis = analysis();
+    for plot_type in PlotType::ALL {
+        check(&plot_type.to_string(), &analysis.plot_png(plot_type)?)?;
+    }
+    check("responsiveness", &analysis.plot_responsiveness_png()?)?;
+    check("gaps", &analysis.plot_gaps_png()?)?;
+    check("volume", &analysis.plot_volume_png(true)?)?;
+    check("sparklines", &analysis.plot_sparklines_png()?)?;
+    assert!(analysis.take_plot_issues().is_empty());
+    Ok(())
+}
+
+#[test]
+fn mono_plots_match_their_references() -> Result<()> {
+    let mut analysis = analysis();
+    analysis.palette = Palette::Mono;
+    check("compound-mono", &analysis.plot_png(PlotType::Compound)?)
+}
+
+#[test]
+fn a_different_plot_is_caught() -> Result<()> {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        return Ok(());
+    }
+    let analysis = analysis();
+    let positive = image::load_from_memory(&analysis.plot_png(PlotType::Positive)?)?.to_rgb8();
+    let compound = image::open(golden_dir().join("compound.png"))?.to_rgb8();
+    assert!(
+        differing_pixels(&positive, &compound)
+            > (PIXEL_TOLERANCE * compound.len() as f64 / 3.0) as usize
+    );
+    Ok(())
+}
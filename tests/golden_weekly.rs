@@ -0,0 +1,56 @@
+//! The weekly digest of a small conversation, compared against the reference csv in `tests/golden/`, so a change to
+//! its columns or to how they're computed fails a test rather than going unnoticed.
+//!
+//! After changing the digest on purpose, write the reference again with
+//! `UPDATE_GOLDEN=1 cargo test --test golden_weekly`, and look at the diff before committing it.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use common::temp_dir;
+use igdm_sentiment_analysis::{analyzer::AnalyzedConversation, pipeline::Pipeline, weekly};
+
+/// Two participants over two weeks, replying to each other, with emoji.
+const EXPORT: &str = r#"{
+  "participants": [{"name": "Ana"}, {"name": "Ben"}],
+  "messages": [
+    {"sender_name": "Ana", "timestamp_ms": 1700611000000, "content": "ugh, this week was awful"},
+    {"sender_name": "Ben", "timestamp_ms": 1700610400000, "content": "still thinking about that cake 😋😋"},
+    {"sender_name": "Ana", "timestamp_ms": 1700604900000, "content": "how was your week? 🙂"},
+    {"sender_name": "Ben", "timestamp_ms": 1700003000000, "content": "haha ok 😂"},
+    {"sender_name": "Ana", "timestamp_ms": 1700002400000, "content": "the cake was great 😂"},
+    {"sender_name": "Ana", "timestamp_ms": 1700001800000, "content": "I baked a cake, I love it 🎂"},
+    {"sender_name": "Ben", "timestamp_ms": 1700000600000, "content": "good morning! 🙂"},
+    {"sender_name": "Ana", "timestamp_ms": 1700000000000, "content": "morning"}
+  ]
+}"#;
+
+fn golden_path() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/weekly.csv")
+}
+
+fn analysis() -> AnalyzedConversation {
+    let input = temp_dir("golden-weekly");
+    std::fs::write(input.join("message_1.json"), EXPORT).unwrap();
+    Pipeline::new(&input).run().unwrap().analysis
+}
+
+#[test]
+fn weekly_csv_matches_the_reference() {
+    let csv = analysis().to_weekly_csv();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(golden_path(), &csv).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(golden_path()).unwrap();
+    assert_eq!(
+        csv.lines().next(),
+        Some(weekly::WEEKLY_COLUMNS.join(",").as_str())
+    );
+    assert_eq!(
+        csv, expected,
+        "the weekly digest differs from the reference"
+    );
+}
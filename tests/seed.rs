@@ -0,0 +1,50 @@
+//! Runs with the same seed are reproducible, byte for byte.
+
+mod common;
+
+use common::{synthetic_export, MemorySink};
+use igdm_sentiment_analysis::{pipeline::Pipeline, quiz::QuizConfig, rng::Seed};
+
+/// Everything a run over `input` seeded by `seed` writes, with the randomized artifacts: the bootstrap confidence
+/// intervals (in the json export and the report) and the quiz's sample of messages.
+fn run(input: &std::path::Path, seed: u64) -> MemorySink {
+    let mut output = Pipeline::new(input)
+        .seed(Seed(seed))
+        .bootstrap(200)
+        .run()
+        .unwrap();
+    let mut sink = MemorySink::default();
+    output.write_json_export(&mut sink).unwrap();
+    output.write_report(&mut sink).unwrap();
+    output
+        .write_quiz(&mut sink, &QuizConfig::default())
+        .unwrap();
+    output.write_manifest(&mut sink).unwrap();
+    assert_eq!(output.manifest.failed_artifacts().count(), 0);
+    sink
+}
+
+#[test]
+fn the_same_seed_writes_the_same_bytes() {
+    let input = synthetic_export("seed", 2_000, 7);
+    let (first, second) = (run(&input, 42), run(&input, 42));
+    assert_eq!(
+        first.files.keys().collect::<Vec<_>>(),
+        second.files.keys().collect::<Vec<_>>()
+    );
+    for (path, contents) in &first.files {
+        assert!(
+            *contents == second.files[path],
+            "{} differs between runs",
+            path.display()
+        );
+    }
+}
+
+#[test]
+fn another_seed_samples_another_quiz() {
+    let input = synthetic_export("seed-other", 2_000, 7);
+    let (first, second) = (run(&input, 42), run(&input, 43));
+    let quiz = std::path::Path::new("quiz.json");
+    assert_ne!(first.files[quiz], second.files[quiz]);
+}
@@ -0,0 +1,109 @@
+//! Every sink gets the same artifacts, whatever it does with them.
+
+#![cfg(feature = "zip")]
+
+mod common;
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use common::{synthetic_export, temp_dir};
+use igdm_sentiment_analysis::{
+    aggregate::Period,
+    pipeline::Pipeline,
+    sink::{DirectorySink, OutputSink, ZipSink},
+};
+
+/// Runs the pipeline over `input`, writing a handful of artifacts and the manifest into `sink`.
+fn run(input: &Path, sink: &mut dyn OutputSink) {
+    let mut output = Pipeline::new(input).run().unwrap();
+    output.write_json_export(sink).unwrap();
+    output.write_report(sink).unwrap();
+    output
+        .write_csv_timeseries(sink, Path::new("exports/timeseries.csv"), Period::Week)
+        .unwrap();
+    output
+        .write_rhythm_csv(sink, Path::new("exports/rhythm.csv"))
+        .unwrap();
+    output.write_manifest(sink).unwrap();
+    sink.finish().unwrap();
+}
+
+/// The files under `root`, by their path relative to it.
+fn directory_files(root: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                directories.push(path);
+            } else {
+                let relative = path.strip_prefix(root).unwrap().to_path_buf();
+                files.insert(relative, std::fs::read(path).unwrap());
+            }
+        }
+    }
+    files
+}
+
+/// The entries of the archive, by name.
+fn archive_files(archive: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(File::open(archive).unwrap()).unwrap();
+    (0..archive.len())
+        .map(|i| {
+            let mut entry = archive.by_index(i).unwrap();
+            let mut contents = Vec::new();
+            entry.read_to_end(&mut contents).unwrap();
+            (PathBuf::from(entry.name()), contents)
+        })
+        .collect()
+}
+
+/// The manifest, with the locations of the artifacts relative to the sink at `root`.
+fn manifest(json: &[u8], root: &Path) -> serde_json::Value {
+    let mut manifest: serde_json::Value = serde_json::from_slice(json).unwrap();
+    for artifact in manifest["artifacts"].as_array_mut().unwrap() {
+        let location = PathBuf::from(artifact["path"].as_str().unwrap());
+        let relative = location.strip_prefix(root).unwrap().to_path_buf();
+        artifact["path"] = relative.to_string_lossy().replace('\\', "/").into();
+    }
+    manifest
+}
+
+#[test]
+fn zip_and_directory_sinks_get_the_same_artifacts() {
+    let input = synthetic_export("sinks-input", 1_000, 3);
+    let output = temp_dir("sinks-output");
+
+    let directory = output.join("run");
+    run(&input, &mut DirectorySink::new(&directory));
+    let archive = output.join("run.zip");
+    run(&input, &mut ZipSink::create(&archive).unwrap());
+
+    let mut in_directory = directory_files(&directory);
+    let mut in_archive = archive_files(&archive);
+    assert_eq!(
+        in_directory.keys().collect::<Vec<_>>(),
+        in_archive.keys().collect::<Vec<_>>()
+    );
+    assert_eq!(in_directory.len(), 5);
+
+    let manifest_path = Path::new("manifest.json");
+    let directory_manifest = manifest(&in_directory.remove(manifest_path).unwrap(), &directory);
+    let archive_manifest = manifest(&in_archive.remove(manifest_path).unwrap(), &archive);
+    assert_eq!(directory_manifest, archive_manifest);
+    assert_eq!(directory_manifest["artifacts"].as_array().unwrap().len(), 4);
+
+    for (path, contents) in &in_directory {
+        assert!(
+            *contents == in_archive[path],
+            "{} differs between the sinks",
+            path.display()
+        );
+    }
+}
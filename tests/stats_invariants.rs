@@ -0,0 +1,152 @@
+//! Invariants of the statistics helpers (`stats` and `aggregate`), over arbitrary inputs.
+
+use chrono::{DateTime, TimeDelta, Utc};
+use igdm_sentiment_analysis::{
+    aggregate::{self, Period},
+    stats::{self, AggregateStat},
+};
+use proptest::prelude::*;
+
+/// Any statistic compound scores can be aggregated with.
+fn aggregate_stat() -> impl Strategy<Value = AggregateStat> {
+    prop_oneof![
+        Just(AggregateStat::Mean),
+        Just(AggregateStat::Median),
+        (0.0..50.0).prop_map(AggregateStat::Trimmed),
+        (0.0..50.0).prop_map(AggregateStat::Winsorized),
+    ]
+}
+
+/// Scores sorted by timestamp, from a few seconds to a few days apart, starting around late 2023.
+fn series() -> impl Strategy<Value = Vec<(DateTime<Utc>, f64)>> {
+    prop::collection::vec((1_000i64..300_000_000, -1.0..=1.0f64), 1..200).prop_map(|steps| {
+        let mut t = DateTime::from_timestamp_millis(1_700_000_000_000).unwrap();
+        steps
+            .into_iter()
+            .map(|(step, score)| {
+                t += TimeDelta::milliseconds(step);
+                (t, score)
+            })
+            .collect()
+    })
+}
+
+fn bounds(values: impl IntoIterator<Item = f64>) -> (f64, f64) {
+    values
+        .into_iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+            (low.min(value), high.max(value))
+        })
+}
+
+proptest! {
+    #[test]
+    fn smoothing_stays_within_the_scores(
+        data in series(),
+        window_hours in 1i64..200,
+        stat in aggregate_stat(),
+    ) {
+        let (low, high) = bounds(data.iter().map(|(_, score)| *score));
+        let smoothed = stats::smoothen_wrt_time(&data, TimeDelta::hours(window_hours), stat);
+        prop_assert!(!smoothed.is_empty());
+        for (_, value) in &smoothed {
+            prop_assert!(*value >= low - 1e-12 && *value <= high + 1e-12);
+        }
+    }
+
+    #[test]
+    fn smoothing_windows_start_at_points_in_order(data in series(), window_hours in 1i64..200) {
+        let window = TimeDelta::hours(window_hours);
+        let smoothed = stats::smoothen_wrt_time(&data, window, AggregateStat::Mean);
+        prop_assert_eq!(smoothed[0].0, data[0].0);
+        for pair in smoothed.windows(2) {
+            prop_assert!(pair[1].0 - pair[0].0 >= window);
+            prop_assert!(data.iter().any(|(t, _)| *t == pair[1].0));
+        }
+    }
+
+    #[test]
+    fn smoothing_a_single_window_aggregates_everything(data in series(), stat in aggregate_stat()) {
+        let span = data.last().unwrap().0 - data[0].0 + TimeDelta::milliseconds(1);
+        let smoothed = stats::smoothen_wrt_time(&data, span, stat);
+        let expected = stat.apply(&mut data.iter().map(|(_, score)| *score).collect::<Vec<_>>());
+        prop_assert_eq!(smoothed, vec![(data[0].0, expected.unwrap())]);
+    }
+
+    #[test]
+    fn aggregates_stay_within_the_values(
+        mut values in prop::collection::vec(-1.0..=1.0f64, 1..100),
+        stat in aggregate_stat(),
+    ) {
+        let (low, high) = bounds(values.iter().copied());
+        let aggregate = stat.apply(&mut values).unwrap();
+        prop_assert!(aggregate >= low - 1e-12 && aggregate <= high + 1e-12);
+    }
+
+    #[test]
+    fn aggregates_of_a_constant_are_the_constant(
+        value in -1.0..=1.0f64,
+        count in 1usize..100,
+        stat in aggregate_stat(),
+    ) {
+        let aggregate = stat.apply(&mut vec![value; count]).unwrap();
+        prop_assert!((aggregate - value).abs() < 1e-12);
+    }
+
+    #[test]
+    fn unit_weights_aggregate_like_no_weights(
+        values in prop::collection::vec(-1.0..=1.0f64, 1..100),
+        stat in aggregate_stat(),
+    ) {
+        let mut weighted = values.iter().map(|value| (*value, 1.0)).collect::<Vec<_>>();
+        prop_assert_eq!(stat.apply_weighted(&mut weighted), stat.apply(&mut values.clone()));
+    }
+
+    #[test]
+    fn bucket_counts_sum_to_the_input_count(
+        timestamps in prop::collection::vec(0usize..4_000_000_000_000, 0..200),
+        period in prop_oneof![Just(Period::Day), Just(Period::Week), Just(Period::Month)],
+    ) {
+        let buckets = aggregate::bucket(timestamps.iter().map(|t| (*t, ())), period);
+        prop_assert_eq!(buckets.values().map(Vec::len).sum::<usize>(), timestamps.len());
+        for (bucket, values) in &buckets {
+            prop_assert!(!values.is_empty());
+            // each bucket starts at or before its timestamps, and the next one after them
+            let in_bucket = timestamps.iter().filter(|t| period.bucket(**t) == *bucket);
+            for t in in_bucket {
+                prop_assert!(period.start_ms(*bucket) <= *t);
+                prop_assert!(*t < period.start_ms(bucket + 1));
+            }
+        }
+    }
+
+    #[test]
+    fn regression_recovers_a_linear_series(
+        data in series(),
+        slope_per_day in -0.5..0.5f64,
+        intercept in -1.0..1.0f64,
+    ) {
+        prop_assume!(data.len() >= 2);
+        let origin = data[0].0;
+        let per_ms = slope_per_day / 86_400_000.0;
+        let line = data
+            .iter()
+            .map(|(t, _)| (*t, intercept + per_ms * (*t - origin).num_milliseconds() as f64))
+            .collect::<Vec<_>>();
+        let fit = stats::least_squares_linear_regression(&line).unwrap();
+        prop_assert!((fit.slope - per_ms).abs() <= 1e-9 * per_ms.abs() + 1e-18);
+        prop_assert!((fit.intercept - intercept).abs() <= 1e-9);
+    }
+
+    #[test]
+    fn quantiles_are_ordered_and_bounded(
+        mut values in prop::collection::vec(-1.0..=1.0f64, 1..100),
+        a in 0.0..=1.0f64,
+        b in 0.0..=1.0f64,
+    ) {
+        values.sort_unstable_by(f64::total_cmp);
+        let (low, high) = (a.min(b), a.max(b));
+        let (low, high) = (stats::quantile(&values, low).unwrap(), stats::quantile(&values, high).unwrap());
+        prop_assert!(values[0] <= low && low <= high && high <= values[values.len() - 1]);
+    }
+}